@@ -0,0 +1,7 @@
+-- NOTE: Accounts registered so far logged in as admins, so they keep that role;
+--       accounts registered from now on default to guests.
+ALTER TABLE account
+ADD COLUMN role VARCHAR NOT NULL DEFAULT 'admin';
+
+ALTER TABLE account
+ALTER COLUMN role SET DEFAULT 'guest';
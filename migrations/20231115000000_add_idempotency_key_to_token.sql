@@ -0,0 +1,5 @@
+-- NOTE: Login retries carrying the same key are answered with the token issued first.
+ALTER TABLE token
+ADD COLUMN idempotency_key VARCHAR;
+
+CREATE INDEX IF NOT EXISTS token_idempotency_key_idx ON token (idempotency_key, created_at);
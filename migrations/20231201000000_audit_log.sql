@@ -0,0 +1,15 @@
+-- NOTE: Rows are appended on profile issuance and table queries and never updated.
+CREATE TABLE IF NOT EXISTS audit_log (
+    id UUID PRIMARY KEY,
+    "event" VARCHAR NOT NULL,
+    provider VARCHAR,
+    recipient VARCHAR NOT NULL,
+    share VARCHAR,
+    "schema" VARCHAR,
+    "table" VARCHAR,
+    ttl BIGINT,
+    file_count BIGINT,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL default CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS audit_log_recipient_idx ON audit_log (recipient, created_at);
@@ -0,0 +1,2 @@
+-- NOTE: Token introspection looks issued tokens up by their bearer value.
+CREATE INDEX IF NOT EXISTS token_value_idx ON token ("value");
@@ -0,0 +1,5 @@
+-- NOTE: The Delta schema seen at the last registration is kept so that re-registrations
+--       can be checked for breaking changes; the hash tells unchanged schemas apart cheaply.
+ALTER TABLE "table"
+ADD COLUMN schema_string TEXT,
+ADD COLUMN schema_hash VARCHAR;
@@ -0,0 +1,7 @@
+-- NOTE: Bearer tokens are stored hashed; rows past expires_at no longer matter since
+--       the tokens are rejected as expired anyway.
+CREATE TABLE IF NOT EXISTS revoked_token (
+    token_hash VARCHAR PRIMARY KEY,
+    expires_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL default CURRENT_TIMESTAMP
+);
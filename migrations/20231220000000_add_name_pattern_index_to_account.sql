@@ -0,0 +1,3 @@
+-- NOTE: Account search matches name prefixes with LIKE, which only uses an index
+--       built with text_pattern_ops under non-C collations.
+CREATE INDEX IF NOT EXISTS account_name_pattern_idx ON account (name text_pattern_ops);
@@ -0,0 +1,4 @@
+-- NOTE: Shares holding sensitive data may sign URLs with a shorter TTL than the global
+--       `signed_url_ttl`; NULL keeps the global one.
+ALTER TABLE share
+ADD COLUMN default_ttl BIGINT;
@@ -0,0 +1,12 @@
+-- NOTE: Refresh tokens are stored hashed and used once; each exchange issues a successor
+--       in the same family, so that a reused token can revoke every token derived from it.
+CREATE TABLE IF NOT EXISTS refresh_token (
+    token_hash VARCHAR PRIMARY KEY,
+    account_id UUID NOT NULL REFERENCES account(id) ON DELETE CASCADE,
+    family_id UUID NOT NULL,
+    expires_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    used_at TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL default CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS refresh_token_family_id_idx ON refresh_token (family_id);
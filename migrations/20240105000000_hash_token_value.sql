@@ -0,0 +1,10 @@
+-- NOTE: Bearer tokens are stored as keyed hashes only. The key is configured on the
+--       server, so values still in plaintext are hashed by `delta-sharing hash-tokens`.
+ALTER TABLE token
+ADD COLUMN value_hash VARCHAR,
+ADD COLUMN expires_at TIMESTAMP WITH TIME ZONE,
+ALTER COLUMN "value" DROP NOT NULL;
+
+DROP INDEX IF EXISTS token_value_idx;
+CREATE INDEX IF NOT EXISTS token_value_hash_idx ON token (value_hash);
+CREATE INDEX IF NOT EXISTS token_expires_at_idx ON token (expires_at);
@@ -0,0 +1,5 @@
+-- NOTE: Each refresh token records the access token issued along with it, so that a
+--       reused refresh token revokes the access tokens of its family as well.
+ALTER TABLE refresh_token
+ADD COLUMN access_token_hash VARCHAR,
+ADD COLUMN access_expires_at TIMESTAMP WITH TIME ZONE;
@@ -0,0 +1,5 @@
+-- NOTE: Entries can be matched against the server logs by the id of the request which
+--       recorded them; NULL for entries recorded before these columns existed.
+ALTER TABLE audit_log
+ADD COLUMN request_id VARCHAR,
+ADD COLUMN correlation_id VARCHAR;
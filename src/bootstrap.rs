@@ -1,19 +1,31 @@
 pub(crate) mod aws;
+pub(crate) mod azure;
 pub(crate) mod gcp;
+pub(crate) mod r2;
 
 mod postgres;
 use anyhow::Context;
 use anyhow::Result;
+use rusoto_credential::AwsCredentials;
 use rusoto_credential::ProfileProvider;
 use sqlx::PgPool;
 use tame_gcs::signing::ServiceAccount;
 
 use crate::config;
+use crate::server::utilities::signed_url::AzureCredentials;
 
 pub(crate) async fn new_pg_pool() -> Result<PgPool> {
     postgres::connect(&config::fetch::<String>("db_url")).await
 }
 
+pub(crate) async fn new_pg_replica_pool() -> Result<Option<PgPool>> {
+    let url = config::fetch::<String>("db_replica_url");
+    if url.is_empty() {
+        return Ok(None);
+    }
+    postgres::connect_replica(&url).await.map(Some)
+}
+
 pub(crate) fn new_gcp_service_account() -> Result<ServiceAccount> {
     let google_applicayion_credentials_path = format!(
         "{}",
@@ -32,3 +44,18 @@ pub(crate) fn new_aws_profile_provider() -> Result<ProfileProvider> {
         std::env::var("AWS_PROFILE").context("failed to get `AWS_PROFILE` environment variable")?;
     aws::new(&aws_profile)
 }
+
+pub(crate) fn new_azure_credentials() -> Result<AzureCredentials> {
+    let account = std::env::var("AZURE_STORAGE_ACCOUNT_NAME")
+        .context("failed to get `AZURE_STORAGE_ACCOUNT_NAME` environment variable")?;
+    let key = std::env::var("AZURE_STORAGE_ACCOUNT_KEY")
+        .context("failed to get `AZURE_STORAGE_ACCOUNT_KEY` environment variable")?;
+    azure::new(&account, &key)
+}
+
+pub(crate) fn new_r2_credentials() -> Result<AwsCredentials> {
+    r2::new(
+        &config::fetch::<String>("r2_access_key_id"),
+        &config::fetch::<String>("r2_secret_access_key"),
+    )
+}
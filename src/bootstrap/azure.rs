@@ -0,0 +1,31 @@
+use anyhow::Context;
+use anyhow::Result;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+
+use crate::server::utilities::signed_url::AzureCredentials;
+
+pub fn new(account: &str, key: &str) -> Result<AzureCredentials> {
+    tracing::info!("creating Azure storage credentials");
+    let key = STANDARD
+        .decode(key)
+        .context("failed to decode Azure storage account key")?;
+    tracing::info!("created Azure storage credentials");
+    Ok(AzureCredentials {
+        account: account.to_string(),
+        key,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_new() {
+        let account = testutils::rand::string(10);
+        let key = STANDARD.encode(testutils::rand::string(32));
+        assert!(new(&account, &key).is_ok());
+        assert!(new(&account, "!!!").is_err());
+    }
+}
@@ -0,0 +1,31 @@
+use anyhow::anyhow;
+use anyhow::Result;
+use rusoto_credential::AwsCredentials;
+
+pub fn new(access_key_id: &str, secret_access_key: &str) -> Result<AwsCredentials> {
+    tracing::info!("creating Cloudflare R2 credentials");
+    if access_key_id.is_empty() || secret_access_key.is_empty() {
+        return Err(anyhow!("Cloudflare R2 access key was not set"));
+    }
+    tracing::info!("created Cloudflare R2 credentials");
+    Ok(AwsCredentials::new(
+        access_key_id,
+        secret_access_key,
+        None,
+        None,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_new() {
+        let access_key_id = testutils::rand::string(20);
+        let secret_access_key = testutils::rand::string(40);
+        assert!(new(&access_key_id, &secret_access_key).is_ok());
+        assert!(new("", &secret_access_key).is_err());
+        assert!(new(&access_key_id, "").is_err());
+    }
+}
@@ -1,5 +1,7 @@
 mod fetcher;
 
+use std::collections::HashMap;
+
 use once_cell::sync::Lazy;
 
 use crate::server::utilities::bootstrap::JwtKeys;
@@ -9,10 +11,40 @@ pub(crate) static AWS_PROFILE: &str = "default";
 pub(crate) static AWS_REGION: &str = "us-east-1";
 
 pub(crate) static JWT_SECRET: Lazy<JwtKeys> = Lazy::new(|| {
-    let secret = fetch::<String>("jwt_secret");
-    JwtKeys::new(secret.as_bytes())
+    let algorithm = fetch::<String>("jwt_algorithm").to_uppercase();
+    match algorithm.as_str() {
+        "" | "HS256" => {
+            let secrets = fetch::<Vec<String>>("jwt_secrets");
+            if secrets.is_empty() {
+                let secret = fetch::<String>("jwt_secret");
+                JwtKeys::new(secret.as_bytes())
+            } else {
+                JwtKeys::from_secrets(&secrets).expect("JWT secrets should not be empty")
+            }
+        }
+        "RS256" => {
+            let private_key = std::fs::read(fetch::<String>("jwt_private_key"))
+                .expect("JWT private key should be readable");
+            let public_key = std::fs::read(fetch::<String>("jwt_public_key"))
+                .expect("JWT public key should be readable");
+            JwtKeys::from_rsa_pem(&private_key, &public_key)
+                .expect("JWT RSA keys should be PEM encoded")
+        }
+        algorithm => panic!(r#"Unsupported JWT algorithm "{}""#, algorithm),
+    }
+});
+
+pub(crate) static JWT_PROVIDER_SECRETS: Lazy<HashMap<String, JwtKeys>> = Lazy::new(|| {
+    fetch::<HashMap<String, String>>("jwt_provider_secrets")
+        .into_iter()
+        .map(|(provider, secret)| (provider, JwtKeys::new(secret.as_bytes())))
+        .collect()
 });
 
+pub(crate) fn jwt_secret(provider: &str) -> &'static JwtKeys {
+    JWT_PROVIDER_SECRETS.get(provider).unwrap_or(&*JWT_SECRET)
+}
+
 pub fn fetch<T>(flag: &str) -> T
 where
     fetcher::Flag<String>: fetcher::Fetch<T>,
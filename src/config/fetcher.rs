@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use config::Config;
 use config::Environment;
 use config::File;
@@ -160,6 +162,28 @@ where
     }
 }
 
+impl<V> Fetch<HashMap<String, String>> for Flag<V>
+where
+    V: std::fmt::Debug + std::fmt::Display,
+{
+    fn fetch(&self, config: &Config) -> HashMap<String, String> {
+        config
+            .get::<HashMap<String, String>>(&self.key.to_string())
+            .unwrap_or_default()
+    }
+}
+
+impl<V> Fetch<Vec<String>> for Flag<V>
+where
+    V: std::fmt::Debug + std::fmt::Display,
+{
+    fn fetch(&self, config: &Config) -> Vec<String> {
+        config
+            .get::<Vec<String>>(&self.key.to_string())
+            .unwrap_or_default()
+    }
+}
+
 mod private {
     pub trait Sealed {}
     impl<V> Sealed for super::Flag<V> {}
@@ -3,6 +3,7 @@ use anyhow::Result;
 use delta_sharing::config;
 use delta_sharing::logging;
 use delta_sharing::server::Server;
+use std::collections::HashMap;
 
 #[tokio::main]
 async fn main() -> Result<()> {
@@ -15,6 +16,11 @@ async fn main() -> Result<()> {
             clap::Command::new("server")
                 .about("Launch the server process")
                 .after_help("The server implements Delta Sharing REST protocol."),
+        )
+        .subcommand(
+            clap::Command::new("hash-tokens")
+                .about("Hash the token values stored in plaintext")
+                .after_help("Run once after upgrading so that tokens issued before values were stored hashed keep working."),
         );
     let args = app.get_matches();
     match args.subcommand().expect("subcommand is required") {
@@ -23,21 +29,84 @@ async fn main() -> Result<()> {
             tracing::info!("delta sharing server is starting");
             tracing::debug!(
                 db_url = config::fetch::<String>("db_url"),
+                db_replica_url = config::fetch::<String>("db_replica_url"),
+                db_max_connections = config::fetch::<String>("db_max_connections"),
+                db_min_connections = config::fetch::<String>("db_min_connections"),
+                db_acquire_timeout_secs = config::fetch::<String>("db_acquire_timeout_secs"),
+                db_statement_timeout_ms = config::fetch::<String>("db_statement_timeout_ms"),
                 server_addr = config::fetch::<String>("server_addr"),
+                public_endpoint = config::fetch::<String>("public_endpoint"),
                 server_bind = config::fetch::<String>("server_bind"),
+                admin_bind = config::fetch::<String>("admin_bind"),
+                sharing_bind = config::fetch::<String>("sharing_bind"),
+                tcp_keep_alive = config::fetch::<u64>("tcp_keep_alive"),
+                http2_keep_alive_interval = config::fetch::<u64>("http2_keep_alive_interval"),
+                http2_keep_alive_timeout = config::fetch::<u64>("http2_keep_alive_timeout"),
+                http2_max_concurrent_streams = config::fetch::<u32>("http2_max_concurrent_streams"),
+                shutdown_timeout_secs = config::fetch::<u64>("shutdown_timeout_secs"),
+                tls_cert_path = config::fetch::<String>("tls_cert_path"),
+                tls_key_path = config::fetch::<String>("tls_key_path"),
+                jwt_algorithm = config::fetch::<String>("jwt_algorithm"),
                 jwt_secret = config::fetch::<String>("jwt_secret"),
+                jwt_secrets = ?config::fetch::<Vec<String>>("jwt_secrets"),
+                jwt_private_key = config::fetch::<String>("jwt_private_key"),
+                jwt_public_key = config::fetch::<String>("jwt_public_key"),
+                jwt_leeway_secs = config::fetch::<u64>("jwt_leeway_secs"),
                 admin_name = config::fetch::<String>("admin_name"),
                 admin_email = config::fetch::<String>("admin_email"),
                 admin_password = config::fetch::<String>("admin_password"),
                 admin_namespace = config::fetch::<String>("admin_namespace"),
                 admin_ttl = config::fetch::<i64>("admin_ttl"),
+                refresh_token_ttl = config::fetch::<i64>("refresh_token_ttl"),
+                profile_min_ttl = config::fetch::<i64>("profile_min_ttl"),
+                profile_max_ttl = config::fetch::<i64>("profile_max_ttl"),
+                profile_refresh_ttl = config::fetch::<i64>("profile_refresh_ttl"),
+                recipient_allowlist_enabled = config::fetch::<bool>("recipient_allowlist_enabled"),
+                recipient_allowlists = ?config::fetch::<HashMap<String, String>>("recipient_allowlists"),
+                idempotency_key_ttl = config::fetch::<i64>("idempotency_key_ttl"),
+                token_hash_secret_set = !config::fetch::<String>("token_hash_secret").is_empty(),
+                page_token_secret_set = !config::fetch::<String>("page_token_secret").is_empty(),
+                token_gc_interval_secs = config::fetch::<u64>("token_gc_interval_secs"),
+                max_page_results = config::fetch::<usize>("max_page_results"),
                 signed_url_ttl = config::fetch::<i64>("signed_url_ttl"),
+                min_signed_url_ttl = config::fetch::<u64>("min_signed_url_ttl"),
+                max_signed_url_ttl = config::fetch::<u64>("max_signed_url_ttl"),
+                signing_retry_attempts = config::fetch::<u32>("signing_retry_attempts"),
+                signing_retry_base_delay_ms = config::fetch::<u64>("signing_retry_base_delay_ms"),
+                signed_url_response_headers = config::fetch::<bool>("signed_url_response_headers"),
+                verify_signed_urls = config::fetch::<bool>("verify_signed_urls"),
+                s3_endpoint = config::fetch::<String>("s3_endpoint"),
+                aws_assume_role_arn = config::fetch::<String>("aws_assume_role_arn"),
+                aws_bucket_profiles = ?config::fetch::<HashMap<String, String>>("aws_bucket_profiles"),
+                gcp_bucket_service_accounts =
+                    ?config::fetch::<HashMap<String, String>>("gcp_bucket_service_accounts"),
+                public_buckets = ?config::fetch::<Vec<String>>("public_buckets"),
+                s3_bucket_signing_styles =
+                    ?config::fetch::<HashMap<String, String>>("s3_bucket_signing_styles"),
+                r2_access_key_id = config::fetch::<String>("r2_access_key_id"),
+                local_tables = config::fetch::<bool>("local_tables"),
+                signed_url_scheme_guard = config::fetch::<String>("signed_url_scheme_guard"),
+                table_schema_check = config::fetch::<String>("table_schema_check"),
+                job_ttl = config::fetch::<u64>("job_ttl"),
+                rate_limit_rps = config::fetch::<u32>("rate_limit_rps"),
+                rate_limit_burst = config::fetch::<u32>("rate_limit_burst"),
+                cors_allowed_origins = config::fetch::<String>("cors_allowed_origins"),
+                cors_allowed_methods = config::fetch::<String>("cors_allowed_methods"),
+                cors_allow_credentials = config::fetch::<bool>("cors_allow_credentials"),
                 use_json_log = config::fetch::<bool>("use_json_log"),
                 log_filter = config::fetch::<String>("log_filter"),
             );
             let server = Server::new().await.context("failed to create server")?;
             server.start().await.context("failed to start server")
         }
+        ("hash-tokens", _args) => {
+            logging::setup();
+            let hashed = Server::hash_plaintext_tokens()
+                .await
+                .context("failed to hash plaintext tokens")?;
+            tracing::info!(hashed, "plaintext tokens were hashed");
+            Ok(())
+        }
         _ => unreachable!("clap should have already checked the subcommands"),
     }
 }
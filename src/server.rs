@@ -8,33 +8,67 @@ pub(crate) mod utilities;
 
 use anyhow::Context;
 use anyhow::Result;
+use rusoto_core::Region;
 use rusoto_credential::AwsCredentials;
 use rusoto_credential::ProvideAwsCredentials;
 use sqlx::PgPool;
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::time::Duration;
 use tame_gcs::signing::ServiceAccount;
 
 use crate::bootstrap;
+use crate::config;
+use crate::server::services::assume_role::Provider as AssumeRoleProvider;
+use crate::server::services::assume_role::StsFetcher;
+use crate::server::utilities::pagination::Utility as PaginationUtility;
+use crate::server::utilities::signed_url::AzureCredentials;
+use crate::server::utilities::signed_url::BucketCredentials;
+use crate::server::utilities::signed_url::SchemeGuard;
+use crate::server::utilities::signed_url::SigningStyle;
+use crate::server::utilities::signed_url::Utility as SignedUrlUtility;
 
 pub use crate::server::middlewares::jwt::Role;
 pub use entities::account::{Entity as AccountEntity, Id as AccountId};
 pub use entities::schema::{Entity as SchemaEntity, Id as SchemaId};
-pub use entities::share::{Entity as ShareEntity, Id as ShareId};
+pub use entities::share::{DefaultTtl as ShareDefaultTtl, Entity as ShareEntity, Id as ShareId};
 pub use entities::table::{Entity as TableEntity, Id as TableId};
-pub use entities::token::{Entity as TokenEntity, Id as TokenId};
+pub use entities::token::{
+    Entity as TokenEntity, Id as TokenId, IdempotencyKey as TokenIdempotencyKey,
+    Value as TokenValue, ValueHash as TokenValueHash,
+};
 pub use repositories::account::Repository as AccountRepository;
 pub use repositories::schema::Repository as SchemaRepository;
 pub use repositories::share::Repository as ShareRepository;
 pub use repositories::table::Repository as TableRepository;
 pub use repositories::token::Repository as TokenRepository;
 pub use services::account::Service as AccountService;
+pub use services::audit::Service as AuditService;
+pub use services::audit::Trace as AuditTrace;
+pub use services::refresh_token::{
+    Exchange as RefreshTokenExchange, Service as RefreshTokenService,
+};
+pub use services::revocation::Service as RevocationService;
 pub use services::schema::Service as SchemaService;
 pub use services::share::Service as ShareService;
 pub use services::table::Service as TableService;
+pub use services::token::Service as TokenService;
+pub use services::token_gc::Collector as TokenCollector;
+pub use utilities::object_reader::{Backend as ObjectBackend, Reader as ObjectReader};
+pub use utilities::pagination::{
+    Cursor as PageCursor, Direction as PageDirection, OrderBy as PageOrderBy,
+    Ordering as PageOrdering,
+};
 
 pub struct Server {
     pg_pool: PgPool,
+    pg_replica_pool: Option<PgPool>,
     gcp_service_account: Option<ServiceAccount>,
     aws_credentials: Option<AwsCredentials>,
+    azure_credentials: Option<AzureCredentials>,
+    r2_credentials: Option<AwsCredentials>,
+    aws_role_provider: Option<AssumeRoleProvider>,
+    bucket_credentials: BucketCredentials,
 }
 
 impl Server {
@@ -42,6 +76,9 @@ impl Server {
         let pg_pool = bootstrap::new_pg_pool()
             .await
             .context("failed to create postgres connection pool")?;
+        let pg_replica_pool = bootstrap::new_pg_replica_pool()
+            .await
+            .context("failed to create postgres replica connection pool")?;
         let gcp_service_account = bootstrap::new_gcp_service_account().ok();
         if gcp_service_account.is_none() {
             tracing::warn!("failed to load GCP service account");
@@ -60,16 +97,125 @@ impl Server {
         if aws_credentials.is_none() {
             tracing::warn!("failed to load AWS credentials");
         }
+        let azure_credentials = bootstrap::new_azure_credentials().ok();
+        if azure_credentials.is_none() {
+            tracing::warn!("failed to load Azure credentials");
+        }
+        let r2_credentials = bootstrap::new_r2_credentials().ok();
+        if r2_credentials.is_none() {
+            tracing::warn!("failed to load Cloudflare R2 credentials");
+        }
+        let aws_role_provider = Self::new_aws_role_provider();
+        if aws_role_provider.is_none() {
+            tracing::warn!("AWS role to assume was not set");
+        }
+        let bucket_credentials = Self::new_bucket_credentials().await;
+        SignedUrlUtility::local_url_secret().context("failed to load local URL secret")?;
+        SchemeGuard::from_config().context("failed to load signed URL scheme guard")?;
+        TokenValue::hash_secret().context("failed to load token hash secret")?;
+        PaginationUtility::page_token_secret().context("failed to load page token secret")?;
         Ok(Server {
             pg_pool,
+            pg_replica_pool,
             gcp_service_account,
             aws_credentials,
+            azure_credentials,
+            r2_credentials,
+            aws_role_provider,
+            bucket_credentials,
         })
     }
 
-    pub async fn start(self) -> Result<()> {
-        routers::bind(self.pg_pool, self.gcp_service_account, self.aws_credentials)
+    /// Hashes the token values stored in plaintext before values were stored
+    /// hashed and returns how many were hashed.
+    pub async fn hash_plaintext_tokens() -> Result<u64> {
+        TokenValue::hash_secret().context("failed to load token hash secret")?;
+        let pg_pool = bootstrap::new_pg_pool()
             .await
-            .context("failed to start API server")
+            .context("failed to create postgres connection pool")?;
+        TokenService::hash_plaintext_values(&pg_pool)
+            .await
+            .context("failed to hash plaintext tokens")
+    }
+
+    async fn new_bucket_credentials() -> BucketCredentials {
+        let mut credentials = BucketCredentials::default();
+        for (bucket, profile) in config::fetch::<HashMap<String, String>>("aws_bucket_profiles") {
+            let Ok(provider) = bootstrap::aws::new(&profile) else {
+                tracing::warn!(
+                    r#"failed to load AWS profile "{}" for "{}""#,
+                    profile,
+                    bucket
+                );
+                continue;
+            };
+            let Ok(aws_credentials) = provider.credentials().await else {
+                tracing::warn!(
+                    r#"failed to load AWS profile "{}" for "{}""#,
+                    profile,
+                    bucket
+                );
+                continue;
+            };
+            credentials.aws.insert(bucket, aws_credentials);
+        }
+        for (bucket, path) in
+            config::fetch::<HashMap<String, String>>("gcp_bucket_service_accounts")
+        {
+            let Ok(gcp_service_account) = bootstrap::gcp::new(&shellexpand::tilde(&path)) else {
+                tracing::warn!(
+                    r#"failed to load GCP service account "{}" for "{}""#,
+                    path,
+                    bucket
+                );
+                continue;
+            };
+            credentials.gcp.insert(bucket, gcp_service_account);
+        }
+        credentials.public = config::fetch::<Vec<String>>("public_buckets")
+            .into_iter()
+            .collect();
+        for (bucket, style) in config::fetch::<HashMap<String, String>>("s3_bucket_signing_styles")
+        {
+            let Ok(signing_style) = SigningStyle::from_str(&style) else {
+                tracing::warn!(
+                    r#"unknown signing style "{}" for "{}", using virtual_host"#,
+                    style,
+                    bucket
+                );
+                continue;
+            };
+            credentials.signing_styles.insert(bucket, signing_style);
+        }
+        credentials
+    }
+
+    fn new_aws_role_provider() -> Option<AssumeRoleProvider> {
+        let role_arn = config::fetch::<String>("aws_assume_role_arn");
+        if role_arn.is_empty() {
+            return None;
+        }
+        let region = std::env::var("AWS_REGION").unwrap_or(config::AWS_REGION.into());
+        let region = Region::from_str(&region).unwrap_or_default();
+        Some(AssumeRoleProvider::new(role_arn, StsFetcher::new(region)))
+    }
+
+    pub async fn start(self) -> Result<()> {
+        TokenCollector::spawn(
+            self.pg_pool.clone(),
+            Duration::from_secs(config::fetch::<u64>("token_gc_interval_secs")),
+        );
+        routers::bind(
+            self.pg_pool,
+            self.pg_replica_pool,
+            self.gcp_service_account,
+            self.aws_credentials,
+            self.azure_credentials,
+            self.r2_credentials,
+            self.aws_role_provider,
+            self.bucket_credentials,
+        )
+        .await
+        .context("failed to start API server")
     }
 }
@@ -1,23 +1,62 @@
 use crate::server::routers::admin;
+use crate::server::routers::health;
+use crate::server::routers::metrics;
 use crate::server::routers::shares;
+use crate::server::routers::sharing;
 use crate::server::services::account;
+use crate::server::services::capability;
 use crate::server::services::error;
+use crate::server::services::job;
 use crate::server::services::profile;
 use crate::server::services::schema;
 use crate::server::services::share;
 use crate::server::services::table;
+use crate::server::services::token;
 use crate::server::utilities::deltalake;
 use crate::server::utilities::json;
+use crate::server::utilities::validation;
+use utoipa::openapi::security::HttpAuthScheme;
+use utoipa::openapi::security::HttpBuilder;
+use utoipa::openapi::security::SecurityScheme;
+use utoipa::Modify;
 use utoipa::OpenApi;
 
+pub const SECURITY_SCHEME: &str = "bearer_token";
+
+pub struct SecurityAddon;
+
+impl Modify for SecurityAddon {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            SECURITY_SCHEME,
+            SecurityScheme::Http(
+                HttpBuilder::new()
+                    .scheme(HttpAuthScheme::Bearer)
+                    .bearer_format("JWT")
+                    .build(),
+            ),
+        );
+    }
+}
+
 #[derive(OpenApi)]
 #[openapi(
     paths(
         admin::login,
+        admin::refresh,
         admin::profile,
+        admin::preview,
+        admin::profiles::batch,
+        admin::tokens::introspect,
+        admin::tokens::revoke,
         admin::accounts::post,
         admin::accounts::get,
         admin::accounts::list,
+        admin::accounts::search,
+        admin::accounts::patch,
+        admin::accounts::delete,
+        admin::accounts::tokens::list,
         admin::shares::post,
         admin::shares::schemas::post,
         admin::shares::schemas::tables::post,
@@ -29,25 +68,42 @@ use utoipa::OpenApi;
         shares::schemas::tables::version::get,
         shares::schemas::tables::metadata::get,
         shares::schemas::tables::query::post,
+        shares::schemas::tables::changes::get,
+        sharing::capabilities,
+        sharing::jobs::get,
+        sharing::profile::refresh,
+        sharing::local::get,
+        health::healthz,
+        health::readyz,
+        metrics::get,
     ),
     components(
 	schemas(
 	    profile::Profile,
 	    account::Account,
+	    capability::Capabilities,
 	    share::Share,
 	    table::Table,
 	    table::TableDetail,
 	    schema::Schema,
 	    schema::SchemaDetail,
+	    token::Token,
 	    error::ErrorMessage,
+	    job::Status,
+	    validation::FieldError,
 	    deltalake::ValueType,
 	    json::OpType,
 	    json::PredicateJson
 	),
-        schemas(admin::AdminLoginRequest, admin::AdminLoginResponse, admin::AdminProfileResponse),
+        schemas(admin::AdminLoginRequest, admin::AdminLoginResponse, admin::AdminRefreshRequest, admin::AdminProfileResponse, admin::AdminProfilePreviewResponse),
+        schemas(admin::profiles::AdminProfilesBatchRequest, admin::profiles::AdminProfilesBatchEntry, admin::profiles::AdminProfilesBatchResponse, admin::profiles::AdminProfilesBatchResult),
+        schemas(admin::tokens::AdminTokensIntrospectRequest, admin::tokens::AdminTokensIntrospectResponse, admin::tokens::AdminTokensRevokeRequest),
         schemas(admin::accounts::AdminAccountsPostRequest, admin::accounts::AdminAccountsPostResponse),
         schemas(admin::accounts::AdminAccountsGetResponse),
+        schemas(admin::accounts::AdminAccountsPatchRequest, admin::accounts::AdminAccountsPatchResponse),
         schemas(admin::accounts::AdminAccountsListResponse),
+        schemas(admin::accounts::AdminAccountsSearchResponse),
+        schemas(admin::accounts::tokens::AdminAccountsTokensListResponse),
         schemas(admin::shares::AdminSharesPostRequest, admin::shares::AdminSharesPostResponse),
         schemas(admin::shares::schemas::AdminSharesSchemasPostRequest, admin::shares::schemas::AdminSharesSchemasPostResponse),
         schemas(admin::shares::schemas::tables::AdminSharesSchemasTablesPostRequest, admin::shares::schemas::tables::AdminSharesSchemasTablesPostResponse),
@@ -56,8 +112,13 @@ use utoipa::OpenApi;
         schemas(shares::all_tables::SharesAllTablesListResponse),
         schemas(shares::schemas::SharesSchemasListResponse),
         schemas(shares::schemas::tables::SharesSchemasTablesListResponse),
-        schemas(shares::schemas::tables::query::SharesSchemasTablesQueryPostRequest),
+        schemas(shares::schemas::tables::query::SharesSchemasTablesQueryPostRequest, shares::schemas::tables::query::SharesSchemasTablesQueryPostAsyncResponse),
+        schemas(sharing::SharingCapabilitiesResponse),
+        schemas(sharing::jobs::SharingJobsGetResponse),
+        schemas(sharing::profile::SharingProfileRefreshResponse),
     ),
+    modifiers(&SecurityAddon),
+    security(("bearer_token" = [])),
     tags(
         (name = "Delta Sharing", description = "Delta Sharing API")
     )
@@ -10,13 +10,16 @@ use getset::Getters;
 use getset::Setters;
 use sqlx::postgres::PgQueryResult;
 use sqlx::PgPool;
+use std::str::FromStr;
 use uuid::Uuid;
 use validator::Validate;
 
 use crate::impl_i64_property;
 use crate::impl_string_property;
 use crate::impl_uuid_property;
+use crate::server::middlewares::jwt::Role;
 use crate::server::repositories::account::Repository;
+use crate::server::utilities::validation::ValidationError;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Id {
@@ -74,6 +77,8 @@ pub struct Entity {
     namespace: Namespace,
     #[getset(get = "pub", set = "pub")]
     ttl: Ttl,
+    #[getset(get = "pub", set = "pub")]
+    role: Role,
 }
 
 fn hash(password: &[u8]) -> Result<String> {
@@ -101,14 +106,77 @@ impl Entity {
         namespace: String,
         ttl: i64,
     ) -> Result<Self> {
-        Ok(Self {
-            id: Id::try_from(id.into().unwrap_or(uuid::Uuid::new_v4().to_string()))?,
-            name: Name::new(name)?,
-            email: Email::new(email)?,
-            password: Password::new(self::hash(password.as_bytes()).unwrap())?,
-            namespace: Namespace::new(namespace)?,
-            ttl: Ttl::new(ttl)?,
-        })
+        let mut errors = ValidationError::default();
+        let id = errors.check(
+            "id",
+            Id::try_from(id.into().unwrap_or(uuid::Uuid::new_v4().to_string())),
+        );
+        let name = errors.check("name", Name::new(name));
+        let email = errors.check("email", Email::new(email));
+        let password = errors.check(
+            "password",
+            Password::new(password)
+                .and_then(|p| self::hash(p.as_str().as_bytes()))
+                .and_then(Password::new),
+        );
+        let namespace = errors.check("namespace", Namespace::new(namespace));
+        let ttl = errors.check("ttl", Ttl::new(ttl));
+        match (id, name, email, password, namespace, ttl) {
+            (Some(id), Some(name), Some(email), Some(password), Some(namespace), Some(ttl)) => {
+                Ok(Self {
+                    id,
+                    name,
+                    email,
+                    password,
+                    namespace,
+                    ttl,
+                    role: Role::Guest,
+                })
+            }
+            _ => Err(errors.into()),
+        }
+    }
+
+    pub fn update(
+        &mut self,
+        name: Option<String>,
+        email: Option<String>,
+        namespace: Option<String>,
+        ttl: Option<i64>,
+        role: Option<String>,
+    ) -> Result<()> {
+        let mut errors = ValidationError::default();
+        let name = name.map(|name| errors.check("name", Name::new(name)));
+        let email = email.map(|email| errors.check("email", Email::new(email)));
+        let namespace =
+            namespace.map(|namespace| errors.check("namespace", Namespace::new(namespace)));
+        let ttl = ttl.map(|ttl| errors.check("ttl", Ttl::new(ttl)));
+        let role = role.map(|role| {
+            errors.check(
+                "role",
+                Role::from_str(&role)
+                    .map_err(|_| anyhow!("role must be guest, moderator or admin")),
+            )
+        });
+        if !errors.fields.is_empty() {
+            return Err(errors.into());
+        }
+        if let Some(Some(name)) = name {
+            self.name = name;
+        }
+        if let Some(Some(email)) = email {
+            self.email = email;
+        }
+        if let Some(Some(namespace)) = namespace {
+            self.namespace = namespace;
+        }
+        if let Some(Some(ttl)) = ttl {
+            self.ttl = ttl;
+        }
+        if let Some(Some(role)) = role {
+            self.role = role;
+        }
+        Ok(())
     }
 
     pub async fn load(name: &Name, pg_pool: &PgPool) -> Result<Option<Self>> {
@@ -120,6 +188,7 @@ impl Entity {
                 password: Password::new(row.password)?,
                 namespace: Namespace::new(row.namespace)?,
                 ttl: Ttl::new(row.ttl)?,
+                role: row.role,
             }
             .into()),
             _ => Ok(None),
@@ -130,6 +199,10 @@ impl Entity {
         Repository::upsert(self, pg_pool).await
     }
 
+    pub async fn resurrect(&self, pg_pool: &PgPool) -> Result<PgQueryResult> {
+        Repository::resurrect(self, pg_pool).await
+    }
+
     pub fn verify(&self, password: &[u8]) -> Result<()> {
         self::verify(password, self.password().as_str())
     }
@@ -198,4 +271,84 @@ mod tests {
     fn test_invalid_ttl() {
         assert!(Ttl::new(testutils::rand::i64(-100000, -1)).is_err());
     }
+
+    #[test]
+    fn test_invalid_entity() {
+        let error = Entity::new(
+            None,
+            String::from(""),
+            testutils::rand::string(20),
+            testutils::rand::string(10),
+            String::from(""),
+            testutils::rand::i64(-100000, -1),
+        )
+        .expect_err("malformed account should not be created");
+        let error = error
+            .downcast::<ValidationError>()
+            .expect("validation error should be returned");
+        assert_eq!(error.names(), vec!["name", "email", "namespace", "ttl"]);
+    }
+
+    fn new_entity() -> Entity {
+        Entity::new(
+            None,
+            testutils::rand::string(10),
+            testutils::rand::email(),
+            testutils::rand::string(10),
+            testutils::rand::string(10),
+            testutils::rand::i64(0, 100000),
+        )
+        .expect("account should be created properly")
+    }
+
+    #[test]
+    fn test_partial_update() {
+        let mut account = new_entity();
+        let original = account.clone();
+        let email = testutils::rand::email();
+        account
+            .update(None, Some(email.clone()), None, None, None)
+            .expect("account should be updated properly");
+        assert_eq!(account.email().as_str(), email);
+        assert_eq!(account.id(), original.id());
+        assert_eq!(account.name(), original.name());
+        assert_eq!(account.password(), original.password());
+        assert_eq!(account.namespace(), original.namespace());
+        assert_eq!(account.ttl(), original.ttl());
+        assert_eq!(account.role(), original.role());
+    }
+
+    #[test]
+    fn test_default_role() {
+        assert_eq!(new_entity().role(), &Role::Guest);
+    }
+
+    #[test]
+    fn test_promote() {
+        let mut account = new_entity();
+        account
+            .update(None, None, None, None, Some(String::from("Admin")))
+            .expect("account should be promoted properly");
+        assert_eq!(account.role(), &Role::Admin);
+    }
+
+    #[test]
+    fn test_invalid_update() {
+        let mut account = new_entity();
+        let original = account.clone();
+        let error = account
+            .update(
+                Some(testutils::rand::string(10)),
+                Some(testutils::rand::string(20)),
+                None,
+                Some(testutils::rand::i64(-100000, -1)),
+                Some(testutils::rand::string(10)),
+            )
+            .expect_err("malformed update should be rejected");
+        let error = error
+            .downcast::<ValidationError>()
+            .expect("validation error should be returned");
+        assert_eq!(error.names(), vec!["email", "ttl", "role"]);
+        assert_eq!(account, original);
+    }
 }
@@ -11,6 +11,7 @@ use crate::impl_uuid_property;
 use crate::server::entities::account::Id as AccountId;
 use crate::server::entities::share::Id as ShareId;
 use crate::server::repositories::schema::Repository;
+use crate::server::utilities::validation::ValidationError;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Id {
@@ -45,12 +46,23 @@ impl Entity {
         share_id: String,
         created_by: String,
     ) -> Result<Self> {
-        Ok(Self {
-            id: Id::try_from(id.into().unwrap_or(uuid::Uuid::new_v4().to_string()))?,
-            name: Name::new(name)?,
-            share_id: ShareId::try_from(share_id)?,
-            created_by: AccountId::try_from(created_by)?,
-        })
+        let mut errors = ValidationError::default();
+        let id = errors.check(
+            "id",
+            Id::try_from(id.into().unwrap_or(uuid::Uuid::new_v4().to_string())),
+        );
+        let name = errors.check("name", Name::new(name));
+        let share_id = errors.check("shareId", ShareId::try_from(share_id));
+        let created_by = errors.check("createdBy", AccountId::try_from(created_by));
+        match (id, name, share_id, created_by) {
+            (Some(id), Some(name), Some(share_id), Some(created_by)) => Ok(Self {
+                id,
+                name,
+                share_id,
+                created_by,
+            }),
+            _ => Err(errors.into()),
+        }
     }
 
     pub async fn load(share_id: &ShareId, name: &Name, pg_pool: &PgPool) -> Result<Option<Self>> {
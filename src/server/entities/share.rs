@@ -6,10 +6,12 @@ use sqlx::PgPool;
 use uuid::Uuid;
 use validator::Validate;
 
+use crate::impl_i64_property;
 use crate::impl_string_property;
 use crate::impl_uuid_property;
 use crate::server::entities::account::Id as AccountId;
 use crate::server::repositories::share::Repository;
+use crate::server::utilities::validation::ValidationError;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Id {
@@ -22,8 +24,15 @@ pub struct Name {
     value: String,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, Validate)]
+pub struct DefaultTtl {
+    #[validate(range(min = 1))]
+    value: i64,
+}
+
 impl_uuid_property!(Id);
 impl_string_property!(Name);
+impl_i64_property!(DefaultTtl);
 
 #[derive(Debug, Clone, PartialEq, Eq, Getters, Setters)]
 pub struct Entity {
@@ -33,15 +42,28 @@ pub struct Entity {
     name: Name,
     #[getset(get = "pub")]
     created_by: AccountId,
+    #[getset(get = "pub", set = "pub")]
+    default_ttl: Option<DefaultTtl>,
 }
 
 impl Entity {
     pub fn new(id: impl Into<Option<String>>, name: String, created_by: String) -> Result<Self> {
-        Ok(Self {
-            id: Id::try_from(id.into().unwrap_or(uuid::Uuid::new_v4().to_string()))?,
-            name: Name::new(name)?,
-            created_by: AccountId::try_from(created_by)?,
-        })
+        let mut errors = ValidationError::default();
+        let id = errors.check(
+            "id",
+            Id::try_from(id.into().unwrap_or(uuid::Uuid::new_v4().to_string())),
+        );
+        let name = errors.check("name", Name::new(name));
+        let created_by = errors.check("createdBy", AccountId::try_from(created_by));
+        match (id, name, created_by) {
+            (Some(id), Some(name), Some(created_by)) => Ok(Self {
+                id,
+                name,
+                created_by,
+                default_ttl: None,
+            }),
+            _ => Err(errors.into()),
+        }
     }
 
     pub async fn load(name: &Name, pg_pool: &PgPool) -> Result<Option<Self>> {
@@ -50,6 +72,7 @@ impl Entity {
                 id: Id::new(row.id),
                 name: Name::new(row.name)?,
                 created_by: AccountId::new(row.created_by),
+                default_ttl: row.default_ttl.map(DefaultTtl::new).transpose()?,
             }
             .into()),
             _ => Ok(None),
@@ -84,4 +107,15 @@ mod tests {
     fn test_invalid_name() {
         assert!(Name::new("").is_err());
     }
+
+    #[test]
+    fn test_valid_default_ttl() {
+        assert!(DefaultTtl::new(testutils::rand::i64(1, 100000)).is_ok());
+    }
+
+    #[test]
+    fn test_invalid_default_ttl() {
+        assert!(DefaultTtl::new(0).is_err());
+        assert!(DefaultTtl::new(-testutils::rand::i64(1, 100000)).is_err());
+    }
 }
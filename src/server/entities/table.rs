@@ -1,6 +1,8 @@
 use anyhow::Result;
 use getset::Getters;
 use getset::Setters;
+use sha2::Digest;
+use sha2::Sha256;
 use sqlx::postgres::PgQueryResult;
 use sqlx::PgPool;
 use uuid::Uuid;
@@ -11,6 +13,7 @@ use crate::impl_uuid_property;
 use crate::server::entities::account::Id as AccountId;
 use crate::server::entities::schema::Id as SchemaId;
 use crate::server::repositories::table::Repository;
+use crate::server::utilities::validation::ValidationError;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Id {
@@ -45,6 +48,10 @@ pub struct Entity {
     location: Location,
     #[getset(get = "pub")]
     created_by: AccountId,
+    #[getset(get = "pub")]
+    schema_string: Option<String>,
+    #[getset(get = "pub")]
+    schema_hash: Option<String>,
 }
 
 impl Entity {
@@ -55,13 +62,38 @@ impl Entity {
         location: String,
         created_by: String,
     ) -> Result<Self> {
-        Ok(Self {
-            id: Id::try_from(id.into().unwrap_or(uuid::Uuid::new_v4().to_string()))?,
-            name: Name::new(name)?,
-            schema_id: SchemaId::try_from(schema_id)?,
-            location: Location::new(location)?,
-            created_by: AccountId::try_from(created_by)?,
-        })
+        let mut errors = ValidationError::default();
+        let id = errors.check(
+            "id",
+            Id::try_from(id.into().unwrap_or(uuid::Uuid::new_v4().to_string())),
+        );
+        let name = errors.check("name", Name::new(name));
+        let schema_id = errors.check("schemaId", SchemaId::try_from(schema_id));
+        let location = errors.check("location", Location::new(location));
+        let created_by = errors.check("createdBy", AccountId::try_from(created_by));
+        match (id, name, schema_id, location, created_by) {
+            (Some(id), Some(name), Some(schema_id), Some(location), Some(created_by)) => Ok(Self {
+                id,
+                name,
+                schema_id,
+                location,
+                created_by,
+                schema_string: None,
+                schema_hash: None,
+            }),
+            _ => Err(errors.into()),
+        }
+    }
+
+    /// Returns the hex SHA-256 of a Delta `schemaString`.
+    pub fn schema_hash_of(schema_string: &str) -> String {
+        format!("{:x}", Sha256::digest(schema_string.as_bytes()))
+    }
+
+    /// Records the Delta schema seen at registration along with its hash.
+    pub fn set_schema_string(&mut self, schema_string: Option<String>) {
+        self.schema_hash = schema_string.as_deref().map(Self::schema_hash_of);
+        self.schema_string = schema_string;
     }
 
     pub async fn load(schema_id: &SchemaId, name: &Name, pg_pool: &PgPool) -> Result<Option<Self>> {
@@ -72,6 +104,8 @@ impl Entity {
                 schema_id: SchemaId::new(row.schema_id),
                 location: Location::new(row.location)?,
                 created_by: AccountId::new(row.created_by),
+                schema_string: row.schema_string,
+                schema_hash: row.schema_hash,
             }
             .into()),
             _ => Ok(None),
@@ -112,8 +146,47 @@ mod tests {
         assert!(Location::new(testutils::rand::string(255)).is_ok());
     }
 
+    #[test]
+    fn test_verbatim_location() {
+        for scheme in ["s3", "gs"] {
+            let url = format!(
+                "{}://{}/{}",
+                scheme,
+                testutils::rand::string(10),
+                testutils::rand::string(10)
+            );
+            let location = Location::new(url.clone()).expect("location should be created");
+            assert_eq!(location.as_str(), url);
+        }
+    }
+
     #[test]
     fn test_invalid_location() {
         assert!(Location::new("").is_err());
     }
+
+    #[test]
+    fn test_set_schema_string() {
+        let mut table = Entity::new(
+            None,
+            testutils::rand::string(10),
+            testutils::rand::uuid(),
+            testutils::rand::string(10),
+            testutils::rand::uuid(),
+        )
+        .expect("table should be created properly");
+        assert_eq!(table.schema_hash(), &None);
+        let schema_string = testutils::rand::string(100);
+        table.set_schema_string(Some(schema_string.clone()));
+        assert_eq!(table.schema_string(), &Some(schema_string.clone()));
+        let hash = table
+            .schema_hash()
+            .clone()
+            .expect("schema hash should be recorded");
+        assert_eq!(hash.len(), 64);
+        assert_eq!(hash, Entity::schema_hash_of(&schema_string));
+        assert_ne!(hash, Entity::schema_hash_of(&testutils::rand::string(100)));
+        table.set_schema_string(None);
+        assert_eq!(table.schema_hash(), &None);
+    }
 }
@@ -1,16 +1,28 @@
+use anyhow::anyhow;
+use anyhow::Context;
 use anyhow::Result;
+use chrono::DateTime;
+use chrono::TimeZone;
+use chrono::Utc;
 use getset::Getters;
 use getset::Setters;
+use hmac::Hmac;
+use hmac::Mac;
+use sha2::Sha256;
 use sqlx::postgres::PgQueryResult;
 use sqlx::PgPool;
 use uuid::Uuid;
 use validator::Validate;
 
+use crate::config;
 use crate::impl_string_property;
 use crate::impl_uuid_property;
 use crate::server::entities::account::Id as AccountId;
+use crate::server::middlewares::jwt;
 use crate::server::middlewares::jwt::Role;
 use crate::server::repositories::token::Repository;
+use crate::server::repositories::token::Row;
+use crate::server::utilities::validation::ValidationError;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Id {
@@ -29,9 +41,54 @@ pub struct Value {
     value: String,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, Validate)]
+pub struct ValueHash {
+    #[validate(length(min = 1))]
+    value: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Validate)]
+pub struct IdempotencyKey {
+    #[validate(length(min = 1, max = 255))]
+    value: String,
+}
+
 impl_uuid_property!(Id);
 impl_string_property!(Email);
 impl_string_property!(Value);
+impl_string_property!(ValueHash);
+impl_string_property!(IdempotencyKey);
+
+impl Value {
+    /// Returns the key bearer values are hashed with. It is kept apart from the
+    /// JWT signing secrets so that rotating those does not orphan stored tokens.
+    pub fn hash_secret() -> Result<String> {
+        let secret = config::fetch::<String>("token_hash_secret");
+        if secret.is_empty() {
+            return Err(anyhow!("token_hash_secret must be set"));
+        }
+        Ok(secret)
+    }
+
+    /// Hashes the bearer value with HMAC-SHA256 keyed by `token_hash_secret`, so
+    /// that issued tokens can be looked up without being stored in plaintext.
+    /// Changing `token_hash_secret` orphans the tokens stored before the change.
+    pub fn hash(&self) -> ValueHash {
+        let secret = config::fetch::<String>("token_hash_secret");
+        let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes())
+            .expect("HMAC should accept keys of any size");
+        mac.update(self.as_str().as_bytes());
+        ValueHash {
+            value: format!("{:x}", mac.finalize().into_bytes()),
+        }
+    }
+
+    /// Returns the expiration embedded in the value, if it is a JWT.
+    pub fn expires_at(&self) -> Option<DateTime<Utc>> {
+        let exp = jwt::decode_expiry(self.as_str()).ok()?;
+        Utc.timestamp_opt(exp, 0).single()
+    }
+}
 
 #[derive(Debug, Clone, PartialEq, Eq, Getters, Setters)]
 pub struct Entity {
@@ -41,10 +98,14 @@ pub struct Entity {
     email: Email,
     #[getset(get = "pub", set = "pub")]
     role: Role,
-    #[getset(get = "pub", set = "pub")]
-    value: Value,
+    #[getset(get = "pub")]
+    value_hash: ValueHash,
+    #[getset(get = "pub")]
+    expires_at: Option<DateTime<Utc>>,
     #[getset(get = "pub")]
     created_by: AccountId,
+    #[getset(get = "pub", set = "pub")]
+    idempotency_key: Option<IdempotencyKey>,
 }
 
 impl Entity {
@@ -55,18 +116,75 @@ impl Entity {
         value: String,
         created_by: String,
     ) -> Result<Self> {
+        let mut errors = ValidationError::default();
+        let id = errors.check(
+            "id",
+            Id::try_from(id.into().unwrap_or(uuid::Uuid::new_v4().to_string())),
+        );
+        let email = errors.check("email", Email::new(email));
+        let value = errors.check("value", Value::new(value));
+        let created_by = errors.check("createdBy", AccountId::try_from(created_by));
+        match (id, email, value, created_by) {
+            (Some(id), Some(email), Some(value), Some(created_by)) => Ok(Self {
+                id,
+                email,
+                role,
+                value_hash: value.hash(),
+                expires_at: value.expires_at(),
+                created_by,
+                idempotency_key: None,
+            }),
+            _ => Err(errors.into()),
+        }
+    }
+
+    fn from_row(row: Row) -> Result<Self> {
         Ok(Self {
-            id: Id::try_from(id.into().unwrap_or(uuid::Uuid::new_v4().to_string()))?,
-            email: Email::new(email)?,
-            role,
-            value: Value::new(value)?,
-            created_by: AccountId::try_from(created_by)?,
+            id: Id::new(row.id),
+            email: Email::new(row.email)?,
+            role: row.role,
+            value_hash: ValueHash::new(row.value_hash)?,
+            expires_at: row.expires_at,
+            created_by: AccountId::new(row.created_by),
+            idempotency_key: row.idempotency_key.map(IdempotencyKey::new).transpose()?,
         })
     }
 
+    pub async fn load_by_idempotency_key(
+        key: &IdempotencyKey,
+        window_secs: i64,
+        pg_pool: &PgPool,
+    ) -> Result<Option<Self>> {
+        match Repository::select_by_idempotency_key(key, window_secs, pg_pool).await? {
+            Some(row) => Self::from_row(row).map(Some),
+            _ => Ok(None),
+        }
+    }
+
+    pub async fn load_by_value(value: &Value, pg_pool: &PgPool) -> Result<Option<Self>> {
+        match Repository::select_by_value_hash(&value.hash(), pg_pool).await? {
+            Some(row) => Self::from_row(row).map(Some),
+            _ => Ok(None),
+        }
+    }
+
     pub async fn save(&self, pg_pool: &PgPool) -> Result<PgQueryResult> {
         Repository::upsert(self, pg_pool).await
     }
+
+    /// Saves all `tokens` in a single transaction; nothing is saved when any of them fails.
+    pub async fn save_all(tokens: &[Self], pg_pool: &PgPool) -> Result<()> {
+        let mut tx = pg_pool
+            .begin()
+            .await
+            .context("failed to begin postgres transaction")?;
+        for token in tokens {
+            Repository::upsert(token, &mut *tx).await?;
+        }
+        tx.commit()
+            .await
+            .context("failed to commit postgres transaction")
+    }
 }
 
 #[cfg(test)]
@@ -102,4 +220,24 @@ mod tests {
     fn test_invalid_value() {
         assert!(Value::new("").is_err());
     }
+
+    #[test]
+    fn test_value_hash() {
+        let value = Value::new(testutils::rand::string(255)).expect("value should be created");
+        let other = Value::new(testutils::rand::string(255)).expect("value should be created");
+        assert_ne!(value.hash().as_str(), value.as_str());
+        assert_eq!(value.hash(), value.hash());
+        assert_ne!(value.hash(), other.hash());
+    }
+
+    #[test]
+    fn test_valid_idempotency_key() {
+        assert!(IdempotencyKey::new(testutils::rand::string(255)).is_ok());
+    }
+
+    #[test]
+    fn test_invalid_idempotency_key() {
+        assert!(IdempotencyKey::new("").is_err());
+        assert!(IdempotencyKey::new(testutils::rand::string(256)).is_err());
+    }
 }
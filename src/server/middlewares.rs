@@ -1 +1,8 @@
+pub mod accept;
+pub mod correlation;
+pub mod in_flight;
 pub mod jwt;
+pub mod metrics;
+pub mod propagation;
+pub mod rate_limit;
+pub mod request_id;
@@ -0,0 +1,169 @@
+use axum::body::boxed;
+use axum::body::Body;
+use axum::http::header;
+use axum::http::header::HeaderValue;
+use axum::http::Request;
+use axum::middleware::Next;
+use axum::response::Response;
+
+use crate::server::services::error::ErrorMessage;
+use crate::server::services::error::PlainMessage;
+
+const JSON_RANGES: [&str; 3] = ["application/json", "application/*", "*/*"];
+
+const TEXT_RANGES: [&str; 2] = ["text/plain", "text/*"];
+
+/// Returns true when the client accepts plain text but not JSON; any other
+/// `Accept` header, including a missing one, falls back to JSON.
+fn prefers_text(accept: Option<&HeaderValue>) -> bool {
+    let Some(accept) = accept.and_then(|accept| accept.to_str().ok()) else {
+        return false;
+    };
+    let ranges: Vec<String> = accept
+        .split(',')
+        .filter_map(|range| {
+            let mut params = range.split(';');
+            let media = params.next()?.trim().to_ascii_lowercase();
+            let rejected = params.any(|param| {
+                param
+                    .trim()
+                    .strip_prefix("q=")
+                    .and_then(|q| q.trim().parse::<f32>().ok())
+                    == Some(0.0)
+            });
+            (!rejected).then_some(media)
+        })
+        .collect();
+    let accepts = |candidates: &[&str]| {
+        ranges
+            .iter()
+            .any(|range| candidates.contains(&range.as_str()))
+    };
+    accepts(&TEXT_RANGES) && !accepts(&JSON_RANGES)
+}
+
+pub async fn negotiate<T>(request: Request<T>, next: Next<T>) -> Response {
+    let text = self::prefers_text(request.headers().get(header::ACCEPT));
+    let response = next.run(request).await;
+    if !text {
+        return response;
+    }
+    let Some(PlainMessage(message)) = response.extensions().get::<PlainMessage>().cloned() else {
+        return response;
+    };
+    // NOTE: Only the body and its headers are replaced so that the status code is the
+    //       same whichever format is negotiated.
+    let (mut parts, _) = response.into_parts();
+    parts.headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("text/plain; charset=utf-8"),
+    );
+    parts.headers.remove(header::CONTENT_LENGTH);
+    parts.extensions.remove::<ErrorMessage>();
+    Response::from_parts(parts, boxed(Body::from(message)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::services::error::Error;
+    use axum::http::StatusCode;
+    use axum::middleware;
+    use axum::routing::get;
+    use axum::Router;
+    use tower::ServiceExt;
+
+    fn app() -> Router {
+        Router::new()
+            .route("/", get(|| async { "ok" }))
+            .route(
+                "/error",
+                get(|| async { Err::<String, Error>(Error::NotFound) }),
+            )
+            .layer(middleware::from_fn(negotiate))
+    }
+
+    async fn get_with_accept(uri: &str, accept: Option<&str>) -> Response {
+        let mut request = Request::builder().uri(uri);
+        if let Some(accept) = accept {
+            request = request.header(header::ACCEPT, accept);
+        }
+        app()
+            .oneshot(
+                request
+                    .body(Body::empty())
+                    .expect("request should be built properly"),
+            )
+            .await
+            .expect("request should be handled properly")
+    }
+
+    fn content_type_of(response: &Response) -> String {
+        response
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .expect("content type should be returned")
+            .to_owned()
+    }
+
+    #[test]
+    fn test_prefers_text() {
+        let prefers = |accept: &str| {
+            prefers_text(Some(
+                &HeaderValue::from_str(accept).expect("header value should be parsed"),
+            ))
+        };
+        assert!(!prefers_text(None));
+        assert!(prefers("text/plain"));
+        assert!(prefers("text/*"));
+        assert!(prefers("Text/Plain; charset=utf-8"));
+        assert!(!prefers("application/json"));
+        assert!(!prefers("*/*"));
+        assert!(!prefers("text/plain, application/json"));
+        assert!(prefers("text/plain, application/json;q=0"));
+        assert!(!prefers("application/x-ndjson"));
+    }
+
+    #[tokio::test]
+    async fn test_error_as_json() {
+        let response = get_with_accept("/error", Some("application/json")).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        assert!(content_type_of(&response).starts_with("application/json"));
+        let body = hyper::body::to_bytes(response.into_body())
+            .await
+            .expect("response body should be read properly");
+        let body: serde_json::Value =
+            serde_json::from_slice(&body).expect("response body should be JSON");
+        assert_eq!(body["errorCode"], "RESOURCE_DOES_NOT_EXIST");
+        assert_eq!(body["message"], "Not found");
+    }
+
+    #[tokio::test]
+    async fn test_error_as_text() {
+        let response = get_with_accept("/error", Some("text/plain")).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        assert!(content_type_of(&response).starts_with("text/plain"));
+        let body = hyper::body::to_bytes(response.into_body())
+            .await
+            .expect("response body should be read properly");
+        assert_eq!(body, "Not found");
+    }
+
+    #[tokio::test]
+    async fn test_error_without_accept() {
+        let response = get_with_accept("/error", None).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        assert!(content_type_of(&response).starts_with("application/json"));
+    }
+
+    #[tokio::test]
+    async fn test_success_is_untouched() {
+        let response = get_with_accept("/", Some("text/plain")).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = hyper::body::to_bytes(response.into_body())
+            .await
+            .expect("response body should be read properly");
+        assert_eq!(body, "ok");
+    }
+}
@@ -0,0 +1,106 @@
+use axum::http::header::HeaderName;
+use axum::http::Request;
+use axum::middleware::Next;
+use axum::response::Response;
+use tracing::Span;
+
+use crate::server::middlewares::propagation;
+use crate::server::middlewares::propagation::Header;
+use crate::server::middlewares::propagation::Id;
+
+pub const HEADER_NAME: &str = "x-correlation-id";
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum CorrelationHeader {}
+
+impl Header for CorrelationHeader {
+    const NAME: &'static str = HEADER_NAME;
+
+    fn span(id: &str) -> Span {
+        tracing::info_span!("correlation", correlation_id = %id)
+    }
+}
+
+pub type CorrelationId = Id<CorrelationHeader>;
+
+pub fn header_name() -> HeaderName {
+    propagation::header_name::<CorrelationHeader>()
+}
+
+pub async fn propagate<T>(request: Request<T>, next: Next<T>) -> Response {
+    // NOTE: The id is issued at login when the client does not send one yet, and the
+    //       client is expected to echo it back on the following profile requests.
+    propagation::propagate::<CorrelationHeader, T>(request, next).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::extract::Extension;
+    use axum::middleware;
+    use axum::routing::get;
+    use axum::Router;
+    use tower::ServiceExt;
+    use uuid::Uuid;
+
+    fn app() -> Router {
+        Router::new()
+            .route(
+                "/",
+                get(
+                    |Extension(correlation): Extension<CorrelationId>| async move {
+                        correlation.to_string()
+                    },
+                ),
+            )
+            .layer(middleware::from_fn(propagate))
+    }
+
+    #[tokio::test]
+    async fn test_issue_correlation_id() {
+        let response = app()
+            .oneshot(
+                Request::builder()
+                    .uri("/")
+                    .body(Body::empty())
+                    .expect("request should be built properly"),
+            )
+            .await
+            .expect("request should be handled properly");
+        let header = response
+            .headers()
+            .get(HEADER_NAME)
+            .expect("correlation id should be returned")
+            .to_str()
+            .expect("correlation id should be ASCII")
+            .to_owned();
+        assert!(Uuid::parse_str(&header).is_ok());
+        let body = hyper::body::to_bytes(response.into_body())
+            .await
+            .expect("response body should be read properly");
+        assert_eq!(body, header.as_bytes());
+    }
+
+    #[tokio::test]
+    async fn test_echo_correlation_id() {
+        let correlation = testutils::rand::uuid();
+        let response = app()
+            .oneshot(
+                Request::builder()
+                    .uri("/")
+                    .header(HEADER_NAME, &correlation)
+                    .body(Body::empty())
+                    .expect("request should be built properly"),
+            )
+            .await
+            .expect("request should be handled properly");
+        assert_eq!(
+            response
+                .headers()
+                .get(HEADER_NAME)
+                .and_then(|v| v.to_str().ok()),
+            Some(correlation.as_str())
+        );
+    }
+}
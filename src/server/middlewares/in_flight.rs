@@ -0,0 +1,34 @@
+use axum::http::Request;
+use axum::middleware::Next;
+use axum::response::Response;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+
+static IN_FLIGHT: AtomicUsize = AtomicUsize::new(0);
+
+struct Guard;
+
+impl Guard {
+    fn new() -> Self {
+        IN_FLIGHT.fetch_add(1, Ordering::SeqCst);
+        Self
+    }
+}
+
+impl Drop for Guard {
+    fn drop(&mut self) {
+        IN_FLIGHT.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Returns the number of requests currently being handled.
+pub fn count() -> usize {
+    IN_FLIGHT.load(Ordering::SeqCst)
+}
+
+pub async fn track<T>(request: Request<T>, next: Next<T>) -> Response {
+    // NOTE: The guard also decrements the counter when the client goes away and
+    //       the handler future is dropped before completion.
+    let _guard = Guard::new();
+    next.run(request).await
+}
@@ -1,9 +1,14 @@
-use crate::config::JWT_SECRET;
+use crate::config;
 use crate::server::entities::account::Entity as AccountEntity;
 use crate::server::entities::account::Name as AccountName;
+use crate::server::middlewares::request_id::RequestId;
 use crate::server::routers::SharedState;
 use crate::server::services::error::Error;
+use crate::server::services::metrics::Service as MetricsService;
+use crate::server::services::revocation::Service as RevocationService;
 use anyhow::anyhow;
+use anyhow::Context;
+use anyhow::Result;
 use axum::headers::authorization::Bearer;
 use axum::headers::Authorization;
 use axum::headers::HeaderMapExt;
@@ -11,11 +16,15 @@ use axum::http::Request;
 use axum::middleware::Next;
 use axum::response::Response;
 use jsonwebtoken::decode;
+use jsonwebtoken::errors::ErrorKind;
+use jsonwebtoken::Algorithm;
 use jsonwebtoken::DecodingKey;
 use jsonwebtoken::EncodingKey;
+use jsonwebtoken::Header;
 use jsonwebtoken::Validation;
+use tracing::Instrument;
 
-#[derive(Debug, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Claims {
     pub name: String,
@@ -23,14 +32,22 @@ pub struct Claims {
     pub namespace: String,
     pub role: Role,
     pub exp: i64,
+    /// Unique id of a refreshed token, which could otherwise sign exactly like the
+    /// token it replaces.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub jti: Option<String>,
 }
 
+// NOTE: The variants are declared in ascending order of privilege so that the derived
+//       ordering yields Admin > Moderator > Guest.
 #[derive(
     Debug,
     Copy,
     Clone,
     PartialEq,
     Eq,
+    PartialOrd,
+    Ord,
     serde::Serialize,
     serde::Deserialize,
     sqlx::Type,
@@ -40,16 +57,19 @@ pub struct Claims {
 #[sqlx(rename_all = "lowercase")]
 #[sqlx(type_name = "VARCHAR")]
 pub enum Role {
-    #[strum(ascii_case_insensitive)]
-    Admin,
     #[strum(ascii_case_insensitive)]
     Guest,
+    #[strum(ascii_case_insensitive)]
+    Moderator,
+    #[strum(ascii_case_insensitive)]
+    Admin,
 }
 
 impl AsRef<str> for Role {
     fn as_ref(&self) -> &str {
         match self {
             Role::Admin => "admin",
+            Role::Moderator => "moderator",
             Role::Guest => "guest",
         }
     }
@@ -61,18 +81,140 @@ impl std::fmt::Display for Role {
     }
 }
 
+const REQUIRED_ROLES: &[(&str, Role)] =
+    &[("/admin/shares", Role::Moderator), ("/admin", Role::Admin)];
+
+fn required_role_of(path: &str) -> Role {
+    REQUIRED_ROLES
+        .iter()
+        .find(|(prefix, _)| path.starts_with(prefix))
+        .map(|(_, role)| *role)
+        .unwrap_or(Role::Admin)
+}
+
 pub struct Keys {
+    pub algorithm: Algorithm,
     pub encoding: EncodingKey,
     pub decoding: DecodingKey,
+    pub retiring: Vec<DecodingKey>,
 }
 
 impl Keys {
     pub fn new(secret: &[u8]) -> Self {
         Self {
+            algorithm: Algorithm::HS256,
             encoding: EncodingKey::from_secret(secret),
             decoding: DecodingKey::from_secret(secret),
+            retiring: Vec::new(),
         }
     }
+
+    /// Signs with the first of `secrets` and verifies with any of them, so
+    /// that a new secret can be rolled out before the old one is retired.
+    pub fn from_secrets(secrets: &[String]) -> Result<Self> {
+        let Some((secret, retiring)) = secrets.split_first() else {
+            return Err(anyhow!("at least one JWT secret is required"));
+        };
+        Ok(Self {
+            retiring: retiring
+                .iter()
+                .map(|secret| DecodingKey::from_secret(secret.as_bytes()))
+                .collect(),
+            ..Self::new(secret.as_bytes())
+        })
+    }
+
+    pub fn from_rsa_pem(private_key: &[u8], public_key: &[u8]) -> Result<Self> {
+        Ok(Self {
+            algorithm: Algorithm::RS256,
+            encoding: EncodingKey::from_rsa_pem(private_key)
+                .context("failed to parse RSA private key")?,
+            decoding: DecodingKey::from_rsa_pem(public_key)
+                .context("failed to parse RSA public key")?,
+            retiring: Vec::new(),
+        })
+    }
+
+    pub fn header(&self) -> Header {
+        Header::new(self.algorithm)
+    }
+
+    pub fn validation(&self) -> Validation {
+        self.validation_with_leeway(config::fetch::<u64>("jwt_leeway_secs"))
+    }
+
+    /// Tolerates `leeway` seconds of clock skew between the issuer and this server
+    /// when checking the expiration.
+    pub fn validation_with_leeway(&self, leeway: u64) -> Validation {
+        let mut validation = Validation::new(self.algorithm);
+        validation.leeway = leeway;
+        validation
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct Expiry {
+    exp: i64,
+}
+
+fn unverified_validation() -> Validation {
+    let mut validation = Validation::default();
+    validation.insecure_disable_signature_validation();
+    validation.validate_exp = false;
+    validation
+}
+
+/// Reads the expiration of `token` without verifying it. This is only meant for
+/// housekeeping of tokens this server stored itself, never for authentication.
+pub fn decode_expiry(token: &str) -> Result<i64> {
+    let unverified = decode::<Expiry>(
+        token,
+        &DecodingKey::from_secret(&[]),
+        &self::unverified_validation(),
+    )
+    .context("failed to decode JWT expiration")?;
+    Ok(unverified.claims.exp)
+}
+
+fn verify<'a>(token: &str, keys: impl Fn(&str) -> &'a Keys) -> Result<Claims> {
+    self::verify_with_leeway(token, keys, config::fetch::<u64>("jwt_leeway_secs"))
+}
+
+fn verify_with_leeway<'a>(
+    token: &str,
+    keys: impl Fn(&str) -> &'a Keys,
+    leeway: u64,
+) -> Result<Claims> {
+    // NOTE: The provider is read from the unverified claims only to select the secret;
+    //       the claims are trusted after being verified against that secret.
+    let unverified = decode::<Claims>(
+        token,
+        &DecodingKey::from_secret(&[]),
+        &self::unverified_validation(),
+    )
+    .context("failed to decode JWT claims")?;
+    let keys = keys(&unverified.claims.namespace);
+    // NOTE: The signature is checked before the expiration, so expired tokens are
+    //       only reported as such once they are known to be signed by us.
+    let validation = keys.validation_with_leeway(leeway);
+    let mut verified = decode::<Claims>(token, &keys.decoding, &validation);
+    for decoding in &keys.retiring {
+        match &verified {
+            Err(e) if *e.kind() == ErrorKind::InvalidSignature => {
+                verified = decode::<Claims>(token, decoding, &validation);
+            }
+            _ => break,
+        }
+    }
+    let verified = verified.map_err(|e| match e.kind() {
+        ErrorKind::ExpiredSignature => anyhow!("token expired"),
+        _ => anyhow::Error::new(e).context("failed to verify JWT claims"),
+    })?;
+    Ok(verified.claims)
+}
+
+pub fn decode_claims(token: &str) -> Result<Claims> {
+    self::verify(token, config::jwt_secret)
 }
 
 #[tracing::instrument(skip(next))]
@@ -88,8 +230,9 @@ where
         return Err(Error::BadRequest);
     };
     let token = auth.token().to_owned();
-    let Ok(jwt) = decode::<Claims>(&token, &JWT_SECRET.decoding, &Validation::default()) else {
+    let Ok(claims) = decode_claims(&token) else {
         tracing::error!("bearer token cannot be decoded");
+        MetricsService::record_token_verification_failure();
         return Err(Error::Unauthorized);
     };
     let Some(state) = request.extensions().get::<SharedState>() else {
@@ -98,7 +241,18 @@ where
         );
         return Err(anyhow!("failed to acquire shared state").into());
     };
-    let Ok(name) = AccountName::new(jwt.claims.name.clone()) else {
+    let Ok(revoked) = RevocationService::is_revoked(&token, &state.pg_pool).await else {
+        tracing::error!(
+            "request is not handled correctly due to a server error while selecting revoked token"
+        );
+        return Err(anyhow!("error occurred while selecting revoked token from database").into());
+    };
+    if revoked {
+        tracing::error!("bearer token was revoked");
+        MetricsService::record_token_verification_failure();
+        return Err(Error::Unauthorized);
+    }
+    let Ok(name) = AccountName::new(claims.name.clone()) else {
         tracing::error!("JWT claims' account name is malformed");
         return Err(Error::ValidationFailed);
     };
@@ -112,7 +266,15 @@ where
         tracing::error!("account was not found");
         return Err(Error::Unauthorized);
     };
-    if jwt.claims.role != Role::Admin {
+    // NOTE: The signing key is picked by the unverified namespace claim, so a
+    //       provider secret is only trusted for accounts in its own namespace
+    //       and never beyond the role stored for the account.
+    if account.namespace().as_str() != claims.namespace {
+        tracing::error!("JWT claims' namespace does not own the account");
+        MetricsService::record_token_verification_failure();
+        return Err(Error::Unauthorized);
+    }
+    if claims.role.min(*account.role()) < required_role_of(request.uri().path()) {
         tracing::error!("request is forbidden from being fulfilled due to the JWT claims' role");
         return Err(Error::Forbidden);
     }
@@ -121,7 +283,10 @@ where
 }
 
 #[tracing::instrument(skip(next))]
-pub async fn as_guest<T>(request: Request<T>, next: Next<T>) -> std::result::Result<Response, Error>
+pub async fn as_guest<T>(
+    mut request: Request<T>,
+    next: Next<T>,
+) -> std::result::Result<Response, Error>
 where
     T: std::fmt::Debug,
 {
@@ -130,9 +295,406 @@ where
         return Err(Error::BadRequest);
     };
     let token = auth.token().to_owned();
-    let Ok(_) = decode::<Claims>(&token, &JWT_SECRET.decoding, &Validation::default()) else {
+    let Ok(claims) = decode_claims(&token) else {
         tracing::error!("bearer token cannot be decoded");
+        MetricsService::record_token_verification_failure();
         return Err(Error::Unauthorized)?;
     };
-    Ok(next.run(request).await)
+    let Some(state) = request.extensions().get::<SharedState>() else {
+        tracing::error!(
+            "request is not handled correctly due to a server error while acquiring server state"
+        );
+        return Err(anyhow!("failed to acquire shared state").into());
+    };
+    let Ok(revoked) = RevocationService::is_revoked(&token, &state.pg_pool).await else {
+        tracing::error!(
+            "request is not handled correctly due to a server error while selecting revoked token"
+        );
+        return Err(anyhow!("error occurred while selecting revoked token from database").into());
+    };
+    if revoked {
+        tracing::error!("bearer token was revoked");
+        MetricsService::record_token_verification_failure();
+        return Err(Error::Unauthorized);
+    }
+    // NOTE: Every event of the sharing request is tagged with its tenant so that
+    //       JSON logs can be filtered by provider and recipient.
+    let request_id = request
+        .extensions()
+        .get::<RequestId>()
+        .map(|request_id| request_id.to_string())
+        .unwrap_or_default();
+    let span = tracing::info_span!(
+        "sharing",
+        provider = %claims.namespace,
+        recipient = %claims.name,
+        request_id = %request_id,
+    );
+    request.extensions_mut().insert(claims);
+    Ok(next.run(request).instrument(span).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::middlewares::request_id;
+    use crate::server::routers::State;
+    use axum::body::Body;
+    use axum::extract::Extension;
+    use axum::http::StatusCode;
+    use axum::middleware;
+    use axum::routing::get;
+    use axum::Router;
+    use jsonwebtoken::encode;
+    use sqlx::PgPool;
+    use std::sync::Arc;
+    use std::sync::Mutex;
+    use tower::ServiceExt;
+
+    fn new_claims(namespace: &str) -> Claims {
+        Claims {
+            name: testutils::rand::string(10),
+            email: testutils::rand::email(),
+            namespace: namespace.to_string(),
+            role: Role::Guest,
+            exp: chrono::Utc::now().timestamp() + testutils::rand::i64(100000, 1000000),
+            jti: None,
+        }
+    }
+
+    #[test]
+    fn test_role_hierarchy() {
+        assert!(Role::Admin > Role::Moderator);
+        assert!(Role::Moderator > Role::Guest);
+    }
+
+    #[test]
+    fn test_moderator_can_access_moderator_routes() {
+        let path = format!("/admin/shares/{}/schemas", testutils::rand::string(10));
+        assert!(Role::Moderator >= required_role_of(&path));
+        assert!(Role::Admin >= required_role_of(&path));
+        assert!(Role::Guest < required_role_of(&path));
+    }
+
+    #[test]
+    fn test_moderator_cannot_access_admin_routes() {
+        let path = format!("/admin/accounts/{}", testutils::rand::string(10));
+        assert!(Role::Moderator < required_role_of(&path));
+        assert!(Role::Admin >= required_role_of(&path));
+        assert!(Role::Moderator < required_role_of("/admin/profile"));
+    }
+
+    #[test]
+    fn test_verify_with_other_provider_secret() {
+        let provider_a = Keys::new(testutils::rand::string(20).as_bytes());
+        let provider_b = Keys::new(testutils::rand::string(20).as_bytes());
+        let keys = |provider: &str| {
+            if provider == "provider_a" {
+                &provider_a
+            } else {
+                &provider_b
+            }
+        };
+        let token = encode(
+            &Header::default(),
+            &new_claims("provider_b"),
+            &provider_a.encoding,
+        )
+        .expect("token should be signed properly");
+        assert!(verify(&token, keys).is_err());
+    }
+
+    #[test]
+    fn test_decode_expiry() {
+        let keys = Keys::new(testutils::rand::string(20).as_bytes());
+        let mut claims = new_claims(&testutils::rand::string(10));
+        claims.exp = chrono::Utc::now().timestamp() - testutils::rand::i64(3600, 7200);
+        let token = encode(&Header::default(), &claims, &keys.encoding)
+            .expect("token should be signed properly");
+        assert_eq!(
+            decode_expiry(&token).expect("expiration should be decoded"),
+            claims.exp
+        );
+        assert!(decode_expiry(&testutils::rand::string(10)).is_err());
+    }
+
+    #[test]
+    fn test_verify_with_rsa_key() {
+        let provider = Keys::from_rsa_pem(
+            include_bytes!("../../../tests/fixtures/jwt/rsa_private.pem"),
+            include_bytes!("../../../tests/fixtures/jwt/rsa_public.pem"),
+        )
+        .expect("RSA keys should be parsed properly");
+        let claims = new_claims("provider");
+        let token = encode(&provider.header(), &claims, &provider.encoding)
+            .expect("token should be signed properly");
+        let verified = verify(&token, |_| &provider).expect("token should be verified properly");
+        assert_eq!(verified.name, claims.name);
+    }
+
+    #[test]
+    fn test_verify_with_rsa_key_rejects_hmac_token() {
+        let provider = Keys::from_rsa_pem(
+            include_bytes!("../../../tests/fixtures/jwt/rsa_private.pem"),
+            include_bytes!("../../../tests/fixtures/jwt/rsa_public.pem"),
+        )
+        .expect("RSA keys should be parsed properly");
+        let secret = Keys::new(testutils::rand::string(20).as_bytes());
+        let token = encode(&secret.header(), &new_claims("provider"), &secret.encoding)
+            .expect("token should be signed properly");
+        assert!(verify(&token, |_| &provider).is_err());
+    }
+
+    #[test]
+    fn test_verify_expired() {
+        let provider = Keys::new(testutils::rand::string(20).as_bytes());
+        let mut claims = new_claims("provider");
+        // NOTE: The configured validation allows 60 seconds of leeway.
+        claims.exp = chrono::Utc::now().timestamp() - testutils::rand::i64(120, 1000);
+        let token = encode(&Header::default(), &claims, &provider.encoding)
+            .expect("token should be signed properly");
+        let err = verify(&token, |_| &provider).expect_err("expired token should be rejected");
+        assert_eq!(err.to_string(), "token expired");
+    }
+
+    #[test]
+    fn test_verify_expired_within_leeway() {
+        let provider = Keys::new(testutils::rand::string(20).as_bytes());
+        let mut claims = new_claims("provider");
+        claims.exp = chrono::Utc::now().timestamp() - 2;
+        let token = encode(&Header::default(), &claims, &provider.encoding)
+            .expect("token should be signed properly");
+        let verified = verify_with_leeway(&token, |_| &provider, 5)
+            .expect("token expired within the leeway should be verified");
+        assert_eq!(verified.name, claims.name);
+    }
+
+    #[test]
+    fn test_verify_expired_beyond_leeway() {
+        let provider = Keys::new(testutils::rand::string(20).as_bytes());
+        let mut claims = new_claims("provider");
+        claims.exp = chrono::Utc::now().timestamp() - testutils::rand::i64(10, 1000);
+        let token = encode(&Header::default(), &claims, &provider.encoding)
+            .expect("token should be signed properly");
+        let err = verify_with_leeway(&token, |_| &provider, 5)
+            .expect_err("token expired beyond the leeway should be rejected");
+        assert_eq!(err.to_string(), "token expired");
+    }
+
+    #[test]
+    fn test_verify_expired_with_other_secret() {
+        let provider_a = Keys::new(testutils::rand::string(20).as_bytes());
+        let provider_b = Keys::new(testutils::rand::string(20).as_bytes());
+        let mut claims = new_claims("provider");
+        claims.exp = chrono::Utc::now().timestamp() - testutils::rand::i64(120, 1000);
+        let token = encode(&Header::default(), &claims, &provider_a.encoding)
+            .expect("token should be signed properly");
+        let err = verify(&token, |_| &provider_b).expect_err("forged token should be rejected");
+        assert_ne!(err.to_string(), "token expired");
+    }
+
+    #[sqlx::test]
+    async fn test_guest_claims(pool: PgPool) {
+        let claims = new_claims(&testutils::rand::string(10));
+        let token = encode(
+            &config::JWT_SECRET.header(),
+            &claims,
+            &config::JWT_SECRET.encoding,
+        )
+        .expect("token should be signed properly");
+        let app = Router::new()
+            .route(
+                "/",
+                get(|Extension(claims): Extension<Claims>| async move { claims.name }),
+            )
+            .layer(middleware::from_fn(as_guest))
+            .layer(Extension(Arc::new(State::with_pool(pool))));
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/")
+                    .header("Authorization", format!("Bearer {}", token))
+                    .body(Body::empty())
+                    .expect("request should be built properly"),
+            )
+            .await
+            .expect("request should be handled properly");
+        let body = hyper::body::to_bytes(response.into_body())
+            .await
+            .expect("response body should be read properly");
+        assert_eq!(body, claims.name.as_bytes());
+    }
+
+    #[sqlx::test]
+    async fn test_admin_claims_from_other_namespace(pool: PgPool) {
+        let account = AccountEntity::new(
+            testutils::rand::uuid(),
+            testutils::rand::string(10),
+            testutils::rand::email(),
+            testutils::rand::string(10),
+            testutils::rand::string(10),
+            testutils::rand::i64(1, 100000),
+        )
+        .expect("account should be created properly");
+        account
+            .save(&pool)
+            .await
+            .expect("account should be saved properly");
+        let claims = Claims {
+            name: account.name().to_string(),
+            email: account.email().to_string(),
+            namespace: testutils::rand::string(11),
+            role: Role::Admin,
+            exp: chrono::Utc::now().timestamp() + testutils::rand::i64(100000, 1000000),
+            jti: None,
+        };
+        let keys = config::jwt_secret(&claims.namespace);
+        let token = encode(&keys.header(), &claims, &keys.encoding)
+            .expect("token should be signed properly");
+        let app = Router::new()
+            .route("/admin/accounts", get(|| async { "accounts" }))
+            .layer(middleware::from_fn(as_admin))
+            .layer(Extension(Arc::new(State::with_pool(pool))));
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/admin/accounts")
+                    .header("Authorization", format!("Bearer {}", token))
+                    .body(Body::empty())
+                    .expect("request should be built properly"),
+            )
+            .await
+            .expect("request should be handled properly");
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[derive(Clone, Default)]
+    struct LogBuffer(Arc<Mutex<Vec<u8>>>);
+
+    impl std::io::Write for LogBuffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0
+                .lock()
+                .expect("log buffer should be locked properly")
+                .extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[sqlx::test]
+    async fn test_guest_log_fields(pool: PgPool) {
+        let buffer = LogBuffer::default();
+        let writer = buffer.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .json()
+            .with_writer(move || writer.clone())
+            .finish();
+        let _guard = tracing::subscriber::set_default(subscriber);
+        let claims = new_claims(&testutils::rand::string(10));
+        let token = encode(
+            &config::JWT_SECRET.header(),
+            &claims,
+            &config::JWT_SECRET.encoding,
+        )
+        .expect("token should be signed properly");
+        let app = Router::new()
+            .route(
+                "/",
+                get(|| async {
+                    tracing::info!("shares were successfully returned");
+                }),
+            )
+            .layer(middleware::from_fn(as_guest))
+            .layer(middleware::from_fn(request_id::propagate))
+            .layer(Extension(Arc::new(State::with_pool(pool))));
+        let request_id = testutils::rand::uuid();
+        app.oneshot(
+            Request::builder()
+                .uri("/")
+                .header("Authorization", format!("Bearer {}", token))
+                .header(request_id::HEADER_NAME, &request_id)
+                .body(Body::empty())
+                .expect("request should be built properly"),
+        )
+        .await
+        .expect("request should be handled properly");
+        let logs = String::from_utf8(
+            buffer
+                .0
+                .lock()
+                .expect("log buffer should be locked properly")
+                .clone(),
+        )
+        .expect("logs should be UTF-8");
+        let line = logs
+            .lines()
+            .find(|line| line.contains("shares were successfully returned"))
+            .expect("handler event should be logged");
+        let line: serde_json::Value = serde_json::from_str(line).expect("log should be JSON");
+        assert_eq!(line["span"]["name"], "sharing");
+        assert_eq!(line["span"]["provider"], claims.namespace);
+        assert_eq!(line["span"]["recipient"], claims.name);
+        assert_eq!(line["span"]["request_id"], request_id);
+    }
+
+    #[test]
+    fn test_verify_with_rotated_secrets() {
+        let newest = testutils::rand::string(20);
+        let older = testutils::rand::string(20);
+        let retired = testutils::rand::string(20);
+        let keys = Keys::from_secrets(&[newest.clone(), older.clone()])
+            .expect("keys should be created properly");
+        for secret in [&newest, &older] {
+            let claims = new_claims("provider");
+            let token = encode(
+                &Header::default(),
+                &claims,
+                &EncodingKey::from_secret(secret.as_bytes()),
+            )
+            .expect("token should be signed properly");
+            let verified = verify(&token, |_| &keys).expect("token should be verified");
+            assert_eq!(verified.name, claims.name);
+        }
+        let token = encode(
+            &Header::default(),
+            &new_claims("provider"),
+            &EncodingKey::from_secret(retired.as_bytes()),
+        )
+        .expect("token should be signed properly");
+        assert!(verify(&token, |_| &keys).is_err());
+    }
+
+    #[test]
+    fn test_sign_with_newest_secret() {
+        let newest = testutils::rand::string(20);
+        let older = testutils::rand::string(20);
+        let keys =
+            Keys::from_secrets(&[newest.clone(), older]).expect("keys should be created properly");
+        let token = encode(&keys.header(), &new_claims("provider"), &keys.encoding)
+            .expect("token should be signed properly");
+        let newest = Keys::new(newest.as_bytes());
+        assert!(verify(&token, |_| &newest).is_ok());
+        assert!(Keys::from_secrets(&[]).is_err());
+    }
+
+    #[test]
+    fn test_verify_expired_with_older_secret() {
+        let older = testutils::rand::string(20);
+        let keys = Keys::from_secrets(&[testutils::rand::string(20), older.clone()])
+            .expect("keys should be created properly");
+        let mut claims = new_claims("provider");
+        claims.exp = chrono::Utc::now().timestamp() - testutils::rand::i64(120, 1000);
+        let token = encode(
+            &Header::default(),
+            &claims,
+            &EncodingKey::from_secret(older.as_bytes()),
+        )
+        .expect("token should be signed properly");
+        let err = verify(&token, |_| &keys).expect_err("expired token should be rejected");
+        assert_eq!(err.to_string(), "token expired");
+    }
 }
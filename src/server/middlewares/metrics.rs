@@ -0,0 +1,29 @@
+use axum::extract::MatchedPath;
+use axum::http::Request;
+use axum::middleware::Next;
+use axum::response::Response;
+use std::time::Instant;
+
+use crate::server::services::metrics::Service as MetricsService;
+
+const UNMATCHED_ROUTE: &str = "unmatched";
+
+pub async fn record<T>(request: Request<T>, next: Next<T>) -> Response {
+    // NOTE: The route template rather than the raw path is used as a label so that
+    //       path parameters do not blow up the cardinality of the series.
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_owned())
+        .unwrap_or_else(|| UNMATCHED_ROUTE.to_owned());
+    let method = request.method().to_string();
+    let started_at = Instant::now();
+    let response = next.run(request).await;
+    MetricsService::record_request(
+        method,
+        route,
+        response.status().as_u16(),
+        started_at.elapsed(),
+    );
+    response
+}
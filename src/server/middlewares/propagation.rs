@@ -0,0 +1,75 @@
+use std::marker::PhantomData;
+
+use axum::http::header::HeaderName;
+use axum::http::header::HeaderValue;
+use axum::http::Request;
+use axum::middleware::Next;
+use axum::response::Response;
+use tracing::Instrument;
+use tracing::Span;
+use uuid::Uuid;
+
+/// Header carrying an id which is propagated from the request to its response.
+pub trait Header: Send + Sync + 'static {
+    const NAME: &'static str;
+
+    fn span(id: &str) -> Span;
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Id<H>(String, PhantomData<H>);
+
+impl<H: Header> Id<H> {
+    pub fn new() -> Self {
+        Self(Uuid::new_v4().to_string(), PhantomData)
+    }
+
+    fn from_request<T>(request: &Request<T>) -> Option<Self> {
+        let value = request.headers().get(H::NAME)?.to_str().ok()?;
+        let value = value.trim();
+        if value.is_empty() || value.len() > 128 {
+            return None;
+        }
+        Some(Self(value.to_owned(), PhantomData))
+    }
+}
+
+impl<H> Clone for Id<H> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone(), PhantomData)
+    }
+}
+
+impl<H: Header> Default for Id<H> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<H> AsRef<str> for Id<H> {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl<H> std::fmt::Display for Id<H> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+pub fn header_name<H: Header>() -> HeaderName {
+    HeaderName::from_static(H::NAME)
+}
+
+/// Takes the id from the request header or issues a new one, makes it available
+/// to the handlers and their spans, and echoes it back on the response.
+pub async fn propagate<H: Header, T>(mut request: Request<T>, next: Next<T>) -> Response {
+    let id = Id::<H>::from_request(&request).unwrap_or_else(Id::new);
+    request.extensions_mut().insert(id.clone());
+    let mut response = next.run(request).instrument(H::span(id.as_ref())).await;
+    if let Ok(value) = HeaderValue::from_str(id.as_ref()) {
+        response.headers_mut().insert(header_name::<H>(), value);
+    }
+    response
+}
@@ -0,0 +1,112 @@
+use anyhow::anyhow;
+use axum::http::header;
+use axum::http::header::HeaderMap;
+use axum::http::Request;
+use axum::middleware::Next;
+use axum::response::IntoResponse;
+use axum::response::Response;
+
+use crate::server::middlewares::jwt::Claims;
+use crate::server::routers::SharedState;
+use crate::server::services::error::Error;
+
+#[tracing::instrument(skip(next))]
+pub async fn limit<T>(request: Request<T>, next: Next<T>) -> std::result::Result<Response, Error>
+where
+    T: std::fmt::Debug,
+{
+    let Some(state) = request.extensions().get::<SharedState>() else {
+        tracing::error!(
+            "request is not handled correctly due to a server error while acquiring server state"
+        );
+        return Err(anyhow!("failed to acquire shared state").into());
+    };
+    let Some(claims) = request.extensions().get::<Claims>() else {
+        tracing::error!("JWT claims are missing");
+        return Err(Error::Unauthorized);
+    };
+    let key = format!("{}/{}", claims.namespace, claims.name);
+    if let Err(retry_after) = state.rate_limiter.acquire(&key) {
+        tracing::error!("request rate limit was exceeded");
+        let retry_after = retry_after.as_secs_f64().ceil().max(1.0) as u64;
+        let mut headers = HeaderMap::new();
+        headers.insert(header::RETRY_AFTER, retry_after.into());
+        return Ok((headers, Error::TooManyRequests).into_response());
+    }
+    Ok(next.run(request).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config;
+    use crate::server::middlewares::jwt;
+    use crate::server::middlewares::jwt::Role;
+    use crate::server::routers::State;
+    use crate::server::services::rate_limit::Limiter as RateLimiter;
+    use axum::body::Body;
+    use axum::extract::Extension;
+    use axum::http::StatusCode;
+    use axum::middleware;
+    use axum::routing::get;
+    use axum::Router;
+    use jsonwebtoken::encode;
+    use sqlx::PgPool;
+    use std::sync::Arc;
+    use tower::ServiceExt;
+
+    #[sqlx::test]
+    async fn test_exhaust_rate_limit(pool: PgPool) {
+        let burst = testutils::rand::u32(1, 5);
+        let state = Arc::new(State {
+            rate_limiter: RateLimiter::new(1, burst),
+            ..State::with_pool(pool)
+        });
+        let app = Router::new()
+            .route("/", get(|| async { "ok" }))
+            .route_layer(middleware::from_fn(limit))
+            .route_layer(middleware::from_fn(jwt::as_guest))
+            .layer(Extension(state));
+        let claims = Claims {
+            name: testutils::rand::string(10),
+            email: testutils::rand::email(),
+            namespace: testutils::rand::string(10),
+            role: Role::Guest,
+            exp: chrono::Utc::now().timestamp() + testutils::rand::i64(100000, 1000000),
+            jti: None,
+        };
+        let token = encode(
+            &config::JWT_SECRET.header(),
+            &claims,
+            &config::JWT_SECRET.encoding,
+        )
+        .expect("token should be signed properly");
+        let request = || {
+            Request::builder()
+                .uri("/")
+                .header("Authorization", format!("Bearer {}", token))
+                .body(Body::empty())
+                .expect("request should be built properly")
+        };
+        for _ in 0..burst {
+            let response = app
+                .clone()
+                .oneshot(request())
+                .await
+                .expect("request should be handled properly");
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+        let response = app
+            .oneshot(request())
+            .await
+            .expect("request should be handled properly");
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(
+            response
+                .headers()
+                .get(header::RETRY_AFTER)
+                .and_then(|v| v.to_str().ok()),
+            Some("1")
+        );
+    }
+}
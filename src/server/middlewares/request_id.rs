@@ -0,0 +1,180 @@
+use axum::body::boxed;
+use axum::body::Body;
+use axum::http::header;
+use axum::http::header::HeaderName;
+use axum::http::Request;
+use axum::middleware::Next;
+use axum::response::Response;
+use tracing::Span;
+
+use crate::server::middlewares::propagation;
+use crate::server::middlewares::propagation::Header;
+use crate::server::middlewares::propagation::Id;
+use crate::server::services::error::ErrorMessage;
+
+pub const HEADER_NAME: &str = "x-request-id";
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum RequestIdHeader {}
+
+impl Header for RequestIdHeader {
+    const NAME: &'static str = HEADER_NAME;
+
+    fn span(id: &str) -> Span {
+        tracing::info_span!("request", request_id = %id)
+    }
+}
+
+pub type RequestId = Id<RequestIdHeader>;
+
+pub fn header_name() -> HeaderName {
+    propagation::header_name::<RequestIdHeader>()
+}
+
+pub async fn propagate<T>(request: Request<T>, next: Next<T>) -> Response {
+    // NOTE: Unlike the correlation id, the request id identifies a single request so
+    //       that error responses can be matched against the server logs.
+    let response = propagation::propagate::<RequestIdHeader, T>(request, next).await;
+    let (mut parts, body) = response.into_parts();
+    let Some(mut message) = parts.extensions.remove::<ErrorMessage>() else {
+        return Response::from_parts(parts, body);
+    };
+    message.request_id = parts
+        .headers
+        .get(HEADER_NAME)
+        .and_then(|value| value.to_str().ok())
+        .map(ToOwned::to_owned);
+    let Ok(json) = serde_json::to_vec(&message) else {
+        return Response::from_parts(parts, body);
+    };
+    parts.headers.remove(header::CONTENT_LENGTH);
+    Response::from_parts(parts, boxed(Body::from(json)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::services::error::Error;
+    use axum::body::Body;
+    use axum::extract::Extension;
+    use axum::http::StatusCode;
+    use axum::middleware;
+    use axum::routing::get;
+    use axum::Router;
+    use std::sync::Arc;
+    use std::sync::Mutex;
+    use tower::ServiceExt;
+    use tracing::field::Field;
+    use tracing::field::Visit;
+    use tracing::span::Attributes;
+    use tracing::span::Id;
+    use tracing::Subscriber;
+    use tracing_subscriber::layer::Context;
+    use tracing_subscriber::layer::SubscriberExt;
+    use tracing_subscriber::Layer;
+    use uuid::Uuid;
+
+    #[derive(Clone, Default)]
+    struct SpanRecorder {
+        request_ids: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl Visit for SpanRecorder {
+        fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+            if field.name() == "request_id" {
+                self.request_ids
+                    .lock()
+                    .expect("recorder should be locked properly")
+                    .push(format!("{:?}", value));
+            }
+        }
+    }
+
+    impl<S: Subscriber> Layer<S> for SpanRecorder {
+        fn on_new_span(&self, attrs: &Attributes<'_>, _: &Id, _: Context<'_, S>) {
+            attrs.record(&mut self.clone());
+        }
+    }
+
+    fn app() -> Router {
+        Router::new()
+            .route(
+                "/",
+                get(
+                    |Extension(request_id): Extension<RequestId>| async move {
+                        request_id.to_string()
+                    },
+                ),
+            )
+            .route(
+                "/error",
+                get(|| async { Err::<String, Error>(Error::NotFound) }),
+            )
+            .layer(middleware::from_fn(propagate))
+    }
+
+    fn request(uri: &str) -> Request<Body> {
+        Request::builder()
+            .uri(uri)
+            .body(Body::empty())
+            .expect("request should be built properly")
+    }
+
+    fn request_id_of(response: &Response) -> String {
+        response
+            .headers()
+            .get(HEADER_NAME)
+            .expect("request id should be returned")
+            .to_str()
+            .expect("request id should be ASCII")
+            .to_owned()
+    }
+
+    #[tokio::test]
+    async fn test_issue_request_id() {
+        let recorder = SpanRecorder::default();
+        let _guard =
+            tracing::subscriber::set_default(tracing_subscriber::registry().with(recorder.clone()));
+        let response = app()
+            .oneshot(request("/"))
+            .await
+            .expect("request should be handled properly");
+        let header = request_id_of(&response);
+        assert!(Uuid::parse_str(&header).is_ok());
+        assert_eq!(
+            *recorder
+                .request_ids
+                .lock()
+                .expect("recorder should be locked properly"),
+            vec![header.clone()]
+        );
+        let body = hyper::body::to_bytes(response.into_body())
+            .await
+            .expect("response body should be read properly");
+        assert_eq!(body, header.as_bytes());
+    }
+
+    #[tokio::test]
+    async fn test_echo_request_id_on_error() {
+        let request_id = testutils::rand::uuid();
+        let response = app()
+            .oneshot(
+                Request::builder()
+                    .uri("/error")
+                    .header(HEADER_NAME, &request_id)
+                    .body(Body::empty())
+                    .expect("request should be built properly"),
+            )
+            .await
+            .expect("request should be handled properly");
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        assert_eq!(request_id_of(&response), request_id);
+        let body = hyper::body::to_bytes(response.into_body())
+            .await
+            .expect("response body should be read properly");
+        let body: serde_json::Value =
+            serde_json::from_slice(&body).expect("response body should be JSON");
+        assert_eq!(body["errorCode"], "RESOURCE_DOES_NOT_EXIST");
+        assert_eq!(body["requestId"], request_id.as_str());
+    }
+}
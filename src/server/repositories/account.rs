@@ -7,6 +7,7 @@ use uuid::Uuid;
 
 use crate::server::entities::account::Entity;
 use crate::server::entities::account::Name;
+use crate::server::middlewares::jwt::Role;
 use crate::server::utilities::postgres::PgAcquire;
 
 #[derive(Debug, Clone, serde::Serialize, sqlx::FromRow)]
@@ -17,8 +18,10 @@ pub struct Row {
     pub password: String,
     pub namespace: String,
     pub ttl: i64,
+    pub role: Role,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    pub deleted_at: Option<DateTime<Utc>>,
 }
 
 pub struct Repository;
@@ -36,15 +39,17 @@ impl Repository {
                  email,
                  password,
                  namespace,
-                 ttl
-             ) VALUES ($1, $2, $3, $4, $5, $6)
+                 ttl,
+                 role
+             ) VALUES ($1, $2, $3, $4, $5, $6, $7)
              ON CONFLICT(id)
              DO UPDATE
              SET name = $2,
                  email = $3,
                  password = $4,
                  namespace = $5,
-                 ttl = $6",
+                 ttl = $6,
+                 role = $7",
         )
         .bind(account.id())
         .bind(account.name())
@@ -52,6 +57,7 @@ impl Repository {
         .bind(account.password())
         .bind(account.namespace())
         .bind(account.ttl())
+        .bind(account.role())
         .execute(&mut *conn)
         .await
         .context(format!(
@@ -60,6 +66,39 @@ impl Repository {
         ))
     }
 
+    pub async fn resurrect(
+        account: &Entity,
+        executor: impl PgAcquire<'_>,
+    ) -> Result<PgQueryResult> {
+        let mut conn = executor
+            .acquire()
+            .await
+            .context("failed to acquire postgres connection")?;
+        sqlx::query(
+            "UPDATE account
+             SET email = $2,
+                 password = $3,
+                 namespace = $4,
+                 ttl = $5,
+                 role = $6,
+                 updated_at = CURRENT_TIMESTAMP,
+                 deleted_at = NULL
+             WHERE name = $1 AND deleted_at IS NOT NULL",
+        )
+        .bind(account.name())
+        .bind(account.email())
+        .bind(account.password())
+        .bind(account.namespace())
+        .bind(account.ttl())
+        .bind(account.role())
+        .execute(&mut *conn)
+        .await
+        .context(format!(
+            r#"failed to resurrect "{}" in [account]"#,
+            account.name().as_str()
+        ))
+    }
+
     pub async fn select_by_name(name: &Name, executor: impl PgAcquire<'_>) -> Result<Option<Row>> {
         let mut conn = executor
             .acquire()
@@ -73,10 +112,12 @@ impl Repository {
                  password,
                  namespace,
                  ttl,
+                 role,
                  created_at,
-                 updated_at
+                 updated_at,
+                 deleted_at
              FROM account
-             WHERE name = $1",
+             WHERE name = $1 AND deleted_at IS NULL",
         )
         .bind(name)
         .fetch_optional(&mut *conn)
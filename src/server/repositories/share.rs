@@ -14,6 +14,7 @@ pub struct Row {
     pub id: Uuid,
     pub name: String,
     pub created_by: Uuid,
+    pub default_ttl: Option<i64>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -30,16 +31,19 @@ impl Repository {
             "INSERT INTO share (
                  id,
                  name,
-                 created_by
-             ) VALUES ($1, $2, $3)
+                 created_by,
+                 default_ttl
+             ) VALUES ($1, $2, $3, $4)
              ON CONFLICT(id)
              DO UPDATE
              SET name = $2,
-                 created_by = $3",
+                 created_by = $3,
+                 default_ttl = $4",
         )
         .bind(share.id())
         .bind(share.name())
         .bind(share.created_by())
+        .bind(share.default_ttl())
         .execute(&mut *conn)
         .await
         .context(format!(
@@ -58,6 +62,7 @@ impl Repository {
                  id,
                  name,
                  created_by,
+                 default_ttl,
                  created_at,
                  updated_at
              FROM share
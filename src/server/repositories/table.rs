@@ -16,6 +16,8 @@ pub struct Row {
     pub name: String,
     pub schema_id: Uuid,
     pub location: String,
+    pub schema_string: Option<String>,
+    pub schema_hash: Option<String>,
     pub created_by: Uuid,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
@@ -35,20 +37,27 @@ impl Repository {
                    name,
                    schema_id,
                    location,
-                   created_by
-               ) VALUES ($1, $2, $3, $4, $5)
+                   created_by,
+                   schema_string,
+                   schema_hash
+               ) VALUES ($1, $2, $3, $4, $5, $6, $7)
                ON CONFLICT(id)
                DO UPDATE
                SET name = $2,
                    schema_id = $3,
                    location = $4,
-                   created_by = $5"#,
+                   created_by = $5,
+                   schema_string = $6,
+                   schema_hash = $7,
+                   updated_at = CURRENT_TIMESTAMP"#,
         )
         .bind(table.id())
         .bind(table.name())
         .bind(table.schema_id())
         .bind(table.location())
         .bind(table.created_by())
+        .bind(table.schema_string())
+        .bind(table.schema_hash())
         .execute(&mut *conn)
         .await
         .context(format!(
@@ -73,6 +82,8 @@ impl Repository {
                    name,
                    schema_id,
                    location,
+                   schema_string,
+                   schema_hash,
                    created_by,
                    created_at,
                    updated_at
@@ -6,6 +6,8 @@ use sqlx::postgres::PgQueryResult;
 use uuid::Uuid;
 
 use crate::server::entities::token::Entity;
+use crate::server::entities::token::IdempotencyKey;
+use crate::server::entities::token::ValueHash;
 use crate::server::middlewares::jwt::Role;
 use crate::server::utilities::postgres::PgAcquire;
 
@@ -14,8 +16,10 @@ pub struct Row {
     pub id: Uuid,
     pub email: String,
     pub role: Role,
-    pub value: String,
+    pub value_hash: String,
+    pub expires_at: Option<DateTime<Utc>>,
     pub created_by: Uuid,
+    pub idempotency_key: Option<String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -33,21 +37,28 @@ impl Repository {
                    id,
                    email,
                    "role",
-                   "value",
-                   created_by
-               ) VALUES ($1, $2, $3, $4, $5)
+                   value_hash,
+                   expires_at,
+                   created_by,
+                   idempotency_key
+               ) VALUES ($1, $2, $3, $4, $5, $6, $7)
                ON CONFLICT(id)
                DO UPDATE
                SET email = $2,
                    "role" = $3,
-                   "value" = $4,
-                   created_by = $5"#,
+                   "value" = NULL,
+                   value_hash = $4,
+                   expires_at = $5,
+                   created_by = $6,
+                   idempotency_key = $7"#,
         )
         .bind(token.id())
         .bind(token.email())
         .bind(token.role())
-        .bind(token.value())
+        .bind(token.value_hash())
+        .bind(token.expires_at())
         .bind(token.created_by())
+        .bind(token.idempotency_key())
         .execute(&mut *conn)
         .await
         .context(format!(
@@ -55,4 +66,71 @@ impl Repository {
             token.id().as_uuid()
         ))
     }
+
+    pub async fn select_by_idempotency_key(
+        key: &IdempotencyKey,
+        window_secs: i64,
+        executor: impl PgAcquire<'_>,
+    ) -> Result<Option<Row>> {
+        let mut conn = executor
+            .acquire()
+            .await
+            .context("failed to acquire postgres connection")?;
+        let row: Option<Row> = sqlx::query_as::<_, Row>(
+            r#"SELECT
+                   id,
+                   email,
+                   "role",
+                   value_hash,
+                   expires_at,
+                   created_by,
+                   idempotency_key,
+                   created_at,
+                   updated_at
+               FROM token
+               WHERE idempotency_key = $1
+                 AND created_at > CURRENT_TIMESTAMP - make_interval(secs => $2)
+               ORDER BY created_at DESC
+               LIMIT 1"#,
+        )
+        .bind(key)
+        .bind(window_secs as f64)
+        .fetch_optional(&mut *conn)
+        .await
+        .context(format!(
+            r#"failed to select "{}" from [token]"#,
+            key.as_str()
+        ))?;
+        Ok(row)
+    }
+
+    pub async fn select_by_value_hash(
+        value_hash: &ValueHash,
+        executor: impl PgAcquire<'_>,
+    ) -> Result<Option<Row>> {
+        let mut conn = executor
+            .acquire()
+            .await
+            .context("failed to acquire postgres connection")?;
+        let row: Option<Row> = sqlx::query_as::<_, Row>(
+            r#"SELECT
+                   id,
+                   email,
+                   "role",
+                   value_hash,
+                   expires_at,
+                   created_by,
+                   idempotency_key,
+                   created_at,
+                   updated_at
+               FROM token
+               WHERE value_hash = $1
+               LIMIT 1"#,
+        )
+        .bind(value_hash)
+        .fetch_optional(&mut *conn)
+        .await
+        .context("failed to select token by value hash from [token]")?;
+        Ok(row)
+    }
 }
@@ -1,23 +1,44 @@
 use anyhow::anyhow;
 use axum::extract::Extension;
 use axum::extract::Json;
+use axum::http::header::HeaderMap;
 use axum::http::StatusCode;
 use axum::response::IntoResponse;
 use axum::response::Response;
+use chrono::DateTime;
+use chrono::Duration;
+use chrono::Utc;
 use utoipa::ToSchema;
+use uuid::Uuid;
 
+use crate::config;
 use crate::server::entities::account::Entity as AccountEntity;
 use crate::server::entities::account::Name as AccountName;
 use crate::server::entities::token::Entity as TokenEntity;
-use crate::server::middlewares::jwt::Role;
+use crate::server::entities::token::IdempotencyKey;
+use crate::server::entities::token::Value as TokenValue;
+use crate::server::middlewares::correlation::CorrelationId;
+use crate::server::middlewares::request_id::RequestId;
 use crate::server::routers::SharedState;
+use crate::server::services::audit::Service as AuditService;
+use crate::server::services::audit::Trace as AuditTrace;
 use crate::server::services::error::Error;
+use crate::server::services::metrics::Service as MetricsService;
 use crate::server::services::profile::Profile;
+use crate::server::services::profile::RecipientNotAllowed;
 use crate::server::services::profile::Service as ProfileService;
+use crate::server::services::refresh_token::Exchange as RefreshTokenExchange;
+use crate::server::services::refresh_token::Service as RefreshTokenService;
+use crate::server::utilities::postgres::PgAcquire;
 use crate::server::utilities::postgres::Utility as PostgresUtility;
+use crate::server::utilities::validation::ValidationError;
 
 pub mod accounts;
+pub mod profiles;
 pub mod shares;
+pub mod tokens;
+
+const IDEMPOTENCY_KEY_HEADER: &str = "Idempotency-Key";
 
 #[derive(serde::Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
@@ -39,6 +60,49 @@ impl std::fmt::Debug for AdminLoginRequest {
 #[serde(rename_all = "camelCase")]
 pub struct AdminLoginResponse {
     pub profile: Profile,
+    /// Single-use token exchanged at `POST /admin/refresh` for a new profile; replayed
+    /// logins omit it since only its hash is kept.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub refresh_token: Option<String>,
+}
+
+/// Issues the login profile whose claims carry the account's persisted role.
+fn login_profile(account: &AccountEntity) -> anyhow::Result<Profile> {
+    ProfileService::issue(
+        account.name().to_string(),
+        account.email().to_string(),
+        account.namespace().to_string(),
+        *account.role(),
+        account.ttl().to_i64(),
+    )
+}
+
+/// Replays the profile of a login retried with the same idempotency key. The key
+/// must have been used by the same account. Only the hash of the issued token is
+/// stored, so the profile is signed again and replayed only if it yields the same
+/// token; an expired profile or one whose account has changed is not replayed.
+fn replayed_profile(
+    issued: &TokenEntity,
+    account: &AccountEntity,
+) -> Result<Option<Profile>, Error> {
+    if issued.created_by() != account.id() {
+        return Err(Error::Conflict);
+    }
+    let Some(expires_at) = (*issued.expires_at()).filter(|exp| *exp > Utc::now()) else {
+        return Ok(None);
+    };
+    let Ok(profile) = ProfileService::reissue(
+        account.name().to_string(),
+        account.email().to_string(),
+        account.namespace().to_string(),
+        *account.role(),
+        expires_at.timestamp(),
+    ) else {
+        return Ok(None);
+    };
+    let replayed = TokenValue::new(profile.bearer_token.clone())
+        .map_or(false, |value| &value.hash() == issued.value_hash());
+    Ok(Some(profile).filter(|_| replayed))
 }
 
 #[utoipa::path(
@@ -46,22 +110,45 @@ pub struct AdminLoginResponse {
     path = "/admin/login",
     tag = "admin",
     request_body = AdminLoginRequest,
+    security(()),
     responses(
         (status = 200, description = "The profile was successfully returned.", body = AdminLoginResponse),
         (status = 400, description = "The request is malformed.", body = ErrorMessage),
         (status = 401, description = "The request is unauthenticated.", body = ErrorMessage),
+        (status = 409, description = "The idempotency key was already used for another login.", body = ErrorMessage),
         (status = 500, description = "The request is not handled correctly due to a server error.", body = ErrorMessage),
     )
 )]
-#[tracing::instrument(skip(state))]
+#[tracing::instrument(skip(state, correlation, request_id, headers), fields(correlation_id = %correlation))]
 pub async fn login(
     Extension(state): Extension<SharedState>,
+    Extension(correlation): Extension<CorrelationId>,
+    request_id: Option<Extension<RequestId>>,
+    headers: HeaderMap,
     Json(payload): Json<AdminLoginRequest>,
 ) -> Result<Response, Error> {
-    let Ok(account) = AccountName::new(payload.account) else {
-        tracing::error!("requested account data is malformed");
+    let Ok(idempotency_key) = headers
+        .get(IDEMPOTENCY_KEY_HEADER)
+        .map(|key| key.to_str())
+        .transpose()
+    else {
+        tracing::error!("requested idempotency key is malformed");
         return Err(Error::ValidationFailed);
     };
+    let idempotency_key = match idempotency_key.map(IdempotencyKey::new).transpose() {
+        Ok(idempotency_key) => idempotency_key,
+        Err(e) => {
+            tracing::error!("requested idempotency key is malformed");
+            return Err(Error::invalid_field(IDEMPOTENCY_KEY_HEADER, e));
+        }
+    };
+    let account = match AccountName::new(payload.account) {
+        Ok(account) => account,
+        Err(e) => {
+            tracing::error!("requested account data is malformed");
+            return Err(Error::invalid_field("account", e));
+        }
+    };
     let Ok(account) = AccountEntity::load(&account, &state.pg_pool).await else {
         tracing::error!(
             "request is not handled correctly due to a server error while selecting account"
@@ -76,22 +163,84 @@ pub async fn login(
         tracing::error!("password is incorrect");
         return Err(Error::Unauthorized);
     };
-    let Ok(profile) = ProfileService::issue(
-        account.name().to_string(),
-        account.email().to_string(),
-        account.namespace().to_string(),
-        Role::Admin,
-        account.ttl().to_i64(),
-    ) else {
-        tracing::error!(
-            "request is not handled correctly due to a server error while creating profile"
-        );
-        return Err(anyhow!("failed to create profile").into());
+    if let Some(key) = &idempotency_key {
+        let Ok(issued) = TokenEntity::load_by_idempotency_key(
+            key,
+            config::fetch::<i64>("idempotency_key_ttl"),
+            &state.pg_pool,
+        )
+        .await
+        else {
+            tracing::error!(
+                "request is not handled correctly due to a server error while selecting token"
+            );
+            return Err(anyhow!("error occured while selecting token from database").into());
+        };
+        if let Some(issued) = issued {
+            match self::replayed_profile(&issued, &account) {
+                Ok(Some(profile)) => {
+                    tracing::info!("profile was successfully replayed");
+                    return Ok((
+                        StatusCode::OK,
+                        Json(AdminLoginResponse {
+                            profile,
+                            refresh_token: None,
+                        }),
+                    )
+                        .into_response());
+                }
+                Ok(None) => {
+                    tracing::warn!("profile issued for idempotency key can no longer be replayed");
+                }
+                Err(e) => {
+                    tracing::error!("idempotency key was already used for another account");
+                    return Err(e);
+                }
+            }
+        }
+    }
+    let expires_at = Utc::now() + Duration::seconds(config::fetch::<i64>("refresh_token_ttl"));
+    self::issue_login(
+        &state,
+        &account,
+        idempotency_key,
+        None,
+        expires_at,
+        &AuditTrace::new(request_id.as_deref(), Some(&correlation)),
+        &state.pg_pool,
+    )
+    .await
+}
+
+/// Issues the login profile along with a refresh token expiring at `expires_at`,
+/// which continues `family` when the login is a refresh. The refresh token is
+/// stored through `executor`.
+async fn issue_login(
+    state: &SharedState,
+    account: &AccountEntity,
+    idempotency_key: Option<IdempotencyKey>,
+    family: Option<Uuid>,
+    expires_at: DateTime<Utc>,
+    trace: &AuditTrace,
+    executor: impl PgAcquire<'_>,
+) -> Result<Response, Error> {
+    let profile = match self::login_profile(account) {
+        Ok(profile) => profile,
+        Err(e) if e.is::<ValidationError>() => {
+            tracing::error!("requested profile ttl is malformed");
+            return Err(Error::validation(e));
+        }
+        Err(_) => {
+            tracing::error!(
+                "request is not handled correctly due to a server error while creating profile"
+            );
+            return Err(anyhow!("failed to create profile").into());
+        }
     };
-    let Ok(token) = TokenEntity::new(
+    let Ok(mut token) = TokenEntity::new(
         None,
         account.email().to_string(),
-        Role::Admin,
+        *account.role(),
         profile.bearer_token.clone(),
         account.id().to_string(),
     ) else {
@@ -100,6 +249,7 @@ pub async fn login(
         );
         return Err(anyhow!("failed to create token").into());
     };
+    token.set_idempotency_key(idempotency_key);
     match PostgresUtility::error(token.save(&state.pg_pool).await)? {
         Ok(_) => {
             tracing::info!("token was successfully registered");
@@ -114,8 +264,169 @@ pub async fn login(
             return Err(anyhow!("error occured while updating account").into());
         }
     }
+    let Ok(access) = TokenValue::new(profile.bearer_token.clone()) else {
+        tracing::error!(
+            "request is not handled correctly due to a server error while creating token"
+        );
+        return Err(anyhow!("failed to create token").into());
+    };
+    let Ok(refresh_token) = RefreshTokenService::issue(
+        account.id().as_uuid(),
+        family,
+        expires_at,
+        &access,
+        executor,
+    )
+    .await
+    else {
+        tracing::error!(
+            "request is not handled correctly due to a server error while issuing refresh token"
+        );
+        return Err(anyhow!("error occured while issuing refresh token").into());
+    };
+    self::audit_issuance(state, account, trace).await;
     tracing::info!("profile was successfully returned");
-    Ok((StatusCode::OK, Json(AdminLoginResponse { profile })).into_response())
+    Ok((
+        StatusCode::OK,
+        Json(AdminLoginResponse {
+            profile,
+            refresh_token: Some(refresh_token),
+        }),
+    )
+        .into_response())
+}
+
+#[derive(serde::Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct AdminRefreshRequest {
+    pub refresh_token: String,
+}
+
+impl std::fmt::Debug for AdminRefreshRequest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AdminRefreshRequest")
+            .field("refresh_token", &"***")
+            .finish()
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/refresh",
+    operation_id = "RefreshLogin",
+    tag = "admin",
+    request_body = AdminRefreshRequest,
+    security(()),
+    responses(
+        (status = 200, description = "The profile was successfully refreshed and the refresh token rotated.", body = AdminLoginResponse),
+        (status = 400, description = "The request is malformed.", body = ErrorMessage),
+        (status = 401, description = "The request is unauthenticated. The refresh token is unknown, expired or was already used.", body = ErrorMessage),
+        (status = 500, description = "The request is not handled correctly due to a server error.", body = ErrorMessage),
+    )
+)]
+#[tracing::instrument(skip(state, correlation, request_id), fields(correlation_id = %correlation))]
+pub async fn refresh(
+    Extension(state): Extension<SharedState>,
+    Extension(correlation): Extension<CorrelationId>,
+    request_id: Option<Extension<RequestId>>,
+    Json(payload): Json<AdminRefreshRequest>,
+) -> Result<Response, Error> {
+    if payload.refresh_token.is_empty() {
+        tracing::error!("requested refresh token is empty");
+        return Err(Error::invalid_field(
+            "refreshToken",
+            anyhow!("refresh token must not be empty"),
+        ));
+    }
+    // NOTE: The token is consumed and its successor stored in one transaction, so a
+    //       failed rotation leaves the token usable instead of locking the client out.
+    let Ok(mut tx) = state.pg_pool.begin().await else {
+        tracing::error!(
+            "request is not handled correctly due to a server error while beginning transaction"
+        );
+        return Err(anyhow!("error occured while beginning transaction").into());
+    };
+    let Ok(exchange) =
+        RefreshTokenService::exchange(&payload.refresh_token, Utc::now(), &mut *tx).await
+    else {
+        tracing::error!(
+            "request is not handled correctly due to a server error while exchanging refresh token"
+        );
+        return Err(anyhow!("error occured while exchanging refresh token").into());
+    };
+    let (account, family, expires_at) = match exchange {
+        RefreshTokenExchange::Rotated {
+            account,
+            family,
+            expires_at,
+        } => (account, family, expires_at),
+        RefreshTokenExchange::Reused { account, family } => {
+            let Ok(_) = tx.commit().await else {
+                tracing::error!(
+                    "request is not handled correctly due to a server error while revoking refresh token family"
+                );
+                return Err(anyhow!("error occured while revoking refresh token family").into());
+            };
+            tracing::warn!(
+                %account,
+                %family,
+                "refresh token was reused; every token of its family was revoked"
+            );
+            MetricsService::record_refresh_token_reuse();
+            return Err(Error::Unauthorized);
+        }
+        RefreshTokenExchange::Invalid => {
+            tracing::error!("refresh token is unknown or expired");
+            return Err(Error::Unauthorized);
+        }
+    };
+    let Ok(account) = AccountName::new(account) else {
+        tracing::error!("account of refresh token is malformed");
+        return Err(Error::Unauthorized);
+    };
+    let Ok(account) = AccountEntity::load(&account, &state.pg_pool).await else {
+        tracing::error!(
+            "request is not handled correctly due to a server error while selecting account"
+        );
+        return Err(anyhow!("error occured while selecting account from database").into());
+    };
+    let Some(account) = account else {
+        tracing::error!("account does not exist");
+        return Err(Error::Unauthorized);
+    };
+    let trace = AuditTrace::new(request_id.as_deref(), Some(&correlation));
+    let response = self::issue_login(
+        &state,
+        &account,
+        None,
+        Some(family),
+        expires_at,
+        &trace,
+        &mut *tx,
+    )
+    .await?;
+    let Ok(_) = tx.commit().await else {
+        tracing::error!(
+            "request is not handled correctly due to a server error while rotating refresh token"
+        );
+        return Err(anyhow!("error occured while rotating refresh token").into());
+    };
+    Ok(response)
+}
+
+// NOTE: Audit logging is best-effort; a failed write is logged and the profile is still returned.
+async fn audit_issuance(state: &SharedState, account: &AccountEntity, trace: &AuditTrace) {
+    if let Err(e) = AuditService::record_profile_issuance(
+        account.namespace().as_str(),
+        account.name().as_str(),
+        account.ttl().to_i64(),
+        trace,
+        &state.pg_pool,
+    )
+    .await
+    {
+        tracing::error!("failed to record profile issuance: {:#}", e);
+    }
 }
 
 #[derive(serde::Serialize, ToSchema)]
@@ -135,20 +446,507 @@ pub struct AdminProfileResponse {
         (status = 500, description = "The request is not handled correctly due to a server error.", body = ErrorMessage),
     )
 )]
-#[tracing::instrument(skip(account))]
-pub async fn profile(Extension(account): Extension<AccountEntity>) -> Result<Response, Error> {
-    let Ok(profile) = ProfileService::issue(
+#[tracing::instrument(skip(state, account, correlation, request_id), fields(correlation_id = %correlation))]
+pub async fn profile(
+    Extension(state): Extension<SharedState>,
+    Extension(account): Extension<AccountEntity>,
+    Extension(correlation): Extension<CorrelationId>,
+    request_id: Option<Extension<RequestId>>,
+) -> Result<Response, Error> {
+    let profile = match ProfileService::issue_guest(
+        state.recipient_allowlists.as_ref(),
         account.name().to_string(),
         account.email().to_string(),
         account.namespace().to_string(),
-        Role::Guest,
         account.ttl().to_i64(),
-    ) else {
-        tracing::error!(
-            "request is not handled correctly due to a server error while creating profile"
-        );
-        return Err(anyhow!("failed to create profile").into());
+    ) {
+        Ok(profile) => profile,
+        Err(e) if e.is::<ValidationError>() => {
+            tracing::error!("requested profile ttl is malformed");
+            return Err(Error::validation(e));
+        }
+        Err(e) if e.is::<RecipientNotAllowed>() => {
+            tracing::error!("requested recipient is not on the provider's allowlist");
+            return Err(Error::Forbidden);
+        }
+        Err(_) => {
+            tracing::error!(
+                "request is not handled correctly due to a server error while creating profile"
+            );
+            return Err(anyhow!("failed to create profile").into());
+        }
     };
+    let trace = AuditTrace::new(request_id.as_deref(), Some(&correlation));
+    self::audit_issuance(&state, &account, &trace).await;
     tracing::info!("profile was successfully returned");
     Ok((StatusCode::OK, Json(AdminProfileResponse { profile })).into_response())
 }
+
+#[derive(serde::Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct AdminProfilePreviewResponse {
+    pub profile: Profile,
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/profile/preview",
+    operation_id = "PreviewProfile",
+    tag = "admin",
+    responses(
+        (status = 200, description = "The profile was successfully previewed. Its bearer token is a placeholder.", body = AdminProfilePreviewResponse),
+        (status = 400, description = "The request is malformed.", body = ErrorMessage),
+        (status = 401, description = "The request is unauthenticated. The bearer token is missing or incorrect.", body = ErrorMessage),
+        (status = 403, description = "The request is forbidden from being fulfilled.", body = ErrorMessage),
+        (status = 500, description = "The request is not handled correctly due to a server error.", body = ErrorMessage),
+    )
+)]
+#[tracing::instrument(skip(state, account, correlation), fields(correlation_id = %correlation))]
+pub async fn preview(
+    Extension(state): Extension<SharedState>,
+    Extension(account): Extension<AccountEntity>,
+    Extension(correlation): Extension<CorrelationId>,
+) -> Result<Response, Error> {
+    let profile = match ProfileService::preview_guest(
+        state.recipient_allowlists.as_ref(),
+        account.name().to_string(),
+        account.email().to_string(),
+        account.namespace().to_string(),
+        account.ttl().to_i64(),
+    ) {
+        Ok(profile) => profile,
+        Err(e) if e.is::<ValidationError>() => {
+            tracing::error!("requested profile ttl is malformed");
+            return Err(Error::validation(e));
+        }
+        Err(e) if e.is::<RecipientNotAllowed>() => {
+            tracing::error!("requested recipient is not on the provider's allowlist");
+            return Err(Error::Forbidden);
+        }
+        Err(_) => {
+            tracing::error!(
+                "request is not handled correctly due to a server error while creating profile"
+            );
+            return Err(anyhow!("failed to create profile").into());
+        }
+    };
+    tracing::info!("profile was successfully previewed");
+    Ok((
+        StatusCode::OK,
+        Json(AdminProfilePreviewResponse { profile }),
+    )
+        .into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::entities::account::Email as AccountEmail;
+    use crate::server::middlewares::correlation;
+    use crate::server::middlewares::jwt;
+    use crate::server::middlewares::jwt::Role;
+    use crate::server::routers::State;
+    use crate::server::services::profile::Allowlists as RecipientAllowlists;
+    use crate::server::services::profile::PREVIEW_BEARER_TOKEN;
+    use crate::server::services::revocation::Service as RevocationService;
+    use axum::body::Body;
+    use axum::http::Request;
+    use axum::middleware;
+    use axum::routing::get;
+    use axum::Router;
+    use sqlx::PgPool;
+    use std::sync::Arc;
+    use std::sync::Mutex;
+    use tower::ServiceExt;
+    use tracing::field::Field;
+    use tracing::span::Attributes;
+    use tracing::span::Id;
+    use tracing::Subscriber;
+    use tracing_subscriber::layer::Context;
+    use tracing_subscriber::layer::Layer;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    #[tokio::test]
+    async fn test_login_with_malformed_account() {
+        let state = Arc::new(State::detached());
+        let error = login(
+            Extension(state),
+            Extension(CorrelationId::new()),
+            None,
+            HeaderMap::new(),
+            Json(AdminLoginRequest {
+                account: String::new(),
+                password: testutils::rand::string(10),
+            }),
+        )
+        .await
+        .err()
+        .expect("malformed account should be rejected");
+        let response = error.into_response();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let body = hyper::body::to_bytes(response.into_body())
+            .await
+            .expect("response body should be read properly");
+        let body: serde_json::Value =
+            serde_json::from_slice(&body).expect("response body should be JSON");
+        assert_eq!(body["details"][0]["field"], "account");
+    }
+
+    fn new_account() -> AccountEntity {
+        AccountEntity::new(
+            testutils::rand::uuid(),
+            testutils::rand::string(10),
+            testutils::rand::email(),
+            testutils::rand::string(10),
+            testutils::rand::string(10),
+            testutils::rand::i64(100000, 1000000),
+        )
+        .expect("account should be created properly")
+    }
+
+    fn new_token(account: &AccountEntity) -> TokenEntity {
+        let profile = login_profile(account).expect("profile should be issued properly");
+        let mut token = TokenEntity::new(
+            None,
+            account.email().to_string(),
+            *account.role(),
+            profile.bearer_token,
+            account.id().to_string(),
+        )
+        .expect("token should be created properly");
+        token.set_idempotency_key(
+            IdempotencyKey::new(testutils::rand::string(10))
+                .expect("idempotency key should be created properly")
+                .into(),
+        );
+        token
+    }
+
+    #[test]
+    fn test_replayed_profile_returns_same_token() {
+        let account = new_account();
+        let token = new_token(&account);
+        let profile = replayed_profile(&token, &account)
+            .expect("same account should be allowed to replay")
+            .expect("unexpired profile should be replayed");
+        let value = TokenValue::new(profile.bearer_token).expect("token value should be created");
+        assert_eq!(&value.hash(), token.value_hash());
+    }
+
+    #[test]
+    fn test_replayed_profile_of_changed_account() {
+        let account = new_account();
+        let token = new_token(&account);
+        let mut changed = account.clone();
+        changed.set_email(
+            AccountEmail::new(testutils::rand::email()).expect("email should be created"),
+        );
+        assert!(replayed_profile(&token, &changed)
+            .expect("same account should be allowed to replay")
+            .is_none());
+    }
+
+    #[test]
+    fn test_replayed_profile_conflicts_with_other_account() {
+        let token = new_token(&new_account());
+        assert!(matches!(
+            replayed_profile(&token, &new_account()),
+            Err(Error::Conflict)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_login_with_malformed_idempotency_key() {
+        let state = Arc::new(State::detached());
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            IDEMPOTENCY_KEY_HEADER,
+            testutils::rand::string(256)
+                .parse()
+                .expect("header value should be parsed properly"),
+        );
+        let error = login(
+            Extension(state),
+            Extension(CorrelationId::new()),
+            None,
+            headers,
+            Json(AdminLoginRequest {
+                account: testutils::rand::string(10),
+                password: testutils::rand::string(10),
+            }),
+        )
+        .await
+        .err()
+        .expect("malformed idempotency key should be rejected");
+        assert_eq!(error.into_response().status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[test]
+    fn test_login_profile_role() {
+        let mut account = AccountEntity::new(
+            testutils::rand::uuid(),
+            testutils::rand::string(10),
+            testutils::rand::email(),
+            testutils::rand::string(10),
+            testutils::rand::string(10),
+            testutils::rand::i64(1, 100000),
+        )
+        .expect("account should be created properly");
+        let profile = login_profile(&account).expect("profile should be issued properly");
+        let claims = jwt::decode_claims(&profile.bearer_token)
+            .expect("bearer token should be decoded properly");
+        assert_eq!(claims.role, Role::Guest);
+        account
+            .update(None, None, None, None, Some(String::from("admin")))
+            .expect("account should be promoted properly");
+        let profile = login_profile(&account).expect("profile should be issued properly");
+        let claims = jwt::decode_claims(&profile.bearer_token)
+            .expect("bearer token should be decoded properly");
+        assert_eq!(claims.role, Role::Admin);
+    }
+
+    #[tokio::test]
+    async fn test_preview() {
+        let account = AccountEntity::new(
+            testutils::rand::uuid(),
+            testutils::rand::string(10),
+            testutils::rand::email(),
+            testutils::rand::string(10),
+            testutils::rand::string(10),
+            testutils::rand::i64(1, 100000),
+        )
+        .expect("account should be created properly");
+        let response = preview(
+            Extension(Arc::new(State::detached())),
+            Extension(account),
+            Extension(CorrelationId::new()),
+        )
+        .await
+        .expect("profile should be previewed properly");
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = hyper::body::to_bytes(response.into_body())
+            .await
+            .expect("response body should be read properly");
+        let body: serde_json::Value =
+            serde_json::from_slice(&body).expect("response body should be JSON");
+        let token = body["profile"]["bearerToken"]
+            .as_str()
+            .expect("bearer token should be returned");
+        assert_eq!(token, PREVIEW_BEARER_TOKEN);
+        assert!(jwt::decode_claims(token).is_err());
+        assert!(body["profile"]["expirationTime"].is_string());
+    }
+
+    #[tokio::test]
+    async fn test_preview_with_zero_ttl() {
+        let account = AccountEntity::new(
+            testutils::rand::uuid(),
+            testutils::rand::string(10),
+            testutils::rand::email(),
+            testutils::rand::string(10),
+            testutils::rand::string(10),
+            0,
+        )
+        .expect("account should be created properly");
+        let error = preview(
+            Extension(Arc::new(State::detached())),
+            Extension(account),
+            Extension(CorrelationId::new()),
+        )
+        .await
+        .err()
+        .expect("profile should not be previewed");
+        assert_eq!(error.into_response().status(), StatusCode::BAD_REQUEST);
+    }
+
+    /// Returns a state whose allowlists let the account's provider issue
+    /// profiles to someone else only.
+    fn disallowing(account: &AccountEntity) -> SharedState {
+        Arc::new(State {
+            recipient_allowlists: Some(RecipientAllowlists::new(&std::collections::HashMap::from(
+                [(account.namespace().to_string(), testutils::rand::email())],
+            ))),
+            ..State::detached()
+        })
+    }
+
+    #[tokio::test]
+    async fn test_profile_with_disallowed_recipient() {
+        let account = new_account();
+        let error = profile(
+            Extension(disallowing(&account)),
+            Extension(account),
+            Extension(CorrelationId::new()),
+            None,
+        )
+        .await
+        .err()
+        .expect("profile should not be issued");
+        assert_eq!(error.into_response().status(), StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn test_preview_with_disallowed_recipient() {
+        let account = new_account();
+        let error = preview(
+            Extension(disallowing(&account)),
+            Extension(account),
+            Extension(CorrelationId::new()),
+        )
+        .await
+        .err()
+        .expect("profile should not be previewed");
+        assert_eq!(error.into_response().status(), StatusCode::FORBIDDEN);
+    }
+
+    #[derive(Clone, Default)]
+    struct Recorder(Arc<Mutex<Vec<(String, String)>>>);
+
+    impl<S: Subscriber> Layer<S> for Recorder {
+        fn on_new_span(&self, attrs: &Attributes<'_>, _: &Id, _: Context<'_, S>) {
+            let name = attrs.metadata().name();
+            attrs.record(&mut |field: &Field, value: &dyn std::fmt::Debug| {
+                if field.name() == "correlation_id" {
+                    self.0
+                        .lock()
+                        .expect("recorder should be locked properly")
+                        .push((name.to_string(), format!("{:?}", value)));
+                }
+            });
+        }
+    }
+
+    #[tokio::test]
+    async fn test_profile_correlation() {
+        let recorder = Recorder::default();
+        let _guard =
+            tracing::subscriber::set_default(tracing_subscriber::registry().with(recorder.clone()));
+        let account = AccountEntity::new(
+            testutils::rand::uuid(),
+            testutils::rand::string(10),
+            testutils::rand::email(),
+            testutils::rand::string(10),
+            testutils::rand::string(10),
+            testutils::rand::i64(1, 100000),
+        )
+        .expect("account should be created properly");
+        // NOTE: The audit write fails on the detached pool without failing the request.
+        let state = Arc::new(State::detached());
+        let app = Router::new()
+            .route("/admin/profile", get(profile))
+            .layer(Extension(state))
+            .layer(Extension(account))
+            .layer(middleware::from_fn(correlation::propagate));
+        // NOTE: The id below stands for the one returned by the preceding login.
+        let correlation = testutils::rand::uuid();
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/admin/profile")
+                    .header(correlation::HEADER_NAME, &correlation)
+                    .body(Body::empty())
+                    .expect("request should be built properly"),
+            )
+            .await
+            .expect("request should be handled properly");
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response
+                .headers()
+                .get(correlation::HEADER_NAME)
+                .and_then(|v| v.to_str().ok()),
+            Some(correlation.as_str())
+        );
+        let spans = recorder
+            .0
+            .lock()
+            .expect("recorder should be locked properly")
+            .clone();
+        assert!(spans.contains(&("profile".to_string(), correlation)));
+    }
+
+    async fn exchange(state: &Arc<State>, refresh_token: &str) -> Result<Response, Error> {
+        refresh(
+            Extension(state.clone()),
+            Extension(CorrelationId::new()),
+            None,
+            Json(AdminRefreshRequest {
+                refresh_token: refresh_token.to_string(),
+            }),
+        )
+        .await
+    }
+
+    #[sqlx::test]
+    async fn test_refresh_rotates_and_detects_reuse(pool: PgPool) {
+        let account = new_account();
+        account
+            .save(&pool)
+            .await
+            .expect("account should be stored properly");
+        let access = TokenValue::new(
+            login_profile(&account)
+                .expect("profile should be issued properly")
+                .bearer_token,
+        )
+        .expect("token value should be created");
+        let expires_at = Utc::now() + chrono::Duration::hours(testutils::rand::i64(1, 24));
+        let issued =
+            RefreshTokenService::issue(account.id().as_uuid(), None, expires_at, &access, &pool)
+                .await
+                .expect("refresh token should be issued properly");
+        let state = Arc::new(State::with_pool(pool.clone()));
+        let response = exchange(&state, &issued)
+            .await
+            .expect("refresh token should be exchanged");
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = hyper::body::to_bytes(response.into_body())
+            .await
+            .expect("response body should be read properly");
+        let body: serde_json::Value =
+            serde_json::from_slice(&body).expect("response body should be JSON");
+        let refreshed = body["profile"]["bearerToken"]
+            .as_str()
+            .expect("profile should be refreshed")
+            .to_string();
+        let rotated = body["refreshToken"]
+            .as_str()
+            .expect("refresh token should be rotated")
+            .to_string();
+        assert_ne!(rotated, issued);
+        // NOTE: The successor expires with the family instead of a full ttl later.
+        let successor: chrono::DateTime<Utc> =
+            sqlx::query_scalar("SELECT expires_at FROM refresh_token WHERE used_at IS NULL")
+                .fetch_one(&pool)
+                .await
+                .expect("successor should be selected");
+        assert_eq!(successor.timestamp(), expires_at.timestamp());
+        let error = exchange(&state, &issued)
+            .await
+            .err()
+            .expect("reused refresh token should be rejected");
+        assert_eq!(error.into_response().status(), StatusCode::UNAUTHORIZED);
+        // NOTE: The reuse revokes the access tokens issued within the family too.
+        for token in [access.as_str(), refreshed.as_str()] {
+            assert!(RevocationService::is_revoked(token, &pool)
+                .await
+                .expect("revocation should be checked"));
+        }
+        // NOTE: The reuse revokes the whole family, including the rotated token.
+        let error = exchange(&state, &rotated)
+            .await
+            .err()
+            .expect("refresh token of a revoked family should be rejected");
+        assert_eq!(error.into_response().status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_refresh_with_empty_token() {
+        let state = Arc::new(State::detached());
+        let error = exchange(&state, "")
+            .await
+            .err()
+            .expect("empty refresh token should be rejected");
+        assert_eq!(error.into_response().status(), StatusCode::BAD_REQUEST);
+    }
+}
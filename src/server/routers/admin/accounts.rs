@@ -9,15 +9,20 @@ use axum::response::Response;
 use utoipa::IntoParams;
 use utoipa::ToSchema;
 
+use crate::config;
 use crate::server::entities::account::Entity as AccountEntity;
 use crate::server::entities::account::Name as AccountName;
 use crate::server::routers::SharedState;
 use crate::server::services::account::Account;
 use crate::server::services::account::Service as AccountService;
 use crate::server::services::error::Error;
+use crate::server::utilities::pagination::Cursor;
+use crate::server::utilities::pagination::Utility as PaginationUtility;
 use crate::server::utilities::postgres::Utility as PostgresUtility;
 
-const DEFAULT_PAGE_RESULTS: usize = 10;
+pub mod tokens;
+
+const DEFAULT_PAGE_RESULTS: usize = 100;
 
 #[derive(Debug, serde::Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
@@ -55,16 +60,19 @@ pub async fn post(
     Extension(state): Extension<SharedState>,
     Json(payload): Json<AdminAccountsPostRequest>,
 ) -> Result<Response, Error> {
-    let Ok(account) = AccountEntity::new(
+    let account = match AccountEntity::new(
         payload.id,
         payload.name,
         payload.email,
         payload.password,
         payload.namespace,
         payload.ttl,
-    ) else {
-        tracing::error!("requested account data is malformed");
-        return Err(Error::ValidationFailed);
+    ) {
+        Ok(account) => account,
+        Err(e) => {
+            tracing::error!("requested account data is malformed");
+            return Err(Error::validation(e));
+        }
     };
     match PostgresUtility::error(account.save(&state.pg_pool).await)? {
         Ok(_) => {
@@ -78,8 +86,25 @@ pub async fn post(
                 .into_response())
         }
         Err(e) if PostgresUtility::is_conflict(&e) => {
-            tracing::error!("account was already registered");
-            Err(Error::Conflict)
+            // NOTE: A soft-deleted account with the same name is brought back instead of conflicting.
+            let Ok(resurrected) = account.resurrect(&state.pg_pool).await else {
+                tracing::error!(
+                    "request is not handled correctly due to a server error while updating account"
+                );
+                return Err(anyhow!("error occured while updating account").into());
+            };
+            if resurrected.rows_affected() == 0 {
+                tracing::error!("account was already registered");
+                return Err(Error::Conflict);
+            }
+            tracing::info!("account was successfully resurrected");
+            Ok((
+                StatusCode::CREATED,
+                Json(AdminAccountsPostResponse {
+                    account: Account::from(account),
+                }),
+            )
+                .into_response())
         }
         _ => {
             tracing::error!(
@@ -122,11 +147,14 @@ pub async fn get(
     Extension(state): Extension<SharedState>,
     Path(params): Path<AdminAccountsGetParams>,
 ) -> Result<Response, Error> {
-    let Ok(account) = AccountName::new(params.account) else {
-        tracing::error!("requested account data is malformed");
-        return Err(Error::ValidationFailed);
+    let account = match AccountName::new(params.account) {
+        Ok(account) => account,
+        Err(e) => {
+            tracing::error!("requested account data is malformed");
+            return Err(Error::invalid_field("account", e));
+        }
     };
-    let Ok(account) = AccountService::query_by_name(&account, &state.pg_pool).await else {
+    let Ok(account) = AccountService::query_by_name(&account, state.pg_read_pool()).await else {
         tracing::error!(
             "request is not handled correctly due to a server error while selecting account"
         );
@@ -140,11 +168,156 @@ pub async fn get(
     Ok((StatusCode::OK, Json(AdminAccountsGetResponse { account })).into_response())
 }
 
+#[derive(Debug, serde::Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+pub struct AdminAccountsPatchParams {
+    account: String,
+}
+
+#[derive(Debug, serde::Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct AdminAccountsPatchRequest {
+    pub name: Option<String>,
+    pub email: Option<String>,
+    pub namespace: Option<String>,
+    pub ttl: Option<i64>,
+    pub role: Option<String>,
+}
+
+#[derive(serde::Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct AdminAccountsPatchResponse {
+    pub account: Account,
+}
+
+#[utoipa::path(
+    patch,
+    path = "/admin/accounts/{account}",
+    operation_id = "UpdateAccount",
+    tag = "admin",
+    params(AdminAccountsPatchParams),
+    request_body = AdminAccountsPatchRequest,
+    responses(
+        (status = 200, description = "The account was successfully updated.", body = AdminAccountsPatchResponse),
+        (status = 400, description = "The request is malformed.", body = ErrorMessage),
+        (status = 401, description = "The request is unauthenticated. The bearer token is missing or incorrect.", body = ErrorMessage),
+        (status = 403, description = "The request is forbidden from being fulfilled.", body = ErrorMessage),
+        (status = 404, description = "The requested resource does not exist.", body = ErrorMessage),
+        (status = 409, description = "The account name was already registered.", body = ErrorMessage),
+        (status = 500, description = "The request is not handled correctly due to a server error.", body = ErrorMessage),
+    )
+)]
+#[tracing::instrument(skip(state))]
+pub async fn patch(
+    Extension(state): Extension<SharedState>,
+    Path(params): Path<AdminAccountsPatchParams>,
+    Json(payload): Json<AdminAccountsPatchRequest>,
+) -> Result<Response, Error> {
+    let account = match AccountName::new(params.account) {
+        Ok(account) => account,
+        Err(e) => {
+            tracing::error!("requested account data is malformed");
+            return Err(Error::invalid_field("account", e));
+        }
+    };
+    let Ok(account) = AccountEntity::load(&account, &state.pg_pool).await else {
+        tracing::error!(
+            "request is not handled correctly due to a server error while selecting account"
+        );
+        return Err(anyhow!("error occured while selecting account from database").into());
+    };
+    let Some(mut account) = account else {
+        tracing::error!("requested account does not exist");
+        return Err(Error::NotFound);
+    };
+    if let Err(e) = account.update(
+        payload.name,
+        payload.email,
+        payload.namespace,
+        payload.ttl,
+        payload.role,
+    ) {
+        tracing::error!("requested account data is malformed");
+        return Err(Error::validation(e));
+    }
+    match PostgresUtility::error(account.save(&state.pg_pool).await)? {
+        Ok(_) => {
+            tracing::info!("account was successfully updated");
+            Ok((
+                StatusCode::OK,
+                Json(AdminAccountsPatchResponse {
+                    account: Account::from(account),
+                }),
+            )
+                .into_response())
+        }
+        Err(e) if PostgresUtility::is_conflict(&e) => {
+            tracing::error!("account name was already registered");
+            Err(Error::Conflict)
+        }
+        _ => {
+            tracing::error!(
+                "request is not handled correctly due to a server error while updating account"
+            );
+            Err(anyhow!("error occured while updating account").into())
+        }
+    }
+}
+
+#[derive(Debug, serde::Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+pub struct AdminAccountsDeleteParams {
+    account: String,
+}
+
+#[utoipa::path(
+    delete,
+    path = "/admin/accounts/{account}",
+    operation_id = "DeleteAccount",
+    tag = "admin",
+    params(AdminAccountsDeleteParams),
+    responses(
+        (status = 204, description = "The account was successfully deleted."),
+        (status = 400, description = "The request is malformed.", body = ErrorMessage),
+        (status = 401, description = "The request is unauthenticated. The bearer token is missing or incorrect.", body = ErrorMessage),
+        (status = 403, description = "The request is forbidden from being fulfilled.", body = ErrorMessage),
+        (status = 404, description = "The requested resource does not exist.", body = ErrorMessage),
+        (status = 500, description = "The request is not handled correctly due to a server error.", body = ErrorMessage),
+    )
+)]
+#[tracing::instrument(skip(state))]
+pub async fn delete(
+    Extension(state): Extension<SharedState>,
+    Path(params): Path<AdminAccountsDeleteParams>,
+) -> Result<Response, Error> {
+    let account = match AccountName::new(params.account) {
+        Ok(account) => account,
+        Err(e) => {
+            tracing::error!("requested account data is malformed");
+            return Err(Error::invalid_field("account", e));
+        }
+    };
+    let Ok(deleted) = AccountService::soft_delete(&account, &state.pg_pool).await else {
+        tracing::error!(
+            "request is not handled correctly due to a server error while deleting account"
+        );
+        return Err(anyhow!("error occured while deleting account").into());
+    };
+    if deleted.rows_affected() == 0 {
+        tracing::error!("requested account does not exist");
+        return Err(Error::NotFound);
+    }
+    tracing::info!("account was successfully deleted");
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
+
 #[derive(Debug, serde::Deserialize, IntoParams)]
 #[serde(rename_all = "camelCase")]
 pub struct AdminAccountsListQuery {
     pub max_results: Option<i64>,
     pub page_token: Option<String>,
+    pub order_by: Option<String>,
+    pub direction: Option<String>,
 }
 
 #[derive(serde::Serialize, ToSchema)]
@@ -174,22 +347,37 @@ pub async fn list(
     Extension(state): Extension<SharedState>,
     Query(query): Query<AdminAccountsListQuery>,
 ) -> Result<Response, Error> {
-    let limit = if let Some(limit) = &query.max_results {
-        let Ok(limit) = usize::try_from(*limit) else {
-            tracing::error!("requested limit is malformed");
-            return Err(Error::ValidationFailed);
-        };
-        limit
-    } else {
-        DEFAULT_PAGE_RESULTS
+    let Some(limit) = PaginationUtility::clamp_page_size(
+        query.max_results,
+        DEFAULT_PAGE_RESULTS,
+        config::fetch::<usize>("max_page_results"),
+    ) else {
+        tracing::error!("requested limit is malformed");
+        return Err(Error::ValidationFailed);
     };
-    let after = if let Some(name) = &query.page_token {
-        AccountName::new(name).ok()
-    } else {
-        None
+    let requested = match PaginationUtility::requested_ordering(
+        query.order_by.as_deref(),
+        query.direction.as_deref(),
+    ) {
+        Ok(requested) => requested,
+        Err(e) => {
+            tracing::error!("requested ordering is malformed");
+            return Err(Error::validation(e));
+        }
     };
-    let Ok(accounts) =
-        AccountService::query(Some(&((limit + 1) as i64)), after.as_ref(), &state.pg_pool).await
+    let Ok((ordering, after)) =
+        PaginationUtility::resume("accounts", query.page_token.as_deref(), requested)
+    else {
+        tracing::error!("requested page token is malformed");
+        return Err(Error::ValidationFailed);
+    };
+    let Ok(accounts) = AccountService::query(
+        Some(&((limit + 1) as i64)),
+        &ordering,
+        after.as_ref(),
+        state.pg_read_pool(),
+    )
+    .await
     else {
         tracing::error!(
             "request is not handled correctly due to a server error while selecting accounts"
@@ -204,7 +392,12 @@ pub async fn list(
             StatusCode::OK,
             Json(AdminAccountsListResponse {
                 items: accounts.to_vec(),
-                next_page_token: next.name.clone().into(),
+                next_page_token: PaginationUtility::encode_page(
+                    "accounts",
+                    &ordering,
+                    &Cursor::new(next.name.clone(), next.created_at),
+                )
+                .into(),
             }),
         )
             .into_response());
@@ -219,3 +412,70 @@ pub async fn list(
     )
         .into_response())
 }
+
+#[derive(Debug, serde::Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+pub struct AdminAccountsSearchQuery {
+    pub prefix: String,
+    pub max_results: Option<i64>,
+}
+
+#[derive(serde::Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct AdminAccountsSearchResponse {
+    pub items: Vec<Account>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/accounts/search",
+    operation_id = "SearchAccounts",
+    tag = "admin",
+    params(AdminAccountsSearchQuery),
+    responses(
+        (status = 200, description = "The matching accounts were successfully returned.", body = AdminAccountsSearchResponse),
+        (status = 400, description = "The request is malformed.", body = ErrorMessage),
+        (status = 401, description = "The request is unauthenticated. The bearer token is missing or incorrect.", body = ErrorMessage),
+        (status = 403, description = "The request is forbidden from being fulfilled.", body = ErrorMessage),
+        (status = 500, description = "The request is not handled correctly due to a server error.", body = ErrorMessage),
+    )
+)]
+#[tracing::instrument(skip(state))]
+pub async fn search(
+    Extension(state): Extension<SharedState>,
+    Query(query): Query<AdminAccountsSearchQuery>,
+) -> Result<Response, Error> {
+    if query.prefix.is_empty() {
+        tracing::error!("requested prefix is empty");
+        return Err(Error::invalid_field(
+            "prefix",
+            anyhow!("prefix must not be empty"),
+        ));
+    }
+    let Some(limit) = PaginationUtility::clamp_page_size(
+        query.max_results,
+        DEFAULT_PAGE_RESULTS,
+        config::fetch::<usize>("max_page_results"),
+    ) else {
+        tracing::error!("requested limit is malformed");
+        return Err(Error::ValidationFailed);
+    };
+    let Ok(accounts) = AccountService::search_by_name_prefix(
+        &query.prefix,
+        Some(&(limit as i64)),
+        state.pg_read_pool(),
+    )
+    .await
+    else {
+        tracing::error!(
+            "request is not handled correctly due to a server error while searching accounts"
+        );
+        return Err(anyhow!("error occured while searching account(s)").into());
+    };
+    tracing::info!("accounts were successfully returned");
+    Ok((
+        StatusCode::OK,
+        Json(AdminAccountsSearchResponse { items: accounts }),
+    )
+        .into_response())
+}
@@ -0,0 +1,242 @@
+use anyhow::anyhow;
+use axum::extract::Extension;
+use axum::extract::Json;
+use axum::extract::Path;
+use axum::extract::Query;
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::response::Response;
+use chrono::DateTime;
+use chrono::SecondsFormat;
+use chrono::Utc;
+use utoipa::IntoParams;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::config;
+use crate::server::entities::account::Entity as AccountEntity;
+use crate::server::entities::account::Name as AccountName;
+use crate::server::middlewares::jwt::Role;
+use crate::server::routers::SharedState;
+use crate::server::services::account::Account;
+use crate::server::services::account::Service as AccountService;
+use crate::server::services::error::Error;
+use crate::server::services::token::Service as TokenService;
+use crate::server::services::token::Token;
+use crate::server::utilities::pagination::Utility as PaginationUtility;
+
+const DEFAULT_PAGE_RESULTS: usize = 100;
+
+fn encode_cursor(token: &Token) -> String {
+    format!(
+        "{}/{}",
+        token
+            .created_at
+            .to_rfc3339_opts(SecondsFormat::Micros, true),
+        token.id
+    )
+}
+
+fn decode_cursor(cursor: &str) -> Option<(DateTime<Utc>, Uuid)> {
+    let (created_at, id) = cursor.split_once('/')?;
+    let created_at = DateTime::parse_from_rfc3339(created_at).ok()?;
+    let id = Uuid::parse_str(id).ok()?;
+    Some((created_at.with_timezone(&Utc), id))
+}
+
+/// Returns true when `caller` may list the tokens of `owner`: its own tokens, or
+/// those of any account in its namespace when it is an admin.
+fn authorized(caller: &AccountEntity, owner: &Account) -> bool {
+    if caller.name().as_str() == owner.name {
+        return true;
+    }
+    *caller.role() == Role::Admin && caller.namespace().as_str() == owner.namespace
+}
+
+#[derive(Debug, serde::Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+pub struct AdminAccountsTokensListParams {
+    account: String,
+}
+
+#[derive(Debug, serde::Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+pub struct AdminAccountsTokensListQuery {
+    pub max_results: Option<i64>,
+    pub page_token: Option<String>,
+}
+
+#[derive(serde::Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct AdminAccountsTokensListResponse {
+    pub items: Vec<Token>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_page_token: Option<String>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/accounts/{account}/tokens",
+    operation_id = "ListAccountTokens",
+    tag = "admin",
+    params(AdminAccountsTokensListParams, AdminAccountsTokensListQuery),
+    responses(
+        (status = 200, description = "The tokens issued for the account were successfully returned.", body = AdminAccountsTokensListResponse),
+        (status = 400, description = "The request is malformed.", body = ErrorMessage),
+        (status = 401, description = "The request is unauthenticated. The bearer token is missing or incorrect.", body = ErrorMessage),
+        (status = 403, description = "The request is forbidden from being fulfilled.", body = ErrorMessage),
+        (status = 404, description = "The requested resource does not exist.", body = ErrorMessage),
+        (status = 500, description = "The request is not handled correctly due to a server error.", body = ErrorMessage),
+    )
+)]
+#[tracing::instrument(skip(state, account))]
+pub async fn list(
+    Extension(account): Extension<AccountEntity>,
+    Extension(state): Extension<SharedState>,
+    Path(params): Path<AdminAccountsTokensListParams>,
+    Query(query): Query<AdminAccountsTokensListQuery>,
+) -> Result<Response, Error> {
+    let owner = match AccountName::new(params.account) {
+        Ok(owner) => owner,
+        Err(e) => {
+            tracing::error!("requested account data is malformed");
+            return Err(Error::invalid_field("account", e));
+        }
+    };
+    let Some(limit) = PaginationUtility::clamp_page_size(
+        query.max_results,
+        DEFAULT_PAGE_RESULTS,
+        config::fetch::<usize>("max_page_results"),
+    ) else {
+        tracing::error!("requested limit is malformed");
+        return Err(Error::ValidationFailed);
+    };
+    let scope = format!("accounts/{}/tokens", owner.as_str());
+    let after = if let Some(token) = &query.page_token {
+        let Some(cursor) = PaginationUtility::decode(&scope, token)
+            .ok()
+            .and_then(|cursor| decode_cursor(&cursor))
+        else {
+            tracing::error!("requested page token is malformed");
+            return Err(Error::ValidationFailed);
+        };
+        Some(cursor)
+    } else {
+        None
+    };
+    let Ok(found) = AccountService::query_by_name(&owner, state.pg_read_pool()).await else {
+        tracing::error!(
+            "request is not handled correctly due to a server error while selecting account"
+        );
+        return Err(anyhow!("error occured while querying account").into());
+    };
+    let Some(found) = found else {
+        tracing::error!("requested account does not exist");
+        return Err(Error::NotFound);
+    };
+    if !self::authorized(&account, &found) {
+        tracing::error!("requested account is not accessible by the authenticated account");
+        return Err(Error::Forbidden);
+    }
+    let Ok(tokens) = TokenService::query_by_account_name(
+        &owner,
+        Some(&((limit + 1) as i64)),
+        after.as_ref(),
+        state.pg_read_pool(),
+    )
+    .await
+    else {
+        tracing::error!(
+            "request is not handled correctly due to a server error while selecting tokens"
+        );
+        return Err(anyhow!("error occured while querying token(s)").into());
+    };
+    if tokens.len() == limit + 1 {
+        let next = &tokens[limit];
+        let tokens = &tokens[..limit];
+        tracing::info!("tokens were successfully returned");
+        return Ok((
+            StatusCode::OK,
+            Json(AdminAccountsTokensListResponse {
+                items: tokens.to_vec(),
+                next_page_token: PaginationUtility::encode(&scope, &encode_cursor(next)).into(),
+            }),
+        )
+            .into_response());
+    }
+    tracing::info!("tokens were successfully returned");
+    Ok((
+        StatusCode::OK,
+        Json(AdminAccountsTokensListResponse {
+            items: tokens,
+            next_page_token: None,
+        }),
+    )
+        .into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cursor_round_trip() {
+        let token = Token {
+            id: testutils::rand::uuid(),
+            account: testutils::rand::string(10),
+            email: testutils::rand::email(),
+            role: String::from("Guest"),
+            expires_at: None,
+            active: true,
+            created_at: Utc::now(),
+        };
+        let (created_at, id) =
+            decode_cursor(&encode_cursor(&token)).expect("cursor should be decoded properly");
+        assert_eq!(
+            created_at.timestamp_micros(),
+            token.created_at.timestamp_micros()
+        );
+        assert_eq!(id.to_string(), token.id);
+    }
+
+    fn new_account(namespace: &str) -> AccountEntity {
+        AccountEntity::new(
+            testutils::rand::uuid(),
+            testutils::rand::string(10),
+            testutils::rand::email(),
+            testutils::rand::string(10),
+            namespace.to_owned(),
+            testutils::rand::i64(1, 100000),
+        )
+        .expect("account should be created properly")
+    }
+
+    #[test]
+    fn test_authorized() {
+        let namespace = testutils::rand::string(10);
+        let caller = new_account(&namespace);
+        let owner = new_account(&namespace);
+        assert!(authorized(&caller, &Account::from(caller.clone())));
+        assert!(!authorized(&caller, &Account::from(owner.clone())));
+        let mut admin = new_account(&namespace);
+        admin
+            .update(None, None, None, None, Some(String::from("Admin")))
+            .expect("account should be promoted properly");
+        assert!(authorized(&admin, &Account::from(owner)));
+        assert!(!authorized(
+            &admin,
+            &Account::from(new_account(&testutils::rand::string(10)))
+        ));
+    }
+
+    #[test]
+    fn test_malformed_cursor() {
+        assert!(decode_cursor(&testutils::rand::string(10)).is_none());
+        assert!(decode_cursor(&format!(
+            "{}/{}",
+            Utc::now().to_rfc3339(),
+            testutils::rand::string(10)
+        ))
+        .is_none());
+    }
+}
@@ -0,0 +1,320 @@
+use anyhow::anyhow;
+use axum::extract::Extension;
+use axum::extract::Json;
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::response::Response;
+use utoipa::ToSchema;
+
+use crate::server::entities::account::Entity as AccountEntity;
+use crate::server::entities::token::Email as TokenEmail;
+use crate::server::entities::token::Entity as TokenEntity;
+use crate::server::middlewares::correlation::CorrelationId;
+use crate::server::middlewares::jwt::Role;
+use crate::server::middlewares::request_id::RequestId;
+use crate::server::routers::SharedState;
+use crate::server::services::audit::Service as AuditService;
+use crate::server::services::audit::Trace as AuditTrace;
+use crate::server::services::error::Error;
+use crate::server::services::profile::Allowlists as RecipientAllowlists;
+use crate::server::services::profile::Profile;
+use crate::server::services::profile::RecipientNotAllowed;
+use crate::server::services::profile::Service as ProfileService;
+use crate::server::utilities::validation::FieldError;
+use crate::server::utilities::validation::ValidationError;
+
+#[derive(Debug, serde::Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct AdminProfilesBatchEntry {
+    pub recipient: String,
+    pub ttl: i64,
+}
+
+#[derive(Debug, serde::Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct AdminProfilesBatchRequest {
+    pub recipients: Vec<AdminProfilesBatchEntry>,
+}
+
+#[derive(serde::Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct AdminProfilesBatchResult {
+    pub recipient: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub profile: Option<Profile>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub details: Vec<FieldError>,
+}
+
+#[derive(serde::Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct AdminProfilesBatchResponse {
+    pub results: Vec<AdminProfilesBatchResult>,
+}
+
+/// Issues the guest profile of `recipient` along with the token recording it.
+/// Validation errors are returned as [`ValidationError`] so that they can be
+/// reported per recipient.
+fn issue(
+    allowlists: Option<&RecipientAllowlists>,
+    account: &AccountEntity,
+    recipient: &str,
+    ttl: i64,
+) -> anyhow::Result<(Profile, TokenEntity)> {
+    let mut errors = ValidationError::default();
+    errors.check("recipient", TokenEmail::new(recipient.to_string()));
+    let profile = match ProfileService::issue_guest(
+        allowlists,
+        recipient.to_string(),
+        recipient.to_string(),
+        account.namespace().to_string(),
+        ttl,
+    ) {
+        Ok(profile) => Some(profile),
+        Err(e) => match e.downcast::<ValidationError>() {
+            Ok(e) => {
+                errors.fields.extend(e.fields);
+                None
+            }
+            Err(e) => return Err(e),
+        },
+    };
+    let Some(profile) = profile.filter(|_| errors.fields.is_empty()) else {
+        return Err(errors.into());
+    };
+    let token = TokenEntity::new(
+        None,
+        recipient.to_string(),
+        Role::Guest,
+        profile.bearer_token.clone(),
+        account.id().to_string(),
+    )?;
+    Ok((profile, token))
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/profiles/batch",
+    operation_id = "IssueProfiles",
+    tag = "admin",
+    request_body = AdminProfilesBatchRequest,
+    responses(
+        (status = 201, description = "The profiles were successfully issued.", body = AdminProfilesBatchResponse),
+        (status = 400, description = "The request is malformed. No profile was issued and the invalid recipients are reported inline.", body = AdminProfilesBatchResponse),
+        (status = 401, description = "The request is unauthenticated. The bearer token is missing or incorrect.", body = ErrorMessage),
+        (status = 403, description = "The request is forbidden from being fulfilled. A recipient is not on the provider's allowlist.", body = ErrorMessage),
+        (status = 500, description = "The request is not handled correctly due to a server error.", body = ErrorMessage),
+    )
+)]
+#[tracing::instrument(skip(state, account, correlation, request_id), fields(correlation_id = %correlation))]
+pub async fn batch(
+    Extension(state): Extension<SharedState>,
+    Extension(account): Extension<AccountEntity>,
+    Extension(correlation): Extension<CorrelationId>,
+    request_id: Option<Extension<RequestId>>,
+    Json(payload): Json<AdminProfilesBatchRequest>,
+) -> Result<Response, Error> {
+    if payload.recipients.is_empty() {
+        tracing::error!("requested recipients are empty");
+        return Err(Error::invalid_field(
+            "recipients",
+            anyhow!("recipients must not be empty"),
+        ));
+    }
+    let mut results = Vec::with_capacity(payload.recipients.len());
+    let mut tokens = Vec::with_capacity(payload.recipients.len());
+    for entry in &payload.recipients {
+        match self::issue(
+            state.recipient_allowlists.as_ref(),
+            &account,
+            &entry.recipient,
+            entry.ttl,
+        ) {
+            Ok((profile, token)) => {
+                tokens.push(token);
+                results.push(AdminProfilesBatchResult {
+                    recipient: entry.recipient.clone(),
+                    profile: Some(profile),
+                    details: Vec::new(),
+                });
+            }
+            Err(e) => match e.downcast::<ValidationError>() {
+                Ok(e) => {
+                    results.push(AdminProfilesBatchResult {
+                        recipient: entry.recipient.clone(),
+                        profile: None,
+                        details: e.fields,
+                    });
+                }
+                Err(e) if e.is::<RecipientNotAllowed>() => {
+                    tracing::error!("requested recipients are not on the provider's allowlist");
+                    return Err(Error::Forbidden);
+                }
+                Err(_) => {
+                    tracing::error!(
+                        "request is not handled correctly due to a server error while creating profile"
+                    );
+                    return Err(anyhow!("failed to create profile").into());
+                }
+            },
+        }
+    }
+    if tokens.len() < results.len() {
+        tracing::error!("requested recipients are malformed");
+        // NOTE: A partially valid batch issues nothing, so the profiles are withheld as well.
+        for result in results.iter_mut() {
+            result.profile = None;
+        }
+        return Ok((
+            StatusCode::BAD_REQUEST,
+            Json(AdminProfilesBatchResponse { results }),
+        )
+            .into_response());
+    }
+    let Ok(_) = TokenEntity::save_all(&tokens, &state.pg_pool).await else {
+        tracing::error!(
+            "request is not handled correctly due to a server error while updating token"
+        );
+        return Err(anyhow!("error occured while updating token").into());
+    };
+    tracing::info!("tokens were successfully registered");
+    // NOTE: Audit logging is best-effort; a failed write is logged and the profiles are still returned.
+    let trace = AuditTrace::new(request_id.as_deref(), Some(&correlation));
+    for entry in &payload.recipients {
+        if let Err(e) = AuditService::record_profile_issuance(
+            account.namespace().as_str(),
+            &entry.recipient,
+            entry.ttl,
+            &trace,
+            &state.pg_pool,
+        )
+        .await
+        {
+            tracing::error!("failed to record profile issuance: {:#}", e);
+        }
+    }
+    tracing::info!("profiles were successfully returned");
+    Ok((
+        StatusCode::CREATED,
+        Json(AdminProfilesBatchResponse { results }),
+    )
+        .into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::entities::token::Value as TokenValue;
+    use crate::server::routers::State;
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    fn new_account() -> AccountEntity {
+        AccountEntity::new(
+            testutils::rand::uuid(),
+            testutils::rand::string(10),
+            testutils::rand::email(),
+            testutils::rand::string(10),
+            testutils::rand::string(10),
+            testutils::rand::i64(1, 100000),
+        )
+        .expect("account should be created properly")
+    }
+
+    #[test]
+    fn test_issue() {
+        let account = new_account();
+        let recipient = testutils::rand::email();
+        let (profile, token) = issue(None, &account, &recipient, testutils::rand::i64(1, 100000))
+            .expect("profile should be issued properly");
+        assert_eq!(token.email().as_str(), recipient);
+        assert_eq!(token.role(), &Role::Guest);
+        let value = TokenValue::new(profile.bearer_token).expect("token value should be created");
+        assert_eq!(&value.hash(), token.value_hash());
+        assert_eq!(token.created_by(), account.id());
+    }
+
+    #[test]
+    fn test_issue_with_invalid_recipient() {
+        let error = issue(None, &new_account(), &testutils::rand::string(10), 0)
+            .err()
+            .expect("malformed recipient should be rejected");
+        let error = error
+            .downcast::<ValidationError>()
+            .expect("error should be a validation error");
+        assert_eq!(error.names(), vec!["recipient", "ttl"]);
+    }
+
+    #[tokio::test]
+    async fn test_batch_with_invalid_recipient() {
+        let state = Arc::new(State::detached());
+        let valid = testutils::rand::email();
+        let invalid = testutils::rand::string(10);
+        let response = batch(
+            Extension(state),
+            Extension(new_account()),
+            Extension(CorrelationId::new()),
+            None,
+            Json(AdminProfilesBatchRequest {
+                recipients: vec![
+                    AdminProfilesBatchEntry {
+                        recipient: valid.clone(),
+                        ttl: testutils::rand::i64(1, 100000),
+                    },
+                    AdminProfilesBatchEntry {
+                        recipient: invalid.clone(),
+                        ttl: testutils::rand::i64(1, 100000),
+                    },
+                ],
+            }),
+        )
+        .await
+        .expect("invalid recipients should be reported inline");
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let body = hyper::body::to_bytes(response.into_body())
+            .await
+            .expect("response body should be read properly");
+        let body: serde_json::Value =
+            serde_json::from_slice(&body).expect("response body should be JSON");
+        assert_eq!(body["results"][0]["recipient"], valid.as_str());
+        assert!(body["results"][0].get("profile").is_none());
+        assert!(body["results"][0].get("details").is_none());
+        assert_eq!(body["results"][1]["recipient"], invalid.as_str());
+        assert_eq!(body["results"][1]["details"][0]["field"], "recipient");
+    }
+
+    #[tokio::test]
+    async fn test_batch_with_disallowed_recipient() {
+        let account = new_account();
+        let allowed = testutils::rand::email();
+        let state = Arc::new(State {
+            recipient_allowlists: Some(RecipientAllowlists::new(&HashMap::from([(
+                account.namespace().to_string(),
+                allowed.clone(),
+            )]))),
+            ..State::detached()
+        });
+        let error = batch(
+            Extension(state),
+            Extension(account),
+            Extension(CorrelationId::new()),
+            None,
+            Json(AdminProfilesBatchRequest {
+                recipients: vec![
+                    AdminProfilesBatchEntry {
+                        recipient: allowed,
+                        ttl: testutils::rand::i64(1, 100000),
+                    },
+                    AdminProfilesBatchEntry {
+                        recipient: testutils::rand::email(),
+                        ttl: testutils::rand::i64(1, 100000),
+                    },
+                ],
+            }),
+        )
+        .await
+        .err()
+        .expect("disallowed recipients should be rejected");
+        assert_eq!(error.into_response().status(), StatusCode::FORBIDDEN);
+    }
+}
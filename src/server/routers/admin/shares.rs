@@ -7,6 +7,7 @@ use axum::response::Response;
 use utoipa::ToSchema;
 
 use crate::server::entities::account::Entity as AccountEntity;
+use crate::server::entities::share::DefaultTtl as ShareDefaultTtl;
 use crate::server::entities::share::Entity as ShareEntity;
 use crate::server::routers::SharedState;
 use crate::server::services::error::Error;
@@ -19,6 +20,9 @@ pub mod schemas;
 #[serde(rename_all = "camelCase")]
 pub struct AdminSharesPostRequest {
     pub name: String,
+    /// TTL in seconds of URLs signed for the share's tables, used instead of the
+    /// global `signed_url_ttl` unless a query requests its own.
+    pub default_ttl: Option<i64>,
 }
 
 #[derive(serde::Serialize, ToSchema)]
@@ -47,10 +51,22 @@ pub async fn post(
     Extension(state): Extension<SharedState>,
     Json(payload): Json<AdminSharesPostRequest>,
 ) -> Result<Response, Error> {
-    let Ok(share) = ShareEntity::new(None, payload.name, account.id().to_string()) else {
-        tracing::error!("requested share data is malformed");
-        return Err(Error::ValidationFailed);
+    let mut share = match ShareEntity::new(None, payload.name, account.id().to_string()) {
+        Ok(share) => share,
+        Err(e) => {
+            tracing::error!("requested share data is malformed");
+            return Err(Error::validation(e));
+        }
     };
+    if let Some(default_ttl) = payload.default_ttl {
+        match ShareDefaultTtl::new(default_ttl) {
+            Ok(default_ttl) => share.set_default_ttl(Some(default_ttl)),
+            Err(e) => {
+                tracing::error!("requested share default ttl is malformed");
+                return Err(Error::invalid_field("defaultTtl", e));
+            }
+        };
+    }
     match PostgresUtility::error(share.save(&state.pg_pool).await)? {
         Ok(_) => {
             tracing::info!("share was successfully registered");
@@ -60,9 +60,12 @@ pub async fn post(
     Path(params): Path<AdminSharesSchemasPostParams>,
     Json(payload): Json<AdminSharesSchemasPostRequest>,
 ) -> Result<Response, Error> {
-    let Ok(share_name) = ShareName::new(params.share) else {
-        tracing::error!("requested share data is malformed");
-        return Err(Error::ValidationFailed);
+    let share_name = match ShareName::new(params.share) {
+        Ok(share_name) => share_name,
+        Err(e) => {
+            tracing::error!("requested share data is malformed");
+            return Err(Error::invalid_field("share", e));
+        }
     };
     let Ok(maybe_share) = ShareEntity::load(&share_name, &state.pg_pool).await else {
         tracing::error!(
@@ -74,18 +77,24 @@ pub async fn post(
         tracing::error!("share was not found");
         return Err(Error::NotFound);
     };
-    let Ok(schema_name) = SchemaName::new(payload.name) else {
-        tracing::error!("schema name is malformed");
-        return Err(Error::ValidationFailed);
+    let schema_name = match SchemaName::new(payload.name) {
+        Ok(schema_name) => schema_name,
+        Err(e) => {
+            tracing::error!("schema name is malformed");
+            return Err(Error::invalid_field("name", e));
+        }
     };
-    let Ok(schema) = SchemaEntity::new(
+    let schema = match SchemaEntity::new(
         None,
         schema_name.to_string(),
         share.id().to_string(),
         account.id().to_string(),
-    ) else {
-        tracing::error!("requested schema data is malformed");
-        return Err(Error::ValidationFailed);
+    ) {
+        Ok(schema) => schema,
+        Err(e) => {
+            tracing::error!("requested schema data is malformed");
+            return Err(Error::validation(e));
+        }
     };
     match PostgresUtility::error(schema.save(&state.pg_pool).await)? {
         Ok(_) => {
@@ -5,20 +5,27 @@ use axum::extract::Path;
 use axum::http::StatusCode;
 use axum::response::IntoResponse;
 use axum::response::Response;
+use deltalake::schema::Schema;
+use std::str::FromStr;
 use utoipa::IntoParams;
 use utoipa::ToSchema;
 
+use crate::config;
 use crate::server::entities::account::Entity as AccountEntity;
 use crate::server::entities::schema::Entity as SchemaEntity;
 use crate::server::entities::schema::Name as SchemaName;
 use crate::server::entities::share::Entity as ShareEntity;
 use crate::server::entities::share::Name as ShareName;
 use crate::server::entities::table::Entity as TableEntity;
+use crate::server::entities::table::Location as TableLocation;
 use crate::server::entities::table::Name as TableName;
 use crate::server::routers::SharedState;
+use crate::server::services::deltalake::Service as DeltalakeService;
 use crate::server::services::error::Error;
 use crate::server::services::table::Table;
+use crate::server::utilities::deltalake::Utility as DeltalakeUtility;
 use crate::server::utilities::postgres::Utility as PostgresUtility;
+use crate::server::utilities::signed_url::Platform;
 
 #[derive(Debug, serde::Deserialize, IntoParams)]
 #[serde(rename_all = "camelCase")]
@@ -40,6 +47,86 @@ pub struct AdminSharesSchemasTablesPostResponse {
     pub table: Table,
 }
 
+/// How re-registrations of a table are checked against the Delta schema recorded
+/// at the previous registration. `off` rejects re-registrations as conflicts.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, strum_macros::EnumString)]
+enum SchemaCheck {
+    #[strum(ascii_case_insensitive)]
+    Off,
+    #[strum(ascii_case_insensitive)]
+    Warn,
+    #[strum(ascii_case_insensitive)]
+    Error,
+}
+
+/// Returns the `schemaString` of the table's latest snapshot, if it can be read.
+async fn snapshot_schema(location: &str) -> Option<String> {
+    let Ok(table) = DeltalakeUtility::open_table(location).await else {
+        tracing::warn!("failed to load delta table to record its schema");
+        return None;
+    };
+    table
+        .schema()
+        .and_then(|schema| serde_json::to_string(schema).ok())
+}
+
+/// Rejects a re-registration dropping or retyping columns of the recorded schema
+/// under [`SchemaCheck::Error`]; the changes are only logged otherwise. A schema
+/// that cannot be read or parsed is rejected as well, since its compatibility is
+/// unknown.
+fn check_compatibility(
+    check: &SchemaCheck,
+    previous: &TableEntity,
+    current: Option<&str>,
+) -> anyhow::Result<()> {
+    let Some(previous_string) = previous.schema_string() else {
+        return Ok(());
+    };
+    let Some(current) = current else {
+        if *check == SchemaCheck::Error {
+            return Err(anyhow!("schema of the table could not be read"));
+        }
+        tracing::warn!("failed to read delta schema to check its compatibility");
+        return Ok(());
+    };
+    if previous.schema_hash().as_deref() == Some(TableEntity::schema_hash_of(current).as_str()) {
+        return Ok(());
+    }
+    let (Ok(previous), Ok(current)) = (
+        serde_json::from_str::<Schema>(previous_string),
+        serde_json::from_str::<Schema>(current),
+    ) else {
+        if *check == SchemaCheck::Error {
+            return Err(anyhow!("schemas of the table could not be parsed"));
+        }
+        tracing::warn!("failed to parse delta schemas to check their compatibility");
+        return Ok(());
+    };
+    let changes = DeltalakeService::breaking_changes(&previous, &current);
+    if changes.is_empty() {
+        return Ok(());
+    }
+    if *check == SchemaCheck::Error {
+        return Err(anyhow!("breaking schema changes: {}", changes.join(", ")));
+    }
+    tracing::warn!("table schema changed incompatibly: {}", changes.join(", "));
+    Ok(())
+}
+
+/// Accepts locations whose scheme resolves to a supported storage platform.
+fn supported_location(location: &str) -> anyhow::Result<()> {
+    match Platform::from_str(location)? {
+        Platform::None { .. } => Err(anyhow!("location scheme is not supported")),
+        _ => Ok(()),
+    }
+}
+
+/// Registers a table under the schema.
+///
+/// Unless `table_schema_check` is `off`, registering an existing table again
+/// updates its location and compares the new snapshot's schema with the one
+/// recorded before; dropped or retyped columns are logged under `warn` and
+/// rejected under `error`.
 #[utoipa::path(
     post,
     path = "/admin/shares/{share}/schemas/{schema}/tables",
@@ -48,8 +135,9 @@ pub struct AdminSharesSchemasTablesPostResponse {
     params(AdminSharesSchemasTablesPostParams),
     request_body = AdminSharesSchemasTablesPostRequest,
     responses(
+        (status = 200, description = "The table was successfully re-registered.", body = AdminSharesSchemasTablesPostResponse),
         (status = 201, description = "The schema was successfully registered.", body = AdminSharesSchemasTablesPostResponse),
-        (status = 400, description = "The request is malformed.", body = ErrorMessage),
+        (status = 400, description = "The request is malformed or breaks the recorded table schema.", body = ErrorMessage),
         (status = 401, description = "The request is unauthenticated. The bearer token is missing or incorrect.", body = ErrorMessage),
         (status = 409, description = "The schema was already registered.", body = ErrorMessage),
         (status = 500, description = "The request is not handled correctly due to a server error.", body = ErrorMessage),
@@ -62,9 +150,12 @@ pub async fn post(
     Path(params): Path<AdminSharesSchemasTablesPostParams>,
     Json(payload): Json<AdminSharesSchemasTablesPostRequest>,
 ) -> Result<Response, Error> {
-    let Ok(share_name) = ShareName::new(params.share) else {
-        tracing::error!("requested share data is malformed");
-        return Err(Error::ValidationFailed);
+    let share_name = match ShareName::new(params.share) {
+        Ok(share_name) => share_name,
+        Err(e) => {
+            tracing::error!("requested share data is malformed");
+            return Err(Error::invalid_field("share", e));
+        }
     };
     let Ok(maybe_share) = ShareEntity::load(&share_name, &state.pg_pool).await else {
         tracing::error!(
@@ -76,9 +167,12 @@ pub async fn post(
         tracing::error!("share was not found");
         return Err(Error::NotFound);
     };
-    let Ok(schema_name) = SchemaName::new(params.schema) else {
-        tracing::error!("requested share data is malformed");
-        return Err(Error::ValidationFailed);
+    let schema_name = match SchemaName::new(params.schema) {
+        Ok(schema_name) => schema_name,
+        Err(e) => {
+            tracing::error!("requested share data is malformed");
+            return Err(Error::invalid_field("schema", e));
+        }
     };
     let Ok(maybe_schema) = SchemaEntity::load(share.id(), &schema_name, &state.pg_pool).await
     else {
@@ -91,20 +185,79 @@ pub async fn post(
         tracing::error!("share was not found");
         return Err(Error::NotFound);
     };
-    let Ok(table_name) = TableName::new(payload.name) else {
-        tracing::error!("requested table data is malformed");
-        return Err(Error::ValidationFailed);
+    let table_name = match TableName::new(payload.name) {
+        Ok(table_name) => table_name,
+        Err(e) => {
+            tracing::error!("requested table data is malformed");
+            return Err(Error::invalid_field("name", e));
+        }
     };
-    let Ok(table) = TableEntity::new(
+    if let Err(e) = self::supported_location(&payload.location) {
+        tracing::error!("requested table location is not supported");
+        return Err(Error::invalid_field("location", e));
+    }
+    let check = SchemaCheck::from_str(&config::fetch::<String>("table_schema_check"))
+        .unwrap_or(SchemaCheck::Off);
+    let Ok(maybe_table) = TableEntity::load(schema.id(), &table_name, &state.pg_pool).await else {
+        tracing::error!(
+            "request is not handled correctly due to a server error while selecting table"
+        );
+        return Err(anyhow!("error occured while selecting table").into());
+    };
+    let schema_string = if check == SchemaCheck::Off {
+        None
+    } else {
+        self::snapshot_schema(&payload.location).await
+    };
+    if let Some(mut table) = maybe_table {
+        if check == SchemaCheck::Off {
+            tracing::error!("table was already registered");
+            return Err(Error::Conflict);
+        }
+        if let Err(e) = self::check_compatibility(&check, &table, schema_string.as_deref()) {
+            tracing::error!("requested table schema is incompatible with the recorded one");
+            return Err(Error::invalid_field("location", e));
+        }
+        let location = match TableLocation::new(payload.location) {
+            Ok(location) => location,
+            Err(e) => {
+                tracing::error!("requested table data is malformed");
+                return Err(Error::invalid_field("location", e));
+            }
+        };
+        table.set_location(location);
+        if schema_string.is_some() {
+            table.set_schema_string(schema_string);
+        }
+        let Ok(_) = table.save(&state.pg_pool).await else {
+            tracing::error!(
+                "request is not handled correctly due to a server error while updating table"
+            );
+            return Err(anyhow!("error occured while updating table").into());
+        };
+        tracing::info!("table was successfully re-registered");
+        return Ok((
+            StatusCode::OK,
+            Json(AdminSharesSchemasTablesPostResponse {
+                table: Table::from(table),
+            }),
+        )
+            .into_response());
+    }
+    let mut table = match TableEntity::new(
         None,
         table_name.to_string(),
         schema.id().to_string(),
         payload.location,
         account.id().to_string(),
-    ) else {
-        tracing::error!("requested schema data is malformed");
-        return Err(Error::ValidationFailed);
+    ) {
+        Ok(table) => table,
+        Err(e) => {
+            tracing::error!("requested table data is malformed");
+            return Err(Error::validation(e));
+        }
     };
+    table.set_schema_string(schema_string);
     match PostgresUtility::error(table.save(&state.pg_pool).await)? {
         Ok(_) => {
             tracing::info!("table was successfully registered");
@@ -128,3 +281,108 @@ pub async fn post(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_supported_location() {
+        let location = format!(
+            "s3://{}/{}",
+            testutils::rand::string(10).to_lowercase(),
+            testutils::rand::string(10)
+        );
+        assert!(supported_location(&location).is_ok());
+    }
+
+    #[test]
+    fn test_unsupported_location() {
+        let location = format!(
+            "http://{}/{}",
+            testutils::rand::string(10).to_lowercase(),
+            testutils::rand::string(10)
+        );
+        assert!(supported_location(&location).is_err());
+        assert!(supported_location(&testutils::rand::string(10)).is_err());
+    }
+
+    fn new_table(schema_string: Option<String>) -> TableEntity {
+        let mut table = TableEntity::new(
+            None,
+            testutils::rand::string(10),
+            testutils::rand::uuid(),
+            testutils::rand::string(10),
+            testutils::rand::uuid(),
+        )
+        .expect("table should be created properly");
+        table.set_schema_string(schema_string);
+        table
+    }
+
+    fn schema_string(fields: serde_json::Value) -> String {
+        serde_json::json!({"type": "struct", "fields": fields}).to_string()
+    }
+
+    #[test]
+    fn test_schema_check() {
+        assert_eq!(
+            SchemaCheck::from_str("WARN").expect("schema check should be parsed"),
+            SchemaCheck::Warn
+        );
+        assert!(SchemaCheck::from_str(&testutils::rand::string(10)).is_err());
+    }
+
+    #[test]
+    fn test_check_compatibility_with_added_column() {
+        let previous = new_table(Some(schema_string(serde_json::json!([
+            {"name": "id", "type": "long", "nullable": true, "metadata": {}},
+        ]))));
+        let current = schema_string(serde_json::json!([
+            {"name": "id", "type": "long", "nullable": true, "metadata": {}},
+            {"name": "name", "type": "string", "nullable": true, "metadata": {}},
+        ]));
+        assert!(check_compatibility(&SchemaCheck::Error, &previous, Some(&current)).is_ok());
+    }
+
+    #[test]
+    fn test_check_compatibility_with_dropped_column() {
+        let previous = new_table(Some(schema_string(serde_json::json!([
+            {"name": "id", "type": "long", "nullable": true, "metadata": {}},
+            {"name": "name", "type": "string", "nullable": true, "metadata": {}},
+        ]))));
+        let current = schema_string(serde_json::json!([
+            {"name": "id", "type": "long", "nullable": true, "metadata": {}},
+        ]));
+        let e = check_compatibility(&SchemaCheck::Error, &previous, Some(&current))
+            .expect_err("dropped column should be rejected");
+        assert!(e.to_string().contains(r#"column "name" was dropped"#));
+        assert!(check_compatibility(&SchemaCheck::Warn, &previous, Some(&current)).is_ok());
+    }
+
+    #[test]
+    fn test_check_compatibility_without_recorded_schema() {
+        let current = schema_string(serde_json::json!([]));
+        assert!(check_compatibility(&SchemaCheck::Error, &new_table(None), Some(&current)).is_ok());
+    }
+
+    #[test]
+    fn test_check_compatibility_with_unreadable_schema() {
+        let previous = new_table(Some(schema_string(serde_json::json!([
+            {"name": "id", "type": "long", "nullable": true, "metadata": {}},
+        ]))));
+        assert!(check_compatibility(&SchemaCheck::Error, &previous, None).is_err());
+        assert!(check_compatibility(&SchemaCheck::Warn, &previous, None).is_ok());
+        let current = testutils::rand::string(10);
+        assert!(check_compatibility(&SchemaCheck::Error, &previous, Some(&current)).is_err());
+        assert!(check_compatibility(&SchemaCheck::Warn, &previous, Some(&current)).is_ok());
+    }
+
+    #[test]
+    fn test_unsupported_location_is_reported_as_field() {
+        let e = supported_location("http://example.com/table")
+            .expect_err("http location should be rejected");
+        let response = Error::invalid_field("location", e).into_response();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+}
@@ -0,0 +1,432 @@
+use anyhow::anyhow;
+use axum::extract::Extension;
+use axum::extract::Json;
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::response::Response;
+use chrono::TimeZone;
+use chrono::Utc;
+use utoipa::ToSchema;
+
+use crate::server::entities::account::Entity as AccountEntity;
+use crate::server::entities::token::Entity as TokenEntity;
+use crate::server::entities::token::Value as TokenValue;
+use crate::server::middlewares::jwt;
+use crate::server::middlewares::jwt::Claims;
+use crate::server::routers::SharedState;
+use crate::server::services::error::Error;
+use crate::server::services::revocation::Service as RevocationService;
+
+#[derive(serde::Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct AdminTokensIntrospectRequest {
+    pub token: String,
+}
+
+impl std::fmt::Debug for AdminTokensIntrospectRequest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AdminTokensIntrospectRequest")
+            .field("token", &"***")
+            .finish()
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, serde::Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct AdminTokensIntrospectResponse {
+    pub active: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recipient: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub provider: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exp: Option<i64>,
+}
+
+impl AdminTokensIntrospectResponse {
+    fn inactive() -> Self {
+        Self {
+            active: false,
+            recipient: None,
+            provider: None,
+            exp: None,
+        }
+    }
+}
+
+/// Describes a token whose signature and expiration were verified, exactly as the
+/// sharing endpoints verify bearer tokens; a token which is no longer stored or is
+/// on the revocation list is reported as inactive like an invalid one.
+fn introspection(claims: Claims, stored: bool, revoked: bool) -> AdminTokensIntrospectResponse {
+    if !stored || revoked {
+        return AdminTokensIntrospectResponse::inactive();
+    }
+    AdminTokensIntrospectResponse {
+        active: true,
+        recipient: Some(claims.name),
+        provider: Some(claims.namespace),
+        exp: Some(claims.exp),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/tokens/introspect",
+    operation_id = "IntrospectToken",
+    tag = "admin",
+    request_body = AdminTokensIntrospectRequest,
+    responses(
+        (status = 200, description = "The token was successfully introspected. Invalid, expired, revoked or unknown tokens and tokens of other providers are reported as inactive.", body = AdminTokensIntrospectResponse),
+        (status = 400, description = "The request is malformed.", body = ErrorMessage),
+        (status = 401, description = "The request is unauthenticated. The bearer token is missing or incorrect.", body = ErrorMessage),
+        (status = 403, description = "The request is forbidden from being fulfilled.", body = ErrorMessage),
+        (status = 500, description = "The request is not handled correctly due to a server error.", body = ErrorMessage),
+    )
+)]
+#[tracing::instrument(skip(state, account))]
+pub async fn introspect(
+    Extension(account): Extension<AccountEntity>,
+    Extension(state): Extension<SharedState>,
+    Json(payload): Json<AdminTokensIntrospectRequest>,
+) -> Result<Response, Error> {
+    let inactive = || {
+        (
+            StatusCode::OK,
+            Json(AdminTokensIntrospectResponse::inactive()),
+        )
+            .into_response()
+    };
+    let Ok(claims) = jwt::decode_claims(&payload.token) else {
+        tracing::info!("token cannot be verified");
+        return Ok(inactive());
+    };
+    // NOTE: Tokens of other providers are not disclosed, not even whether they are valid.
+    if claims.namespace != account.namespace().as_str() {
+        tracing::info!("token was issued by another provider");
+        return Ok(inactive());
+    }
+    let Ok(value) = TokenValue::new(payload.token.clone()) else {
+        tracing::info!("token is malformed");
+        return Ok(inactive());
+    };
+    let Ok(stored) = TokenEntity::load_by_value(&value, &state.pg_pool).await else {
+        tracing::error!(
+            "request is not handled correctly due to a server error while selecting token"
+        );
+        return Err(anyhow!("error occured while selecting token from database").into());
+    };
+    let Ok(revoked) = RevocationService::is_revoked(&payload.token, &state.pg_pool).await else {
+        tracing::error!(
+            "request is not handled correctly due to a server error while selecting revoked token"
+        );
+        return Err(anyhow!("error occured while selecting revoked token from database").into());
+    };
+    tracing::info!("token was successfully introspected");
+    Ok((
+        StatusCode::OK,
+        Json(self::introspection(claims, stored.is_some(), revoked)),
+    )
+        .into_response())
+}
+
+#[derive(serde::Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct AdminTokensRevokeRequest {
+    pub token: String,
+}
+
+impl std::fmt::Debug for AdminTokensRevokeRequest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AdminTokensRevokeRequest")
+            .field("token", &"***")
+            .finish()
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/tokens/revoke",
+    operation_id = "RevokeToken",
+    tag = "admin",
+    request_body = AdminTokensRevokeRequest,
+    responses(
+        (status = 204, description = "The token was successfully revoked."),
+        (status = 400, description = "The request is malformed. The token is invalid or has already expired.", body = ErrorMessage),
+        (status = 401, description = "The request is unauthenticated. The bearer token is missing or incorrect.", body = ErrorMessage),
+        (status = 403, description = "The request is forbidden from being fulfilled. The token was issued by another provider.", body = ErrorMessage),
+        (status = 500, description = "The request is not handled correctly due to a server error.", body = ErrorMessage),
+    )
+)]
+#[tracing::instrument(skip(state, account))]
+pub async fn revoke(
+    Extension(account): Extension<AccountEntity>,
+    Extension(state): Extension<SharedState>,
+    Json(payload): Json<AdminTokensRevokeRequest>,
+) -> Result<Response, Error> {
+    let Ok(claims) = jwt::decode_claims(&payload.token) else {
+        tracing::error!("token cannot be verified");
+        return Err(Error::ValidationFailed);
+    };
+    if claims.namespace != account.namespace().as_str() {
+        tracing::error!("token was issued by another provider");
+        return Err(Error::Forbidden);
+    }
+    let Some(expires_at) = Utc.timestamp_opt(claims.exp, 0).single() else {
+        tracing::error!("JWT claims' expiration is malformed");
+        return Err(Error::ValidationFailed);
+    };
+    if RevocationService::revoke(&payload.token, expires_at, &state.pg_pool)
+        .await
+        .is_err()
+    {
+        tracing::error!(
+            "request is not handled correctly due to a server error while inserting revoked token"
+        );
+        return Err(anyhow!("error occured while inserting revoked token into database").into());
+    }
+    tracing::info!("token was successfully revoked");
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config;
+    use crate::server::middlewares::jwt::Role;
+    use crate::server::routers::State;
+    use axum::body::Body;
+    use axum::http::Request;
+    use axum::middleware;
+    use axum::routing::get;
+    use axum::Router;
+    use sqlx::PgPool;
+    use std::sync::Arc;
+    use tower::ServiceExt;
+
+    fn new_claims(exp: i64) -> Claims {
+        Claims {
+            name: testutils::rand::string(10),
+            email: testutils::rand::email(),
+            namespace: testutils::rand::string(10),
+            role: Role::Guest,
+            exp,
+            jti: None,
+        }
+    }
+
+    #[test]
+    fn test_introspection_of_active_token() {
+        let exp = chrono::Utc::now().timestamp() + testutils::rand::i64(100000, 1000000);
+        let claims = new_claims(exp);
+        assert_eq!(
+            introspection(claims.clone(), true, false),
+            AdminTokensIntrospectResponse {
+                active: true,
+                recipient: Some(claims.name),
+                provider: Some(claims.namespace),
+                exp: Some(exp),
+            }
+        );
+    }
+
+    #[test]
+    fn test_introspection_of_revoked_token() {
+        let exp = chrono::Utc::now().timestamp() + testutils::rand::i64(100000, 1000000);
+        assert_eq!(
+            introspection(new_claims(exp), true, true),
+            AdminTokensIntrospectResponse::inactive()
+        );
+    }
+
+    #[test]
+    fn test_introspection_of_unstored_token() {
+        let exp = chrono::Utc::now().timestamp() + testutils::rand::i64(100000, 1000000);
+        assert_eq!(
+            introspection(new_claims(exp), false, false),
+            AdminTokensIntrospectResponse::inactive()
+        );
+    }
+
+    fn new_token(claims: &Claims) -> String {
+        let keys = config::jwt_secret(&claims.namespace);
+        jsonwebtoken::encode(&keys.header(), claims, &keys.encoding)
+            .expect("token should be encoded properly")
+    }
+
+    async fn introspected(
+        state: SharedState,
+        account: AccountEntity,
+        token: String,
+    ) -> serde_json::Value {
+        let response = introspect(
+            Extension(account),
+            Extension(state),
+            Json(AdminTokensIntrospectRequest { token }),
+        )
+        .await
+        .expect("token should be introspected");
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = hyper::body::to_bytes(response.into_body())
+            .await
+            .expect("response body should be read properly");
+        serde_json::from_slice(&body).expect("response body should be JSON")
+    }
+
+    async fn stored_token(account: &AccountEntity, claims: &Claims, pool: &PgPool) -> String {
+        account
+            .save(pool)
+            .await
+            .expect("account should be saved properly");
+        let token = new_token(claims);
+        TokenEntity::new(
+            None,
+            claims.email.clone(),
+            claims.role,
+            token.clone(),
+            account.id().to_string(),
+        )
+        .expect("token should be created properly")
+        .save(pool)
+        .await
+        .expect("token should be saved properly");
+        token
+    }
+
+    #[tokio::test]
+    async fn test_introspect_expired_token() {
+        let state = Arc::new(State::detached());
+        let claims = new_claims(chrono::Utc::now().timestamp() - testutils::rand::i64(3600, 7200));
+        assert_eq!(
+            introspected(state, new_account(&claims.namespace), new_token(&claims)).await,
+            serde_json::json!({ "active": false })
+        );
+    }
+
+    #[sqlx::test]
+    async fn test_introspect_stored_token(pool: PgPool) {
+        let exp = chrono::Utc::now().timestamp() + testutils::rand::i64(100000, 1000000);
+        let claims = new_claims(exp);
+        let account = new_account(&claims.namespace);
+        let token = stored_token(&account, &claims, &pool).await;
+        let state = Arc::new(State::with_pool(pool));
+        assert_eq!(
+            introspected(state, account, token).await,
+            serde_json::json!({
+                "active": true,
+                "recipient": claims.name,
+                "provider": claims.namespace,
+                "exp": exp,
+            })
+        );
+    }
+
+    #[sqlx::test]
+    async fn test_introspect_unstored_token(pool: PgPool) {
+        let state = Arc::new(State::with_pool(pool));
+        let exp = chrono::Utc::now().timestamp() + testutils::rand::i64(100000, 1000000);
+        let claims = new_claims(exp);
+        assert_eq!(
+            introspected(state, new_account(&claims.namespace), new_token(&claims)).await,
+            serde_json::json!({ "active": false })
+        );
+    }
+
+    #[sqlx::test]
+    async fn test_introspect_revoked_token(pool: PgPool) {
+        let exp = chrono::Utc::now().timestamp() + testutils::rand::i64(100000, 1000000);
+        let claims = new_claims(exp);
+        let account = new_account(&claims.namespace);
+        let token = stored_token(&account, &claims, &pool).await;
+        RevocationService::revoke(
+            &token,
+            chrono::Utc::now() + chrono::Duration::hours(1),
+            &pool,
+        )
+        .await
+        .expect("token should be revoked");
+        let state = Arc::new(State::with_pool(pool));
+        assert_eq!(
+            introspected(state, account, token).await,
+            serde_json::json!({ "active": false })
+        );
+    }
+
+    #[sqlx::test]
+    async fn test_introspect_token_of_other_provider(pool: PgPool) {
+        let exp = chrono::Utc::now().timestamp() + testutils::rand::i64(100000, 1000000);
+        let claims = new_claims(exp);
+        let token = stored_token(&new_account(&claims.namespace), &claims, &pool).await;
+        let state = Arc::new(State::with_pool(pool));
+        assert_eq!(
+            introspected(state, new_account(&testutils::rand::string(11)), token).await,
+            serde_json::json!({ "active": false })
+        );
+    }
+
+    fn new_account(namespace: &str) -> AccountEntity {
+        AccountEntity::new(
+            testutils::rand::uuid(),
+            testutils::rand::string(10),
+            testutils::rand::email(),
+            testutils::rand::string(10),
+            namespace.to_owned(),
+            testutils::rand::i64(1, 100000),
+        )
+        .expect("account should be created properly")
+    }
+
+    async fn shared(state: SharedState, token: &str) -> StatusCode {
+        let app = Router::new()
+            .route("/shares", get(|| async { "shares" }))
+            .layer(middleware::from_fn(jwt::as_guest))
+            .layer(Extension(state));
+        app.oneshot(
+            Request::builder()
+                .uri("/shares")
+                .header("Authorization", format!("Bearer {}", token))
+                .body(Body::empty())
+                .expect("request should be built properly"),
+        )
+        .await
+        .expect("request should be handled properly")
+        .status()
+    }
+
+    #[sqlx::test]
+    async fn test_revoke_token(pool: PgPool) {
+        let state = Arc::new(State::with_pool(pool));
+        let exp = chrono::Utc::now().timestamp() + testutils::rand::i64(100000, 1000000);
+        let claims = new_claims(exp);
+        let token = new_token(&claims);
+        assert_eq!(shared(state.clone(), &token).await, StatusCode::OK);
+        let response = revoke(
+            Extension(new_account(&claims.namespace)),
+            Extension(state.clone()),
+            Json(AdminTokensRevokeRequest {
+                token: token.clone(),
+            }),
+        )
+        .await
+        .expect("token should be revoked");
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        assert_eq!(shared(state, &token).await, StatusCode::UNAUTHORIZED);
+    }
+
+    #[sqlx::test]
+    async fn test_revoke_token_of_other_provider(pool: PgPool) {
+        let state = Arc::new(State::with_pool(pool));
+        let exp = chrono::Utc::now().timestamp() + testutils::rand::i64(100000, 1000000);
+        let token = new_token(&new_claims(exp));
+        let error = revoke(
+            Extension(new_account(&testutils::rand::string(11))),
+            Extension(state.clone()),
+            Json(AdminTokensRevokeRequest {
+                token: token.clone(),
+            }),
+        )
+        .await
+        .expect_err("token of another provider should not be revoked");
+        assert_eq!(error.into_response().status(), StatusCode::FORBIDDEN);
+        assert_eq!(shared(state, &token).await, StatusCode::OK);
+    }
+}
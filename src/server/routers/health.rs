@@ -0,0 +1,52 @@
+use axum::extract::Extension;
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::response::Response;
+use std::time::Duration;
+
+use crate::server::routers::SharedState;
+use crate::server::services::error::Error;
+
+const READINESS_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[utoipa::path(
+    get,
+    path = "/healthz",
+    operation_id = "GetLiveness",
+    tag = "health",
+    security(()),
+    responses(
+        (status = 200, description = "The server is alive."),
+    )
+)]
+#[tracing::instrument]
+pub async fn healthz() -> Result<Response, Error> {
+    Ok(StatusCode::OK.into_response())
+}
+
+#[utoipa::path(
+    get,
+    path = "/readyz",
+    operation_id = "GetReadiness",
+    tag = "health",
+    security(()),
+    responses(
+        (status = 200, description = "The server is ready to handle requests."),
+        (status = 503, description = "The database is not reachable.", body = ErrorMessage),
+    )
+)]
+#[tracing::instrument(skip(state))]
+pub async fn readyz(Extension(state): Extension<SharedState>) -> Result<Response, Error> {
+    let ping = sqlx::query("SELECT 1").execute(&state.pg_pool);
+    match tokio::time::timeout(READINESS_TIMEOUT, ping).await {
+        Ok(Ok(_)) => Ok(StatusCode::OK.into_response()),
+        Ok(Err(e)) => {
+            tracing::error!("database is not reachable: {}", e);
+            Err(Error::ServiceUnavailable)
+        }
+        Err(_) => {
+            tracing::error!("database did not respond in time");
+            Err(Error::ServiceUnavailable)
+        }
+    }
+}
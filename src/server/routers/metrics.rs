@@ -0,0 +1,31 @@
+use axum::http::header;
+use axum::http::HeaderValue;
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::response::Response;
+
+use crate::server::services::error::Error;
+use crate::server::services::metrics::Service as MetricsService;
+
+#[utoipa::path(
+    get,
+    path = "/metrics",
+    operation_id = "GetMetrics",
+    tag = "health",
+    security(()),
+    responses(
+        (status = 200, description = "The metrics were successfully returned in the Prometheus text format.", body = String),
+    )
+)]
+#[tracing::instrument]
+pub async fn get() -> Result<Response, Error> {
+    Ok((
+        StatusCode::OK,
+        [(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static("text/plain; version=0.0.4"),
+        )],
+        MetricsService::render(),
+    )
+        .into_response())
+}
@@ -9,17 +9,74 @@ use axum::response::Response;
 use utoipa::IntoParams;
 use utoipa::ToSchema;
 
+use crate::config;
+use crate::server::entities::share::Entity as ShareEntity;
 use crate::server::entities::share::Name as ShareName;
+use crate::server::middlewares::jwt::Claims;
 use crate::server::routers::SharedState;
+use crate::server::routers::State;
 use crate::server::services::error::Error;
 use crate::server::services::share::Service as ShareService;
 use crate::server::services::share::Share;
+use crate::server::utilities::pagination::Cursor;
+use crate::server::utilities::pagination::Utility as PaginationUtility;
 
 pub mod all_tables;
 pub mod schemas;
 
 const DEFAULT_PAGE_RESULTS: usize = 10;
 
+/// Parses the share name in the path of a sharing request. Malformed names are
+/// rejected as 400 with `share` as the invalid field.
+pub(crate) fn share_name(name: String) -> Result<ShareName, Error> {
+    match ShareName::new(name) {
+        Ok(share) => Ok(share),
+        Err(e) => {
+            tracing::error!("requested share data is malformed");
+            Err(Error::invalid_field("share", e))
+        }
+    }
+}
+
+/// Resolves the share a sharing request addresses.
+///
+/// Every sharing endpoint applies the same policy to the share in its path. Names
+/// are parsed with [`share_name`] along with the rest of the request before any
+/// lookup, so a malformed request is 400 regardless of what exists. A share the
+/// recipient may not see, including one owned by an account outside the token's
+/// namespace, is 404 exactly like a missing one, so responses never reveal
+/// whether a hidden share exists. Access checks on shares belong here and must
+/// fail with [`Error::NotFound`], never [`Error::Forbidden`].
+pub(crate) async fn visible_share(
+    state: &State,
+    claims: &Claims,
+    share: &ShareName,
+) -> Result<ShareEntity, Error> {
+    let Ok(share) = ShareEntity::load(share, state.pg_read_pool()).await else {
+        tracing::error!(
+            "request is not handled correctly due to a server error while selecting share"
+        );
+        return Err(anyhow!("error occured while selecting share").into());
+    };
+    let Some(share) = share else {
+        tracing::error!("requested share does not exist");
+        return Err(Error::NotFound);
+    };
+    let Ok(namespace) =
+        ShareService::query_namespace_by_name(share.name(), state.pg_read_pool()).await
+    else {
+        tracing::error!(
+            "request is not handled correctly due to a server error while selecting share"
+        );
+        return Err(anyhow!("error occured while selecting share").into());
+    };
+    if namespace.as_deref() != Some(claims.namespace.as_str()) {
+        tracing::error!("requested share is owned outside the JWT claims' namespace");
+        return Err(Error::NotFound);
+    }
+    Ok(share)
+}
+
 #[derive(Debug, serde::Deserialize, IntoParams)]
 #[serde(rename_all = "camelCase")]
 pub struct SharesGetParams {
@@ -47,27 +104,22 @@ pub struct SharesGetResponse {
         (status = 500, description = "The request is not handled correctly due to a server error.", body = ErrorMessage),
     )
 )]
-#[tracing::instrument(skip(state))]
+#[tracing::instrument(skip(state, claims))]
 pub async fn get(
     Extension(state): Extension<SharedState>,
+    Extension(claims): Extension<Claims>,
     Path(params): Path<SharesGetParams>,
 ) -> Result<Response, Error> {
-    let Ok(share) = ShareName::new(params.share) else {
-        tracing::error!("requested share data is malformed");
-        return Err(Error::ValidationFailed);
-    };
-    let Ok(share) = ShareService::query_by_name(&share, &state.pg_pool).await else {
-        tracing::error!(
-            "request is not handled correctly due to a server error while selecting share"
-        );
-        return Err(anyhow!("error occured while selecting share").into());
-    };
-    let Some(share) = share else {
-        tracing::error!("requested share does not exist");
-        return Err(Error::NotFound);
-    };
+    let share = self::share_name(params.share)?;
+    let share = self::visible_share(&state, &claims, &share).await?;
     tracing::info!("share's metadata was successfully returned");
-    Ok((StatusCode::OK, Json(SharesGetResponse { share })).into_response())
+    Ok((
+        StatusCode::OK,
+        Json(SharesGetResponse {
+            share: Share::from(share),
+        }),
+    )
+        .into_response())
 }
 
 #[derive(Debug, serde::Deserialize, IntoParams)]
@@ -75,6 +127,8 @@ pub async fn get(
 pub struct SharesListQuery {
     pub max_results: Option<i64>,
     pub page_token: Option<String>,
+    pub order_by: Option<String>,
+    pub direction: Option<String>,
 }
 
 #[derive(serde::Serialize, ToSchema)]
@@ -99,27 +153,44 @@ pub struct SharesListResponse {
         (status = 500, description = "The request is not handled correctly due to a server error.", body = ErrorMessage),
     )
 )]
-#[tracing::instrument(skip(state))]
+#[tracing::instrument(skip(state, claims))]
 pub async fn list(
     Extension(state): Extension<SharedState>,
+    Extension(claims): Extension<Claims>,
     Query(query): Query<SharesListQuery>,
 ) -> Result<Response, Error> {
-    let limit = if let Some(limit) = &query.max_results {
-        let Ok(limit) = usize::try_from(*limit) else {
-            tracing::error!("requested limit is malformed");
-            return Err(Error::ValidationFailed);
-        };
-        limit
-    } else {
-        DEFAULT_PAGE_RESULTS
+    let Some(limit) = PaginationUtility::clamp_page_size(
+        query.max_results,
+        DEFAULT_PAGE_RESULTS,
+        config::fetch::<usize>("max_page_results"),
+    ) else {
+        tracing::error!("requested limit is malformed");
+        return Err(Error::ValidationFailed);
     };
-    let after = if let Some(name) = &query.page_token {
-        ShareName::new(name).ok()
-    } else {
-        None
+    let requested = match PaginationUtility::requested_ordering(
+        query.order_by.as_deref(),
+        query.direction.as_deref(),
+    ) {
+        Ok(requested) => requested,
+        Err(e) => {
+            tracing::error!("requested ordering is malformed");
+            return Err(Error::validation(e));
+        }
     };
-    let Ok(shares) =
-        ShareService::query(Some(&((limit + 1) as i64)), after.as_ref(), &state.pg_pool).await
+    let Ok((ordering, after)) =
+        PaginationUtility::resume("shares", query.page_token.as_deref(), requested)
+    else {
+        tracing::error!("requested page token is malformed");
+        return Err(Error::ValidationFailed);
+    };
+    let Ok(shares) = ShareService::query(
+        &claims.namespace,
+        Some(&((limit + 1) as i64)),
+        &ordering,
+        after.as_ref(),
+        state.pg_read_pool(),
+    )
+    .await
     else {
         tracing::error!(
             "request is not handled correctly due to a server error while selecting shares"
@@ -134,7 +205,12 @@ pub async fn list(
             StatusCode::OK,
             Json(SharesListResponse {
                 items: shares.to_vec(),
-                next_page_token: next.name.clone().into(),
+                next_page_token: PaginationUtility::encode_page(
+                    "shares",
+                    &ordering,
+                    &Cursor::new(next.name.clone(), next.created_at),
+                )
+                .into(),
             }),
         )
             .into_response());
@@ -149,3 +225,130 @@ pub async fn list(
     )
         .into_response())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::entities::account::Entity as AccountEntity;
+    use crate::server::middlewares::jwt::Role;
+    use crate::server::routers::State;
+    use sqlx::PgPool;
+    use std::sync::Arc;
+
+    fn new_claims(namespace: &str) -> Claims {
+        Claims {
+            name: testutils::rand::string(10),
+            email: testutils::rand::email(),
+            namespace: namespace.to_string(),
+            role: Role::Guest,
+            exp: chrono::Utc::now().timestamp() + testutils::rand::i64(100000, 1000000),
+            jti: None,
+        }
+    }
+
+    #[test]
+    fn test_empty_list_response() {
+        let response = SharesListResponse {
+            items: Vec::new(),
+            next_page_token: None,
+        };
+        assert_eq!(
+            serde_json::to_value(response).expect("response should be serialized"),
+            serde_json::json!({ "items": [] })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_list_with_unsupported_ordering() {
+        let error = list(
+            Extension(Arc::new(State::detached())),
+            Extension(new_claims(&testutils::rand::string(10))),
+            Query(SharesListQuery {
+                max_results: None,
+                page_token: None,
+                order_by: Some(testutils::rand::string(10)),
+                direction: Some("desc".to_string()),
+            }),
+        )
+        .await
+        .err()
+        .expect("unsupported ordering should be rejected");
+        assert_eq!(error.into_response().status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_get_malformed_share() {
+        let error = get(
+            Extension(Arc::new(State::detached())),
+            Extension(new_claims(&testutils::rand::string(10))),
+            Path(SharesGetParams {
+                share: String::new(),
+            }),
+        )
+        .await
+        .err()
+        .expect("malformed share should be rejected");
+        assert_eq!(error.into_response().status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[sqlx::test]
+    async fn test_get_hidden_and_visible_share(pool: PgPool) {
+        let account = AccountEntity::new(
+            testutils::rand::uuid(),
+            testutils::rand::string(10),
+            testutils::rand::email(),
+            testutils::rand::string(10),
+            testutils::rand::string(10),
+            testutils::rand::i64(1, 100000),
+        )
+        .expect("account should be created properly");
+        account
+            .save(&pool)
+            .await
+            .expect("account should be saved properly");
+        let share = ShareEntity::new(
+            testutils::rand::uuid(),
+            testutils::rand::string(10),
+            account.id().to_uuid().to_string(),
+        )
+        .expect("share should be created properly");
+        share
+            .save(&pool)
+            .await
+            .expect("share should be saved properly");
+        let state = Arc::new(State::with_pool(pool));
+        let claims = new_claims(account.namespace().as_str());
+        let error = get(
+            Extension(state.clone()),
+            Extension(claims.clone()),
+            Path(SharesGetParams {
+                share: testutils::rand::string(10),
+            }),
+        )
+        .await
+        .err()
+        .expect("hidden share should not be found");
+        assert_eq!(error.into_response().status(), StatusCode::NOT_FOUND);
+        let error = get(
+            Extension(state.clone()),
+            Extension(new_claims(&testutils::rand::string(11))),
+            Path(SharesGetParams {
+                share: share.name().as_str().to_string(),
+            }),
+        )
+        .await
+        .err()
+        .expect("share owned by another namespace should not be found");
+        assert_eq!(error.into_response().status(), StatusCode::NOT_FOUND);
+        let response = get(
+            Extension(state),
+            Extension(claims),
+            Path(SharesGetParams {
+                share: share.name().as_str().to_string(),
+            }),
+        )
+        .await
+        .expect("visible share should be returned");
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+}
@@ -9,13 +9,15 @@ use axum::response::Response;
 use utoipa::IntoParams;
 use utoipa::ToSchema;
 
-use crate::server::entities::schema::Name as SchemaName;
-use crate::server::entities::share::Entity as ShareEntity;
-use crate::server::entities::share::Name as ShareName;
+use crate::config;
+use crate::server::middlewares::jwt::Claims;
+use crate::server::routers::shares;
 use crate::server::routers::SharedState;
 use crate::server::services::error::Error;
 use crate::server::services::schema::SchemaDetail;
 use crate::server::services::schema::Service as SchemaService;
+use crate::server::utilities::pagination::Cursor;
+use crate::server::utilities::pagination::Utility as PaginationUtility;
 
 pub mod tables;
 
@@ -32,6 +34,8 @@ pub struct SharesSchemasListParams {
 pub struct SharesSchemasListQuery {
     pub max_results: Option<i64>,
     pub page_token: Option<String>,
+    pub order_by: Option<String>,
+    pub direction: Option<String>,
 }
 
 #[derive(serde::Serialize, ToSchema)]
@@ -60,45 +64,46 @@ pub struct SharesSchemasListResponse {
         (status = 500, description = "The request is not handled correctly due to a server error.", body = ErrorMessage),
     )
 )]
-#[tracing::instrument(skip(state))]
+#[tracing::instrument(skip(state, claims))]
 pub async fn list(
     Extension(state): Extension<SharedState>,
+    Extension(claims): Extension<Claims>,
     Path(params): Path<SharesSchemasListParams>,
     Query(query): Query<SharesSchemasListQuery>,
 ) -> Result<Response, Error> {
-    let Ok(share) = ShareName::new(params.share) else {
-        tracing::error!("requested share data is malformed");
+    let share = shares::share_name(params.share)?;
+    let Some(limit) = PaginationUtility::clamp_page_size(
+        query.max_results,
+        DEFAULT_PAGE_RESULTS,
+        config::fetch::<usize>("max_page_results"),
+    ) else {
+        tracing::error!("requested limit is malformed");
         return Err(Error::ValidationFailed);
     };
-    let Ok(share) = ShareEntity::load(&share, &state.pg_pool).await else {
-        tracing::error!(
-            "request is not handled correctly due to a server error while selecting share"
-        );
-        return Err(anyhow!("error occured while selecting share").into());
-    };
-    let Some(share) = share else {
-        tracing::error!("requested share does not exist");
-        return Err(Error::NotFound);
+    let scope = format!("shares/{}/schemas", share.as_str());
+    let requested = match PaginationUtility::requested_ordering(
+        query.order_by.as_deref(),
+        query.direction.as_deref(),
+    ) {
+        Ok(requested) => requested,
+        Err(e) => {
+            tracing::error!("requested ordering is malformed");
+            return Err(Error::validation(e));
+        }
     };
-    let limit = if let Some(limit) = &query.max_results {
-        let Ok(limit) = usize::try_from(*limit) else {
-            tracing::error!("requested limit is malformed");
-            return Err(Error::ValidationFailed);
-        };
-        limit
-    } else {
-        DEFAULT_PAGE_RESULTS
-    };
-    let after = if let Some(name) = &query.page_token {
-        SchemaName::new(name).ok()
-    } else {
-        None
+    let Ok((ordering, after)) =
+        PaginationUtility::resume(&scope, query.page_token.as_deref(), requested)
+    else {
+        tracing::error!("requested page token is malformed");
+        return Err(Error::ValidationFailed);
     };
+    let share = shares::visible_share(&state, &claims, &share).await?;
     let Ok(schemas) = SchemaService::query_by_share_name(
         share.name(),
         Some(&((limit + 1) as i64)),
+        &ordering,
         after.as_ref(),
-        &state.pg_pool,
+        state.pg_read_pool(),
     )
     .await
     else {
@@ -115,7 +120,12 @@ pub async fn list(
             StatusCode::OK,
             Json(SharesSchemasListResponse {
                 items: schemas.to_vec(),
-                next_page_token: next.name.clone().into(),
+                next_page_token: PaginationUtility::encode_page(
+                    &scope,
+                    &ordering,
+                    &Cursor::new(next.name.clone(), next.created_at),
+                )
+                .into(),
             }),
         )
             .into_response());
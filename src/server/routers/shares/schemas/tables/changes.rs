@@ -0,0 +1,259 @@
+use anyhow::anyhow;
+use axum::extract::Extension;
+use axum::extract::Path;
+use axum::extract::Query;
+use axum::http::header;
+use axum::http::header::HeaderMap;
+use axum::http::header::HeaderValue;
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::response::Response;
+use axum_extra::json_lines::JsonLines;
+use chrono::Duration;
+use std::str::FromStr;
+use utoipa::IntoParams;
+
+use crate::server::entities::schema::Name as SchemaName;
+use crate::server::entities::table::Name as TableName;
+use crate::server::middlewares::jwt::Claims;
+use crate::server::middlewares::request_id::RequestId;
+use crate::server::routers::shares;
+use crate::server::routers::shares::schemas::tables::query;
+use crate::server::routers::SharedState;
+use crate::server::services::audit::Service as AuditService;
+use crate::server::services::audit::Trace as AuditTrace;
+use crate::server::services::deltalake::Service as DeltalakeService;
+use crate::server::services::error::Error;
+use crate::server::services::table::Service as TableService;
+use crate::server::utilities::deltalake::Utility as DeltalakeUtility;
+use crate::server::utilities::signed_url::Platform;
+use crate::server::utilities::signed_url::SchemeGuard;
+
+const HEADER_NAME: &str = "Delta-Table-Version";
+
+#[derive(Debug, serde::Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+pub struct SharesSchemasTablesChangesGetParams {
+    share: String,
+    schema: String,
+    table: String,
+}
+
+#[derive(Debug, serde::Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+pub struct SharesSchemasTablesChangesGetQuery {
+    pub starting_version: Option<i64>,
+    pub ending_version: Option<i64>,
+    pub starting_timestamp: Option<String>,
+    pub ending_timestamp: Option<String>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/shares/{share}/schemas/{schema}/tables/{table}/changes",
+    operation_id = "GetTableChanges",
+    tag = "official",
+    params(SharesSchemasTablesChangesGetParams, SharesSchemasTablesChangesGetQuery),
+    responses(
+        (status = 200, description = "The table changes were successfully returned.", body = String, content_type = "application/x-ndjson"),
+        (status = 400, description = "The request is malformed.", body = ErrorMessage),
+        (status = 401, description = "The request is unauthenticated. The bearer token is missing or incorrect.", body = ErrorMessage),
+        (status = 403, description = "The request is forbidden from being fulfilled.", body = ErrorMessage),
+        (status = 404, description = "The requested resource does not exist.", body = ErrorMessage),
+        (status = 500, description = "The request is not handled correctly due to a server error.", body = ErrorMessage),
+    )
+)]
+#[tracing::instrument(skip(state, claims, request_id, headers))]
+pub async fn get(
+    Extension(state): Extension<SharedState>,
+    Extension(claims): Extension<Claims>,
+    request_id: Option<Extension<RequestId>>,
+    Path(params): Path<SharesSchemasTablesChangesGetParams>,
+    Query(query): Query<SharesSchemasTablesChangesGetQuery>,
+    headers: HeaderMap,
+) -> Result<Response, Error> {
+    let reader_version = query::reader_version(&headers)?;
+    let parse = |timestamp: &Option<String>| match timestamp {
+        Some(timestamp) => DeltalakeUtility::datetime_yyyy_mm_dd_hh_mm_ss(timestamp).map(Some),
+        None => Ok(None),
+    };
+    let Ok(starting_timestamp) = parse(&query.starting_timestamp) else {
+        tracing::error!("requested starting timestamp is malformed");
+        return Err(Error::ValidationFailed);
+    };
+    let Ok(ending_timestamp) = parse(&query.ending_timestamp) else {
+        tracing::error!("requested ending timestamp is malformed");
+        return Err(Error::ValidationFailed);
+    };
+    if query.starting_version.is_none() && starting_timestamp.is_none() {
+        tracing::error!("requested changes have neither starting version nor timestamp");
+        return Err(Error::ValidationFailed);
+    }
+    let share = shares::share_name(params.share)?;
+    let schema = match SchemaName::new(params.schema) {
+        Ok(schema) => schema,
+        Err(e) => {
+            tracing::error!("requested schema data is malformed");
+            return Err(Error::invalid_field("schema", e));
+        }
+    };
+    let table = match TableName::new(params.table) {
+        Ok(table) => table,
+        Err(e) => {
+            tracing::error!("requested table data is malformed");
+            return Err(Error::invalid_field("table", e));
+        }
+    };
+    let fqn = (
+        share.as_str().to_owned(),
+        schema.as_str().to_owned(),
+        table.as_str().to_owned(),
+    );
+    let share = shares::visible_share(&state, &claims, &share).await?;
+    let Ok(table) =
+        TableService::query_by_fqn(share.name(), &schema, &table, state.pg_read_pool()).await
+    else {
+        tracing::error!(
+            "request is not handled correctly due to a server error while selecting table"
+        );
+        return Err(anyhow!("error occured while selecting table(s)").into());
+    };
+    let Some(table) = table else {
+        tracing::error!("requested table does not exist");
+        return Err(Error::NotFound);
+    };
+    let table_name = table.name.clone();
+    let Ok(platform) = Platform::from_str(&table.location) else {
+        tracing::error!("requested cloud platform is not supported");
+        return Err(anyhow!("error occured while identifying cloud platform").into());
+    };
+    let Ok(mut table) = DeltalakeUtility::open_table(&table.location).await else {
+        tracing::error!(
+            "request is not handled correctly due to a server error while loading delta table"
+        );
+        return Err(anyhow!("error occured while selecting table(s)").into());
+    };
+    let Ok(protocol) = DeltalakeService::protocol_from(&table) else {
+        tracing::error!("requested table requires an unsupported delta protocol");
+        return Err(Error::UnsupportedProtocol);
+    };
+    if !protocol.is_readable_by(reader_version) {
+        tracing::error!("requested table requires a newer delta reader than the client supports");
+        return Err(Error::UnsupportedProtocol);
+    }
+    let metadata = {
+        let Ok(metadata) = table.get_metadata() else {
+            tracing::error!("request is not handled correctly due to a server error while loading delta table metadata");
+            return Err(anyhow!("error occured while selecting table(s)").into());
+        };
+        metadata.to_owned()
+    };
+    if !DeltalakeService::is_change_data_feed_enabled(&metadata) {
+        tracing::error!("requested table does not enable change data feed");
+        return Err(Error::BadRequest);
+    }
+    let latest_version = table.version();
+    let starting_version = match (query.starting_version, starting_timestamp) {
+        (Some(version), _) => version,
+        (None, Some(timestamp)) => {
+            let Ok(version) = DeltalakeService::version_at_or_after(&mut table, timestamp).await
+            else {
+                tracing::error!("request is not handled correctly due to a server error while time-traveling delta table");
+                return Err(anyhow!("error occured while selecting table(s)").into());
+            };
+            let Some(version) = version else {
+                tracing::error!("requested starting timestamp is after the latest version");
+                return Err(Error::ValidationFailed);
+            };
+            version
+        }
+        (None, None) => {
+            tracing::error!("requested changes have neither starting version nor timestamp");
+            return Err(Error::BadRequest);
+        }
+    };
+    let ending_version = match (query.ending_version, ending_timestamp) {
+        (Some(version), _) => version.min(latest_version),
+        (None, Some(timestamp)) => {
+            // NOTE: The ending version is the last commit at or before the timestamp,
+            //       i.e. the one preceding the first commit after it.
+            let Ok(version) = DeltalakeService::version_at_or_after(
+                &mut table,
+                timestamp + Duration::milliseconds(1),
+            )
+            .await
+            else {
+                tracing::error!("request is not handled correctly due to a server error while time-traveling delta table");
+                return Err(anyhow!("error occured while selecting table(s)").into());
+            };
+            version.map_or(latest_version, |version| version - 1)
+        }
+        (None, None) => latest_version,
+    };
+    if starting_version < 0 || starting_version > ending_version {
+        tracing::error!("requested version range is malformed");
+        return Err(Error::ValidationFailed);
+    }
+    let Ok(scheme_guard) = SchemeGuard::from_config() else {
+        tracing::error!("signed url scheme guard is malformed");
+        return Err(anyhow!("error occured while resolving signed url scheme guard").into());
+    };
+    let Some(ttl) = query::url_ttl(None, &share) else {
+        tracing::error!("share default ttl is malformed");
+        return Err(anyhow!("error occured while resolving signed url ttl").into());
+    };
+    let aws_credentials = query::aws_credentials(&state, &platform).await;
+    let url_signer = |name: String| {
+        query::sign(
+            &state,
+            aws_credentials.as_ref(),
+            &platform,
+            &scheme_guard,
+            &ttl,
+            &table_name,
+            name,
+        )
+    };
+    let Ok(changes) = DeltalakeService::changes(
+        &mut table,
+        starting_version,
+        ending_version,
+        query::expiration_timestamp(&platform, ttl),
+        url_signer,
+    )
+    .await
+    else {
+        tracing::error!(
+            "request is not handled correctly due to a server error while reading delta table changes"
+        );
+        return Err(anyhow!("error occured while selecting table changes").into());
+    };
+    // NOTE: Audit logging is best-effort; a failed write is logged and the changes are still returned.
+    let trace = AuditTrace::new(request_id.as_deref(), None);
+    if let Err(e) = AuditService::record_changes_read(
+        &claims.name,
+        &fqn.0,
+        &fqn.1,
+        &fqn.2,
+        i64::try_from(changes.len()).unwrap_or(i64::MAX),
+        &trace,
+        &state.pg_pool,
+    )
+    .await
+    {
+        tracing::error!("failed to record changes read: {:#}", e);
+    }
+    let mut headers = HeaderMap::new();
+    headers.insert(HEADER_NAME, starting_version.into());
+    headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("application/x-ndjson"),
+    );
+    tracing::info!("delta table changes were successfully returned");
+    Ok((
+        StatusCode::OK,
+        headers,
+        JsonLines::new(DeltalakeService::changes_from(protocol, metadata, changes)),
+    )
+        .into_response())
+}
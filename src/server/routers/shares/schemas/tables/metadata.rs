@@ -8,19 +8,27 @@ use axum::http::StatusCode;
 use axum::response::IntoResponse;
 use axum::response::Response;
 use axum_extra::json_lines::JsonLines;
+use deltalake::delta::DeltaTableMetaData;
 use utoipa::IntoParams;
 
+use crate::server::entities::schema::Entity as SchemaEntity;
 use crate::server::entities::schema::Name as SchemaName;
-use crate::server::entities::share::Name as ShareName;
+use crate::server::entities::table::Entity as TableEntity;
 use crate::server::entities::table::Name as TableName;
+use crate::server::middlewares::jwt::Claims;
+use crate::server::routers::shares;
+use crate::server::routers::shares::schemas::tables::query;
 use crate::server::routers::SharedState;
+use crate::server::services::deltalake::Protocol;
 use crate::server::services::deltalake::Service as DeltalakeService;
+use crate::server::services::deltalake::Statistics;
 use crate::server::services::error::Error;
-use crate::server::services::table::Service as TableService;
 use crate::server::utilities::deltalake::Utility as DeltalakeUtility;
 
 const HEADER_NAME: &str = "Delta-Table-Version";
 
+const CONTENT_TYPE: &str = "application/x-ndjson; charset=utf-8";
+
 #[derive(Debug, serde::Deserialize, IntoParams)]
 #[serde(rename_all = "camelCase")]
 pub struct SharesSchemasTablesMetadataGetParams {
@@ -36,7 +44,7 @@ pub struct SharesSchemasTablesMetadataGetParams {
     tag = "official",
     params(SharesSchemasTablesMetadataGetParams),
     responses(
-        (status = 200, description = "The table metadata was successfully returned.", body = String),
+        (status = 200, description = "The table metadata was successfully returned.", body = String, content_type = "application/x-ndjson; charset=utf-8"),
         (status = 400, description = "The request is malformed.", body = ErrorMessage),
         (status = 401, description = "The request is unauthenticated. The bearer token is missing or incorrect.", body = ErrorMessage),
         (status = 403, description = "The request is forbidden from being fulfilled.", body = ErrorMessage),
@@ -44,55 +52,174 @@ pub struct SharesSchemasTablesMetadataGetParams {
         (status = 500, description = "The request is not handled correctly due to a server error.", body = ErrorMessage),
     )
 )]
-#[tracing::instrument(skip(state))]
+#[tracing::instrument(skip(state, claims, headers))]
 pub async fn get(
     Extension(state): Extension<SharedState>,
+    Extension(claims): Extension<Claims>,
     Path(params): Path<SharesSchemasTablesMetadataGetParams>,
+    headers: HeaderMap,
 ) -> Result<Response, Error> {
-    let Ok(share) = ShareName::new(params.share) else {
-        tracing::error!("requested share data is malformed");
-        return Err(Error::ValidationFailed);
+    let reader_version = query::reader_version(&headers)?;
+    let share = shares::share_name(params.share)?;
+    let schema = match SchemaName::new(params.schema) {
+        Ok(schema) => schema,
+        Err(e) => {
+            tracing::error!("requested schema data is malformed");
+            return Err(Error::invalid_field("schema", e));
+        }
     };
-    let Ok(schema) = SchemaName::new(params.schema) else {
-        tracing::error!("requested schema data is malformed");
-        return Err(Error::ValidationFailed);
+    let table = match TableName::new(params.table) {
+        Ok(table) => table,
+        Err(e) => {
+            tracing::error!("requested table data is malformed");
+            return Err(Error::invalid_field("table", e));
+        }
     };
-    let Ok(table) = TableName::new(params.table) else {
-        tracing::error!("requested table data is malformed");
-        return Err(Error::ValidationFailed);
+    let share = shares::visible_share(&state, &claims, &share).await?;
+    let Ok(schema) = SchemaEntity::load(share.id(), &schema, state.pg_read_pool()).await else {
+        tracing::error!(
+            "request is not handled correctly due to a server error while selecting schema"
+        );
+        return Err(anyhow!("error occured while selecting schema").into());
+    };
+    let Some(schema) = schema else {
+        tracing::error!("requested schema does not exist");
+        return Err(Error::NotFound);
     };
-    let Ok(table) = TableService::query_by_fqn(&share, &schema, &table, &state.pg_pool).await
-    else {
+    let Ok(table) = TableEntity::load(schema.id(), &table, state.pg_read_pool()).await else {
         tracing::error!(
             "request is not handled correctly due to a server error while selecting table"
         );
-        return Err(anyhow!("error occured while selecting table(s)").into());
+        return Err(anyhow!("error occured while selecting table").into());
     };
     let Some(table) = table else {
         tracing::error!("requested table does not exist");
         return Err(Error::NotFound);
     };
-    let Ok(table) = DeltalakeUtility::open_table(&table.location).await else {
+    let Ok(table) = DeltalakeUtility::open_table(table.location().as_str()).await else {
         tracing::error!(
             "request is not handled correctly due to a server error while loading delta table"
         );
         return Err(anyhow!("error occured while selecting table(s)").into());
     };
+    let Ok(protocol) = DeltalakeService::protocol_from(&table) else {
+        tracing::error!("requested table requires an unsupported delta protocol");
+        return Err(Error::UnsupportedProtocol);
+    };
+    if !protocol.is_readable_by(reader_version) {
+        tracing::error!("requested table requires a newer delta reader than the client supports");
+        return Err(Error::UnsupportedProtocol);
+    }
     let Ok(metadata) = table.get_metadata() else {
         tracing::error!("request is not handled correctly due to a server error while loading delta table metadata");
         return Err(anyhow!("error occured while selecting table(s)").into());
     };
-    let mut headers = HeaderMap::new();
-    headers.insert(HEADER_NAME, table.version().into());
-    headers.insert(
-        header::CONTENT_TYPE,
-        HeaderValue::from_static("application/x-ndjson"),
-    );
+    let statistics = DeltalakeService::statistics_from(table.get_state().files());
     tracing::info!("delta table metadata was successfully returned");
-    Ok((
+    Ok(respond(
+        table.version(),
+        protocol,
+        metadata.to_owned(),
+        statistics,
+    ))
+}
+
+fn respond(
+    version: i64,
+    protocol: Protocol,
+    metadata: DeltaTableMetaData,
+    statistics: Option<Statistics>,
+) -> Response {
+    let mut headers = HeaderMap::new();
+    headers.insert(HEADER_NAME, version.into());
+    headers.insert(header::CONTENT_TYPE, HeaderValue::from_static(CONTENT_TYPE));
+    (
         StatusCode::OK,
         headers,
-        JsonLines::new(DeltalakeService::metadata_from(metadata.to_owned())),
+        JsonLines::new(DeltalakeService::metadata_from(
+            protocol, metadata, statistics,
+        )),
     )
-        .into_response())
+        .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::services::deltalake::ProtocolDetail;
+    use deltalake::schema::Schema;
+    use std::collections::HashMap;
+
+    #[tokio::test]
+    async fn test_respond() {
+        let version = testutils::rand::i64(0, 100);
+        let protocol = Protocol {
+            protocol: ProtocolDetail {
+                min_reader_version: 1,
+                min_writer_version: testutils::rand::i32(1, 8),
+                reader_features: None,
+                writer_features: None,
+            },
+        };
+        let metadata = DeltaTableMetaData::new(
+            Some(testutils::rand::string(10)),
+            None,
+            None,
+            Schema::new(vec![]),
+            vec![],
+            HashMap::new(),
+        );
+        let response = respond(version, protocol, metadata, None);
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers().get(header::CONTENT_TYPE),
+            Some(&HeaderValue::from_static(CONTENT_TYPE))
+        );
+        assert_eq!(
+            response.headers().get(HEADER_NAME),
+            Some(&HeaderValue::from(version))
+        );
+        let body = hyper::body::to_bytes(response.into_body())
+            .await
+            .expect("response body should be read properly");
+        let body = String::from_utf8(body.to_vec()).expect("response body should be UTF-8");
+        let lines: Vec<serde_json::Value> = body
+            .lines()
+            .map(|line| serde_json::from_str(line).expect("each line should be JSON"))
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].get("protocol").is_some());
+        assert!(lines[1]["metaData"]["schemaString"].is_string());
+        assert!(lines[1]["metaData"]["partitionColumns"].is_array());
+        assert_eq!(lines[1]["metaData"]["format"]["provider"], "parquet");
+        assert!(lines[1]["metaData"].get("statistics").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_respond_with_statistics() {
+        let table = deltalake::delta::open_table("tests/fixtures/delta/statistics")
+            .await
+            .expect("delta table should be opened properly");
+        let protocol =
+            DeltalakeService::protocol_from(&table).expect("protocol should be supported");
+        let metadata = table
+            .get_metadata()
+            .expect("metadata should be loaded properly")
+            .to_owned();
+        let statistics = DeltalakeService::statistics_from(table.get_state().files());
+        let response = respond(table.version(), protocol, metadata, statistics);
+        let body = hyper::body::to_bytes(response.into_body())
+            .await
+            .expect("response body should be read properly");
+        let body = String::from_utf8(body.to_vec()).expect("response body should be UTF-8");
+        let lines: Vec<serde_json::Value> = body
+            .lines()
+            .map(|line| serde_json::from_str(line).expect("each line should be JSON"))
+            .collect();
+        let statistics = &lines[1]["metaData"]["statistics"];
+        assert_eq!(statistics["numFiles"], 2);
+        assert_eq!(statistics["columns"]["id"]["minValue"], -3);
+        assert_eq!(statistics["columns"]["id"]["maxValue"], 20);
+        assert_eq!(statistics["columns"]["name"]["nullCount"], 3);
+    }
 }
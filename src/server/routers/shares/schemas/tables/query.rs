@@ -2,6 +2,7 @@ use anyhow::anyhow;
 use axum::extract::Extension;
 use axum::extract::Json;
 use axum::extract::Path;
+use axum::extract::Query;
 use axum::http::header;
 use axum::http::header::HeaderMap;
 use axum::http::header::HeaderValue;
@@ -9,23 +10,41 @@ use axum::http::StatusCode;
 use axum::response::IntoResponse;
 use axum::response::Response;
 use axum_extra::json_lines::JsonLines;
+use deltalake::action::Add;
+use rusoto_credential::AwsCredentials;
 use std::str::FromStr;
+use std::time::Duration;
 use utoipa::IntoParams;
 use utoipa::ToSchema;
 
 use crate::config;
 use crate::server::entities::schema::Name as SchemaName;
-use crate::server::entities::share::Name as ShareName;
+use crate::server::entities::share::Entity as ShareEntity;
 use crate::server::entities::table::Name as TableName;
+use crate::server::middlewares::jwt::Claims;
+use crate::server::middlewares::request_id::RequestId;
+use crate::server::routers::shares;
 use crate::server::routers::SharedState;
+use crate::server::routers::State;
+use crate::server::services::audit::Service as AuditService;
+use crate::server::services::audit::Trace as AuditTrace;
+use crate::server::services::capability::Service as CapabilityService;
+use crate::server::services::capability::HEADER_NAME as CAPABILITIES_HEADER_NAME;
+use crate::server::services::deltalake::ResponseFormat;
 use crate::server::services::deltalake::Service as DeltalakeService;
 use crate::server::services::error::Error;
+use crate::server::services::metrics::Service as MetricsService;
+use crate::server::services::profile::Service as ProfileService;
 use crate::server::services::table::Service as TableService;
 use crate::server::utilities::deltalake::Utility as DeltalakeUtility;
 use crate::server::utilities::json::PartitionFilter as JSONPartitionFilter;
 use crate::server::utilities::json::PredicateJson;
 use crate::server::utilities::json::Utility as JSONUtility;
+use crate::server::utilities::retry::Policy as RetryPolicy;
+use crate::server::utilities::retry::Utility as RetryUtility;
 use crate::server::utilities::signed_url::Platform;
+use crate::server::utilities::signed_url::ResponseOverrides;
+use crate::server::utilities::signed_url::SchemeGuard;
 use crate::server::utilities::signed_url::Utility as SignedUrlUtility;
 use crate::server::utilities::sql::PartitionFilter as SQLPartitionFilter;
 use crate::server::utilities::sql::Utility as SQLUtility;
@@ -40,6 +59,9 @@ pub struct SharesSchemasTablesQueryPostRequest {
     pub limit_hint: Option<i32>,
     pub version: Option<i64>,
     pub timestamp: Option<String>,
+    #[serde(alias = "expirationSeconds")]
+    pub url_ttl_seconds: Option<i64>,
+    pub response_format: Option<String>,
 }
 
 #[derive(Debug, serde::Deserialize, IntoParams)]
@@ -50,15 +72,315 @@ pub struct SharesSchemasTablesQueryPostParams {
     table: String,
 }
 
+#[derive(Debug, serde::Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+pub struct SharesSchemasTablesQueryPostQuery {
+    #[serde(rename = "async")]
+    pub is_async: Option<bool>,
+    pub dry_run: Option<bool>,
+}
+
+#[derive(serde::Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SharesSchemasTablesQueryPostAsyncResponse {
+    pub job: String,
+}
+
+/// Resolves the TTL of signed URLs from the requested one, then the share's default
+/// and then the global `signed_url_ttl`; requested TTLs and share defaults are both
+/// clamped to `min_signed_url_ttl` and `max_signed_url_ttl`.
+pub(crate) fn url_ttl(requested: Option<i64>, share: &ShareEntity) -> Option<u64> {
+    let ttl = match (requested, share.default_ttl()) {
+        (Some(requested), _) => requested,
+        (None, Some(default_ttl)) => default_ttl.to_i64(),
+        (None, None) => return Some(config::fetch::<u64>("signed_url_ttl")),
+    };
+    let ttl = u64::try_from(ttl).ok().filter(|ttl| *ttl > 0)?;
+    Some(
+        ttl.max(config::fetch::<u64>("min_signed_url_ttl"))
+            .min(config::fetch::<u64>("max_signed_url_ttl")),
+    )
+}
+
+pub(crate) fn expiration_timestamp(platform: &Platform, ttl: u64) -> Option<i64> {
+    if let Platform::None { .. } = platform {
+        return None;
+    }
+    let ttl = i64::try_from(ttl).ok()?;
+    Some(chrono::Utc::now().timestamp_millis() + ttl * 1000)
+}
+
+/// Returns the delta reader version advertised by the client's capabilities header.
+pub(crate) fn reader_version(headers: &HeaderMap) -> Result<i32, Error> {
+    let Ok(capabilities) = headers
+        .get(CAPABILITIES_HEADER_NAME)
+        .map(|capabilities| capabilities.to_str())
+        .transpose()
+    else {
+        tracing::error!("requested capabilities are malformed");
+        return Err(Error::ValidationFailed);
+    };
+    let Some(reader_version) = CapabilityService::reader_version(capabilities) else {
+        tracing::error!("requested capabilities are malformed");
+        return Err(Error::ValidationFailed);
+    };
+    Ok(reader_version)
+}
+
+/// Returns the AWS credentials to sign the table with, preferring the ones mapped
+/// to the table's bucket and then assuming the configured role when one is set.
+pub(crate) async fn aws_credentials(state: &State, platform: &Platform) -> Option<AwsCredentials> {
+    let Platform::Aws { bucket, .. } = platform else {
+        return state.aws_credentials.clone();
+    };
+    // NOTE: Credentials mapped to the bucket explicitly take precedence over the assumed role.
+    let (Some(provider), false) = (
+        &state.aws_role_provider,
+        state.bucket_credentials.aws.contains_key(bucket),
+    ) else {
+        return state
+            .bucket_credentials
+            .aws_for(bucket, state.aws_credentials.as_ref())
+            .cloned();
+    };
+    match provider.credentials(bucket).await {
+        Ok(credentials) => Some(credentials),
+        Err(e) => {
+            tracing::error!("failed to assume AWS role: {}", e);
+            MetricsService::record_signed_url_error("aws");
+            None
+        }
+    }
+}
+
+/// Records which recipient read which table, without failing the query when
+/// the audit log cannot be written.
+async fn audit_query(
+    state: &State,
+    recipient: &str,
+    fqn: &(String, String, String),
+    files: usize,
+    trace: &AuditTrace,
+) {
+    let (share, schema, table) = fqn;
+    if let Err(e) = AuditService::record_table_query(
+        recipient,
+        share,
+        schema,
+        table,
+        i64::try_from(files).unwrap_or(i64::MAX),
+        trace,
+        &state.pg_pool,
+    )
+    .await
+    {
+        tracing::error!("failed to record table query: {:#}", e);
+    }
+}
+
+/// Falls back to the unsigned table location when a file cannot be signed, unless
+/// the scheme guard is `reject`: then no URL that failed signing or the guard is
+/// handed out and the request fails instead.
+fn unsigned(url: &str, scheme_guard: &SchemeGuard, e: anyhow::Error) -> anyhow::Result<String> {
+    if *scheme_guard == SchemeGuard::Reject {
+        return Err(e);
+    }
+    Ok(url.to_string())
+}
+
+pub(crate) async fn sign(
+    state: &State,
+    aws_credentials: Option<&AwsCredentials>,
+    platform: &Platform,
+    scheme_guard: &SchemeGuard,
+    ttl: &u64,
+    table_name: &str,
+    name: String,
+) -> anyhow::Result<String> {
+    let overrides = config::fetch::<bool>("signed_url_response_headers")
+        .then(|| ResponseOverrides::for_file(table_name, &name));
+    match platform {
+        Platform::Aws { url, bucket, path } => {
+            if state.bucket_credentials.is_public(bucket) {
+                let file: String = format!("{}/{}", path, name);
+                let region = SignedUrlUtility::aws_region(&config::fetch::<String>("s3_endpoint"));
+                return match SignedUrlUtility::public_aws(&region, bucket, &file)
+                    .and_then(|url| SignedUrlUtility::guard(url, scheme_guard))
+                {
+                    Ok(public) => Ok(public.into()),
+                    Err(e) => {
+                        tracing::error!("failed to build AWS S3 public url");
+                        self::unsigned(url, scheme_guard, e)
+                    }
+                };
+            }
+            if let Some(aws_credentials) = aws_credentials {
+                let file: String = format!("{}/{}", path, name);
+                let region = SignedUrlUtility::aws_region(&config::fetch::<String>("s3_endpoint"));
+                return match SignedUrlUtility::sign_aws(
+                    aws_credentials,
+                    &region,
+                    bucket,
+                    &file,
+                    ttl,
+                    &state.bucket_credentials.signing_style_for(bucket),
+                    overrides.as_ref(),
+                )
+                .and_then(|url| SignedUrlUtility::guard(url, scheme_guard))
+                {
+                    Ok(signed) => Ok(signed.into()),
+                    Err(e) => {
+                        tracing::error!("failed to sign up AWS S3 url");
+                        MetricsService::record_signed_url_error("aws");
+                        self::unsigned(url, scheme_guard, e)
+                    }
+                };
+            }
+            tracing::warn!("AWS credentials were not set");
+            Ok(url.clone())
+        }
+        Platform::Gcp { url, bucket, path } => {
+            if state.bucket_credentials.is_public(bucket) {
+                let file: String = format!("{}/{}", path, name);
+                return match SignedUrlUtility::public_gcp(bucket, &file)
+                    .and_then(|url| SignedUrlUtility::guard(url, scheme_guard))
+                {
+                    Ok(public) => Ok(public.into()),
+                    Err(e) => {
+                        tracing::error!("failed to build GCP GCS public url");
+                        self::unsigned(url, scheme_guard, e)
+                    }
+                };
+            }
+            if let Some(gcp_service_account) = state
+                .bucket_credentials
+                .gcp_for(bucket, state.gcp_service_account.as_ref())
+            {
+                let file: String = format!("{}/{}", path, name);
+                return match RetryUtility::retry(&RetryPolicy::from_config(), || async {
+                    SignedUrlUtility::sign_gcp(
+                        gcp_service_account,
+                        bucket,
+                        &file,
+                        ttl,
+                        overrides.as_ref(),
+                    )
+                })
+                .await
+                .and_then(|url| SignedUrlUtility::guard(url, scheme_guard))
+                {
+                    Ok(signed) => Ok(signed.into()),
+                    Err(e) => {
+                        tracing::error!("failed to sign up GCP GCS url");
+                        MetricsService::record_signed_url_error("gcp");
+                        self::unsigned(url, scheme_guard, e)
+                    }
+                };
+            }
+            tracing::warn!("GCP service account was not set");
+            Ok(url.clone())
+        }
+        Platform::Azure {
+            url,
+            account,
+            container,
+            path,
+        } => {
+            if let Some(azure_credentials) = &state.azure_credentials {
+                if &azure_credentials.account != account {
+                    tracing::warn!("Azure credentials do not match the storage account");
+                    return Ok(url.clone());
+                }
+                let file: String = format!("{}/{}", path, name);
+                return match SignedUrlUtility::sign_azure(azure_credentials, container, &file, ttl)
+                    .and_then(|url| SignedUrlUtility::guard(url, scheme_guard))
+                {
+                    Ok(signed) => Ok(signed.into()),
+                    Err(e) => {
+                        tracing::error!("failed to sign up Azure Blob Storage url");
+                        MetricsService::record_signed_url_error("azure");
+                        self::unsigned(url, scheme_guard, e)
+                    }
+                };
+            }
+            tracing::warn!("Azure credentials were not set");
+            Ok(url.clone())
+        }
+        Platform::R2 {
+            url,
+            account_id,
+            bucket,
+            path,
+        } => {
+            if let Some(r2_credentials) = &state.r2_credentials {
+                let file: String = format!("{}/{}", path, name);
+                return match SignedUrlUtility::sign_r2(
+                    r2_credentials,
+                    account_id,
+                    bucket,
+                    &file,
+                    ttl,
+                    &state.bucket_credentials.signing_style_for(bucket),
+                    overrides.as_ref(),
+                )
+                .and_then(|url| SignedUrlUtility::guard(url, scheme_guard))
+                {
+                    Ok(signed) => Ok(signed.into()),
+                    Err(e) => {
+                        tracing::error!("failed to sign up Cloudflare R2 url");
+                        MetricsService::record_signed_url_error("r2");
+                        self::unsigned(url, scheme_guard, e)
+                    }
+                };
+            }
+            tracing::warn!("Cloudflare R2 credentials were not set");
+            Ok(url.clone())
+        }
+        Platform::File { url, path } => {
+            // NOTE: The URL points back at this server, so the scheme guard is not applied.
+            let file: String = format!("{}/{}", path, name);
+            match SignedUrlUtility::sign_file(&ProfileService::endpoint(), &file, ttl) {
+                Ok(signed) => Ok(signed.into()),
+                Err(e) => {
+                    tracing::error!("failed to sign up local file url");
+                    MetricsService::record_signed_url_error("file");
+                    self::unsigned(url, scheme_guard, e)
+                }
+            }
+        }
+        Platform::None { url } => {
+            tracing::warn!("no supported platforms");
+            Ok(url.clone())
+        }
+    }
+}
+
+/// Returns the protocol, metadata and signed file actions of the table.
+///
+/// When `limitHint` is given, file actions stop being accumulated once the
+/// cumulative `numRecords` of their statistics reaches the hint; files
+/// without statistics are always returned so the result stays correct.
+///
+/// `version` and `timestamp` pin the query to a past snapshot of the table;
+/// specifying both is rejected as malformed.
+///
+/// `responseFormat` selects between the flat `parquet` actions (the default)
+/// and the `delta` actions wrapped in `deltaProtocol`, `deltaMetadata` and
+/// `deltaSingleAction`.
+///
+/// With `?dryRun=true` the files are pruned as usual but none of them is
+/// signed; a single JSON object with `numFiles`, `totalBytes` and
+/// `numRecords` is returned instead of the actions.
 #[utoipa::path(
     post,
     path = "/shares/{share}/schemas/{schema}/tables/{table}/query",
     operation_id = "QueryTable",
     tag = "official",
     request_body = SharesSchemasTablesQueryPostRequest,
-    params(SharesSchemasTablesQueryPostParams),
+    params(SharesSchemasTablesQueryPostParams, SharesSchemasTablesQueryPostQuery),
     responses(
         (status = 200, description = "The tables were successfully returned.", body = String),
+        (status = 202, description = "The query was successfully queued.", body = SharesSchemasTablesQueryPostAsyncResponse),
         (status = 400, description = "The request is malformed.", body = ErrorMessage),
         (status = 401, description = "The request is unauthenticated. The bearer token is missing or incorrect.", body = ErrorMessage),
         (status = 403, description = "The request is forbidden from being fulfilled.", body = ErrorMessage),
@@ -66,12 +388,18 @@ pub struct SharesSchemasTablesQueryPostParams {
         (status = 500, description = "The request is not handled correctly due to a server error.", body = ErrorMessage),
     )
 )]
-#[tracing::instrument(skip(state))]
+#[tracing::instrument(skip(state, claims, request_id))]
 pub async fn post(
     Extension(state): Extension<SharedState>,
+    Extension(claims): Extension<Claims>,
+    request_id: Option<Extension<RequestId>>,
     Path(params): Path<SharesSchemasTablesQueryPostParams>,
+    Query(query): Query<SharesSchemasTablesQueryPostQuery>,
+    headers: HeaderMap,
     Json(payload): Json<SharesSchemasTablesQueryPostRequest>,
 ) -> Result<Response, Error> {
+    let trace = AuditTrace::new(request_id.as_deref(), None);
+    let reader_version = self::reader_version(&headers)?;
     let predicate_hints = if let Some(predicate_hints) = payload.predicate_hints {
         let predicate_hints: Result<Vec<SQLPartitionFilter>, _> = predicate_hints
             .into_iter()
@@ -95,6 +423,15 @@ pub async fn post(
     };
     let json_predicate_hints =
         json_predicate_hints.map(|predicate| JSONPartitionFilter { predicate });
+    let response_format = if let Some(response_format) = &payload.response_format {
+        let Ok(response_format) = ResponseFormat::from_str(response_format) else {
+            tracing::error!("requested response format is not supported");
+            return Err(Error::ValidationFailed);
+        };
+        response_format
+    } else {
+        ResponseFormat::Parquet
+    };
     let timestamp = if let Some(timestamp) = &payload.timestamp {
         let Ok(timestamp) = DeltalakeUtility::datetime_yyyy_mm_dd_hh_mm_ss(timestamp) else {
             tracing::error!("requested timestamp is malformed");
@@ -104,19 +441,33 @@ pub async fn post(
     } else {
         None
     };
-    let Ok(share) = ShareName::new(params.share) else {
-        tracing::error!("requested share data is malformed");
-        return Err(Error::ValidationFailed);
-    };
-    let Ok(schema) = SchemaName::new(params.schema) else {
-        tracing::error!("requested schema data is malformed");
+    if payload.version.is_some() && timestamp.is_some() {
+        tracing::error!("requested version and timestamp must not be specified together");
         return Err(Error::ValidationFailed);
+    }
+    let share = shares::share_name(params.share)?;
+    let schema = match SchemaName::new(params.schema) {
+        Ok(schema) => schema,
+        Err(e) => {
+            tracing::error!("requested schema data is malformed");
+            return Err(Error::invalid_field("schema", e));
+        }
     };
-    let Ok(table) = TableName::new(params.table) else {
-        tracing::error!("requested table data is malformed");
-        return Err(Error::ValidationFailed);
+    let table = match TableName::new(params.table) {
+        Ok(table) => table,
+        Err(e) => {
+            tracing::error!("requested table data is malformed");
+            return Err(Error::invalid_field("table", e));
+        }
     };
-    let Ok(table) = TableService::query_by_fqn(&share, &schema, &table, &state.pg_pool).await
+    let fqn = (
+        share.as_str().to_owned(),
+        schema.as_str().to_owned(),
+        table.as_str().to_owned(),
+    );
+    let share = shares::visible_share(&state, &claims, &share).await?;
+    let Ok(table) =
+        TableService::query_by_fqn(share.name(), &schema, &table, state.pg_read_pool()).await
     else {
         tracing::error!(
             "request is not handled correctly due to a server error while selecting table"
@@ -137,22 +488,19 @@ pub async fn post(
         );
         return Err(anyhow!("error occured while selecting table(s)").into());
     };
-    let mut is_time_traveled = false;
-    // NOTE: version precedes over timestamp
-    if let Some(timestamp) = timestamp {
-        let Ok(_) = table.load_with_datetime(timestamp).await else {
-            tracing::error!("request is not handled correctly due to a server error while time-traveling delta table");
-            return Err(anyhow!("error occured while selecting table(s)").into());
-        };
-        is_time_traveled = true;
-    }
-    // NOTE: version precedes over timestamp
-    if let Some(version) = &payload.version {
-        let Ok(_) = table.load_version(*version).await else {
-            tracing::error!("request is not handled correctly due to a server error while time-traveling delta table");
-            return Err(anyhow!("error occured while selecting table(s)").into());
-        };
-        is_time_traveled = true;
+    let Ok(is_time_traveled) =
+        DeltalakeUtility::load_snapshot(&mut table, payload.version, timestamp).await
+    else {
+        tracing::error!("request is not handled correctly due to a server error while time-traveling delta table");
+        return Err(anyhow!("error occured while selecting table(s)").into());
+    };
+    let Ok(protocol) = DeltalakeService::protocol_from(&table) else {
+        tracing::error!("requested table requires an unsupported delta protocol");
+        return Err(Error::UnsupportedProtocol);
+    };
+    if !protocol.is_readable_by(reader_version) {
+        tracing::error!("requested table requires a newer delta reader than the client supports");
+        return Err(Error::UnsupportedProtocol);
     }
     let metadata = {
         let Ok(metadata) = table.get_metadata() else {
@@ -161,48 +509,113 @@ pub async fn post(
         };
         metadata.to_owned()
     };
-    let url_signer = |name: String| match &platform {
-        Platform::Aws { url, bucket, path } => {
-            if let Some(aws_credentials) = &state.aws_credentials {
-                let file: String = format!("{}/{}", path, name);
-                let Ok(signed) = SignedUrlUtility::sign_aws(
-                    aws_credentials,
-                    bucket,
-                    &file,
-                    &config::fetch::<u64>("signed_url_ttl"),
-                ) else {
-                    tracing::error!("failed to sign up AWS S3 url");
-                    return url.clone();
-                };
-                return signed.into();
-            }
-            tracing::warn!("AWS credentials were not set");
-            url.clone()
-        }
-        Platform::Gcp { url, bucket, path } => {
-            if let Some(gcp_service_account) = &state.gcp_service_account {
-                let file: String = format!("{}/{}", path, name);
-                let Ok(signed) = SignedUrlUtility::sign_gcp(
-                    gcp_service_account,
-                    bucket,
-                    &file,
-                    &config::fetch::<u64>("signed_url_ttl"),
-                ) else {
-                    tracing::error!("failed to sign up GCP GCS url");
-                    return url.clone();
-                };
-                return signed.into();
-            }
-            tracing::warn!("GCP service account was not set");
-            url.clone()
-        }
-        Platform::None { url } => {
-            tracing::warn!("no supported platforms");
-            url.clone()
+    if query.dry_run.unwrap_or(false) {
+        let adds = DeltalakeService::adds(
+            &table,
+            predicate_hints,
+            json_predicate_hints,
+            payload.limit_hint,
+        );
+        let mut headers = HeaderMap::new();
+        headers.insert(HEADER_NAME, table.version().into());
+        tracing::info!("delta table query was successfully estimated");
+        return Ok((
+            StatusCode::OK,
+            headers,
+            Json(DeltalakeService::estimate_from(&adds)),
+        )
+            .into_response());
+    }
+    let Ok(scheme_guard) = SchemeGuard::from_config() else {
+        tracing::error!("signed url scheme guard is malformed");
+        return Err(anyhow!("error occured while resolving signed url scheme guard").into());
+    };
+    let limit_hint = payload.limit_hint;
+    let Some(ttl) = self::url_ttl(payload.url_ttl_seconds, &share) else {
+        tracing::error!("requested signed url ttl is not positive");
+        return Err(Error::ValidationFailed);
+    };
+    let aws_credentials = self::aws_credentials(&state, &platform).await;
+    if query.is_async.unwrap_or(false) {
+        let jobs = state.jobs.clone();
+        let job_ttl = Duration::from_secs(config::fetch::<u64>("job_ttl"));
+        let submitter = claims.clone();
+        let job = jobs.submit(&submitter, job_ttl, async move {
+            let url_signer = |name: String| {
+                self::sign(
+                    &state,
+                    aws_credentials.as_ref(),
+                    &platform,
+                    &scheme_guard,
+                    &ttl,
+                    &fqn.2,
+                    name,
+                )
+            };
+            let files = DeltalakeService::files(
+                &table,
+                &metadata,
+                predicate_hints,
+                json_predicate_hints,
+                limit_hint,
+                is_time_traveled,
+                self::expiration_timestamp(&platform, ttl),
+                url_signer,
+            )
+            .await?;
+            let files = if config::fetch::<bool>("verify_signed_urls") {
+                DeltalakeService::verify_files(files, SignedUrlUtility::exists).await?
+            } else {
+                files
+            };
+            self::audit_query(&state, &claims.name, &fqn, files.len(), &trace).await;
+            Ok((
+                table.version(),
+                DeltalakeService::lines_from(protocol, metadata, files, response_format),
+            ))
+        });
+        tracing::info!("delta table query was successfully queued");
+        return Ok((
+            StatusCode::ACCEPTED,
+            Json(SharesSchemasTablesQueryPostAsyncResponse {
+                job: job.to_string(),
+            }),
+        )
+            .into_response());
+    }
+    let (version, timestamp) = DeltalakeService::snapshot_of(&table, &metadata, is_time_traveled);
+    let adds = DeltalakeService::adds(&table, predicate_hints, json_predicate_hints, limit_hint);
+    self::audit_query(&state, &claims.name, &fqn, adds.len(), &trace).await;
+    let expiration_timestamp = self::expiration_timestamp(&platform, ttl);
+    let table_version = table.version();
+    let table_name = fqn.2.clone();
+    let file_from = move |add: Add| {
+        let state = state.clone();
+        let aws_credentials = aws_credentials.clone();
+        let platform = platform.clone();
+        let table_name = table_name.clone();
+        async move {
+            let url_signer = |name: String| {
+                self::sign(
+                    &state,
+                    aws_credentials.as_ref(),
+                    &platform,
+                    &scheme_guard,
+                    &ttl,
+                    &table_name,
+                    name,
+                )
+            };
+            DeltalakeService::file_from(add, version, timestamp, expiration_timestamp, url_signer)
+                .await
         }
     };
+    let verify_signed_urls = config::fetch::<bool>("verify_signed_urls");
+    let url_verifier = move |url: String| async move {
+        !verify_signed_urls || SignedUrlUtility::exists(url).await
+    };
     let mut headers = HeaderMap::new();
-    headers.insert(HEADER_NAME, table.version().into());
+    headers.insert(HEADER_NAME, table_version.into());
     headers.insert(
         header::CONTENT_TYPE,
         HeaderValue::from_static("application/x-ndjson"),
@@ -211,15 +624,195 @@ pub async fn post(
     Ok((
         StatusCode::OK,
         headers,
-        JsonLines::new(DeltalakeService::files_from(
-            table,
+        JsonLines::new(DeltalakeService::stream_from(
+            protocol,
             metadata,
-            predicate_hints,
-            json_predicate_hints,
-            payload.limit_hint,
-            is_time_traveled,
-            &url_signer,
+            adds,
+            response_format,
+            file_from,
+            url_verifier,
         )),
     )
         .into_response())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::entities::share::DefaultTtl as ShareDefaultTtl;
+    use crate::server::utilities::signed_url::BucketCredentials;
+
+    fn new_state(bucket_credentials: BucketCredentials) -> State {
+        State {
+            bucket_credentials,
+            ..State::detached()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sign_public_bucket() {
+        let bucket = testutils::rand::string(10).to_lowercase();
+        let path = testutils::rand::string(10);
+        let name = format!("{}.parquet", testutils::rand::string(10));
+        let mut bucket_credentials = BucketCredentials::default();
+        bucket_credentials.public.insert(bucket.clone());
+        let state = new_state(bucket_credentials);
+        let platform = Platform::from_str(&format!("gs://{}/{}", bucket, path))
+            .expect("should parse GCP url properly");
+        let url = sign(
+            &state,
+            None,
+            &platform,
+            &SchemeGuard::Off,
+            &300,
+            &testutils::rand::string(10),
+            name.clone(),
+        )
+        .await
+        .expect("public url should be built properly");
+        let url = url::Url::parse(&url).expect("public url should be parsed properly");
+        assert_eq!(url.host_str(), Some("storage.googleapis.com"));
+        assert_eq!(url.path(), format!("/{}/{}/{}", bucket, path, name));
+        assert!(url.query().is_none());
+        let platform = Platform::from_str(&format!("s3://{}/{}", bucket, path))
+            .expect("should parse AWS url properly");
+        let url = sign(
+            &state,
+            None,
+            &platform,
+            &SchemeGuard::Off,
+            &300,
+            &testutils::rand::string(10),
+            name.clone(),
+        )
+        .await
+        .expect("public url should be built properly");
+        let url = url::Url::parse(&url).expect("public url should be parsed properly");
+        assert!(url.path().ends_with(&format!("{}/{}", path, name)));
+        assert!(url.query().is_none());
+    }
+
+    #[test]
+    fn test_reader_version() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(reader_version(&headers).ok(), Some(1));
+        headers.insert(
+            CAPABILITIES_HEADER_NAME,
+            HeaderValue::from_static("responseformat=delta;readerversion=2"),
+        );
+        assert_eq!(reader_version(&headers).ok(), Some(2));
+        headers.insert(
+            CAPABILITIES_HEADER_NAME,
+            HeaderValue::from_static("readerversion=two"),
+        );
+        assert!(reader_version(&headers).is_err());
+    }
+
+    #[test]
+    fn test_unsigned_under_scheme_guard() {
+        let url = format!(
+            "s3://{}/{}",
+            testutils::rand::string(10),
+            testutils::rand::string(10)
+        );
+        assert_eq!(
+            unsigned(&url, &SchemeGuard::Off, anyhow!("failed to sign")).ok(),
+            Some(url.clone())
+        );
+        assert_eq!(
+            unsigned(&url, &SchemeGuard::Upgrade, anyhow!("failed to sign")).ok(),
+            Some(url.clone())
+        );
+        assert!(unsigned(&url, &SchemeGuard::Reject, anyhow!("failed to sign")).is_err());
+    }
+
+    fn new_share(default_ttl: Option<i64>) -> ShareEntity {
+        let mut share = ShareEntity::new(
+            testutils::rand::uuid(),
+            testutils::rand::string(10),
+            testutils::rand::uuid(),
+        )
+        .expect("share should be created properly");
+        share.set_default_ttl(
+            default_ttl.map(|ttl| {
+                ShareDefaultTtl::new(ttl).expect("default ttl should be created properly")
+            }),
+        );
+        share
+    }
+
+    #[test]
+    fn test_url_ttl() {
+        let min = config::fetch::<u64>("min_signed_url_ttl");
+        let max = config::fetch::<u64>("max_signed_url_ttl");
+        let share = new_share(None);
+        assert_eq!(
+            url_ttl(None, &share),
+            Some(config::fetch::<u64>("signed_url_ttl"))
+        );
+        let requested = testutils::rand::u64(min, max);
+        assert_eq!(url_ttl(Some(requested as i64), &share), Some(requested));
+        assert_eq!(url_ttl(Some(max as i64), &share), Some(max));
+        assert_eq!(url_ttl(Some(max as i64 + 1), &share), Some(max));
+        assert_eq!(url_ttl(Some(min as i64), &share), Some(min));
+        assert_eq!(url_ttl(Some(min as i64 - 1), &share), Some(min));
+    }
+
+    #[test]
+    fn test_url_ttl_not_positive() {
+        let share = new_share(None);
+        assert_eq!(url_ttl(Some(0), &share), None);
+        assert_eq!(
+            url_ttl(Some(-testutils::rand::i64(1, 100000)), &share),
+            None
+        );
+    }
+
+    #[test]
+    fn test_url_ttl_with_share_default_out_of_range() {
+        let min = config::fetch::<u64>("min_signed_url_ttl");
+        let max = config::fetch::<u64>("max_signed_url_ttl");
+        assert_eq!(url_ttl(None, &new_share(Some(min as i64 - 1))), Some(min));
+        assert_eq!(url_ttl(None, &new_share(Some(max as i64 + 1))), Some(max));
+    }
+
+    #[tokio::test]
+    async fn test_url_ttl_with_share_default() {
+        let min = config::fetch::<u64>("min_signed_url_ttl");
+        let max = config::fetch::<u64>("max_signed_url_ttl");
+        let default_ttl = testutils::rand::u64(min, max);
+        let share = new_share(Some(default_ttl as i64));
+        let requested = testutils::rand::u64(min, max);
+        assert_eq!(url_ttl(Some(requested as i64), &share), Some(requested));
+        let ttl = url_ttl(None, &share).expect("share default ttl should be resolved");
+        assert_eq!(ttl, default_ttl);
+        let state = new_state(BucketCredentials::default());
+        let platform = Platform::from_str(&format!(
+            "s3://{}/{}",
+            testutils::rand::string(10).to_lowercase(),
+            testutils::rand::string(10)
+        ))
+        .expect("should parse AWS url properly");
+        let aws_credentials = AwsCredentials::new(
+            testutils::rand::string(20),
+            testutils::rand::string(40),
+            None,
+            None,
+        );
+        let url = sign(
+            &state,
+            Some(&aws_credentials),
+            &platform,
+            &SchemeGuard::Off,
+            &ttl,
+            &testutils::rand::string(10),
+            format!("{}.parquet", testutils::rand::string(10)),
+        )
+        .await
+        .expect("url should be signed properly");
+        let url = url::Url::parse(&url).expect("signed url should be parsed properly");
+        assert!(url
+            .query_pairs()
+            .any(|(key, value)| key == "X-Amz-Expires" && value == default_ttl.to_string()));
+    }
+}
@@ -2,16 +2,20 @@ use anyhow::anyhow;
 use axum::extract::Extension;
 use axum::extract::Path;
 use axum::extract::Query;
+use axum::http::header;
 use axum::http::header::HeaderMap;
+use axum::http::header::HeaderValue;
 use axum::http::StatusCode;
 use axum::response::IntoResponse;
 use axum::response::Response;
 use utoipa::IntoParams;
 
 use crate::server::entities::schema::Name as SchemaName;
-use crate::server::entities::share::Name as ShareName;
 use crate::server::entities::table::Name as TableName;
+use crate::server::middlewares::jwt::Claims;
+use crate::server::routers::shares;
 use crate::server::routers::SharedState;
+use crate::server::services::deltalake::Service as DeltalakeService;
 use crate::server::services::error::Error;
 use crate::server::services::table::Service as TableService;
 use crate::server::utilities::deltalake::Utility as DeltalakeUtility;
@@ -37,9 +41,10 @@ pub struct SharesSchemasTablesVersionGetQuery {
     path = "/shares/{share}/schemas/{schema}/tables/{table}/version",
     operation_id = "GetTableVersion",
     tag = "official",
-    params(SharesSchemasTablesVersionGetParams),
+    params(SharesSchemasTablesVersionGetParams, SharesSchemasTablesVersionGetQuery),
     responses(
         (status = 200, description = "The table version was successfully returned."),
+        (status = 304, description = "The table version has not changed since the given ETag."),
         (status = 400, description = "The request is malformed.", body = ErrorMessage),
         (status = 401, description = "The request is unauthenticated. The bearer token is missing or incorrect.", body = ErrorMessage),
         (status = 403, description = "The request is forbidden from being fulfilled.", body = ErrorMessage),
@@ -47,11 +52,13 @@ pub struct SharesSchemasTablesVersionGetQuery {
         (status = 500, description = "The request is not handled correctly due to a server error.", body = ErrorMessage),
     )
 )]
-#[tracing::instrument(skip(state))]
+#[tracing::instrument(skip(state, claims))]
 pub async fn get(
     Extension(state): Extension<SharedState>,
+    Extension(claims): Extension<Claims>,
     Path(params): Path<SharesSchemasTablesVersionGetParams>,
     Query(query): Query<SharesSchemasTablesVersionGetQuery>,
+    headers: HeaderMap,
 ) -> Result<Response, Error> {
     let starting_timestamp = if let Some(starting_timestamp) = &query.starting_timestamp {
         let Ok(starting_timestamp) =
@@ -64,19 +71,24 @@ pub async fn get(
     } else {
         None
     };
-    let Ok(share) = ShareName::new(params.share) else {
-        tracing::error!("requested share data is malformed");
-        return Err(Error::ValidationFailed);
+    let share = shares::share_name(params.share)?;
+    let schema = match SchemaName::new(params.schema) {
+        Ok(schema) => schema,
+        Err(e) => {
+            tracing::error!("requested schema data is malformed");
+            return Err(Error::invalid_field("schema", e));
+        }
     };
-    let Ok(schema) = SchemaName::new(params.schema) else {
-        tracing::error!("requested schema data is malformed");
-        return Err(Error::ValidationFailed);
+    let table = match TableName::new(params.table) {
+        Ok(table) => table,
+        Err(e) => {
+            tracing::error!("requested table data is malformed");
+            return Err(Error::invalid_field("table", e));
+        }
     };
-    let Ok(table) = TableName::new(params.table) else {
-        tracing::error!("requested table data is malformed");
-        return Err(Error::ValidationFailed);
-    };
-    let Ok(table) = TableService::query_by_fqn(&share, &schema, &table, &state.pg_pool).await
+    let share = shares::visible_share(&state, &claims, &share).await?;
+    let Ok(table) =
+        TableService::query_by_fqn(share.name(), &schema, &table, state.pg_read_pool()).await
     else {
         tracing::error!(
             "request is not handled correctly due to a server error while selecting table"
@@ -87,20 +99,168 @@ pub async fn get(
         tracing::error!("requested table does not exist");
         return Err(Error::NotFound);
     };
-    let Ok(mut table) = DeltalakeUtility::open_table(&table.location).await else {
+    // NOTE: Only the log is read so that the version, and the 304 answered from it,
+    //       do not cost loading the table snapshot.
+    let Ok(mut table) = DeltalakeUtility::open_table_log(&table.location) else {
         tracing::error!(
             "request is not handled correctly due to a server error while loading delta table"
         );
         return Err(anyhow!("error occured while selecting table(s)").into());
     };
-    if let Some(starting_timestamp) = starting_timestamp {
-        let Ok(_) = table.load_with_datetime(starting_timestamp).await else {
+    let version = if let Some(starting_timestamp) = starting_timestamp {
+        let Ok(version) =
+            DeltalakeService::version_at_or_after(&mut table, starting_timestamp).await
+        else {
             tracing::error!("request is not handled correctly due to a server error while time-traveling delta table");
             return Err(anyhow!("error occured while selecting table(s)").into());
         };
-    }
-    let mut headers = HeaderMap::new();
-    headers.insert(HEADER_NAME, table.version().into());
+        let Some(version) = version else {
+            tracing::error!("requested starting timestamp is after the latest version");
+            return Err(Error::ValidationFailed);
+        };
+        version
+    } else {
+        let Ok(version) = table.get_latest_version().await else {
+            tracing::error!("request is not handled correctly due to a server error while resolving delta table version");
+            return Err(anyhow!("error occured while selecting table(s)").into());
+        };
+        version
+    };
+    let timestamp = if version >= 0 {
+        table.get_version_timestamp(version).await.ok()
+    } else {
+        None
+    };
     tracing::info!("delta table version was successfully returned");
-    Ok((StatusCode::OK, headers).into_response())
+    Ok(respond(
+        version,
+        timestamp,
+        headers.get(header::IF_NONE_MATCH),
+    ))
+}
+
+fn etag(version: i64, timestamp: i64) -> String {
+    format!(r#""{}-{}""#, version, timestamp)
+}
+
+fn matches(if_none_match: &HeaderValue, etag: &str) -> bool {
+    let Ok(if_none_match) = if_none_match.to_str() else {
+        return false;
+    };
+    if_none_match.split(',').map(str::trim).any(|candidate| {
+        candidate == "*" || candidate.strip_prefix("W/").unwrap_or(candidate) == etag
+    })
+}
+
+/// Responds with the table version header only, so that `HEAD` requests, which axum
+/// routes to this handler as well, are as cheap as `GET` ones.
+fn respond(version: i64, timestamp: Option<i64>, if_none_match: Option<&HeaderValue>) -> Response {
+    // NOTE: A table without any commit reports a negative version, which is exposed as 0.
+    let mut headers = HeaderMap::new();
+    headers.insert(HEADER_NAME, version.max(0).into());
+    let Some(timestamp) = timestamp else {
+        return (StatusCode::OK, headers).into_response();
+    };
+    let etag = self::etag(version, timestamp);
+    let is_not_modified = if_none_match.map_or(false, |value| self::matches(value, &etag));
+    if let Ok(etag) = HeaderValue::from_str(&etag) {
+        headers.insert(header::ETAG, etag);
+    }
+    if is_not_modified {
+        return (StatusCode::NOT_MODIFIED, headers).into_response();
+    }
+    (StatusCode::OK, headers).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::http::Method;
+    use axum::http::Request;
+    use axum::routing::get;
+    use axum::Router;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_respond() {
+        let version = testutils::rand::i64(0, 100);
+        let timestamp = testutils::rand::i64(0, 1700000000000);
+        let response = respond(version, Some(timestamp), None);
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers().get(HEADER_NAME),
+            Some(&HeaderValue::from(version))
+        );
+        assert_eq!(
+            response.headers().get(header::ETAG),
+            Some(&HeaderValue::from_str(&etag(version, timestamp)).expect("etag should be valid"))
+        );
+        let body = hyper::body::to_bytes(response.into_body())
+            .await
+            .expect("response body should be read properly");
+        assert!(body.is_empty());
+    }
+
+    #[test]
+    fn test_respond_without_commits() {
+        let response = respond(-1, None, None);
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers().get(HEADER_NAME),
+            Some(&HeaderValue::from(0))
+        );
+        assert!(response.headers().get(header::ETAG).is_none());
+    }
+
+    #[test]
+    fn test_respond_not_modified() {
+        let version = testutils::rand::i64(0, 100);
+        let timestamp = testutils::rand::i64(0, 1700000000000);
+        let if_none_match = HeaderValue::from_str(&format!(
+            r#""{}", W/{}"#,
+            testutils::rand::string(10),
+            etag(version, timestamp)
+        ))
+        .expect("header value should be valid");
+        let response = respond(version, Some(timestamp), Some(&if_none_match));
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(
+            response.headers().get(HEADER_NAME),
+            Some(&HeaderValue::from(version))
+        );
+        let stale = HeaderValue::from_str(&etag(version, timestamp + 1))
+            .expect("header value should be valid");
+        let response = respond(version, Some(timestamp), Some(&stale));
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_head() {
+        let version = testutils::rand::i64(0, 100);
+        let timestamp = testutils::rand::i64(0, 1700000000000);
+        let app = Router::new().route(
+            "/version",
+            get(move || async move { respond(version, Some(timestamp), None) }),
+        );
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method(Method::HEAD)
+                    .uri("/version")
+                    .body(Body::empty())
+                    .expect("request should be built properly"),
+            )
+            .await
+            .expect("request should be handled properly");
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers().get(HEADER_NAME),
+            Some(&HeaderValue::from(version))
+        );
+        let body = hyper::body::to_bytes(response.into_body())
+            .await
+            .expect("response body should be read properly");
+        assert!(body.is_empty());
+    }
 }
@@ -0,0 +1,41 @@
+use axum::extract::Json;
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::response::Response;
+use utoipa::ToSchema;
+
+use crate::server::services::capability::Capabilities;
+use crate::server::services::capability::Service as CapabilityService;
+use crate::server::services::error::Error;
+
+pub mod jobs;
+pub mod local;
+pub mod profile;
+
+#[derive(serde::Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SharingCapabilitiesResponse {
+    pub capabilities: Capabilities,
+}
+
+#[utoipa::path(
+    get,
+    path = "/sharing/capabilities",
+    operation_id = "GetCapabilities",
+    tag = "sharing",
+    security(()),
+    responses(
+        (status = 200, description = "The capabilities were successfully returned.", body = SharingCapabilitiesResponse),
+        (status = 500, description = "The request is not handled correctly due to a server error.", body = ErrorMessage),
+    )
+)]
+#[tracing::instrument]
+pub async fn capabilities() -> Result<Response, Error> {
+    let capabilities = CapabilityService::describe();
+    tracing::info!("capabilities were successfully returned");
+    Ok((
+        StatusCode::OK,
+        Json(SharingCapabilitiesResponse { capabilities }),
+    )
+        .into_response())
+}
@@ -0,0 +1,154 @@
+use anyhow::anyhow;
+use axum::extract::Extension;
+use axum::extract::Json;
+use axum::extract::Path;
+use axum::http::header;
+use axum::http::header::HeaderMap;
+use axum::http::header::HeaderValue;
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::response::Response;
+use axum::BoxError;
+use axum_extra::json_lines::JsonLines;
+use std::time::Duration;
+use utoipa::IntoParams;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::config;
+use crate::server::middlewares::jwt::Claims;
+use crate::server::routers::SharedState;
+use crate::server::services::error::Error;
+use crate::server::services::job::Status;
+
+const HEADER_NAME: &str = "Delta-Table-Version";
+
+#[derive(Debug, serde::Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+pub struct SharingJobsGetParams {
+    job: String,
+}
+
+#[derive(serde::Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SharingJobsGetResponse {
+    pub status: Status,
+}
+
+#[utoipa::path(
+    get,
+    path = "/sharing/jobs/{job}",
+    operation_id = "GetQueryJob",
+    tag = "sharing",
+    params(SharingJobsGetParams),
+    responses(
+        (status = 200, description = "The query result was successfully returned.", body = String),
+        (status = 202, description = "The query is still running.", body = SharingJobsGetResponse),
+        (status = 400, description = "The request is malformed.", body = ErrorMessage),
+        (status = 401, description = "The request is unauthenticated. The bearer token is missing or incorrect.", body = ErrorMessage),
+        (status = 404, description = "The requested resource does not exist.", body = ErrorMessage),
+        (status = 500, description = "The request is not handled correctly due to a server error.", body = ErrorMessage),
+    )
+)]
+#[tracing::instrument(skip(state, claims))]
+pub async fn get(
+    Extension(state): Extension<SharedState>,
+    Extension(claims): Extension<Claims>,
+    Path(params): Path<SharingJobsGetParams>,
+) -> Result<Response, Error> {
+    let Ok(id) = Uuid::parse_str(&params.job) else {
+        tracing::error!("requested job id is malformed");
+        return Err(Error::ValidationFailed);
+    };
+    let ttl = Duration::from_secs(config::fetch::<u64>("job_ttl"));
+    let Some(job) = state.jobs.get(&id, &claims, ttl) else {
+        tracing::error!("requested job does not exist");
+        return Err(Error::NotFound);
+    };
+    match job.status {
+        Status::Pending => {
+            tracing::info!("query job is still pending");
+            Ok((
+                StatusCode::ACCEPTED,
+                Json(SharingJobsGetResponse { status: job.status }),
+            )
+                .into_response())
+        }
+        Status::Failed => {
+            tracing::error!("query job failed");
+            Err(anyhow!("error occured while running query job").into())
+        }
+        Status::Done => {
+            let Some(version) = job.version else {
+                tracing::error!("query job finished without table version");
+                return Err(anyhow!("error occured while running query job").into());
+            };
+            let mut headers = HeaderMap::new();
+            headers.insert(HEADER_NAME, version.into());
+            headers.insert(
+                header::CONTENT_TYPE,
+                HeaderValue::from_static("application/x-ndjson"),
+            );
+            tracing::info!("query job result was successfully returned");
+            Ok((
+                StatusCode::OK,
+                headers,
+                JsonLines::new(futures_util::stream::iter(
+                    job.lines.into_iter().map(Ok::<serde_json::Value, BoxError>),
+                )),
+            )
+                .into_response())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::middlewares::jwt::Role;
+    use crate::server::routers::State;
+    use std::sync::Arc;
+
+    fn new_claims() -> Claims {
+        Claims {
+            name: testutils::rand::string(10),
+            email: testutils::rand::email(),
+            namespace: testutils::rand::string(10),
+            role: Role::Guest,
+            exp: chrono::Utc::now().timestamp() + testutils::rand::i64(100000, 1000000),
+            jti: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_get_job_of_other_recipient() {
+        let state = Arc::new(State::detached());
+        let claims = new_claims();
+        let ttl = Duration::from_secs(config::fetch::<u64>("job_ttl"));
+        let job = state.jobs.submit(&claims, ttl, async {
+            tokio::time::sleep(Duration::from_secs(60)).await;
+            Ok((0, Vec::<serde_json::Value>::new()))
+        });
+        let error = get(
+            Extension(state.clone()),
+            Extension(new_claims()),
+            Path(SharingJobsGetParams {
+                job: job.to_string(),
+            }),
+        )
+        .await
+        .err()
+        .expect("job of another recipient should not be found");
+        assert_eq!(error.into_response().status(), StatusCode::NOT_FOUND);
+        let response = get(
+            Extension(state),
+            Extension(claims),
+            Path(SharingJobsGetParams {
+                job: job.to_string(),
+            }),
+        )
+        .await
+        .expect("job should be returned to its submitter");
+        assert_eq!(response.status(), StatusCode::ACCEPTED);
+    }
+}
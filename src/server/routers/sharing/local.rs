@@ -0,0 +1,296 @@
+use std::path::Path;
+
+use anyhow::anyhow;
+use axum::body::StreamBody;
+use axum::extract::Extension;
+use axum::extract::Query;
+use axum::http::header;
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::response::Response;
+use tokio_util::io::ReaderStream;
+use url::Url;
+use utoipa::IntoParams;
+
+use crate::server::routers::SharedState;
+use crate::server::services::error::Error;
+use crate::server::services::table::Service as TableService;
+use crate::server::utilities::signed_url::Utility as SignedUrlUtility;
+
+const CONTENT_TYPE: &str = "application/octet-stream";
+
+#[derive(Debug, serde::Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+pub struct SharingLocalGetQuery {
+    pub path: String,
+    pub expires: i64,
+    pub sig: String,
+}
+
+/// Returns true if the canonical `path` lies under one of the table `locations`.
+/// Locations which cannot be resolved are skipped.
+async fn contained(path: &Path, locations: &[String]) -> bool {
+    for location in locations {
+        let Some(root) = Url::parse(location)
+            .ok()
+            .and_then(|url| url.to_file_path().ok())
+        else {
+            continue;
+        };
+        let Ok(root) = tokio::fs::canonicalize(root).await else {
+            continue;
+        };
+        if path.starts_with(&root) {
+            return true;
+        }
+    }
+    false
+}
+
+/// Streams a file of a table stored on the local filesystem through the
+/// signed URL returned by the query endpoints. The path is resolved first, so
+/// that neither `..` nor symlinks reach files outside the registered tables.
+#[utoipa::path(
+    get,
+    path = "/sharing/_local",
+    operation_id = "GetLocalFile",
+    tag = "sharing",
+    security(()),
+    params(SharingLocalGetQuery),
+    responses(
+        (status = 200, description = "The file was successfully returned.", body = String),
+        (status = 400, description = "The request is malformed.", body = ErrorMessage),
+        (status = 403, description = "The signed URL is invalid or expired, or the file lies outside the shared tables.", body = ErrorMessage),
+        (status = 404, description = "The requested resource does not exist.", body = ErrorMessage),
+        (status = 500, description = "The request is not handled correctly due to a server error.", body = ErrorMessage),
+    )
+)]
+#[tracing::instrument(skip(state))]
+pub async fn get(
+    Extension(state): Extension<SharedState>,
+    Query(query): Query<SharingLocalGetQuery>,
+) -> Result<Response, Error> {
+    let Ok(_) = SignedUrlUtility::verify_file(&query.path, query.expires, &query.sig) else {
+        tracing::error!("requested local file url is invalid or expired");
+        return Err(Error::Forbidden);
+    };
+    let Ok(path) = tokio::fs::canonicalize(&query.path).await else {
+        tracing::error!("requested local file does not exist");
+        return Err(Error::NotFound);
+    };
+    let Ok(locations) = TableService::query_local_locations(state.pg_read_pool()).await else {
+        tracing::error!(
+            "request is not handled correctly due to a server error while selecting tables"
+        );
+        return Err(anyhow!("error occured while selecting tables from database").into());
+    };
+    if !self::contained(&path, &locations).await {
+        tracing::error!("requested local file lies outside the shared tables");
+        return Err(Error::Forbidden);
+    }
+    let Ok(file) = tokio::fs::File::open(&path).await else {
+        tracing::error!("requested local file does not exist");
+        return Err(Error::NotFound);
+    };
+    tracing::info!("local file was successfully returned");
+    Ok((
+        StatusCode::OK,
+        [(header::CONTENT_TYPE, CONTENT_TYPE)],
+        StreamBody::new(ReaderStream::new(file)),
+    )
+        .into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::entities::account::Entity as AccountEntity;
+    use crate::server::entities::schema::Entity as SchemaEntity;
+    use crate::server::entities::share::Entity as ShareEntity;
+    use crate::server::entities::table::Entity as TableEntity;
+    use crate::server::routers::State;
+    use axum::body::Body;
+    use axum::http::Request;
+    use axum::routing::get;
+    use axum::Router;
+    use sqlx::PgPool;
+    use std::path::PathBuf;
+    use std::sync::Arc;
+    use tower::ServiceExt;
+
+    fn request(url: &Url) -> Request<Body> {
+        Request::builder()
+            .uri(format!(
+                "{}?{}",
+                url.path(),
+                url.query().expect("signed url should have a query")
+            ))
+            .body(Body::empty())
+            .expect("request should be built properly")
+    }
+
+    fn app(state: State) -> Router {
+        Router::new()
+            .route("/sharing/_local", get(self::get))
+            .layer(Extension(Arc::new(state)))
+    }
+
+    fn sign(path: &Path) -> Url {
+        SignedUrlUtility::sign_file("http://127.0.0.1:8080", &path.to_string_lossy(), &60)
+            .expect("local file url should be signed properly")
+    }
+
+    /// Lays out a table directory holding one file next to a file outside of it.
+    async fn new_layout() -> (PathBuf, PathBuf, PathBuf) {
+        let dir = std::env::temp_dir().join(testutils::rand::uuid());
+        let table = dir.join("table");
+        tokio::fs::create_dir_all(&table)
+            .await
+            .expect("table directory should be created properly");
+        let inside = table.join("part-00000.parquet");
+        tokio::fs::write(&inside, testutils::rand::string(100))
+            .await
+            .expect("table file should be written properly");
+        let outside = dir.join("secret");
+        tokio::fs::write(&outside, testutils::rand::string(100))
+            .await
+            .expect("outside file should be written properly");
+        (table, inside, outside)
+    }
+
+    async fn register(pool: &PgPool, location: &Path) {
+        let account = AccountEntity::new(
+            testutils::rand::uuid(),
+            testutils::rand::string(10),
+            testutils::rand::email(),
+            testutils::rand::string(10),
+            testutils::rand::string(10),
+            testutils::rand::i64(1, 100000),
+        )
+        .expect("account should be created properly");
+        account
+            .save(pool)
+            .await
+            .expect("account should be saved properly");
+        let share = ShareEntity::new(
+            testutils::rand::uuid(),
+            testutils::rand::string(10),
+            account.id().to_uuid().to_string(),
+        )
+        .expect("share should be created properly");
+        share
+            .save(pool)
+            .await
+            .expect("share should be saved properly");
+        let schema = SchemaEntity::new(
+            testutils::rand::uuid(),
+            testutils::rand::string(10),
+            share.id().to_uuid().to_string(),
+            account.id().to_uuid().to_string(),
+        )
+        .expect("schema should be created properly");
+        schema
+            .save(pool)
+            .await
+            .expect("schema should be saved properly");
+        let table = TableEntity::new(
+            testutils::rand::uuid(),
+            testutils::rand::string(10),
+            schema.id().to_uuid().to_string(),
+            format!("file://{}", location.display()),
+            account.id().to_uuid().to_string(),
+        )
+        .expect("table should be created properly");
+        table
+            .save(pool)
+            .await
+            .expect("table should be saved properly");
+    }
+
+    #[tokio::test]
+    async fn test_contained() {
+        let (table, inside, outside) = new_layout().await;
+        let locations = vec![format!("file://{}", table.display())];
+        let resolve = |path: PathBuf| async move {
+            tokio::fs::canonicalize(path)
+                .await
+                .expect("path should be resolved")
+        };
+        assert!(contained(&resolve(inside).await, &locations).await);
+        assert!(!contained(&resolve(table.join("../secret")).await, &locations).await);
+        assert!(!contained(&resolve(outside.clone()).await, &locations).await);
+        let sibling = table.with_file_name("table_sibling");
+        tokio::fs::create_dir_all(&sibling)
+            .await
+            .expect("sibling directory should be created properly");
+        assert!(!contained(&resolve(sibling).await, &locations).await);
+    }
+
+    #[sqlx::test]
+    async fn test_download(pool: PgPool) {
+        let (table, inside, outside) = new_layout().await;
+        register(&pool, &table).await;
+        let app = app(State::with_pool(pool));
+        let response = app
+            .clone()
+            .oneshot(request(&sign(&inside)))
+            .await
+            .expect("request should be handled properly");
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = hyper::body::to_bytes(response.into_body())
+            .await
+            .expect("response body should be read properly");
+        let content = tokio::fs::read(&inside)
+            .await
+            .expect("table file should be read properly");
+        assert_eq!(body, content);
+        for path in [table.join("../secret"), outside] {
+            let response = app
+                .clone()
+                .oneshot(request(&sign(&path)))
+                .await
+                .expect("request should be handled properly");
+            assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_download_with_tampered_path() {
+        let (_, inside, outside) = new_layout().await;
+        let url = sign(&inside);
+        let mut tampered = url.clone();
+        tampered
+            .query_pairs_mut()
+            .clear()
+            .append_pair("path", &outside.to_string_lossy())
+            .append_pair(
+                "expires",
+                &url.query_pairs()
+                    .find(|(k, _)| k == "expires")
+                    .map(|(_, v)| v.to_string())
+                    .expect("signed url should expire"),
+            )
+            .append_pair(
+                "sig",
+                &url.query_pairs()
+                    .find(|(k, _)| k == "sig")
+                    .map(|(_, v)| v.to_string())
+                    .expect("signed url should be signed"),
+            );
+        let response = app(State::detached())
+            .oneshot(request(&tampered))
+            .await
+            .expect("request should be handled properly");
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn test_download_missing_file() {
+        let path = std::env::temp_dir().join(testutils::rand::uuid());
+        let response = app(State::detached())
+            .oneshot(request(&sign(&path)))
+            .await
+            .expect("request should be handled properly");
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+}
@@ -0,0 +1,247 @@
+use anyhow::anyhow;
+use axum::extract::Extension;
+use axum::extract::Json;
+use axum::headers::authorization::Bearer;
+use axum::headers::Authorization;
+use axum::headers::HeaderMapExt;
+use axum::http::header::HeaderMap;
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::response::Response;
+use chrono::TimeZone;
+use chrono::Utc;
+use utoipa::ToSchema;
+
+use crate::config;
+use crate::server::entities::token::Entity as TokenEntity;
+use crate::server::entities::token::Value as TokenValue;
+use crate::server::middlewares::jwt::Claims;
+use crate::server::middlewares::jwt::Role;
+use crate::server::middlewares::request_id::RequestId;
+use crate::server::routers::SharedState;
+use crate::server::services::audit::Service as AuditService;
+use crate::server::services::audit::Trace as AuditTrace;
+use crate::server::services::error::Error;
+use crate::server::services::profile::Profile;
+use crate::server::services::profile::RecipientNotAllowed;
+use crate::server::services::profile::Service as ProfileService;
+use crate::server::services::revocation::Service as RevocationService;
+use crate::server::utilities::validation::ValidationError;
+
+#[derive(serde::Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SharingProfileRefreshResponse {
+    pub profile: Profile,
+}
+
+/// Returns true unless the token has expired; tokens accepted within the JWT
+/// leeway are not refreshed.
+fn refreshable(claims: &Claims, now: i64) -> bool {
+    claims.exp > now
+}
+
+/// Issues a fresh profile for the recipient and provider of the bearer token and
+/// revokes the token, so that only the new profile keeps working. The fresh profile
+/// expires no later than the token it replaces.
+#[utoipa::path(
+    post,
+    path = "/sharing/profile/refresh",
+    operation_id = "RefreshProfile",
+    tag = "sharing",
+    responses(
+        (status = 200, description = "The profile was successfully refreshed.", body = SharingProfileRefreshResponse),
+        (status = 400, description = "The request is malformed.", body = ErrorMessage),
+        (status = 401, description = "The request is unauthenticated. The bearer token is missing, expired or revoked.", body = ErrorMessage),
+        (status = 403, description = "The request is forbidden from being fulfilled. The bearer token is not a guest profile or the recipient is not on the provider's allowlist.", body = ErrorMessage),
+        (status = 500, description = "The request is not handled correctly due to a server error.", body = ErrorMessage),
+    )
+)]
+#[tracing::instrument(skip(state, claims, request_id, headers))]
+pub async fn refresh(
+    Extension(state): Extension<SharedState>,
+    Extension(claims): Extension<Claims>,
+    request_id: Option<Extension<RequestId>>,
+    headers: HeaderMap,
+) -> Result<Response, Error> {
+    let Some(auth) = headers.typed_get::<Authorization<Bearer>>() else {
+        tracing::error!("bearer token is missing");
+        return Err(Error::BadRequest);
+    };
+    if claims.role != Role::Guest {
+        tracing::error!("only guest profiles can be refreshed");
+        return Err(Error::Forbidden);
+    }
+    if !self::refreshable(&claims, Utc::now().timestamp()) {
+        tracing::error!("bearer token has already expired");
+        return Err(Error::Unauthorized);
+    }
+    let Some(expires_at) = Utc.timestamp_opt(claims.exp, 0).single() else {
+        tracing::error!("JWT claims' expiration is malformed");
+        return Err(Error::ValidationFailed);
+    };
+    let ttl = config::fetch::<i64>("profile_refresh_ttl");
+    let profile =
+        match ProfileService::refresh_guest(state.recipient_allowlists.as_ref(), &claims, ttl) {
+            Ok(profile) => profile,
+            Err(e) if e.is::<ValidationError>() => {
+                tracing::error!("configured profile ttl is malformed");
+                return Err(Error::validation(e));
+            }
+            Err(e) if e.is::<RecipientNotAllowed>() => {
+                tracing::error!("recipient is no longer on the provider's allowlist");
+                return Err(Error::Forbidden);
+            }
+            Err(_) => {
+                tracing::error!(
+                    "request is not handled correctly due to a server error while creating profile"
+                );
+                return Err(anyhow!("failed to create profile").into());
+            }
+        };
+    let Ok(value) = TokenValue::new(auth.token().to_string()) else {
+        tracing::error!("bearer token is malformed");
+        return Err(Error::BadRequest);
+    };
+    let Ok(stored) = TokenEntity::load_by_value(&value, &state.pg_pool).await else {
+        tracing::error!(
+            "request is not handled correctly due to a server error while selecting token"
+        );
+        return Err(anyhow!("error occured while selecting token from database").into());
+    };
+    // NOTE: Tokens recorded at issuance are replaced by a record of the new one.
+    if let Some(stored) = stored {
+        let Ok(token) = TokenEntity::new(
+            None,
+            stored.email().as_str().to_string(),
+            *stored.role(),
+            profile.bearer_token.clone(),
+            stored.created_by().to_string(),
+        ) else {
+            tracing::error!(
+                "request is not handled correctly due to a server error while creating token"
+            );
+            return Err(anyhow!("failed to create token").into());
+        };
+        let Ok(_) = token.save(&state.pg_pool).await else {
+            tracing::error!(
+                "request is not handled correctly due to a server error while updating token"
+            );
+            return Err(anyhow!("error occured while updating token").into());
+        };
+    }
+    let Ok(_) = RevocationService::revoke(value.as_str(), expires_at, &state.pg_pool).await else {
+        tracing::error!(
+            "request is not handled correctly due to a server error while revoking token"
+        );
+        return Err(anyhow!("error occured while revoking token").into());
+    };
+    // NOTE: Audit logging is best-effort; a failed write is logged and the profile is still returned.
+    if let Err(e) = AuditService::record_profile_issuance(
+        &claims.namespace,
+        &claims.name,
+        ttl,
+        &AuditTrace::new(request_id.as_deref(), None),
+        &state.pg_pool,
+    )
+    .await
+    {
+        tracing::error!("failed to record profile issuance: {:#}", e);
+    }
+    tracing::info!("profile was successfully refreshed");
+    Ok((
+        StatusCode::OK,
+        Json(SharingProfileRefreshResponse { profile }),
+    )
+        .into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::routers::State;
+    use crate::server::services::profile::Allowlists as RecipientAllowlists;
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    fn new_claims(exp: i64) -> Claims {
+        Claims {
+            name: testutils::rand::string(10),
+            email: testutils::rand::email(),
+            namespace: testutils::rand::string(10),
+            role: Role::Guest,
+            exp,
+            jti: None,
+        }
+    }
+
+    #[test]
+    fn test_refreshable() {
+        let now = Utc::now().timestamp();
+        assert!(refreshable(
+            &new_claims(now + testutils::rand::i64(1, 100000)),
+            now
+        ));
+        assert!(!refreshable(&new_claims(now), now));
+        assert!(!refreshable(
+            &new_claims(now - testutils::rand::i64(1, 100000)),
+            now
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_refresh_expired_token() {
+        let state = Arc::new(State::detached());
+        let mut headers = HeaderMap::new();
+        headers.typed_insert(
+            Authorization::bearer(&testutils::rand::string(20))
+                .expect("bearer token should be created properly"),
+        );
+        let claims = new_claims(Utc::now().timestamp() - testutils::rand::i64(1, 60));
+        let error = refresh(Extension(state), Extension(claims), None, headers)
+            .await
+            .err()
+            .expect("expired token should not be refreshed");
+        assert_eq!(error.into_response().status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_refresh_disallowed_recipient() {
+        let claims = new_claims(Utc::now().timestamp() + testutils::rand::i64(100000, 1000000));
+        let state = Arc::new(State {
+            recipient_allowlists: Some(RecipientAllowlists::new(&HashMap::from([(
+                claims.namespace.clone(),
+                testutils::rand::email(),
+            )]))),
+            ..State::detached()
+        });
+        let mut headers = HeaderMap::new();
+        headers.typed_insert(
+            Authorization::bearer(&testutils::rand::string(20))
+                .expect("bearer token should be created properly"),
+        );
+        let error = refresh(Extension(state), Extension(claims), None, headers)
+            .await
+            .err()
+            .expect("disallowed recipient should not be refreshed");
+        assert_eq!(error.into_response().status(), StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn test_refresh_non_guest_token() {
+        let state = Arc::new(State::detached());
+        let mut headers = HeaderMap::new();
+        headers.typed_insert(
+            Authorization::bearer(&testutils::rand::string(20))
+                .expect("bearer token should be created properly"),
+        );
+        let claims = Claims {
+            role: Role::Admin,
+            ..new_claims(Utc::now().timestamp() + testutils::rand::i64(100000, 1000000))
+        };
+        let error = refresh(Extension(state), Extension(claims), None, headers)
+            .await
+            .err()
+            .expect("non-guest token should not be refreshed");
+        assert_eq!(error.into_response().status(), StatusCode::FORBIDDEN);
+    }
+}
@@ -1,7 +1,17 @@
 pub mod account;
+pub mod assume_role;
+pub mod audit;
+pub mod capability;
 pub mod deltalake;
 pub mod error;
+pub mod job;
+pub mod metrics;
 pub mod profile;
+pub mod rate_limit;
+pub mod refresh_token;
+pub mod revocation;
 pub mod schema;
 pub mod share;
 pub mod table;
+pub mod token;
+pub mod token_gc;
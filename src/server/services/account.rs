@@ -1,11 +1,16 @@
 use anyhow::Context;
 use anyhow::Result;
+use chrono::DateTime;
+use chrono::Utc;
+use sqlx::postgres::PgQueryResult;
 use sqlx::query_builder::QueryBuilder;
 use sqlx::Execute;
 use utoipa::ToSchema;
 
 use crate::server::entities::account::Entity as AccountEntity;
 use crate::server::entities::account::Name as AccountName;
+use crate::server::utilities::pagination::Cursor;
+use crate::server::utilities::pagination::Ordering;
 use crate::server::utilities::postgres::PgAcquire;
 
 #[derive(Debug, Clone, serde::Serialize, sqlx::FromRow, ToSchema)]
@@ -15,6 +20,10 @@ pub struct Account {
     pub email: String,
     pub namespace: String,
     pub ttl: i64,
+    pub role: String,
+    #[serde(skip)]
+    #[sqlx(default)]
+    pub created_at: Option<DateTime<Utc>>,
 }
 
 impl Account {
@@ -24,6 +33,8 @@ impl Account {
             email: entity.email().to_string(),
             namespace: entity.namespace().to_string(),
             ttl: entity.ttl().to_i64(),
+            role: entity.role().as_ref().to_string(),
+            created_at: None,
         }
     }
 }
@@ -33,7 +44,8 @@ pub struct Service;
 impl Service {
     pub async fn query(
         limit: Option<&i64>,
-        after: Option<&AccountName>,
+        ordering: &Ordering,
+        after: Option<&Cursor>,
         executor: impl PgAcquire<'_>,
     ) -> Result<Vec<Account>> {
         let mut conn = executor
@@ -45,21 +57,23 @@ impl Service {
                  name,
                  email,
                  namespace,
-                 ttl
-             FROM account",
+                 ttl,
+                 role,
+                 created_at
+             FROM account
+             WHERE deleted_at IS NULL",
         );
-        if let Some(name) = after {
-            builder.push(" WHERE name >= ");
-            builder.push_bind(name);
+        if let Some(cursor) = after {
+            ordering.push_after(&mut builder, "AND", cursor);
         }
-        builder.push(" ORDER BY name ");
+        ordering.push_order(&mut builder);
         if let Some(limit) = limit {
             builder.push(" LIMIT ");
             builder.push_bind(limit);
         }
         let mut query = sqlx::query_as::<_, Account>(builder.build().sql());
-        if let Some(name) = after {
-            query = query.bind(name);
+        if let Some(cursor) = after {
+            query = ordering.bind_after(query, cursor);
         }
         if let Some(limit) = limit {
             query = query.bind(limit);
@@ -84,9 +98,10 @@ impl Service {
                  name,
                  email,
                  namespace,
-                 ttl
+                 ttl,
+                 role
              FROM account
-             WHERE name = $1",
+             WHERE name = $1 AND deleted_at IS NULL",
         )
         .bind(name)
         .fetch_optional(&mut *conn)
@@ -97,4 +112,84 @@ impl Service {
         ))?;
         Ok(row)
     }
+
+    /// Lists the live accounts whose names start with `prefix`. The pattern is matched
+    /// with `LIKE` so that the `text_pattern_ops` index on `account.name` is used.
+    pub async fn search_by_name_prefix(
+        prefix: &str,
+        limit: Option<&i64>,
+        executor: impl PgAcquire<'_>,
+    ) -> Result<Vec<Account>> {
+        let mut conn = executor
+            .acquire()
+            .await
+            .context("failed to acquire postgres connection")?;
+        let pattern = format!("{}%", Self::escape_like(prefix));
+        let rows: Vec<Account> = sqlx::query_as::<_, Account>(
+            "SELECT
+                 name,
+                 email,
+                 namespace,
+                 ttl,
+                 role
+             FROM account
+             WHERE name LIKE $1 ESCAPE '\\' AND deleted_at IS NULL
+             ORDER BY name
+             LIMIT $2",
+        )
+        .bind(pattern)
+        .bind(limit.copied())
+        .fetch_all(&mut *conn)
+        .await
+        .context(format!(
+            r#"failed to search accounts prefixed with "{}" from [account]"#,
+            prefix
+        ))?;
+        Ok(rows)
+    }
+
+    fn escape_like(value: &str) -> String {
+        let mut escaped = String::with_capacity(value.len());
+        for c in value.chars() {
+            if matches!(c, '\\' | '%' | '_') {
+                escaped.push('\\');
+            }
+            escaped.push(c);
+        }
+        escaped
+    }
+
+    pub async fn soft_delete(
+        name: &AccountName,
+        executor: impl PgAcquire<'_>,
+    ) -> Result<PgQueryResult> {
+        let mut conn = executor
+            .acquire()
+            .await
+            .context("failed to acquire postgres connection")?;
+        sqlx::query(
+            "UPDATE account
+             SET deleted_at = CURRENT_TIMESTAMP
+             WHERE name = $1 AND deleted_at IS NULL",
+        )
+        .bind(name)
+        .execute(&mut *conn)
+        .await
+        .context(format!(
+            r#"failed to soft delete "{}" from [account]"#,
+            name.as_str()
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escape_like() {
+        assert_eq!(Service::escape_like("account"), "account");
+        assert_eq!(Service::escape_like("a_b%c"), "a\\_b\\%c");
+        assert_eq!(Service::escape_like("a\\b"), "a\\\\b");
+    }
 }
@@ -0,0 +1,326 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::DateTime;
+use chrono::Duration;
+use chrono::Utc;
+use rusoto_core::Region;
+use rusoto_credential::AwsCredentials;
+use rusoto_credential::ProvideAwsCredentials;
+use rusoto_sts::StsAssumeRoleSessionCredentialsProvider;
+use rusoto_sts::StsClient;
+use tokio::sync::Mutex;
+
+use crate::server::utilities::retry::Policy as RetryPolicy;
+use crate::server::utilities::retry::Utility as RetryUtility;
+
+const SESSION_NAME: &str = "delta-sharing";
+
+const BUCKET_PLACEHOLDER: &str = "{bucket}";
+
+const REFRESH_MARGIN_SECS: i64 = 300;
+
+// NOTE: STS answers with these codes when the role or the caller is misconfigured,
+//       which retrying does not fix.
+const PERMANENT_ERROR_CODES: [&str; 5] = [
+    "AccessDenied",
+    "InvalidClientTokenId",
+    "SignatureDoesNotMatch",
+    "ExpiredToken",
+    "ValidationError",
+];
+
+pub trait Clock: Send + Sync {
+    fn now(&self) -> DateTime<Utc>;
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+#[async_trait]
+pub trait Fetcher: Send + Sync {
+    async fn assume(&self, role_arn: &str) -> Result<AwsCredentials>;
+}
+
+pub struct StsFetcher {
+    client: StsClient,
+}
+
+impl StsFetcher {
+    pub fn new(region: Region) -> Self {
+        Self {
+            client: StsClient::new(region),
+        }
+    }
+}
+
+#[async_trait]
+impl Fetcher for StsFetcher {
+    async fn assume(&self, role_arn: &str) -> Result<AwsCredentials> {
+        let provider = StsAssumeRoleSessionCredentialsProvider::new(
+            self.client.clone(),
+            role_arn.into(),
+            SESSION_NAME.into(),
+            None,
+            None,
+            None,
+            None,
+        );
+        provider.credentials().await.map_err(|e| {
+            let permanent = PERMANENT_ERROR_CODES
+                .iter()
+                .any(|code| e.message.contains(code));
+            let e = anyhow::Error::new(e)
+                .context(format!(r#"failed to assume AWS role "{}""#, role_arn));
+            if permanent {
+                e
+            } else {
+                RetryUtility::transient(e)
+            }
+        })
+    }
+}
+
+#[derive(Clone)]
+pub struct Provider {
+    role_arn: String,
+    fetcher: Arc<dyn Fetcher>,
+    clock: Arc<dyn Clock>,
+    cache: Arc<Mutex<HashMap<String, AwsCredentials>>>,
+    retry: RetryPolicy,
+}
+
+impl std::fmt::Debug for Provider {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Provider")
+            .field("role_arn", &self.role_arn)
+            .finish()
+    }
+}
+
+impl Provider {
+    /// Creates a provider assuming `role_arn`, in which `{bucket}` is
+    /// replaced by the bucket of the table being signed.
+    pub fn new(role_arn: String, fetcher: impl Fetcher + 'static) -> Self {
+        Self::with_clock(role_arn, fetcher, SystemClock)
+    }
+
+    pub fn with_clock(
+        role_arn: String,
+        fetcher: impl Fetcher + 'static,
+        clock: impl Clock + 'static,
+    ) -> Self {
+        Self {
+            role_arn,
+            fetcher: Arc::new(fetcher),
+            clock: Arc::new(clock),
+            cache: Arc::new(Mutex::new(HashMap::new())),
+            retry: RetryPolicy::from_config(),
+        }
+    }
+
+    pub fn role_arn_of(&self, bucket: &str) -> String {
+        self.role_arn.replace(BUCKET_PLACEHOLDER, bucket)
+    }
+
+    fn is_fresh(credentials: &AwsCredentials, now: DateTime<Utc>) -> bool {
+        match credentials.expires_at() {
+            Some(expires_at) => *expires_at - now > Duration::seconds(REFRESH_MARGIN_SECS),
+            None => true,
+        }
+    }
+
+    /// Returns the cached credentials of the role for `bucket`, assuming the
+    /// role again once they are within a few minutes of expiring.
+    pub async fn credentials(&self, bucket: &str) -> Result<AwsCredentials> {
+        let role_arn = self.role_arn_of(bucket);
+        // NOTE: The lock is held while assuming the role so that concurrent
+        //       queries do not call STS for the same role at once.
+        let mut cache = self.cache.lock().await;
+        if let Some(credentials) = cache.get(&role_arn) {
+            if Self::is_fresh(credentials, self.clock.now()) {
+                return Ok(credentials.clone());
+            }
+        }
+        let credentials =
+            RetryUtility::retry(&self.retry, || self.fetcher.assume(&role_arn)).await?;
+        cache.insert(role_arn, credentials.clone());
+        Ok(credentials)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::atomic::Ordering;
+
+    #[derive(Clone)]
+    struct MockClock(Arc<std::sync::Mutex<DateTime<Utc>>>);
+
+    impl MockClock {
+        fn advance(&self, duration: Duration) {
+            let mut now = self.0.lock().expect("clock should be locked properly");
+            *now = *now + duration;
+        }
+    }
+
+    impl Clock for MockClock {
+        fn now(&self) -> DateTime<Utc> {
+            *self.0.lock().expect("clock should be locked properly")
+        }
+    }
+
+    #[derive(Clone)]
+    struct MockFetcher {
+        clock: MockClock,
+        ttl: Duration,
+        calls: Arc<AtomicUsize>,
+    }
+
+    #[async_trait]
+    impl Fetcher for MockFetcher {
+        async fn assume(&self, role_arn: &str) -> Result<AwsCredentials> {
+            let call = self.calls.fetch_add(1, Ordering::SeqCst);
+            Ok(AwsCredentials::new(
+                format!("{}-{}", role_arn, call),
+                testutils::rand::string(40),
+                Some(testutils::rand::string(40)),
+                Some(self.clock.now() + self.ttl),
+            ))
+        }
+    }
+
+    struct FlakyFetcher {
+        calls: Arc<AtomicUsize>,
+        transient: bool,
+    }
+
+    #[async_trait]
+    impl Fetcher for FlakyFetcher {
+        async fn assume(&self, role_arn: &str) -> Result<AwsCredentials> {
+            if self.calls.fetch_add(1, Ordering::SeqCst) == 0 {
+                let e = anyhow::anyhow!(r#"failed to assume AWS role "{}""#, role_arn);
+                if self.transient {
+                    return Err(RetryUtility::transient(e));
+                }
+                return Err(e);
+            }
+            Ok(AwsCredentials::new(
+                role_arn,
+                testutils::rand::string(40),
+                None,
+                None,
+            ))
+        }
+    }
+
+    fn provider(role_arn: &str, ttl: Duration) -> (Provider, MockClock, Arc<AtomicUsize>) {
+        let clock = MockClock(Arc::new(std::sync::Mutex::new(Utc::now())));
+        let calls = Arc::new(AtomicUsize::new(0));
+        let fetcher = MockFetcher {
+            clock: clock.clone(),
+            ttl,
+            calls: calls.clone(),
+        };
+        (
+            Provider::with_clock(role_arn.into(), fetcher, clock.clone()),
+            clock,
+            calls,
+        )
+    }
+
+    #[test]
+    fn test_role_arn_of() {
+        let (provider, _, _) = provider(
+            "arn:aws:iam::123456789012:role/{bucket}-reader",
+            Duration::hours(1),
+        );
+        let bucket = testutils::rand::string(10);
+        assert_eq!(
+            provider.role_arn_of(&bucket),
+            format!("arn:aws:iam::123456789012:role/{}-reader", bucket)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_credentials_are_cached_until_near_expiry() {
+        let (provider, clock, calls) =
+            provider("arn:aws:iam::123456789012:role/reader", Duration::hours(1));
+        let first = provider
+            .credentials("bucket")
+            .await
+            .expect("credentials should be assumed");
+        clock.advance(Duration::minutes(50));
+        let cached = provider
+            .credentials("bucket")
+            .await
+            .expect("credentials should be cached");
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert_eq!(cached.aws_access_key_id(), first.aws_access_key_id());
+        clock.advance(Duration::minutes(6));
+        let refreshed = provider
+            .credentials("bucket")
+            .await
+            .expect("credentials should be refreshed");
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        assert_ne!(refreshed.aws_access_key_id(), first.aws_access_key_id());
+    }
+
+    #[tokio::test]
+    async fn test_credentials_are_cached_per_role() {
+        let (provider, _, calls) = provider(
+            "arn:aws:iam::123456789012:role/{bucket}",
+            Duration::hours(1),
+        );
+        let a = provider
+            .credentials("a")
+            .await
+            .expect("credentials should be assumed");
+        let b = provider
+            .credentials("b")
+            .await
+            .expect("credentials should be assumed");
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        assert_ne!(a.aws_access_key_id(), b.aws_access_key_id());
+    }
+
+    #[tokio::test]
+    async fn test_credentials_are_retried_on_transient_failure() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let provider = Provider::new(
+            "arn:aws:iam::123456789012:role/reader".into(),
+            FlakyFetcher {
+                calls: calls.clone(),
+                transient: true,
+            },
+        );
+        provider
+            .credentials("bucket")
+            .await
+            .expect("credentials should be assumed on retry");
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_credentials_are_not_retried_on_access_denied() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let provider = Provider::new(
+            "arn:aws:iam::123456789012:role/reader".into(),
+            FlakyFetcher {
+                calls: calls.clone(),
+                transient: false,
+            },
+        );
+        assert!(provider.credentials("bucket").await.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+}
@@ -0,0 +1,215 @@
+use anyhow::Context;
+use anyhow::Result;
+use chrono::DateTime;
+use chrono::Utc;
+use sqlx::postgres::PgQueryResult;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::server::middlewares::correlation::CorrelationId;
+use crate::server::middlewares::request_id::RequestId;
+use crate::server::utilities::postgres::PgAcquire;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, strum_macros::Display, strum_macros::EnumString)]
+#[strum(serialize_all = "snake_case")]
+pub enum Event {
+    ProfileIssued,
+    TableQueried,
+    ChangesRead,
+}
+
+/// Ids tying an audit entry to the logs of the request which recorded it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Trace {
+    pub request_id: Option<String>,
+    pub correlation_id: Option<String>,
+}
+
+impl Trace {
+    pub fn new(request_id: Option<&RequestId>, correlation: Option<&CorrelationId>) -> Self {
+        Self {
+            request_id: request_id.map(ToString::to_string),
+            correlation_id: correlation.map(ToString::to_string),
+        }
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize, sqlx::FromRow, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditLog {
+    pub id: String,
+    pub event: String,
+    pub provider: Option<String>,
+    pub recipient: String,
+    pub share: Option<String>,
+    pub schema: Option<String>,
+    pub table: Option<String>,
+    pub ttl: Option<i64>,
+    pub file_count: Option<i64>,
+    pub request_id: Option<String>,
+    pub correlation_id: Option<String>,
+    #[schema(value_type = String)]
+    pub created_at: DateTime<Utc>,
+}
+
+pub struct Service;
+
+impl Service {
+    pub async fn record_profile_issuance(
+        provider: &str,
+        recipient: &str,
+        ttl: i64,
+        trace: &Trace,
+        executor: impl PgAcquire<'_>,
+    ) -> Result<PgQueryResult> {
+        let mut conn = executor
+            .acquire()
+            .await
+            .context("failed to acquire postgres connection")?;
+        sqlx::query(
+            "INSERT INTO audit_log (
+                 id,
+                 \"event\",
+                 provider,
+                 recipient,
+                 ttl,
+                 request_id,
+                 correlation_id
+             ) VALUES ($1, $2, $3, $4, $5, $6, $7)",
+        )
+        .bind(Uuid::new_v4())
+        .bind(Event::ProfileIssued.to_string())
+        .bind(provider)
+        .bind(recipient)
+        .bind(ttl)
+        .bind(&trace.request_id)
+        .bind(&trace.correlation_id)
+        .execute(&mut *conn)
+        .await
+        .context(format!(
+            r#"failed to record profile issuance for "{}" into [audit_log]"#,
+            recipient
+        ))
+    }
+
+    pub async fn record_table_query(
+        recipient: &str,
+        share: &str,
+        schema: &str,
+        table: &str,
+        file_count: i64,
+        trace: &Trace,
+        executor: impl PgAcquire<'_>,
+    ) -> Result<PgQueryResult> {
+        Self::record_table_read(
+            Event::TableQueried,
+            recipient,
+            (share, schema, table),
+            file_count,
+            trace,
+            executor,
+        )
+        .await
+    }
+
+    pub async fn record_changes_read(
+        recipient: &str,
+        share: &str,
+        schema: &str,
+        table: &str,
+        change_count: i64,
+        trace: &Trace,
+        executor: impl PgAcquire<'_>,
+    ) -> Result<PgQueryResult> {
+        Self::record_table_read(
+            Event::ChangesRead,
+            recipient,
+            (share, schema, table),
+            change_count,
+            trace,
+            executor,
+        )
+        .await
+    }
+
+    async fn record_table_read(
+        event: Event,
+        recipient: &str,
+        (share, schema, table): (&str, &str, &str),
+        file_count: i64,
+        trace: &Trace,
+        executor: impl PgAcquire<'_>,
+    ) -> Result<PgQueryResult> {
+        let mut conn = executor
+            .acquire()
+            .await
+            .context("failed to acquire postgres connection")?;
+        sqlx::query(
+            "INSERT INTO audit_log (
+                 id,
+                 \"event\",
+                 recipient,
+                 share,
+                 \"schema\",
+                 \"table\",
+                 file_count,
+                 request_id,
+                 correlation_id
+             ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)",
+        )
+        .bind(Uuid::new_v4())
+        .bind(event.to_string())
+        .bind(recipient)
+        .bind(share)
+        .bind(schema)
+        .bind(table)
+        .bind(file_count)
+        .bind(&trace.request_id)
+        .bind(&trace.correlation_id)
+        .execute(&mut *conn)
+        .await
+        .context(format!(
+            r#"failed to record {} of "{}.{}.{}" by "{}" into [audit_log]"#,
+            event, share, schema, table, recipient
+        ))
+    }
+
+    pub async fn query_by_recipient(
+        recipient: &str,
+        limit: Option<&i64>,
+        executor: impl PgAcquire<'_>,
+    ) -> Result<Vec<AuditLog>> {
+        let mut conn = executor
+            .acquire()
+            .await
+            .context("failed to acquire postgres connection")?;
+        let rows: Vec<AuditLog> = sqlx::query_as::<_, AuditLog>(
+            "SELECT
+                 id::text AS id,
+                 \"event\",
+                 provider,
+                 recipient,
+                 share,
+                 \"schema\",
+                 \"table\",
+                 ttl,
+                 file_count,
+                 request_id,
+                 correlation_id,
+                 created_at
+             FROM audit_log
+             WHERE recipient = $1
+             ORDER BY created_at DESC, id
+             LIMIT $2",
+        )
+        .bind(recipient)
+        .bind(limit.copied())
+        .fetch_all(&mut *conn)
+        .await
+        .context(format!(
+            r#"failed to list audit logs of "{}" from [audit_log]"#,
+            recipient
+        ))?;
+        Ok(rows)
+    }
+}
@@ -0,0 +1,119 @@
+use utoipa::ToSchema;
+
+use crate::config;
+use crate::server::services::profile::VERSION as SHARE_CREDENTIALS_VERSION;
+
+pub const RESPONSE_FORMATS: [&str; 2] = ["parquet", "delta"];
+
+pub const HEADER_NAME: &str = "delta-sharing-capabilities";
+
+pub const DEFAULT_READER_VERSION: i32 = 1;
+
+/// Whether table changes are served; the `/changes` endpoint is always routed.
+pub const CHANGE_DATA_FEED: bool = true;
+
+#[derive(Debug, serde::Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Capabilities {
+    pub share_credentials_version: i32,
+    pub response_formats: Vec<String>,
+    pub change_data_feed: bool,
+    pub max_page_results: usize,
+    pub signed_url_ttl: i64,
+    pub min_signed_url_ttl: u64,
+    pub max_signed_url_ttl: u64,
+    pub admin_ttl: i64,
+    pub profile_min_ttl: i64,
+    pub profile_max_ttl: i64,
+}
+
+pub struct Service;
+
+impl Service {
+    pub fn describe() -> Capabilities {
+        Capabilities {
+            share_credentials_version: SHARE_CREDENTIALS_VERSION,
+            response_formats: RESPONSE_FORMATS.iter().map(|f| f.to_string()).collect(),
+            change_data_feed: CHANGE_DATA_FEED,
+            max_page_results: config::fetch::<usize>("max_page_results"),
+            signed_url_ttl: config::fetch::<i64>("signed_url_ttl"),
+            min_signed_url_ttl: config::fetch::<u64>("min_signed_url_ttl"),
+            max_signed_url_ttl: config::fetch::<u64>("max_signed_url_ttl"),
+            admin_ttl: config::fetch::<i64>("admin_ttl"),
+            profile_min_ttl: config::fetch::<i64>("profile_min_ttl"),
+            profile_max_ttl: config::fetch::<i64>("profile_max_ttl"),
+        }
+    }
+
+    /// Returns the highest delta reader version advertised by the client through the
+    /// `delta-sharing-capabilities` header, e.g. `responseformat=delta;readerversion=3`.
+    /// Clients that do not advertise one are assumed to read version 1 tables only.
+    pub fn reader_version(header: Option<&str>) -> Option<i32> {
+        let Some(header) = header else {
+            return Some(DEFAULT_READER_VERSION);
+        };
+        for capability in header.split(';') {
+            let Some((key, value)) = capability.split_once('=') else {
+                continue;
+            };
+            if key.trim().eq_ignore_ascii_case("readerversion") {
+                return value.trim().parse::<i32>().ok().filter(|v| *v > 0);
+            }
+        }
+        Some(DEFAULT_READER_VERSION)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_describe() {
+        let capabilities = Service::describe();
+        assert_eq!(
+            capabilities.share_credentials_version,
+            SHARE_CREDENTIALS_VERSION
+        );
+        assert_eq!(
+            capabilities.response_formats,
+            vec!["parquet".to_string(), "delta".to_string()]
+        );
+        assert!(capabilities.change_data_feed);
+        // NOTE: The limits are the ones configured in config/dev/config.toml.
+        assert_eq!(capabilities.max_page_results, 500);
+        assert_eq!(capabilities.signed_url_ttl, 28800);
+        assert_eq!(capabilities.min_signed_url_ttl, 60);
+        assert_eq!(capabilities.max_signed_url_ttl, 604800);
+        assert_eq!(capabilities.admin_ttl, 28800);
+        assert_eq!(capabilities.profile_min_ttl, 60);
+        assert_eq!(capabilities.profile_max_ttl, 31536000);
+    }
+
+    #[test]
+    fn test_reader_version() {
+        assert_eq!(Service::reader_version(None), Some(DEFAULT_READER_VERSION));
+        assert_eq!(
+            Service::reader_version(Some("responseformat=delta")),
+            Some(DEFAULT_READER_VERSION)
+        );
+        let version = testutils::rand::i32(1, 4);
+        assert_eq!(
+            Service::reader_version(Some(&format!(
+                "responseformat=delta; ReaderVersion={}",
+                version
+            ))),
+            Some(version)
+        );
+    }
+
+    #[test]
+    fn test_malformed_reader_version() {
+        assert!(Service::reader_version(Some(&format!(
+            "readerversion={}",
+            testutils::rand::string(10)
+        )))
+        .is_none());
+        assert!(Service::reader_version(Some("readerversion=0")).is_none());
+    }
+}
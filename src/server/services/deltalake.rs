@@ -1,28 +1,72 @@
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
 use std::collections::HashMap;
+use std::collections::HashSet;
+use std::future::Future;
+use std::str::FromStr;
 
+use anyhow::anyhow;
 use anyhow::Result;
 use axum::BoxError;
+use chrono::DateTime;
+use chrono::Utc;
+use deltalake::action::Action;
 use deltalake::action::Add;
 use deltalake::delta::DeltaTable;
 use deltalake::delta::DeltaTableMetaData;
+use deltalake::delta::PeekCommit;
 use deltalake::schema::Schema;
 use futures_util::stream::Stream;
+use futures_util::stream::StreamExt;
 use md5;
 use serde_json::json;
 use utoipa::ToSchema;
 
+use crate::server::utilities::deltalake::Stats;
 use crate::server::utilities::deltalake::Utility as DeltalakeUtility;
 use crate::server::utilities::json::PartitionFilter as JSONPartitionFilter;
 use crate::server::utilities::json::Utility as JSONUtility;
 use crate::server::utilities::sql::PartitionFilter as SQLPartitionFilter;
 use crate::server::utilities::sql::Utility as SQLUtility;
 
-pub const VERSION: i32 = 1;
+pub const VERSION: i32 = 2;
+
+const COLUMN_MAPPING_MODE: &str = "delta.columnMapping.mode";
+
+const COLUMN_MAPPING_READER_VERSION: i32 = 2;
+
+const TABLE_FEATURES_READER_VERSION: i32 = 3;
+
+const SUPPORTED_READER_FEATURES: [&str; 1] = ["columnMapping"];
+
+const VERIFICATION_CONCURRENCY: usize = 16;
+
+const STREAM_BUFFER: usize = 64;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, strum_macros::EnumString)]
+#[strum(serialize_all = "lowercase", ascii_case_insensitive)]
+pub enum ResponseFormat {
+    Parquet,
+    Delta,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, strum_macros::EnumString)]
+#[strum(serialize_all = "lowercase", ascii_case_insensitive)]
+pub enum ColumnMappingMode {
+    None,
+    Name,
+    Id,
+}
 
 #[derive(serde::Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct ProtocolDetail {
     pub min_reader_version: i32,
+    pub min_writer_version: i32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reader_features: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub writer_features: Option<Vec<String>>,
 }
 
 #[derive(serde::Serialize, ToSchema)]
@@ -32,10 +76,73 @@ pub struct Protocol {
 }
 
 impl Protocol {
-    fn new() -> Self {
-        Self {
+    fn new(min_reader_version: i32, min_writer_version: i32) -> Result<Self> {
+        Self::with_features(min_reader_version, min_writer_version, None, None)
+    }
+
+    /// Accepts table features protocols as long as every reader feature is one the
+    /// server handles, since those tables read the same as older versions.
+    fn with_features(
+        min_reader_version: i32,
+        min_writer_version: i32,
+        reader_features: Option<Vec<String>>,
+        writer_features: Option<Vec<String>>,
+    ) -> Result<Self> {
+        if min_reader_version == TABLE_FEATURES_READER_VERSION {
+            let Some(features) = &reader_features else {
+                return Err(anyhow!(
+                    "delta table requires reader version {} but lists no reader features",
+                    min_reader_version
+                ));
+            };
+            if let Some(feature) = features
+                .iter()
+                .find(|feature| !SUPPORTED_READER_FEATURES.contains(&feature.as_str()))
+            {
+                return Err(anyhow!(
+                    "delta table requires reader feature {} which is not supported",
+                    feature
+                ));
+            }
+        } else if min_reader_version > VERSION {
+            return Err(anyhow!(
+                "delta table requires reader version {} but only up to {} is supported",
+                min_reader_version,
+                VERSION
+            ));
+        }
+        Ok(Self {
             protocol: ProtocolDetail {
-                min_reader_version: VERSION,
+                min_reader_version,
+                min_writer_version,
+                reader_features,
+                writer_features,
+            },
+        })
+    }
+
+    pub fn is_readable_by(&self, reader_version: i32) -> bool {
+        self.protocol.min_reader_version <= reader_version
+    }
+}
+
+#[derive(serde::Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DeltaProtocolDetail {
+    pub delta_protocol: ProtocolDetail,
+}
+
+#[derive(serde::Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DeltaProtocol {
+    pub protocol: DeltaProtocolDetail,
+}
+
+impl DeltaProtocol {
+    fn from(protocol: Protocol) -> Self {
+        Self {
+            protocol: DeltaProtocolDetail {
+                delta_protocol: protocol.protocol,
             },
         }
     }
@@ -65,6 +172,42 @@ pub struct MetadataDetail {
     pub size: Option<i64>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub num_files: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub statistics: Option<Statistics>,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ColumnStatistics {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Object)]
+    pub min_value: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Object)]
+    pub max_value: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub null_count: Option<i64>,
+}
+
+/// Statistics aggregated over the files of a snapshot carrying `stats`; files
+/// without them are left out, so `numFiles` counts the contributing files only.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Statistics {
+    pub num_files: i64,
+    pub num_records: i64,
+    pub columns: BTreeMap<String, ColumnStatistics>,
+}
+
+/// Cost of scanning the files of a query; `numRecords` only sums the files
+/// carrying `stats` and is omitted when none of them do.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Estimate {
+    pub num_files: i64,
+    pub total_bytes: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub num_records: Option<i64>,
 }
 
 #[derive(serde::Serialize, ToSchema)]
@@ -89,6 +232,56 @@ impl Metadata {
                 version: None,
                 size: None,
                 num_files: None,
+                statistics: None,
+            },
+        }
+    }
+}
+
+#[derive(serde::Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DeltaMetadataAction {
+    pub id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    pub format: Format,
+    pub schema_string: String,
+    pub partition_columns: Vec<String>,
+    pub configuration: HashMap<String, Option<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub created_time: Option<i64>,
+}
+
+#[derive(serde::Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DeltaMetadataDetail {
+    pub delta_metadata: DeltaMetadataAction,
+}
+
+#[derive(serde::Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DeltaMetadata {
+    pub meta_data: DeltaMetadataDetail,
+}
+
+impl DeltaMetadata {
+    fn from(metadata: DeltaTableMetaData) -> Self {
+        Self {
+            meta_data: DeltaMetadataDetail {
+                delta_metadata: DeltaMetadataAction {
+                    id: metadata.id,
+                    name: metadata.name,
+                    description: metadata.description,
+                    format: Format {
+                        provider: metadata.format.get_provider(),
+                    },
+                    schema_string: json!(metadata.schema).to_string(),
+                    partition_columns: metadata.partition_columns,
+                    configuration: metadata.configuration,
+                    created_time: metadata.created_time,
+                },
             },
         }
     }
@@ -107,6 +300,10 @@ pub struct FileDetail {
     pub version: Option<i64>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub timestamp: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expiration_timestamp: Option<i64>,
+    #[serde(skip)]
+    pub modification_time: i64,
 }
 
 #[derive(serde::Serialize, ToSchema)]
@@ -115,12 +312,74 @@ pub struct File {
     pub file: FileDetail,
 }
 
+#[derive(serde::Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DeltaAdd {
+    pub path: String,
+    pub partition_values: HashMap<String, String>,
+    pub size: i64,
+    pub modification_time: i64,
+    pub data_change: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stats: Option<String>,
+}
+
+#[derive(serde::Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DeltaSingleAction {
+    pub add: DeltaAdd,
+}
+
+#[derive(serde::Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DeltaFileDetail {
+    pub id: String,
+    pub delta_single_action: DeltaSingleAction,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expiration_timestamp: Option<i64>,
+}
+
+#[derive(serde::Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DeltaFile {
+    pub file: DeltaFileDetail,
+}
+
+impl DeltaFile {
+    fn from(file: File) -> Self {
+        let file = file.file;
+        Self {
+            file: DeltaFileDetail {
+                id: file.id,
+                delta_single_action: DeltaSingleAction {
+                    add: DeltaAdd {
+                        path: file.url,
+                        partition_values: file.partition_values,
+                        size: file.size,
+                        modification_time: file.modification_time,
+                        data_change: true,
+                        stats: file.stats,
+                    },
+                },
+                version: file.version,
+                timestamp: file.timestamp,
+                expiration_timestamp: file.expiration_timestamp,
+            },
+        }
+    }
+}
+
 impl File {
     fn from(
         add: Add,
         version: Option<i64>,
         timestamp: Option<i64>,
-        url_signer: &dyn Fn(String) -> String,
+        expiration_timestamp: Option<i64>,
+        url: String,
     ) -> Self {
         let mut partition_values: HashMap<String, String> = HashMap::new();
         for (k, v) in add.partition_values.into_iter() {
@@ -131,17 +390,65 @@ impl File {
         Self {
             file: FileDetail {
                 id: format!("{:x}", md5::compute(add.path.as_bytes())),
-                url: url_signer(add.path),
+                url,
                 partition_values,
                 size: add.size,
                 stats: add.stats,
                 version,
                 timestamp,
+                expiration_timestamp,
+                modification_time: add.modification_time,
             },
         }
     }
 }
 
+#[derive(serde::Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ChangeDetail {
+    pub id: String,
+    pub url: String,
+    pub partition_values: HashMap<String, String>,
+    pub size: i64,
+    pub version: i64,
+    pub timestamp: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expiration_timestamp: Option<i64>,
+}
+
+#[derive(serde::Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum Change {
+    Add(ChangeDetail),
+    Cdf(ChangeDetail),
+    Remove(ChangeDetail),
+}
+
+impl ChangeDetail {
+    fn from(
+        path: String,
+        partition_values: HashMap<String, Option<String>>,
+        size: i64,
+        version: i64,
+        timestamp: i64,
+        expiration_timestamp: Option<i64>,
+        url: String,
+    ) -> Self {
+        Self {
+            id: format!("{:x}", md5::compute(path.as_bytes())),
+            url,
+            partition_values: partition_values
+                .into_iter()
+                .filter_map(|(k, v)| v.map(|v| (k, v)))
+                .collect(),
+            size,
+            version,
+            timestamp,
+            expiration_timestamp,
+        }
+    }
+}
+
 pub struct Service;
 
 impl Service {
@@ -158,7 +465,7 @@ impl Service {
                 let Ok(stats) = DeltalakeUtility::get_stats(f) else {
                     return true;
                 };
-                if records_so_far > limit_hint.into() {
+                if records_so_far >= limit_hint.into() {
                     false
                 } else {
                     records_so_far += stats.num_records;
@@ -183,6 +490,9 @@ impl Service {
                     .into_iter()
                     .filter(|f| {
                         predicates.iter().all(|p| {
+                            if let Some(value) = f.partition_values.get(&p.column) {
+                                return SQLUtility::filter_partition(p, value.as_ref(), &schema);
+                            }
                             // NOTE: The server may try its best to filter files in a BEST EFFORT mode.
                             let Ok(stats) = DeltalakeUtility::get_stats(f) else {
                                 return true;
@@ -220,25 +530,64 @@ impl Service {
         files
     }
 
-    pub fn files_from(
-        table: DeltaTable,
-        metadata: DeltaTableMetaData,
+    /// Returns the column mapping mode configured on the table. In `name` and `id`
+    /// modes the schema fields carry their physical parquet names and ids in the
+    /// `delta.columnMapping.*` field metadata, which is passed through as is.
+    pub fn column_mapping_mode(metadata: &DeltaTableMetaData) -> Result<ColumnMappingMode> {
+        let Some(Some(mode)) = metadata.configuration.get(COLUMN_MAPPING_MODE) else {
+            return Ok(ColumnMappingMode::None);
+        };
+        ColumnMappingMode::from_str(mode)
+            .map_err(|_| anyhow!("column mapping mode {} is not supported", mode))
+    }
+
+    pub fn protocol_from(table: &DeltaTable) -> Result<Protocol> {
+        let mut min_reader_version = table.get_min_reader_version();
+        if let Ok(metadata) = table.get_metadata() {
+            if Self::column_mapping_mode(metadata)? != ColumnMappingMode::None {
+                min_reader_version = min_reader_version.max(COLUMN_MAPPING_READER_VERSION);
+            }
+        }
+        let state = table.get_state();
+        Protocol::with_features(
+            min_reader_version,
+            table.get_min_writer_version(),
+            Self::feature_names(state.reader_features()),
+            Self::feature_names(state.writer_features()),
+        )
+    }
+
+    /// Returns the names of table features sorted, as they are spelled in the log.
+    fn feature_names<T: serde::Serialize>(features: Option<&HashSet<T>>) -> Option<Vec<String>> {
+        let mut names: Vec<String> = features?
+            .iter()
+            .filter_map(|feature| match serde_json::to_value(feature) {
+                Ok(serde_json::Value::String(name)) => Some(name),
+                _ => None,
+            })
+            .collect();
+        names.sort();
+        Some(names)
+    }
+
+    pub fn snapshot_of(
+        table: &DeltaTable,
+        metadata: &DeltaTableMetaData,
+        is_time_traveled: bool,
+    ) -> (Option<i64>, Option<i64>) {
+        if is_time_traveled {
+            (Some(table.version()), metadata.created_time)
+        } else {
+            (None, None)
+        }
+    }
+
+    pub fn adds(
+        table: &DeltaTable,
         predicate_hints: Option<Vec<SQLPartitionFilter>>,
         json_predicate_hints: Option<JSONPartitionFilter>,
         limit_hint: Option<i32>,
-        is_time_traveled: bool,
-        url_signer: &dyn Fn(String) -> String,
-    ) -> impl Stream<Item = Result<serde_json::Value, BoxError>> {
-        let version = if is_time_traveled {
-            Some(table.version())
-        } else {
-            None
-        };
-        let timestamp = if is_time_traveled {
-            metadata.created_time
-        } else {
-            None
-        };
+    ) -> Vec<Add> {
         let files = Self::filter_with_sql_hints(
             table.get_state().files().to_owned(),
             table.schema().cloned(),
@@ -246,39 +595,1106 @@ impl Service {
         );
         let files =
             Self::filter_with_json_hints(files, table.schema().cloned(), json_predicate_hints);
-        let files = Self::filter_with_limit_hint(files, limit_hint);
-        let mut files = files
-            .into_iter()
+        Self::filter_with_limit_hint(files, limit_hint)
+    }
+
+    pub async fn file_from<S, Fut>(
+        add: Add,
+        version: Option<i64>,
+        timestamp: Option<i64>,
+        expiration_timestamp: Option<i64>,
+        url_signer: S,
+    ) -> Result<File>
+    where
+        S: FnOnce(String) -> Fut,
+        Fut: Future<Output = Result<String>>,
+    {
+        let url = url_signer(add.path.clone()).await?;
+        Ok(File::from(
+            add,
+            version,
+            timestamp,
+            expiration_timestamp,
+            url,
+        ))
+    }
+
+    pub async fn files<S, Fut>(
+        table: &DeltaTable,
+        metadata: &DeltaTableMetaData,
+        predicate_hints: Option<Vec<SQLPartitionFilter>>,
+        json_predicate_hints: Option<JSONPartitionFilter>,
+        limit_hint: Option<i32>,
+        is_time_traveled: bool,
+        expiration_timestamp: Option<i64>,
+        url_signer: S,
+    ) -> Result<Vec<File>>
+    where
+        S: Fn(String) -> Fut,
+        Fut: Future<Output = Result<String>>,
+    {
+        let (version, timestamp) = Self::snapshot_of(table, metadata, is_time_traveled);
+        let mut files = Vec::new();
+        for add in Self::adds(table, predicate_hints, json_predicate_hints, limit_hint) {
+            let url = url_signer(add.path.clone()).await?;
+            files.push(File::from(
+                add,
+                version,
+                timestamp,
+                expiration_timestamp,
+                url,
+            ));
+        }
+        Ok(files)
+    }
+
+    /// Orders stats values of the same JSON type; values of different types, and
+    /// nested struct stats, are not comparable.
+    fn compare_stats(a: &serde_json::Value, b: &serde_json::Value) -> Option<Ordering> {
+        match (a, b) {
+            (serde_json::Value::Number(a), serde_json::Value::Number(b)) => {
+                match (a.as_i64(), b.as_i64()) {
+                    (Some(a), Some(b)) => Some(a.cmp(&b)),
+                    _ => a.as_f64()?.partial_cmp(&b.as_f64()?),
+                }
+            }
+            (serde_json::Value::String(a), serde_json::Value::String(b)) => Some(a.cmp(b)),
+            (serde_json::Value::Bool(a), serde_json::Value::Bool(b)) => Some(a.cmp(b)),
+            _ => None,
+        }
+    }
+
+    fn merge_bound(
+        bound: &mut Option<serde_json::Value>,
+        value: Option<&serde_json::Value>,
+        keep: Ordering,
+    ) {
+        let Some(current) = bound.as_ref() else {
+            return;
+        };
+        // NOTE: A bound missing from any file, or not comparable with it, is unknown.
+        match value.and_then(|value| Some((value, Self::compare_stats(value, current)?))) {
+            Some((value, ordering)) if ordering == keep => *bound = Some(value.clone()),
+            Some(_) => {}
+            None => *bound = None,
+        }
+    }
+
+    fn statistics_of(stats: &Stats) -> Statistics {
+        let mut names: Vec<&String> = stats
+            .min_values
+            .keys()
+            .chain(stats.max_values.keys())
+            .chain(stats.null_count.keys())
+            .collect();
+        names.sort();
+        names.dedup();
+        Statistics {
+            num_files: 1,
+            num_records: stats.num_records,
+            columns: names
+                .into_iter()
+                .map(|name| {
+                    let column = ColumnStatistics {
+                        min_value: stats.min_values.get(name).cloned(),
+                        max_value: stats.max_values.get(name).cloned(),
+                        null_count: stats.null_count.get(name).copied(),
+                    };
+                    (name.clone(), column)
+                })
+                .collect(),
+        }
+    }
+
+    /// Aggregates per-column bounds and null counts over the files carrying
+    /// `stats`, returning `None` when none of them does.
+    pub fn statistics_from(files: &[Add]) -> Option<Statistics> {
+        let mut aggregated: Option<Statistics> = None;
+        for stats in files
+            .iter()
+            .filter_map(|file| DeltalakeUtility::get_stats(file).ok())
+        {
+            let Some(statistics) = aggregated.as_mut() else {
+                aggregated = Some(Self::statistics_of(&stats));
+                continue;
+            };
+            statistics.num_files += 1;
+            statistics.num_records += stats.num_records;
+            for (name, column) in statistics.columns.iter_mut() {
+                Self::merge_bound(
+                    &mut column.min_value,
+                    stats.min_values.get(name),
+                    Ordering::Less,
+                );
+                Self::merge_bound(
+                    &mut column.max_value,
+                    stats.max_values.get(name),
+                    Ordering::Greater,
+                );
+                column.null_count = column
+                    .null_count
+                    .zip(stats.null_count.get(name))
+                    .map(|(total, count)| total + count);
+            }
+        }
+        aggregated
+    }
+
+    /// Describes the changes from `previous` to `current` that break recipients
+    /// reading the previous schema, i.e. dropped and retyped columns; added
+    /// columns are compatible.
+    pub fn breaking_changes(previous: &Schema, current: &Schema) -> Vec<String> {
+        previous
+            .get_fields()
+            .iter()
+            .filter_map(|field| {
+                let Ok(next) = current.get_field_with_name(field.get_name()) else {
+                    return Some(format!(r#"column "{}" was dropped"#, field.get_name()));
+                };
+                (next.get_type() != field.get_type())
+                    .then(|| format!(r#"column "{}" was retyped"#, field.get_name()))
+            })
+            .collect()
+    }
+
+    pub fn estimate_from(files: &[Add]) -> Estimate {
+        Estimate {
+            num_files: i64::try_from(files.len()).unwrap_or(i64::MAX),
+            total_bytes: files.iter().map(|file| file.size).sum(),
+            num_records: Self::statistics_from(files).map(|statistics| statistics.num_records),
+        }
+    }
+
+    pub fn is_change_data_feed_enabled(metadata: &DeltaTableMetaData) -> bool {
+        matches!(
+            metadata.configuration.get("delta.enableChangeDataFeed"),
+            Some(Some(enabled)) if enabled.eq_ignore_ascii_case("true")
+        )
+    }
+
+    /// Returns the timestamp in milliseconds of the commit of `version`.
+    pub async fn commit_timestamp(table: &mut DeltaTable, version: i64) -> Result<i64> {
+        if let PeekCommit::New(_, actions) = table.peek_next_commit(version - 1).await? {
+            let timestamp = actions.iter().find_map(|action| match action {
+                Action::commitInfo(info) => info.timestamp,
+                _ => None,
+            });
+            if let Some(timestamp) = timestamp {
+                return Ok(timestamp);
+            }
+        }
+        Ok(table.get_version_timestamp(version).await?)
+    }
+
+    /// Returns the first version committed at or after `timestamp`, which is how
+    /// the protocol resolves `startingTimestamp`, or `None` if every version was
+    /// committed before it.
+    pub async fn version_at_or_after(
+        table: &mut DeltaTable,
+        timestamp: DateTime<Utc>,
+    ) -> Result<Option<i64>> {
+        let timestamp = timestamp.timestamp_millis();
+        let latest = table.get_latest_version().await?;
+        let (mut low, mut high) = (0, latest + 1);
+        while low < high {
+            let pivot = low + (high - low) / 2;
+            if Self::commit_timestamp(table, pivot).await? < timestamp {
+                low = pivot + 1;
+            } else {
+                high = pivot;
+            }
+        }
+        Ok((low <= latest).then_some(low))
+    }
+
+    pub async fn changes<S, Fut>(
+        table: &mut DeltaTable,
+        starting_version: i64,
+        ending_version: i64,
+        expiration_timestamp: Option<i64>,
+        url_signer: S,
+    ) -> Result<Vec<Change>>
+    where
+        S: Fn(String) -> Fut,
+        Fut: Future<Output = Result<String>>,
+    {
+        let mut changes = Vec::new();
+        for version in starting_version..=ending_version {
+            let PeekCommit::New(_, actions) = table.peek_next_commit(version - 1).await? else {
+                break;
+            };
+            let timestamp = actions.iter().find_map(|action| match action {
+                Action::commitInfo(info) => info.timestamp,
+                _ => None,
+            });
+            let timestamp = match timestamp {
+                Some(timestamp) => timestamp,
+                None => table.get_version_timestamp(version).await?,
+            };
+            // NOTE: Commits carrying change data files are described by them alone,
+            //       otherwise the data-changing add/remove actions are returned as is.
+            let has_cdc = actions
+                .iter()
+                .any(|action| matches!(action, Action::cdc(_)));
+            for action in actions {
+                match action {
+                    Action::cdc(cdc) => {
+                        let url = url_signer(cdc.path.clone()).await?;
+                        changes.push(Change::Cdf(ChangeDetail::from(
+                            cdc.path,
+                            cdc.partition_values,
+                            cdc.size,
+                            version,
+                            timestamp,
+                            expiration_timestamp,
+                            url,
+                        )))
+                    }
+                    Action::add(add) if !has_cdc && add.data_change => {
+                        let url = url_signer(add.path.clone()).await?;
+                        changes.push(Change::Add(ChangeDetail::from(
+                            add.path,
+                            add.partition_values,
+                            add.size,
+                            version,
+                            timestamp,
+                            expiration_timestamp,
+                            url,
+                        )))
+                    }
+                    Action::remove(remove) if !has_cdc && remove.data_change => {
+                        let url = url_signer(remove.path.clone()).await?;
+                        changes.push(Change::Remove(ChangeDetail::from(
+                            remove.path,
+                            remove.partition_values.unwrap_or_default(),
+                            remove.size.unwrap_or_default(),
+                            version,
+                            timestamp,
+                            expiration_timestamp,
+                            url,
+                        )))
+                    }
+                    _ => {}
+                }
+            }
+        }
+        Ok(changes)
+    }
+
+    pub fn changes_from(
+        protocol: Protocol,
+        metadata: DeltaTableMetaData,
+        changes: Vec<Change>,
+    ) -> impl Stream<Item = Result<serde_json::Value, BoxError>> {
+        let mut ret = vec![json!(protocol), json!(Metadata::from(metadata))];
+        ret.extend(changes.into_iter().map(|c| json!(c)));
+        futures_util::stream::iter(ret.into_iter().map(Ok::<serde_json::Value, BoxError>))
+    }
+
+    /// Verifies the signed URL of every file; a single file failing verification
+    /// fails them all, as a table missing files would be silently wrong.
+    pub async fn verify_files<F, Fut>(files: Vec<File>, url_verifier: F) -> Result<Vec<File>>
+    where
+        F: Fn(String) -> Fut,
+        Fut: Future<Output = bool>,
+    {
+        futures_util::stream::iter(files)
             .map(|f| {
-                Ok::<serde_json::Value, BoxError>(json!(File::from(
-                    f, version, timestamp, url_signer
-                )))
+                let verified = url_verifier(f.file.url.clone());
+                async move { (f, verified.await) }
             })
-            .collect::<Vec<Result<serde_json::Value, BoxError>>>();
-        let mut ret = vec![
-            Ok(json!(Protocol::new())),
-            Ok(json!(Metadata::from(metadata))),
-        ];
-        ret.append(&mut files);
-        futures_util::stream::iter(ret)
+            .buffered(VERIFICATION_CONCURRENCY)
+            .map(|(f, verified)| {
+                if verified {
+                    Ok(f)
+                } else {
+                    tracing::error!(id = %f.file.id, "signed url failed verification");
+                    Err(anyhow!(
+                        "signed url of file {} failed verification",
+                        f.file.id
+                    ))
+                }
+            })
+            .collect::<Vec<Result<File>>>()
+            .await
+            .into_iter()
+            .collect::<Result<Vec<File>>>()
+    }
+
+    fn header_from(
+        protocol: Protocol,
+        metadata: DeltaTableMetaData,
+        format: ResponseFormat,
+    ) -> Vec<serde_json::Value> {
+        match format {
+            ResponseFormat::Parquet => vec![json!(protocol), json!(Metadata::from(metadata))],
+            ResponseFormat::Delta => vec![
+                json!(DeltaProtocol::from(protocol)),
+                json!(DeltaMetadata::from(metadata)),
+            ],
+        }
+    }
+
+    fn line_from(file: File, format: ResponseFormat) -> serde_json::Value {
+        match format {
+            ResponseFormat::Parquet => json!(file),
+            ResponseFormat::Delta => json!(DeltaFile::from(file)),
+        }
+    }
+
+    pub fn lines_from(
+        protocol: Protocol,
+        metadata: DeltaTableMetaData,
+        files: Vec<File>,
+        format: ResponseFormat,
+    ) -> Vec<serde_json::Value> {
+        let mut ret = Self::header_from(protocol, metadata, format);
+        ret.extend(files.into_iter().map(|f| Self::line_from(f, format)));
+        ret
+    }
+
+    /// Streams the protocol, metadata and file lines through a bounded channel,
+    /// signing (and verifying) each file only once the client has consumed
+    /// enough of the previous lines. A file failing to be signed or verified ends
+    /// the stream with an error instead of a truncated table.
+    pub fn stream_from<S, SFut, F, Fut>(
+        protocol: Protocol,
+        metadata: DeltaTableMetaData,
+        adds: Vec<Add>,
+        format: ResponseFormat,
+        file_from: S,
+        url_verifier: F,
+    ) -> impl Stream<Item = Result<serde_json::Value, BoxError>>
+    where
+        S: Fn(Add) -> SFut + Send + Sync + 'static,
+        SFut: Future<Output = Result<File>> + Send,
+        F: Fn(String) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = bool> + Send,
+    {
+        let (tx, rx) =
+            tokio::sync::mpsc::channel::<Result<serde_json::Value, BoxError>>(STREAM_BUFFER);
+        let header = Self::header_from(protocol, metadata, format);
+        tokio::spawn(async move {
+            for line in header {
+                if tx.send(Ok(line)).await.is_err() {
+                    return;
+                }
+            }
+            let url_verifier = &url_verifier;
+            let mut files = futures_util::stream::iter(adds)
+                .map(|add| {
+                    let file = file_from(add);
+                    async move {
+                        let file = file.await?;
+                        let verified = url_verifier(file.file.url.clone()).await;
+                        Ok::<(File, bool), anyhow::Error>((file, verified))
+                    }
+                })
+                .buffered(VERIFICATION_CONCURRENCY);
+            while let Some(file) = files.next().await {
+                let (file, verified) = match file {
+                    Ok(file) => file,
+                    Err(e) => {
+                        tracing::error!("failed to sign file: {:#}", e);
+                        let _ = tx.send(Err(e.into())).await;
+                        return;
+                    }
+                };
+                if !verified {
+                    tracing::error!(id = %file.file.id, "signed url failed verification");
+                    let _ = tx
+                        .send(Err(anyhow!(
+                            "signed url of file {} failed verification",
+                            file.file.id
+                        )
+                        .into()))
+                        .await;
+                    return;
+                }
+                if tx.send(Ok(Self::line_from(file, format))).await.is_err() {
+                    tracing::warn!("client went away while streaming files");
+                    return;
+                }
+            }
+        });
+        futures_util::stream::unfold(rx, |mut rx| async move {
+            rx.recv().await.map(|line| (line, rx))
+        })
     }
 
     pub fn metadata_from(
+        protocol: Protocol,
         metadata: DeltaTableMetaData,
+        statistics: Option<Statistics>,
     ) -> impl Stream<Item = Result<serde_json::Value, BoxError>> {
-        let ret = vec![
-            Ok(json!(Protocol::new())),
-            Ok(json!(Metadata::from(metadata))),
-        ];
+        let mut metadata = Metadata::from(metadata);
+        metadata.meta_data.statistics = statistics;
+        let ret = vec![Ok(json!(protocol)), Ok(json!(metadata))];
         futures_util::stream::iter(ret)
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::atomic::Ordering;
+    use std::sync::Arc;
 
     #[tokio::test]
     async fn test() {
         println!("TEST DELTALAKE!!!");
     }
+
+    #[test]
+    fn test_supported_protocol() {
+        let min_writer_version = testutils::rand::i32(1, 8);
+        let protocol = Protocol::new(VERSION, min_writer_version)
+            .expect("supported protocol should be accepted");
+        assert_eq!(
+            json!(protocol),
+            json!({
+                "protocol": {
+                    "minReaderVersion": VERSION,
+                    "minWriterVersion": min_writer_version,
+                }
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_verify_files() {
+        let new_file = |url: String| File {
+            file: FileDetail {
+                id: format!("{:x}", md5::compute(url.as_bytes())),
+                url,
+                partition_values: HashMap::new(),
+                size: testutils::rand::i64(0, 100000),
+                stats: None,
+                version: None,
+                timestamp: None,
+                expiration_timestamp: None,
+                modification_time: 0,
+            },
+        };
+        let existing = format!("https://{}/existing", testutils::rand::string(10));
+        let missing = format!("https://{}/missing", testutils::rand::string(10));
+        let files = vec![new_file(existing.clone()), new_file(missing.clone())];
+        let verified = Service::verify_files(vec![new_file(existing.clone())], |url: String| {
+            let exists = url == existing;
+            async move { exists }
+        })
+        .await
+        .expect("existing files should be verified");
+        assert_eq!(verified.len(), 1);
+        assert_eq!(verified[0].file.url, existing);
+        assert!(Service::verify_files(files, |url: String| {
+            let exists = url == existing;
+            async move { exists }
+        })
+        .await
+        .is_err());
+    }
+
+    #[test]
+    fn test_response_format() {
+        assert_eq!(
+            ResponseFormat::from_str("parquet").ok(),
+            Some(ResponseFormat::Parquet)
+        );
+        assert_eq!(
+            ResponseFormat::from_str("Delta").ok(),
+            Some(ResponseFormat::Delta)
+        );
+        assert!(ResponseFormat::from_str(&testutils::rand::string(10)).is_err());
+    }
+
+    #[test]
+    fn test_lines_with_response_format() {
+        let new_file = || File {
+            file: FileDetail {
+                id: testutils::rand::string(10),
+                url: format!("https://{}", testutils::rand::string(10)),
+                partition_values: HashMap::new(),
+                size: testutils::rand::i64(0, 100000),
+                stats: None,
+                version: None,
+                timestamp: None,
+                expiration_timestamp: None,
+                modification_time: testutils::rand::i64(0, 100000),
+            },
+        };
+        let new_metadata = || {
+            DeltaTableMetaData::new(
+                None,
+                None,
+                None,
+                Schema::new(vec![]),
+                vec![],
+                HashMap::new(),
+            )
+        };
+        let new_protocol = || Protocol::new(VERSION, 2).expect("protocol should be supported");
+
+        let lines = Service::lines_from(
+            new_protocol(),
+            new_metadata(),
+            vec![new_file()],
+            ResponseFormat::Parquet,
+        );
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0]["protocol"]["minReaderVersion"].is_number());
+        assert!(lines[1]["metaData"]["schemaString"].is_string());
+        assert!(lines[2]["file"]["url"].is_string());
+        assert!(lines[2]["file"].get("modificationTime").is_none());
+
+        let file = new_file();
+        let url = file.file.url.clone();
+        let modification_time = file.file.modification_time;
+        let lines = Service::lines_from(
+            new_protocol(),
+            new_metadata(),
+            vec![file],
+            ResponseFormat::Delta,
+        );
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0]["protocol"]["deltaProtocol"]["minReaderVersion"].is_number());
+        assert!(lines[1]["metaData"]["deltaMetadata"]["schemaString"].is_string());
+        let add = &lines[2]["file"]["deltaSingleAction"]["add"];
+        assert_eq!(add["path"], json!(url));
+        assert_eq!(add["modificationTime"], json!(modification_time));
+        assert_eq!(add["dataChange"], json!(true));
+        assert!(lines[2]["file"].get("url").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_stream_before_all_files_are_signed() {
+        let signed = Arc::new(AtomicUsize::new(0));
+        let total = STREAM_BUFFER * 8;
+        let adds = (0..total)
+            .map(|_| {
+                serde_json::from_value(json!({
+                    "path": format!("{}.parquet", testutils::rand::string(10)),
+                    "size": testutils::rand::i64(1, 100000),
+                    "partitionValues": {},
+                    "modificationTime": 0,
+                    "dataChange": true,
+                }))
+                .expect("add action should be deserialized properly")
+            })
+            .collect::<Vec<Add>>();
+        let counter = signed.clone();
+        let stream = Service::stream_from(
+            Protocol::new(VERSION, 2).expect("protocol should be supported"),
+            DeltaTableMetaData::new(
+                None,
+                None,
+                None,
+                Schema::new(vec![]),
+                vec![],
+                HashMap::new(),
+            ),
+            adds,
+            ResponseFormat::Parquet,
+            move |add| {
+                counter.fetch_add(1, Ordering::SeqCst);
+                let url = add.path.clone();
+                async move { Ok::<File, anyhow::Error>(File::from(add, None, None, None, url)) }
+            },
+            |_| async { true },
+        );
+        futures_util::pin_mut!(stream);
+        let first = stream
+            .next()
+            .await
+            .expect("first line should be streamed")
+            .expect("first line should be valid");
+        assert!(first.get("protocol").is_some());
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        assert!(signed.load(Ordering::SeqCst) < total);
+        let rest = stream.collect::<Vec<_>>().await;
+        assert_eq!(rest.len(), total + 1);
+        assert_eq!(signed.load(Ordering::SeqCst), total);
+    }
+
+    #[tokio::test]
+    async fn test_stream_fails_on_signing_error() {
+        let add: Add = serde_json::from_value(json!({
+            "path": format!("{}.parquet", testutils::rand::string(10)),
+            "size": testutils::rand::i64(1, 100000),
+            "partitionValues": {},
+            "modificationTime": 0,
+            "dataChange": true,
+        }))
+        .expect("add action should be deserialized properly");
+        let stream = Service::stream_from(
+            Protocol::new(VERSION, 2).expect("protocol should be supported"),
+            DeltaTableMetaData::new(
+                None,
+                None,
+                None,
+                Schema::new(vec![]),
+                vec![],
+                HashMap::new(),
+            ),
+            vec![add],
+            ResponseFormat::Parquet,
+            |add| {
+                Service::file_from(add, None, None, None, |_| async {
+                    Err::<String, anyhow::Error>(anyhow!("signed url was downgraded to http"))
+                })
+            },
+            |_| async { true },
+        );
+        let lines = stream.collect::<Vec<_>>().await;
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].is_ok());
+        assert!(lines[1].is_ok());
+        assert!(lines[2].is_err());
+    }
+
+    #[tokio::test]
+    async fn test_stream_fails_on_verification_failure() {
+        let adds = (0..2)
+            .map(|_| {
+                serde_json::from_value(json!({
+                    "path": format!("{}.parquet", testutils::rand::string(10)),
+                    "size": testutils::rand::i64(1, 100000),
+                    "partitionValues": {},
+                    "modificationTime": 0,
+                    "dataChange": true,
+                }))
+                .expect("add action should be deserialized properly")
+            })
+            .collect::<Vec<Add>>();
+        let missing = adds[1].path.clone();
+        let stream = Service::stream_from(
+            Protocol::new(VERSION, 2).expect("protocol should be supported"),
+            DeltaTableMetaData::new(
+                None,
+                None,
+                None,
+                Schema::new(vec![]),
+                vec![],
+                HashMap::new(),
+            ),
+            adds,
+            ResponseFormat::Parquet,
+            |add| {
+                let url = add.path.clone();
+                async move { Ok::<File, anyhow::Error>(File::from(add, None, None, None, url)) }
+            },
+            move |url| {
+                let exists = url != missing;
+                async move { exists }
+            },
+        );
+        let lines = stream.collect::<Vec<_>>().await;
+        assert_eq!(lines.len(), 4);
+        assert!(lines[2].is_ok());
+        assert!(lines[3].is_err());
+    }
+
+    #[test]
+    fn test_unsupported_protocol() {
+        let min_reader_version = testutils::rand::i32(VERSION + 1, 4);
+        let min_writer_version = testutils::rand::i32(1, 8);
+        assert!(Protocol::new(min_reader_version, min_writer_version).is_err());
+    }
+
+    #[test]
+    fn test_protocol_is_readable_by() {
+        let protocol = Protocol::new(VERSION, 2).expect("protocol should be supported");
+        assert!(protocol.is_readable_by(VERSION));
+        assert!(protocol.is_readable_by(testutils::rand::i32(VERSION, 4)));
+        assert!(!protocol.is_readable_by(VERSION - 1));
+        let protocol = Protocol::new(1, 2).expect("protocol should be supported");
+        assert!(protocol.is_readable_by(1));
+    }
+
+    #[tokio::test]
+    async fn test_version_at_or_after() {
+        let mut table = deltalake::delta::open_table("tests/fixtures/delta/multi_commit")
+            .await
+            .expect("delta table should be opened properly");
+        let at = |millis: i64| {
+            chrono::TimeZone::timestamp_millis_opt(&Utc, millis)
+                .single()
+                .expect("timestamp should be valid")
+        };
+        for (millis, version) in [
+            (1699999999999, Some(0)),
+            (1700000000000, Some(0)),
+            (1700000000001, Some(1)),
+            (1700000100000, Some(1)),
+            (1700000100001, Some(2)),
+            (1700000200000, Some(2)),
+            (1700000200001, None),
+        ] {
+            assert_eq!(
+                Service::version_at_or_after(&mut table, at(millis))
+                    .await
+                    .expect("version should be resolved"),
+                version
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_protocol_from_v1_table() {
+        let table = deltalake::delta::open_table("tests/fixtures/delta/multi_commit")
+            .await
+            .expect("delta table should be opened properly");
+        let protocol = Service::protocol_from(&table).expect("protocol should be supported");
+        assert_eq!(protocol.protocol.min_reader_version, 1);
+        assert_eq!(protocol.protocol.min_writer_version, 2);
+        assert!(protocol.is_readable_by(1));
+    }
+
+    #[tokio::test]
+    async fn test_metadata_without_column_mapping() {
+        let table = deltalake::delta::open_table("tests/fixtures/delta/multi_commit")
+            .await
+            .expect("delta table should be opened properly");
+        let metadata = table
+            .get_metadata()
+            .expect("metadata should be loaded properly")
+            .to_owned();
+        assert_eq!(
+            Service::column_mapping_mode(&metadata).expect("mode should be detected"),
+            ColumnMappingMode::None
+        );
+        let metadata = json!(Metadata::from(metadata));
+        let schema: serde_json::Value = serde_json::from_str(
+            metadata["metaData"]["schemaString"]
+                .as_str()
+                .expect("schema string should be returned"),
+        )
+        .expect("schema string should be JSON");
+        assert_eq!(schema["fields"][0]["name"], "id");
+        assert_eq!(schema["fields"][0]["metadata"], json!({}));
+    }
+
+    #[tokio::test]
+    async fn test_metadata_with_column_mapping_in_name_mode() {
+        let table = deltalake::delta::open_table("tests/fixtures/delta/column_mapping")
+            .await
+            .expect("delta table should be opened properly");
+        let protocol = Service::protocol_from(&table).expect("protocol should be supported");
+        assert_eq!(
+            protocol.protocol.min_reader_version,
+            COLUMN_MAPPING_READER_VERSION
+        );
+        assert!(!protocol.is_readable_by(1));
+        let metadata = table
+            .get_metadata()
+            .expect("metadata should be loaded properly")
+            .to_owned();
+        assert_eq!(
+            Service::column_mapping_mode(&metadata).expect("mode should be detected"),
+            ColumnMappingMode::Name
+        );
+        let metadata = json!(Metadata::from(metadata));
+        assert_eq!(
+            metadata["metaData"]["configuration"][COLUMN_MAPPING_MODE],
+            "name"
+        );
+        let schema: serde_json::Value = serde_json::from_str(
+            metadata["metaData"]["schemaString"]
+                .as_str()
+                .expect("schema string should be returned"),
+        )
+        .expect("schema string should be JSON");
+        assert_eq!(schema["fields"][0]["name"], "id");
+        assert_eq!(
+            schema["fields"][0]["metadata"]["delta.columnMapping.physicalName"],
+            "col-3b8a5c1e-7d24-4f6a-9e0b-1c2d3e4f5a6b"
+        );
+        assert_eq!(schema["fields"][0]["metadata"]["delta.columnMapping.id"], 1);
+    }
+
+    #[test]
+    fn test_unsupported_column_mapping_mode() {
+        let mut metadata = DeltaTableMetaData::new(
+            None,
+            None,
+            None,
+            Schema::new(vec![]),
+            vec![],
+            HashMap::from([(
+                COLUMN_MAPPING_MODE.to_string(),
+                Some(testutils::rand::string(10)),
+            )]),
+        );
+        assert!(Service::column_mapping_mode(&metadata).is_err());
+        metadata.configuration.clear();
+        assert_eq!(
+            Service::column_mapping_mode(&metadata).expect("mode should be detected"),
+            ColumnMappingMode::None
+        );
+    }
+
+    #[tokio::test]
+    async fn test_statistics_from() {
+        let table = deltalake::delta::open_table("tests/fixtures/delta/statistics")
+            .await
+            .expect("delta table should be opened properly");
+        let statistics = Service::statistics_from(table.get_state().files())
+            .expect("statistics should be aggregated");
+        assert_eq!(
+            json!(statistics),
+            json!({
+                "numFiles": 2,
+                "numRecords": 15,
+                "columns": {
+                    "id": {"minValue": -3, "maxValue": 20, "nullCount": 0},
+                    "name": {"minValue": "apple", "maxValue": "zucchini", "nullCount": 3},
+                },
+            })
+        );
+    }
+
+    fn new_schema(fields: serde_json::Value) -> Schema {
+        serde_json::from_value(json!({
+            "type": "struct",
+            "fields": fields,
+        }))
+        .expect("schema should be deserialized properly")
+    }
+
+    #[test]
+    fn test_breaking_changes_of_added_column() {
+        let previous = new_schema(json!([
+            {"name": "id", "type": "long", "nullable": true, "metadata": {}},
+        ]));
+        let current = new_schema(json!([
+            {"name": "id", "type": "long", "nullable": true, "metadata": {}},
+            {"name": "name", "type": "string", "nullable": true, "metadata": {}},
+        ]));
+        assert!(Service::breaking_changes(&previous, &current).is_empty());
+    }
+
+    #[test]
+    fn test_breaking_changes_of_dropped_column() {
+        let previous = new_schema(json!([
+            {"name": "id", "type": "long", "nullable": true, "metadata": {}},
+            {"name": "name", "type": "string", "nullable": true, "metadata": {}},
+        ]));
+        let current = new_schema(json!([
+            {"name": "id", "type": "long", "nullable": true, "metadata": {}},
+        ]));
+        assert_eq!(
+            Service::breaking_changes(&previous, &current),
+            vec![r#"column "name" was dropped"#]
+        );
+    }
+
+    #[test]
+    fn test_breaking_changes_of_retyped_column() {
+        let previous = new_schema(json!([
+            {"name": "id", "type": "long", "nullable": true, "metadata": {}},
+        ]));
+        let current = new_schema(json!([
+            {"name": "id", "type": "string", "nullable": true, "metadata": {}},
+        ]));
+        assert_eq!(
+            Service::breaking_changes(&previous, &current),
+            vec![r#"column "id" was retyped"#]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_estimate_from() {
+        let table = deltalake::delta::open_table("tests/fixtures/delta/statistics")
+            .await
+            .expect("delta table should be opened properly");
+        let adds = Service::adds(&table, None, None, None);
+        assert_eq!(
+            json!(Service::estimate_from(&adds)),
+            json!({
+                "numFiles": 3,
+                "totalBytes": 896,
+                "numRecords": 15,
+            })
+        );
+    }
+
+    #[test]
+    fn test_estimate_from_files_without_stats() {
+        let size = testutils::rand::i64(1, 1000);
+        let add = Add {
+            path: testutils::rand::string(10),
+            size,
+            stats: None,
+            ..Default::default()
+        };
+        assert_eq!(
+            Service::estimate_from(&[add]),
+            Estimate {
+                num_files: 1,
+                total_bytes: size,
+                num_records: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_statistics_from_files_without_stats() {
+        let add = Add {
+            path: testutils::rand::string(10),
+            stats: None,
+            ..Default::default()
+        };
+        assert_eq!(Service::statistics_from(&[add]), None);
+    }
+
+    #[tokio::test]
+    async fn test_protocol_from_v3_table_with_deletion_vectors() {
+        let table = deltalake::delta::open_table("tests/fixtures/delta/deletion_vectors")
+            .await
+            .expect("delta table should be opened properly");
+        assert_eq!(table.get_min_reader_version(), 3);
+        let error = Service::protocol_from(&table)
+            .err()
+            .expect("unsupported reader feature should be rejected");
+        assert!(error.to_string().contains("deletionVectors"));
+    }
+
+    #[tokio::test]
+    async fn test_protocol_from_v3_table_with_supported_features() {
+        let table = deltalake::delta::open_table("tests/fixtures/delta/table_features")
+            .await
+            .expect("delta table should be opened properly");
+        let protocol = Service::protocol_from(&table).expect("protocol should be supported");
+        assert!(protocol.is_readable_by(TABLE_FEATURES_READER_VERSION));
+        assert!(!protocol.is_readable_by(VERSION));
+        assert_eq!(
+            json!(protocol),
+            json!({
+                "protocol": {
+                    "minReaderVersion": 3,
+                    "minWriterVersion": 7,
+                    "readerFeatures": ["columnMapping"],
+                    "writerFeatures": ["columnMapping"],
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn test_protocol_without_reader_features() {
+        assert!(Protocol::with_features(
+            TABLE_FEATURES_READER_VERSION,
+            7,
+            None,
+            Some(vec![String::from("columnMapping")])
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_filter_with_partition_hints() {
+        let schema: Schema = serde_json::from_value(json!({
+            "type": "struct",
+            "fields": [
+                {"name": "date", "type": "date", "nullable": true, "metadata": {}},
+                {"name": "value", "type": "long", "nullable": true, "metadata": {}},
+            ]
+        }))
+        .expect("schema should be deserialized properly");
+        let new_add = |date: &str| -> Add {
+            serde_json::from_value(json!({
+                "path": format!("date={}/{}.parquet", date, testutils::rand::string(10)),
+                "size": testutils::rand::i64(1, 100000),
+                "partitionValues": {"date": date},
+                "modificationTime": 0,
+                "dataChange": true,
+            }))
+            .expect("add action should be deserialized properly")
+        };
+        let files = vec![
+            new_add("2021-01-01"),
+            new_add("2021-01-02"),
+            new_add("2021-01-01"),
+        ];
+        let predicates = vec![SQLUtility::parse("date = '2021-01-01'".into())
+            .expect("predicate should be parsed properly")];
+        let filtered = Service::filter_with_sql_hints(files, Some(schema), Some(predicates));
+        assert_eq!(filtered.len(), 2);
+        assert!(filtered
+            .iter()
+            .all(|f| f.partition_values.get("date") == Some(&Some("2021-01-01".into()))));
+    }
+
+    #[test]
+    fn test_filter_with_limit_hint() {
+        let new_add = |stats: Option<String>| -> Add {
+            serde_json::from_value(json!({
+                "path": format!("{}.parquet", testutils::rand::string(10)),
+                "size": testutils::rand::i64(1, 100000),
+                "partitionValues": {},
+                "modificationTime": 0,
+                "dataChange": true,
+                "stats": stats,
+            }))
+            .expect("add action should be deserialized properly")
+        };
+        let with_stats = || {
+            new_add(Some(
+                json!({
+                    "numRecords": 10,
+                    "minValues": {},
+                    "maxValues": {},
+                    "nullCount": {},
+                })
+                .to_string(),
+            ))
+        };
+        let files = (0..5).map(|_| with_stats()).collect::<Vec<Add>>();
+        assert_eq!(Service::filter_with_limit_hint(files, Some(15)).len(), 2);
+        let files = (0..5).map(|_| with_stats()).collect::<Vec<Add>>();
+        assert_eq!(Service::filter_with_limit_hint(files, Some(10)).len(), 1);
+        let files = (0..5).map(|_| with_stats()).collect::<Vec<Add>>();
+        assert_eq!(Service::filter_with_limit_hint(files, None).len(), 5);
+        let files = (0..5).map(|_| new_add(None)).collect::<Vec<Add>>();
+        assert_eq!(Service::filter_with_limit_hint(files, Some(1)).len(), 5);
+    }
+
+    #[test]
+    fn test_change_lines() {
+        let detail = || ChangeDetail {
+            id: testutils::rand::string(10),
+            url: testutils::rand::string(10),
+            partition_values: HashMap::new(),
+            size: testutils::rand::i64(0, 100000),
+            version: testutils::rand::i64(0, 100),
+            timestamp: testutils::rand::i64(0, 100000),
+            expiration_timestamp: None,
+        };
+        assert!(json!(Change::Add(detail())).get("add").is_some());
+        assert!(json!(Change::Cdf(detail())).get("cdf").is_some());
+        assert!(json!(Change::Remove(detail())).get("remove").is_some());
+    }
+
+    #[test]
+    fn test_is_change_data_feed_enabled() {
+        let new_metadata = |configuration: HashMap<String, Option<String>>| {
+            DeltaTableMetaData::new(None, None, None, Schema::new(vec![]), vec![], configuration)
+        };
+        assert!(!Service::is_change_data_feed_enabled(&new_metadata(
+            HashMap::new()
+        )));
+        assert!(Service::is_change_data_feed_enabled(&new_metadata(
+            HashMap::from([(
+                String::from("delta.enableChangeDataFeed"),
+                Some(String::from("true"))
+            )])
+        )));
+        assert!(!Service::is_change_data_feed_enabled(&new_metadata(
+            HashMap::from([(
+                String::from("delta.enableChangeDataFeed"),
+                Some(String::from("false"))
+            )])
+        )));
+    }
 }
@@ -4,13 +4,25 @@ use axum::response::Response;
 use axum::Json;
 use utoipa::ToSchema;
 
-#[derive(serde::Serialize, ToSchema)]
+use crate::server::utilities::validation::FieldError;
+use crate::server::utilities::validation::ValidationError;
+
+#[derive(Debug, Clone, serde::Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct ErrorMessage {
     pub error_code: String,
     pub message: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub details: Vec<FieldError>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
 }
 
+/// Message of an error response, kept on the response so that it can be
+/// rendered as plain text for clients not accepting JSON.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlainMessage(pub String);
+
 pub enum Error {
     InternalServerProblem(anyhow::Error),
     BadRequest,
@@ -18,9 +30,13 @@ pub enum Error {
     Forbidden,
     NotFound,
     ValidationFailed,
+    InvalidFields(ValidationError),
     Conflict,
     EnvironmentVariableMissing,
     NotImplemented,
+    UnsupportedProtocol,
+    ServiceUnavailable,
+    TooManyRequests,
 }
 
 impl std::fmt::Debug for Error {
@@ -45,6 +61,10 @@ impl std::fmt::Debug for Error {
             Error::ValidationFailed => {
                 f.field(&"Validation failed");
             }
+            Error::InvalidFields(e) => {
+                f.field(&"Validation failed");
+                f.field(&e.names());
+            }
             Error::Conflict => {
                 f.field(&"Confliction occured");
             }
@@ -54,6 +74,15 @@ impl std::fmt::Debug for Error {
             Error::NotImplemented => {
                 f.field(&"Not implemented");
             }
+            Error::UnsupportedProtocol => {
+                f.field(&"Unsupported protocol");
+            }
+            Error::ServiceUnavailable => {
+                f.field(&"Service unavailable");
+            }
+            Error::TooManyRequests => {
+                f.field(&"Too many requests");
+            }
         };
         f.finish()
     }
@@ -77,31 +106,213 @@ impl From<anyhow::Error> for Error {
     }
 }
 
+impl Error {
+    pub fn validation(e: anyhow::Error) -> Self {
+        match e.downcast::<ValidationError>() {
+            Ok(e) => Error::InvalidFields(e),
+            Err(_) => Error::ValidationFailed,
+        }
+    }
+
+    /// Reports `field` as the invalid field of the request with the reason
+    /// carried by `e`.
+    pub fn invalid_field(field: &str, e: anyhow::Error) -> Self {
+        let mut errors = ValidationError::default();
+        errors.check::<()>(field, Err(e));
+        Error::InvalidFields(errors)
+    }
+}
+
 impl IntoResponse for Error {
     fn into_response(self) -> Response {
-        let (status, message) = match self {
+        let details = if let Error::InvalidFields(e) = &self {
+            e.fields.clone()
+        } else {
+            Vec::new()
+        };
+        let (status, code, message) = match self {
             Error::InternalServerProblem(e) => {
                 tracing::error!("stacktrace: {}", e.backtrace());
-                (StatusCode::INTERNAL_SERVER_ERROR, "Internal server error")
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "INTERNAL_ERROR",
+                    "Internal server error",
+                )
             }
-            Error::BadRequest => (StatusCode::BAD_REQUEST, "Bad request"),
-            Error::Unauthorized => (StatusCode::UNAUTHORIZED, "Unauthorized"),
-            Error::Forbidden => (StatusCode::FORBIDDEN, "Forbidden"),
-            Error::NotFound => (StatusCode::NOT_FOUND, "Not found"),
-            Error::ValidationFailed => (StatusCode::BAD_REQUEST, "Bad request"),
-            Error::Conflict => (StatusCode::CONFLICT, "Conflict"),
-            Error::EnvironmentVariableMissing => {
-                (StatusCode::INTERNAL_SERVER_ERROR, "Internal server error")
-            }
-            Error::NotImplemented => (StatusCode::NOT_IMPLEMENTED, "Not implemented"),
+            Error::BadRequest => (StatusCode::BAD_REQUEST, "MALFORMED_REQUEST", "Bad request"),
+            Error::Unauthorized => (StatusCode::UNAUTHORIZED, "UNAUTHENTICATED", "Unauthorized"),
+            Error::Forbidden => (StatusCode::FORBIDDEN, "PERMISSION_DENIED", "Forbidden"),
+            Error::NotFound => (
+                StatusCode::NOT_FOUND,
+                "RESOURCE_DOES_NOT_EXIST",
+                "Not found",
+            ),
+            Error::ValidationFailed => (
+                StatusCode::BAD_REQUEST,
+                "INVALID_PARAMETER_VALUE",
+                "Bad request",
+            ),
+            Error::InvalidFields(_) => (
+                StatusCode::BAD_REQUEST,
+                "INVALID_PARAMETER_VALUE",
+                "Bad request",
+            ),
+            Error::Conflict => (StatusCode::CONFLICT, "RESOURCE_ALREADY_EXISTS", "Conflict"),
+            Error::EnvironmentVariableMissing => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "INTERNAL_ERROR",
+                "Internal server error",
+            ),
+            Error::NotImplemented => (
+                StatusCode::NOT_IMPLEMENTED,
+                "UNSUPPORTED_OPERATION",
+                "Not implemented",
+            ),
+            Error::UnsupportedProtocol => (
+                StatusCode::BAD_REQUEST,
+                "UNSUPPORTED_OPERATION",
+                "Unsupported protocol",
+            ),
+            Error::ServiceUnavailable => (
+                StatusCode::SERVICE_UNAVAILABLE,
+                "TEMPORARILY_UNAVAILABLE",
+                "Service unavailable",
+            ),
+            Error::TooManyRequests => (
+                StatusCode::TOO_MANY_REQUESTS,
+                "REQUEST_LIMIT_EXCEEDED",
+                "Too many requests",
+            ),
         };
-        (
-            status,
-            Json(ErrorMessage {
-                error_code: status.as_str().into(),
-                message: message.into(),
-            }),
+        let body = ErrorMessage {
+            error_code: code.into(),
+            message: message.into(),
+            details,
+            request_id: None,
+        };
+        // NOTE: The message is also kept on the response so that the request id
+        //       middleware can complete the body with the id of the request.
+        let mut response = (status, Json(body.clone())).into_response();
+        response.extensions_mut().insert(body);
+        response
+            .extensions_mut()
+            .insert(PlainMessage(message.into()));
+        response
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn assert_response(error: Error, status: StatusCode, code: &str) {
+        let response = error.into_response();
+        assert_eq!(response.status(), status);
+        let body = hyper::body::to_bytes(response.into_body())
+            .await
+            .expect("response body should be read properly");
+        let body: serde_json::Value =
+            serde_json::from_slice(&body).expect("response body should be JSON");
+        assert_eq!(body["errorCode"], code);
+        assert!(body["message"].is_string());
+    }
+
+    #[tokio::test]
+    async fn test_error_codes() {
+        assert_response(
+            Error::InternalServerProblem(anyhow::anyhow!(testutils::rand::string(10))),
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "INTERNAL_ERROR",
+        )
+        .await;
+        assert_response(
+            Error::BadRequest,
+            StatusCode::BAD_REQUEST,
+            "MALFORMED_REQUEST",
+        )
+        .await;
+        assert_response(
+            Error::Unauthorized,
+            StatusCode::UNAUTHORIZED,
+            "UNAUTHENTICATED",
+        )
+        .await;
+        assert_response(Error::Forbidden, StatusCode::FORBIDDEN, "PERMISSION_DENIED").await;
+        assert_response(
+            Error::NotFound,
+            StatusCode::NOT_FOUND,
+            "RESOURCE_DOES_NOT_EXIST",
+        )
+        .await;
+        assert_response(
+            Error::ValidationFailed,
+            StatusCode::BAD_REQUEST,
+            "INVALID_PARAMETER_VALUE",
         )
-            .into_response()
+        .await;
+        assert_response(
+            Error::InvalidFields(ValidationError::default()),
+            StatusCode::BAD_REQUEST,
+            "INVALID_PARAMETER_VALUE",
+        )
+        .await;
+        assert_response(
+            Error::Conflict,
+            StatusCode::CONFLICT,
+            "RESOURCE_ALREADY_EXISTS",
+        )
+        .await;
+        assert_response(
+            Error::EnvironmentVariableMissing,
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "INTERNAL_ERROR",
+        )
+        .await;
+        assert_response(
+            Error::NotImplemented,
+            StatusCode::NOT_IMPLEMENTED,
+            "UNSUPPORTED_OPERATION",
+        )
+        .await;
+        assert_response(
+            Error::UnsupportedProtocol,
+            StatusCode::BAD_REQUEST,
+            "UNSUPPORTED_OPERATION",
+        )
+        .await;
+        assert_response(
+            Error::ServiceUnavailable,
+            StatusCode::SERVICE_UNAVAILABLE,
+            "TEMPORARILY_UNAVAILABLE",
+        )
+        .await;
+        assert_response(
+            Error::TooManyRequests,
+            StatusCode::TOO_MANY_REQUESTS,
+            "REQUEST_LIMIT_EXCEEDED",
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_invalid_field() {
+        let field = testutils::rand::string(10);
+        let error = Error::invalid_field(
+            &field,
+            crate::server::entities::account::Email::new(testutils::rand::string(10))
+                .expect_err("malformed email should be rejected"),
+        );
+        let response = error.into_response();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let body = hyper::body::to_bytes(response.into_body())
+            .await
+            .expect("response body should be read properly");
+        let body: serde_json::Value =
+            serde_json::from_slice(&body).expect("response body should be JSON");
+        assert_eq!(body["details"][0]["field"], field);
+        assert_eq!(
+            body["details"][0]["message"],
+            "email constraint is not satisfied"
+        );
     }
 }
@@ -0,0 +1,198 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Arc;
+use std::sync::RwLock;
+use std::time::Duration;
+use std::time::Instant;
+
+use anyhow::Result;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::server::middlewares::jwt::Claims;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, serde::Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Status {
+    Pending,
+    Done,
+    Failed,
+}
+
+#[derive(Debug, Clone)]
+pub struct Job {
+    pub status: Status,
+    pub version: Option<i64>,
+    pub lines: Vec<serde_json::Value>,
+    namespace: String,
+    submitter: String,
+    created_at: Instant,
+}
+
+impl Job {
+    fn pending(claims: &Claims) -> Self {
+        Self {
+            status: Status::Pending,
+            version: None,
+            lines: Vec::new(),
+            namespace: claims.namespace.clone(),
+            submitter: claims.name.clone(),
+            created_at: Instant::now(),
+        }
+    }
+
+    fn is_submitted_by(&self, claims: &Claims) -> bool {
+        self.namespace == claims.namespace && self.submitter == claims.name
+    }
+
+    fn is_expired(&self, ttl: &Duration) -> bool {
+        self.created_at.elapsed() > *ttl
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Store {
+    jobs: Arc<RwLock<HashMap<Uuid, Job>>>,
+}
+
+impl Store {
+    pub fn submit<F>(&self, claims: &Claims, ttl: Duration, task: F) -> Uuid
+    where
+        F: Future<Output = Result<(i64, Vec<serde_json::Value>)>> + Send + 'static,
+    {
+        let id = Uuid::new_v4();
+        if let Ok(mut jobs) = self.jobs.write() {
+            jobs.retain(|_, job| !job.is_expired(&ttl));
+            jobs.insert(id, Job::pending(claims));
+        }
+        let store = self.clone();
+        tokio::spawn(async move {
+            let result = task.await;
+            let Ok(mut jobs) = store.jobs.write() else {
+                tracing::error!("failed to acquire job store");
+                return;
+            };
+            let Some(job) = jobs.get_mut(&id) else {
+                tracing::warn!(%id, "job expired before completion");
+                return;
+            };
+            match result {
+                Ok((version, lines)) => {
+                    job.status = Status::Done;
+                    job.version = Some(version);
+                    job.lines = lines;
+                }
+                Err(e) => {
+                    tracing::error!(%id, "job failed: {}", e);
+                    job.status = Status::Failed;
+                }
+            }
+        });
+        id
+    }
+
+    /// Returns the job only to the recipient who submitted it; a job submitted
+    /// by anyone else is indistinguishable from a missing one.
+    pub fn get(&self, id: &Uuid, claims: &Claims, ttl: Duration) -> Option<Job> {
+        let Ok(mut jobs) = self.jobs.write() else {
+            tracing::error!("failed to acquire job store");
+            return None;
+        };
+        jobs.retain(|_, job| !job.is_expired(&ttl));
+        jobs.get(id)
+            .filter(|job| job.is_submitted_by(claims))
+            .cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::middlewares::jwt::Role;
+    use anyhow::anyhow;
+
+    fn new_claims() -> Claims {
+        Claims {
+            name: testutils::rand::string(10),
+            email: testutils::rand::email(),
+            namespace: testutils::rand::string(10),
+            role: Role::Guest,
+            exp: chrono::Utc::now().timestamp() + testutils::rand::i64(100000, 1000000),
+            jti: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_submit_and_poll() {
+        let store = Store::default();
+        let claims = new_claims();
+        let ttl = Duration::from_secs(60);
+        let version = testutils::rand::i64(0, 100);
+        let id = store.submit(&claims, ttl, async move {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            Ok((version, vec![serde_json::json!({ "protocol": {} })]))
+        });
+        let job = store
+            .get(&id, &claims, ttl)
+            .expect("submitted job should be found");
+        assert_eq!(job.status, Status::Pending);
+        let job = loop {
+            let job = store
+                .get(&id, &claims, ttl)
+                .expect("submitted job should be found");
+            if job.status != Status::Pending {
+                break job;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        };
+        assert_eq!(job.status, Status::Done);
+        assert_eq!(job.version, Some(version));
+        assert_eq!(job.lines.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_failed_job() {
+        let store = Store::default();
+        let claims = new_claims();
+        let ttl = Duration::from_secs(60);
+        let id = store.submit(&claims, ttl, async { Err(anyhow!("signing failed")) });
+        let job = loop {
+            let job = store
+                .get(&id, &claims, ttl)
+                .expect("submitted job should be found");
+            if job.status != Status::Pending {
+                break job;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        };
+        assert_eq!(job.status, Status::Failed);
+    }
+
+    #[tokio::test]
+    async fn test_expired_job() {
+        let store = Store::default();
+        let claims = new_claims();
+        let id = store.submit(&claims, Duration::from_secs(60), async {
+            Ok((0, Vec::<serde_json::Value>::new()))
+        });
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(store.get(&id, &claims, Duration::from_millis(10)).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_job_of_other_recipient() {
+        let store = Store::default();
+        let claims = new_claims();
+        let ttl = Duration::from_secs(60);
+        let id = store.submit(&claims, ttl, async {
+            Ok((0, Vec::<serde_json::Value>::new()))
+        });
+        assert!(store.get(&id, &new_claims(), ttl).is_none());
+        let other = Claims {
+            namespace: testutils::rand::string(11),
+            ..claims.clone()
+        };
+        assert!(store.get(&id, &other, ttl).is_none());
+        assert!(store.get(&id, &claims, ttl).is_some());
+    }
+}
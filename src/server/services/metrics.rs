@@ -0,0 +1,102 @@
+use std::time::Duration;
+
+use metrics_exporter_prometheus::Matcher;
+use metrics_exporter_prometheus::PrometheusBuilder;
+use metrics_exporter_prometheus::PrometheusHandle;
+use once_cell::sync::Lazy;
+
+pub const HTTP_REQUESTS_TOTAL: &str = "delta_sharing_http_requests_total";
+
+pub const HTTP_REQUEST_DURATION_SECONDS: &str = "delta_sharing_http_request_duration_seconds";
+
+pub const TOKEN_VERIFICATION_FAILURES_TOTAL: &str =
+    "delta_sharing_token_verification_failures_total";
+
+pub const SIGNED_URL_ERRORS_TOTAL: &str = "delta_sharing_signed_url_errors_total";
+
+pub const REFRESH_TOKEN_REUSES_TOTAL: &str = "delta_sharing_refresh_token_reuses_total";
+
+const LATENCY_BUCKETS: &[f64] = &[
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+static HANDLE: Lazy<PrometheusHandle> = Lazy::new(|| {
+    PrometheusBuilder::new()
+        .set_buckets_for_metric(
+            Matcher::Full(HTTP_REQUEST_DURATION_SECONDS.into()),
+            LATENCY_BUCKETS,
+        )
+        .expect("latency buckets should not be empty")
+        .install_recorder()
+        .expect("prometheus recorder should be installed only once")
+});
+
+pub struct Service;
+
+impl Service {
+    /// Installs the process-wide Prometheus recorder on first use.
+    pub fn handle() -> &'static PrometheusHandle {
+        &HANDLE
+    }
+
+    pub fn render() -> String {
+        Self::handle().render()
+    }
+
+    pub fn record_request(method: String, route: String, status: u16, latency: Duration) {
+        Self::handle();
+        metrics::increment_counter!(
+            HTTP_REQUESTS_TOTAL,
+            "method" => method.clone(),
+            "route" => route.clone(),
+            "status" => status.to_string()
+        );
+        metrics::histogram!(
+            HTTP_REQUEST_DURATION_SECONDS,
+            latency.as_secs_f64(),
+            "method" => method,
+            "route" => route
+        );
+    }
+
+    pub fn record_token_verification_failure() {
+        Self::handle();
+        metrics::increment_counter!(TOKEN_VERIFICATION_FAILURES_TOTAL);
+    }
+
+    pub fn record_signed_url_error(platform: &'static str) {
+        Self::handle();
+        metrics::increment_counter!(SIGNED_URL_ERRORS_TOTAL, "platform" => platform);
+    }
+
+    pub fn record_refresh_token_reuse() {
+        Self::handle();
+        metrics::increment_counter!(REFRESH_TOKEN_REUSES_TOTAL);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_token_verification_failure() {
+        Service::record_token_verification_failure();
+        assert!(Service::render().contains(TOKEN_VERIFICATION_FAILURES_TOTAL));
+    }
+
+    #[test]
+    fn test_record_refresh_token_reuse() {
+        Service::record_refresh_token_reuse();
+        assert!(Service::render().contains(REFRESH_TOKEN_REUSES_TOTAL));
+    }
+
+    #[test]
+    fn test_record_signed_url_error() {
+        Service::record_signed_url_error("aws");
+        assert!(Service::render()
+            .lines()
+            .any(|line| line.starts_with(SIGNED_URL_ERRORS_TOTAL)
+                && line.contains(r#"platform="aws""#)));
+    }
+}
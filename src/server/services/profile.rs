@@ -1,23 +1,28 @@
+use std::collections::HashMap;
 use std::time::Duration;
 use std::time::SystemTime;
 use std::time::UNIX_EPOCH;
 
+use anyhow::anyhow;
 use anyhow::Context;
 use anyhow::Result;
 use chrono::DateTime;
 use chrono::NaiveDateTime;
 use chrono::Utc;
 use jsonwebtoken::encode;
-use jsonwebtoken::Header;
 use utoipa::ToSchema;
+use uuid::Uuid;
 
 use crate::config;
-use crate::config::JWT_SECRET;
 use crate::server::middlewares::jwt::Claims;
 use crate::server::middlewares::jwt::Role;
+use crate::server::utilities::validation::ValidationError;
 
 pub const VERSION: i32 = 1;
 
+/// Bearer token of previewed profiles, which is not a JWT and authenticates nothing.
+pub const PREVIEW_BEARER_TOKEN: &str = "<issued by /admin/profile>";
+
 #[derive(serde::Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct Profile {
@@ -27,6 +32,63 @@ pub struct Profile {
     pub expiration_time: String,
 }
 
+/// Recipients each provider may issue guest profiles to. A provider missing from
+/// the allowlists may issue to nobody.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Allowlists {
+    recipients: HashMap<String, Vec<String>>,
+}
+
+impl Allowlists {
+    /// Parses comma-separated recipients keyed by provider.
+    pub fn new(allowlists: &HashMap<String, String>) -> Self {
+        let recipients = allowlists
+            .iter()
+            .map(|(provider, recipients)| {
+                let recipients = recipients
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|recipient| !recipient.is_empty())
+                    .map(String::from)
+                    .collect();
+                (provider.clone(), recipients)
+            })
+            .collect();
+        Self { recipients }
+    }
+
+    /// Returns the configured allowlists, or `None` when they are disabled.
+    pub fn from_config() -> Option<Self> {
+        if !config::fetch::<bool>("recipient_allowlist_enabled") {
+            return None;
+        }
+        Some(Self::new(&config::fetch::<HashMap<String, String>>(
+            "recipient_allowlists",
+        )))
+    }
+
+    pub fn allows(&self, provider: &str, recipient: &str) -> bool {
+        self.recipients.get(provider).map_or(false, |recipients| {
+            recipients
+                .iter()
+                .any(|allowed| allowed.eq_ignore_ascii_case(recipient))
+        })
+    }
+}
+
+/// Marks a guest profile refused because the recipient is not on the provider's
+/// allowlist.
+#[derive(Debug)]
+pub struct RecipientNotAllowed;
+
+impl std::fmt::Display for RecipientNotAllowed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "recipient is not on the provider's allowlist")
+    }
+}
+
+impl std::error::Error for RecipientNotAllowed {}
+
 pub struct Service;
 
 fn new_token(
@@ -36,15 +98,20 @@ fn new_token(
     role: Role,
     expiry: i64,
 ) -> Result<String> {
-    let claims = Claims {
+    self::sign(&Claims {
         name,
         email,
         namespace,
         role,
         exp: expiry,
-    };
-    let token = encode(&Header::default(), &claims, &JWT_SECRET.encoding)
-        .context("failed to create JWT token")?;
+        jti: None,
+    })
+}
+
+fn sign(claims: &Claims) -> Result<String> {
+    let keys = config::jwt_secret(&claims.namespace);
+    let token =
+        encode(&keys.header(), claims, &keys.encoding).context("failed to create JWT token")?;
     Ok(token)
 }
 
@@ -63,7 +130,35 @@ fn new_expiration(ttl: i64) -> Result<(i64, DateTime<Utc>)> {
     Ok((expiration_secs, expiration_time))
 }
 
+fn new_ttl(ttl: i64, min: i64, max: i64) -> Result<i64> {
+    if ttl <= 0 {
+        let mut errors = ValidationError::default();
+        errors.check::<()>("ttl", Err(anyhow!("ttl must be positive")));
+        return Err(errors.into());
+    }
+    Ok(ttl.max(min).min(max))
+}
+
+fn new_endpoint(public_endpoint: &str, server_addr: &str) -> String {
+    // NOTE: Clients append paths such as `/shares` to the endpoint, so a trailing
+    //       slash would yield `//shares`.
+    let endpoint = if public_endpoint.trim().is_empty() {
+        server_addr
+    } else {
+        public_endpoint
+    };
+    endpoint.trim().trim_end_matches('/').to_string()
+}
+
 impl Service {
+    /// Returns the externally reachable URL of this server.
+    pub fn endpoint() -> String {
+        self::new_endpoint(
+            &config::fetch::<String>("public_endpoint"),
+            &config::fetch::<String>("server_addr"),
+        )
+    }
+
     pub fn issue(
         name: String,
         email: String,
@@ -71,13 +166,116 @@ impl Service {
         role: Role,
         ttl: i64,
     ) -> Result<Profile> {
+        let ttl = self::new_ttl(
+            ttl,
+            config::fetch::<i64>("profile_min_ttl"),
+            config::fetch::<i64>("profile_max_ttl"),
+        )?;
         let (expiration_secs, expiration_time) =
             self::new_expiration(ttl).context("expiration time calculation failed")?;
         let token = self::new_token(name, email, namespace, role, expiration_secs)
             .context("profile creation failed")?;
         Ok(Profile {
             share_credentials_version: VERSION,
-            endpoint: config::fetch::<String>("server_addr"),
+            endpoint: Self::endpoint(),
+            bearer_token: token,
+            expiration_time: expiration_time.to_string(),
+        })
+    }
+
+    /// Issues the guest profile of the recipient `email` on behalf of the provider
+    /// `namespace`. Every guest profile is issued here, so that the allowlists are
+    /// enforced whichever endpoint issues it.
+    pub fn issue_guest(
+        allowlists: Option<&Allowlists>,
+        name: String,
+        email: String,
+        namespace: String,
+        ttl: i64,
+    ) -> Result<Profile> {
+        if let Some(allowlists) = allowlists {
+            if !allowlists.allows(&namespace, &email) {
+                return Err(RecipientNotAllowed.into());
+            }
+        }
+        Self::issue(name, email, namespace, Role::Guest, ttl)
+    }
+
+    /// Returns the guest profile `issue_guest` would issue, with a placeholder in
+    /// place of the bearer token so that previewing hands out no credential.
+    pub fn preview_guest(
+        allowlists: Option<&Allowlists>,
+        name: String,
+        email: String,
+        namespace: String,
+        ttl: i64,
+    ) -> Result<Profile> {
+        let profile = Self::issue_guest(allowlists, name, email, namespace, ttl)?;
+        Ok(Profile {
+            bearer_token: PREVIEW_BEARER_TOKEN.to_string(),
+            ..profile
+        })
+    }
+
+    /// Issues a guest profile replacing the one whose claims are `claims`. The new
+    /// profile lasts `ttl` seconds but never outlives the one it replaces, so that a
+    /// chain of refreshes stays within the lifetime the provider granted.
+    pub fn refresh_guest(
+        allowlists: Option<&Allowlists>,
+        claims: &Claims,
+        ttl: i64,
+    ) -> Result<Profile> {
+        if claims.role != Role::Guest {
+            return Err(anyhow!("only guest profiles can be refreshed"));
+        }
+        if let Some(allowlists) = allowlists {
+            if !allowlists.allows(&claims.namespace, &claims.email) {
+                return Err(RecipientNotAllowed.into());
+            }
+        }
+        let ttl = self::new_ttl(
+            ttl,
+            config::fetch::<i64>("profile_min_ttl"),
+            config::fetch::<i64>("profile_max_ttl"),
+        )?;
+        let (expiration_secs, _) =
+            self::new_expiration(ttl).context("expiration time calculation failed")?;
+        let expiration_secs = expiration_secs.min(claims.exp);
+        let expiration_time = NaiveDateTime::from_timestamp_opt(expiration_secs, 0)
+            .context("faield to parse expiration seconds to datetime")?;
+        let expiration_time = DateTime::<Utc>::from_utc(expiration_time, Utc);
+        let token = self::sign(&Claims {
+            exp: expiration_secs,
+            jti: Some(Uuid::new_v4().to_string()),
+            ..claims.clone()
+        })
+        .context("profile refresh failed")?;
+        Ok(Profile {
+            share_credentials_version: VERSION,
+            endpoint: Self::endpoint(),
+            bearer_token: token,
+            expiration_time: expiration_time.to_string(),
+        })
+    }
+
+    /// Signs the claims of a previously issued profile again with its expiration.
+    /// Both supported JWT algorithms sign deterministically, so the same claims
+    /// yield the same bearer token.
+    pub fn reissue(
+        name: String,
+        email: String,
+        namespace: String,
+        role: Role,
+        expiration_secs: i64,
+    ) -> Result<Profile> {
+        let expiration_time = NaiveDateTime::from_timestamp_opt(expiration_secs, 0)
+            .context("faield to parse expiration seconds to datetime")?;
+        let expiration_time = DateTime::<Utc>::from_utc(expiration_time, Utc);
+        let token = self::new_token(name, email, namespace, role, expiration_secs)
+            .context("profile reissuance failed")?;
+        Ok(Profile {
+            share_credentials_version: VERSION,
+            endpoint: Self::endpoint(),
             bearer_token: token,
             expiration_time: expiration_time.to_string(),
         })
@@ -88,15 +286,15 @@ impl Service {
 mod tests {
     use super::*;
     use crate::config::JWT_SECRET;
+    use crate::server::middlewares::jwt::decode_claims;
     use jsonwebtoken::decode;
-    use jsonwebtoken::Validation;
     use std::str::FromStr;
     use std::thread::sleep;
     use std::time::Duration;
 
     //#[test]
     fn test_expired_profile() -> Result<()> {
-        let roles = vec!["Admin", "Guest"];
+        let roles = vec!["Admin", "Moderator", "Guest"];
         let role = testutils::rand::choose(&roles);
         let role = Role::from_str(role).context("failed to choose role")?;
         let two_mins = Duration::from_millis(120000);
@@ -105,14 +303,14 @@ mod tests {
             testutils::rand::string(10),
             testutils::rand::string(10),
             role,
-            0,
+            1,
         )
         .expect("profile should be issued properly");
         sleep(two_mins);
         let Err(_) = decode::<Claims>(
             &profile.bearer_token,
             &JWT_SECRET.decoding,
-            &Validation::default(),
+            &JWT_SECRET.validation(),
         ) else {
             panic!("new profile should be expired");
         };
@@ -121,7 +319,7 @@ mod tests {
 
     #[test]
     fn test_unexpired_profile() -> Result<()> {
-        let roles = vec!["Admin", "Guest"];
+        let roles = vec!["Admin", "Moderator", "Guest"];
         let role = testutils::rand::choose(&roles);
         let role = Role::from_str(role).context("failed to choose role")?;
         let profile = Service::issue(
@@ -135,10 +333,192 @@ mod tests {
         let Ok(_) = decode::<Claims>(
             &profile.bearer_token,
             &JWT_SECRET.decoding,
-            &Validation::default(),
+            &JWT_SECRET.validation(),
         ) else {
             panic!("new profile should not be expired");
         };
         Ok(())
     }
+
+    #[test]
+    fn test_reissue_profile() {
+        let name = testutils::rand::string(10);
+        let email = testutils::rand::email();
+        let namespace = testutils::rand::string(10);
+        let profile = Service::issue(
+            name.clone(),
+            email.clone(),
+            namespace.clone(),
+            Role::Guest,
+            testutils::rand::i64(100000, 1000000),
+        )
+        .expect("profile should be issued properly");
+        let claims = decode_claims(&profile.bearer_token).expect("claims should be decoded");
+        let reissued = Service::reissue(name, email, namespace, Role::Guest, claims.exp)
+            .expect("profile should be reissued");
+        assert_eq!(reissued.bearer_token, profile.bearer_token);
+        assert_eq!(reissued.expiration_time, profile.expiration_time);
+        assert_eq!(reissued.endpoint, profile.endpoint);
+    }
+
+    #[test]
+    fn test_allowed_recipient() {
+        let provider = testutils::rand::string(10);
+        let recipient = testutils::rand::email();
+        let allowlists = Allowlists::new(&HashMap::from([(
+            provider.clone(),
+            format!("{}, {}", testutils::rand::email(), recipient.to_uppercase()),
+        )]));
+        assert!(allowlists.allows(&provider, &recipient));
+    }
+
+    #[test]
+    fn test_disallowed_recipient() {
+        let provider = testutils::rand::string(10);
+        let allowlists = Allowlists::new(&HashMap::from([(
+            provider.clone(),
+            testutils::rand::email(),
+        )]));
+        assert!(!allowlists.allows(&provider, &testutils::rand::email()));
+        assert!(!allowlists.allows(&testutils::rand::string(11), &testutils::rand::email()));
+    }
+
+    #[test]
+    fn test_issue_guest_to_disallowed_recipient() {
+        let provider = testutils::rand::string(10);
+        let recipient = testutils::rand::email();
+        let allowlists = Allowlists::new(&HashMap::from([(provider.clone(), recipient.clone())]));
+        let error = Service::issue_guest(
+            Some(&allowlists),
+            testutils::rand::string(10),
+            testutils::rand::email(),
+            provider.clone(),
+            testutils::rand::i64(100000, 1000000),
+        )
+        .err()
+        .expect("profile should not be issued");
+        assert!(error.is::<RecipientNotAllowed>());
+        assert!(Service::issue_guest(
+            Some(&allowlists),
+            testutils::rand::string(10),
+            recipient,
+            provider,
+            testutils::rand::i64(100000, 1000000),
+        )
+        .is_ok());
+        assert!(Service::issue_guest(
+            None,
+            testutils::rand::string(10),
+            testutils::rand::email(),
+            testutils::rand::string(10),
+            testutils::rand::i64(100000, 1000000),
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn test_refresh_guest_within_lifetime() {
+        let exp = Utc::now().timestamp() + testutils::rand::i64(10, 60);
+        let claims = Claims {
+            name: testutils::rand::string(10),
+            email: testutils::rand::email(),
+            namespace: testutils::rand::string(10),
+            role: Role::Guest,
+            exp,
+            jti: None,
+        };
+        let profile =
+            Service::refresh_guest(None, &claims, config::fetch::<i64>("profile_max_ttl"))
+                .expect("profile should be refreshed");
+        let refreshed = decode_claims(&profile.bearer_token).expect("claims should be decoded");
+        assert_eq!(refreshed.exp, exp);
+        assert!(refreshed.jti.is_some());
+        let again =
+            Service::refresh_guest(None, &refreshed, config::fetch::<i64>("profile_max_ttl"))
+                .expect("profile should be refreshed");
+        assert_ne!(again.bearer_token, profile.bearer_token);
+    }
+
+    #[test]
+    fn test_refresh_non_guest() {
+        let claims = Claims {
+            name: testutils::rand::string(10),
+            email: testutils::rand::email(),
+            namespace: testutils::rand::string(10),
+            role: Role::Admin,
+            exp: Utc::now().timestamp() + testutils::rand::i64(100000, 1000000),
+            jti: None,
+        };
+        assert!(Service::refresh_guest(None, &claims, testutils::rand::i64(1, 100000)).is_err());
+    }
+
+    #[test]
+    fn test_new_endpoint_falls_back_to_server_addr() {
+        let server_addr = format!("http://{}:8080", testutils::rand::string(10));
+        assert_eq!(new_endpoint("", &server_addr), server_addr);
+        assert_eq!(new_endpoint("", &format!("{}/", server_addr)), server_addr);
+    }
+
+    #[test]
+    fn test_new_endpoint_prefers_public_endpoint() {
+        let server_addr = format!("http://{}:8080", testutils::rand::string(10));
+        let public_endpoint = format!("https://{}.com/delta-sharing", testutils::rand::string(10));
+        assert_eq!(
+            new_endpoint(&public_endpoint, &server_addr),
+            public_endpoint
+        );
+        assert_eq!(
+            new_endpoint(&format!("{}/", public_endpoint), &server_addr),
+            public_endpoint
+        );
+        assert_eq!(
+            format!(
+                "{}/shares",
+                new_endpoint(&format!("{}//", public_endpoint), &server_addr)
+            ),
+            format!("{}/shares", public_endpoint)
+        );
+    }
+
+    #[test]
+    fn test_new_ttl() {
+        let min = testutils::rand::i64(1, 100);
+        let max = testutils::rand::i64(1000, 100000);
+        let ttl = testutils::rand::i64(min, max);
+        assert_eq!(new_ttl(ttl, min, max).expect("ttl should be accepted"), ttl);
+        assert_eq!(
+            new_ttl(max + testutils::rand::i64(1, 100000), min, max)
+                .expect("ttl should be clamped"),
+            max
+        );
+        assert_eq!(
+            new_ttl(testutils::rand::i64(1, min), min, max).expect("ttl should be clamped"),
+            min
+        );
+    }
+
+    #[test]
+    fn test_new_ttl_not_positive() {
+        for ttl in [0, testutils::rand::i64(-100000, -1)] {
+            let error = new_ttl(ttl, 1, 100).expect_err("ttl should be rejected");
+            let error = error
+                .downcast::<ValidationError>()
+                .expect("validation error should be returned");
+            assert_eq!(error.names(), vec!["ttl"]);
+        }
+    }
+
+    #[test]
+    fn test_issue_with_negative_ttl() {
+        let error = Service::issue(
+            testutils::rand::string(10),
+            testutils::rand::email(),
+            testutils::rand::string(10),
+            Role::Guest,
+            testutils::rand::i64(-100000, 0),
+        )
+        .err()
+        .expect("profile should not be issued");
+        assert!(error.is::<ValidationError>());
+    }
 }
@@ -0,0 +1,167 @@
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::time::Duration;
+use std::time::Instant;
+
+const MAX_BUCKETS: usize = 10000;
+
+#[derive(Debug, Clone)]
+struct Bucket {
+    tokens: f64,
+    updated_at: Instant,
+    seen: u64,
+}
+
+impl Bucket {
+    fn refill(&mut self, now: Instant, rate: f64, capacity: f64) {
+        let elapsed = now.duration_since(self.updated_at).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * rate).min(capacity);
+        self.updated_at = now;
+    }
+}
+
+/// Buckets keyed by client, bounded to `capacity` by evicting the least recently
+/// seen one. `order` indexes the keys by the sequence they were last seen at so
+/// that both touching and evicting take logarithmic time.
+#[derive(Debug, Default)]
+struct Buckets {
+    capacity: usize,
+    buckets: HashMap<String, Bucket>,
+    order: BTreeMap<u64, String>,
+    clock: u64,
+}
+
+impl Buckets {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            ..Self::default()
+        }
+    }
+
+    /// Returns the bucket of `key` marked as the most recently seen one, creating
+    /// it full of `tokens` when missing.
+    fn touch(&mut self, key: &str, tokens: f64, now: Instant) -> &mut Bucket {
+        self.clock += 1;
+        let seen = self.clock;
+        if let Some(bucket) = self.buckets.get(key) {
+            self.order.remove(&bucket.seen);
+        } else {
+            while self.buckets.len() >= self.capacity.max(1) {
+                let Some((_, oldest)) = self.order.pop_first() else {
+                    break;
+                };
+                self.buckets.remove(&oldest);
+            }
+        }
+        self.order.insert(seen, key.to_owned());
+        let bucket = self.buckets.entry(key.to_owned()).or_insert(Bucket {
+            tokens,
+            updated_at: now,
+            seen,
+        });
+        bucket.seen = seen;
+        bucket
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Limiter {
+    rate: u32,
+    burst: u32,
+    buckets: Arc<Mutex<Buckets>>,
+}
+
+impl Limiter {
+    /// Creates a token bucket limiter refilling `rate` tokens per second up to
+    /// `burst` tokens; a zero rate disables limiting.
+    pub fn new(rate: u32, burst: u32) -> Self {
+        Self::with_capacity(rate, burst, MAX_BUCKETS)
+    }
+
+    fn with_capacity(rate: u32, burst: u32, capacity: usize) -> Self {
+        Self {
+            rate,
+            burst,
+            buckets: Arc::new(Mutex::new(Buckets::new(capacity))),
+        }
+    }
+
+    /// Takes a token from the bucket of `key`, or returns how long the caller
+    /// should wait before the next token becomes available.
+    pub fn acquire(&self, key: &str) -> Result<(), Duration> {
+        if self.rate == 0 {
+            return Ok(());
+        }
+        let Ok(mut buckets) = self.buckets.lock() else {
+            tracing::error!("failed to acquire rate limit buckets");
+            return Ok(());
+        };
+        let now = Instant::now();
+        let rate = f64::from(self.rate);
+        let capacity = f64::from(self.burst.max(1));
+        let bucket = buckets.touch(key, capacity, now);
+        bucket.refill(now, rate, capacity);
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            return Ok(());
+        }
+        Err(Duration::from_secs_f64((1.0 - bucket.tokens) / rate))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_disabled() {
+        let limiter = Limiter::default();
+        let key = testutils::rand::string(10);
+        for _ in 0..testutils::rand::u32(10, 100) {
+            assert!(limiter.acquire(&key).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_exhaust_bucket() {
+        let burst = testutils::rand::u32(1, 10);
+        let limiter = Limiter::new(1, burst);
+        let key = testutils::rand::string(10);
+        for _ in 0..burst {
+            assert!(limiter.acquire(&key).is_ok());
+        }
+        let retry_after = limiter
+            .acquire(&key)
+            .expect_err("exhausted bucket should be rejected");
+        assert!(retry_after <= Duration::from_secs(1));
+        assert!(limiter.acquire(&testutils::rand::string(11)).is_ok());
+    }
+
+    #[test]
+    fn test_refill_bucket() {
+        let limiter = Limiter::new(100, 1);
+        let key = testutils::rand::string(10);
+        assert!(limiter.acquire(&key).is_ok());
+        assert!(limiter.acquire(&key).is_err());
+        std::thread::sleep(Duration::from_millis(20));
+        assert!(limiter.acquire(&key).is_ok());
+    }
+
+    #[test]
+    fn test_evict_least_recently_seen() {
+        let limiter = Limiter::with_capacity(1, 1, 2);
+        let oldest = testutils::rand::string(10);
+        let recent = testutils::rand::string(11);
+        assert!(limiter.acquire(&oldest).is_ok());
+        assert!(limiter.acquire(&recent).is_ok());
+        assert!(limiter.acquire(&oldest).is_err());
+        assert!(limiter.acquire(&testutils::rand::string(12)).is_ok());
+        // NOTE: The bucket of `recent` was seen least recently, so it was evicted and
+        //       starts full again while the exhausted one of `oldest` is kept.
+        assert!(limiter.acquire(&oldest).is_err());
+        assert!(limiter.acquire(&recent).is_ok());
+    }
+}
@@ -0,0 +1,149 @@
+use anyhow::Context;
+use anyhow::Result;
+use chrono::DateTime;
+use chrono::Utc;
+use rand::distributions::Alphanumeric;
+use rand::Rng;
+use sha2::Digest;
+use sha2::Sha256;
+use uuid::Uuid;
+
+use crate::server::entities::token::Value as TokenValue;
+use crate::server::services::revocation::Service as RevocationService;
+use crate::server::utilities::postgres::PgAcquire;
+
+const TOKEN_LENGTH: usize = 64;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Exchange {
+    /// The token was consumed; a successor is to be issued in the same family and
+    /// expires with the family at `expires_at`.
+    Rotated {
+        account: String,
+        family: Uuid,
+        expires_at: DateTime<Utc>,
+    },
+    /// The token had already been consumed, so its whole family was revoked.
+    Reused { account: String, family: Uuid },
+    /// The token is unknown, expired or belongs to a deleted account.
+    Invalid,
+}
+
+pub struct Service;
+
+impl Service {
+    fn hash(token: &str) -> String {
+        format!("{:x}", Sha256::digest(token.as_bytes()))
+    }
+
+    /// Issues a refresh token for `account_id` along with the access token `access`,
+    /// starting a new family unless `family` is given.
+    pub async fn issue(
+        account_id: &Uuid,
+        family: Option<Uuid>,
+        expires_at: DateTime<Utc>,
+        access: &TokenValue,
+        executor: impl PgAcquire<'_>,
+    ) -> Result<String> {
+        let mut conn = executor
+            .acquire()
+            .await
+            .context("failed to acquire postgres connection")?;
+        let token: String = rand::thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(TOKEN_LENGTH)
+            .map(char::from)
+            .collect();
+        sqlx::query(
+            "INSERT INTO refresh_token (
+                 token_hash,
+                 account_id,
+                 family_id,
+                 expires_at,
+                 access_token_hash,
+                 access_expires_at
+             ) VALUES ($1, $2, $3, $4, $5, $6)",
+        )
+        .bind(Self::hash(&token))
+        .bind(account_id)
+        .bind(family.unwrap_or_else(Uuid::new_v4))
+        .bind(expires_at)
+        .bind(access.hash())
+        .bind(access.expires_at())
+        .execute(&mut *conn)
+        .await
+        .context("failed to insert token into [refresh_token]")?;
+        Ok(token)
+    }
+
+    /// Consumes `token`. A token presented twice revokes every unused token of its
+    /// family and the access tokens issued with them, since either the legitimate
+    /// client or an attacker holds a copy. Run it in the transaction issuing the
+    /// successor, so that a failed rotation leaves the token unused.
+    pub async fn exchange(
+        token: &str,
+        now: DateTime<Utc>,
+        executor: impl PgAcquire<'_>,
+    ) -> Result<Exchange> {
+        let mut conn = executor
+            .acquire()
+            .await
+            .context("failed to acquire postgres connection")?;
+        let hash = Self::hash(token);
+        let consumed: Option<(String, Uuid, DateTime<Utc>)> = sqlx::query_as(
+            "UPDATE refresh_token
+             SET used_at = $2
+             FROM account
+             WHERE refresh_token.account_id = account.id
+               AND account.deleted_at IS NULL
+               AND refresh_token.token_hash = $1
+               AND refresh_token.used_at IS NULL
+               AND refresh_token.expires_at > $2
+             RETURNING account.name, refresh_token.family_id, refresh_token.expires_at",
+        )
+        .bind(&hash)
+        .bind(now)
+        .fetch_optional(&mut *conn)
+        .await
+        .context("failed to consume token in [refresh_token]")?;
+        if let Some((account, family, expires_at)) = consumed {
+            return Ok(Exchange::Rotated {
+                account,
+                family,
+                expires_at,
+            });
+        }
+        let used: Option<(String, Uuid)> = sqlx::query_as(
+            "SELECT
+                 account.name,
+                 refresh_token.family_id
+             FROM refresh_token
+             INNER JOIN account ON refresh_token.account_id = account.id
+             WHERE refresh_token.token_hash = $1
+               AND refresh_token.used_at IS NOT NULL",
+        )
+        .bind(&hash)
+        .fetch_optional(&mut *conn)
+        .await
+        .context("failed to select token from [refresh_token]")?;
+        let Some((account, family)) = used else {
+            return Ok(Exchange::Invalid);
+        };
+        sqlx::query(
+            "UPDATE refresh_token
+             SET used_at = $2
+             WHERE family_id = $1
+               AND used_at IS NULL",
+        )
+        .bind(family)
+        .bind(now)
+        .execute(&mut *conn)
+        .await
+        .context(format!(
+            r#"failed to revoke family "{}" in [refresh_token]"#,
+            family
+        ))?;
+        RevocationService::revoke_refresh_family(&family, &mut *conn).await?;
+        Ok(Exchange::Reused { account, family })
+    }
+}
@@ -0,0 +1,124 @@
+use anyhow::Context;
+use anyhow::Result;
+use chrono::DateTime;
+use chrono::Utc;
+use sha2::Digest;
+use sha2::Sha256;
+use sqlx::postgres::PgQueryResult;
+use uuid::Uuid;
+
+use crate::server::entities::token::Value as TokenValue;
+use crate::server::utilities::postgres::PgAcquire;
+
+pub struct Service;
+
+impl Service {
+    /// Hashes the token the same way stored token values are hashed.
+    fn hash(token: &str) -> Result<String> {
+        let token = TokenValue::new(token.to_owned()).context("failed to validate token")?;
+        Ok(token.hash().to_string())
+    }
+
+    /// Hashes the token with the unkeyed digest revocations were stored with
+    /// before they were keyed.
+    fn legacy_hash(token: &str) -> String {
+        format!("{:x}", Sha256::digest(token.as_bytes()))
+    }
+
+    pub async fn revoke(
+        token: &str,
+        expires_at: DateTime<Utc>,
+        executor: impl PgAcquire<'_>,
+    ) -> Result<PgQueryResult> {
+        let mut conn = executor
+            .acquire()
+            .await
+            .context("failed to acquire postgres connection")?;
+        sqlx::query(
+            "INSERT INTO revoked_token (
+                 token_hash,
+                 expires_at
+             ) VALUES ($1, $2)
+             ON CONFLICT(token_hash)
+             DO NOTHING",
+        )
+        .bind(Self::hash(token)?)
+        .bind(expires_at)
+        .execute(&mut *conn)
+        .await
+        .context("failed to insert token into [revoked_token]")
+    }
+
+    /// Revokes the access tokens issued along with the refresh tokens of `family`.
+    pub async fn revoke_refresh_family(
+        family: &Uuid,
+        executor: impl PgAcquire<'_>,
+    ) -> Result<PgQueryResult> {
+        let mut conn = executor
+            .acquire()
+            .await
+            .context("failed to acquire postgres connection")?;
+        sqlx::query(
+            "INSERT INTO revoked_token (
+                 token_hash,
+                 expires_at
+             )
+             SELECT
+                 access_token_hash,
+                 access_expires_at
+             FROM refresh_token
+             WHERE family_id = $1
+               AND access_token_hash IS NOT NULL
+               AND access_expires_at IS NOT NULL
+             ON CONFLICT(token_hash)
+             DO NOTHING",
+        )
+        .bind(family)
+        .execute(&mut *conn)
+        .await
+        .context(format!(
+            r#"failed to revoke access tokens of family "{}" into [revoked_token]"#,
+            family
+        ))
+    }
+
+    pub async fn is_revoked(token: &str, executor: impl PgAcquire<'_>) -> Result<bool> {
+        let mut conn = executor
+            .acquire()
+            .await
+            .context("failed to acquire postgres connection")?;
+        // NOTE: Revocations stored with the unkeyed digest are still honored until
+        //       they expire and are deleted by the token collector.
+        let revoked: bool = sqlx::query_scalar(
+            "SELECT EXISTS (
+                 SELECT 1
+                 FROM revoked_token
+                 WHERE token_hash IN ($1, $2)
+             )",
+        )
+        .bind(Self::hash(token)?)
+        .bind(Self::legacy_hash(token))
+        .fetch_one(&mut *conn)
+        .await
+        .context("failed to select token from [revoked_token]")?;
+        Ok(revoked)
+    }
+
+    /// Deletes revocations of tokens which have expired anyway and returns how many
+    /// were deleted.
+    pub async fn delete_expired(now: DateTime<Utc>, executor: impl PgAcquire<'_>) -> Result<u64> {
+        let mut conn = executor
+            .acquire()
+            .await
+            .context("failed to acquire postgres connection")?;
+        let deleted = sqlx::query(
+            "DELETE FROM revoked_token
+             WHERE expires_at <= $1",
+        )
+        .bind(now)
+        .execute(&mut *conn)
+        .await
+        .context("failed to delete expired tokens from [revoked_token]")?;
+        Ok(deleted.rows_affected())
+    }
+}
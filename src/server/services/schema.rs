@@ -1,12 +1,15 @@
 use anyhow::Context;
 use anyhow::Result;
+use chrono::DateTime;
+use chrono::Utc;
 use sqlx::query_builder::QueryBuilder;
 use sqlx::Execute;
 use utoipa::ToSchema;
 
 use crate::server::entities::schema::Entity as SchemaEntity;
-use crate::server::entities::schema::Name as SchemaName;
 use crate::server::entities::share::Name as ShareName;
+use crate::server::utilities::pagination::Cursor;
+use crate::server::utilities::pagination::Ordering;
 use crate::server::utilities::postgres::PgAcquire;
 
 #[derive(Debug, Clone, serde::Serialize, ToSchema)]
@@ -30,6 +33,9 @@ impl Schema {
 pub struct SchemaDetail {
     pub name: String,
     pub share: String,
+    #[serde(skip)]
+    #[sqlx(default)]
+    pub created_at: Option<DateTime<Utc>>,
 }
 
 pub struct Service;
@@ -38,7 +44,8 @@ impl Service {
     pub async fn query_by_share_name(
         share_name: &ShareName,
         limit: Option<&i64>,
-        after: Option<&SchemaName>,
+        ordering: &Ordering,
+        after: Option<&Cursor>,
         executor: impl PgAcquire<'_>,
     ) -> Result<Vec<SchemaDetail>> {
         let mut conn = executor
@@ -48,33 +55,36 @@ impl Service {
         let mut builder = QueryBuilder::new(
             r#"WITH these_schemas AS (
                    SELECT
-                       DISTINCT "schema".name AS name, share.name AS share
+                       "schema".name AS name,
+                       share.name AS share,
+                       MIN("schema".created_at) AS created_at
                    FROM "schema"
                    LEFT JOIN share ON share.id = "schema".share_id
                    WHERE share.name = "#,
         );
         builder.push_bind(share_name);
         builder.push(
-            "
+            r#"
+                   GROUP BY "schema".name, share.name
                )
                SELECT
                    name,
-                   share
-               FROM these_schemas",
+                   share,
+                   created_at
+               FROM these_schemas"#,
         );
-        if let Some(name) = after {
-            builder.push(" WHERE name >= ");
-            builder.push_bind(name);
+        if let Some(cursor) = after {
+            ordering.push_after(&mut builder, "WHERE", cursor);
         }
-        builder.push(" ORDER BY name ");
+        ordering.push_order(&mut builder);
         if let Some(limit) = limit {
             builder.push(" LIMIT ");
             builder.push_bind(limit);
         }
         let mut query = sqlx::query_as::<_, SchemaDetail>(builder.build().sql());
         query = query.bind(share_name);
-        if let Some(name) = after {
-            query = query.bind(name);
+        if let Some(cursor) = after {
+            query = ordering.bind_after(query, cursor);
         }
         if let Some(limit) = limit {
             query = query.bind(limit);
@@ -1,11 +1,15 @@
 use anyhow::Context;
 use anyhow::Result;
+use chrono::DateTime;
+use chrono::Utc;
 use sqlx::query_builder::QueryBuilder;
 use sqlx::Execute;
 use utoipa::ToSchema;
 
 use crate::server::entities::share::Entity as ShareEntity;
 use crate::server::entities::share::Name as ShareName;
+use crate::server::utilities::pagination::Cursor;
+use crate::server::utilities::pagination::Ordering;
 use crate::server::utilities::postgres::PgAcquire;
 
 #[derive(Debug, Clone, serde::Serialize, sqlx::FromRow, ToSchema)]
@@ -13,6 +17,9 @@ use crate::server::utilities::postgres::PgAcquire;
 pub struct Share {
     pub id: String,
     pub name: String,
+    #[serde(skip)]
+    #[sqlx(default)]
+    pub created_at: Option<DateTime<Utc>>,
 }
 
 impl Share {
@@ -20,6 +27,7 @@ impl Share {
         Self {
             id: entity.id().to_string(),
             name: entity.name().to_string(),
+            created_at: None,
         }
     }
 }
@@ -28,8 +36,10 @@ pub struct Service;
 
 impl Service {
     pub async fn query(
+        namespace: &str,
         limit: Option<&i64>,
-        after: Option<&ShareName>,
+        ordering: &Ordering,
+        after: Option<&Cursor>,
         executor: impl PgAcquire<'_>,
     ) -> Result<Vec<Share>> {
         let mut conn = executor
@@ -39,21 +49,24 @@ impl Service {
         let mut builder = QueryBuilder::new(
             "SELECT
                  id::text,
-                 name
-             FROM share",
+                 name,
+                 created_at
+             FROM share
+             WHERE created_by IN (SELECT id FROM account WHERE namespace = ",
         );
-        if let Some(name) = after {
-            builder.push(" WHERE name >= ");
-            builder.push_bind(name);
+        builder.push_bind(namespace);
+        builder.push(")");
+        if let Some(cursor) = after {
+            ordering.push_after(&mut builder, "AND", cursor);
         }
-        builder.push(" ORDER BY name ");
+        ordering.push_order(&mut builder);
         if let Some(limit) = limit {
             builder.push(" LIMIT ");
             builder.push_bind(limit);
         }
-        let mut query = sqlx::query_as::<_, Share>(builder.build().sql());
-        if let Some(name) = after {
-            query = query.bind(name);
+        let mut query = sqlx::query_as::<_, Share>(builder.build().sql()).bind(namespace);
+        if let Some(cursor) = after {
+            query = ordering.bind_after(query, cursor);
         }
         if let Some(limit) = limit {
             query = query.bind(limit);
@@ -89,4 +102,28 @@ impl Service {
         ))?;
         Ok(row)
     }
+
+    pub async fn query_namespace_by_name(
+        name: &ShareName,
+        executor: impl PgAcquire<'_>,
+    ) -> Result<Option<String>> {
+        let mut conn = executor
+            .acquire()
+            .await
+            .context("failed to acquire postgres connection")?;
+        let namespace: Option<String> = sqlx::query_scalar(
+            "SELECT account.namespace
+             FROM share
+             JOIN account ON account.id = share.created_by
+             WHERE share.name = $1",
+        )
+        .bind(name)
+        .fetch_optional(&mut *conn)
+        .await
+        .context(format!(
+            r#"failed to select namespace of "{}" from [share]"#,
+            name.as_str()
+        ))?;
+        Ok(namespace)
+    }
 }
@@ -1,5 +1,7 @@
 use anyhow::Context;
 use anyhow::Result;
+use chrono::DateTime;
+use chrono::Utc;
 use sqlx::query_builder::QueryBuilder;
 use sqlx::Execute;
 use utoipa::ToSchema;
@@ -8,6 +10,8 @@ use crate::server::entities::schema::Name as SchemaName;
 use crate::server::entities::share::Name as ShareName;
 use crate::server::entities::table::Entity as TableEntity;
 use crate::server::entities::table::Name as TableName;
+use crate::server::utilities::pagination::Cursor;
+use crate::server::utilities::pagination::Ordering;
 use crate::server::utilities::postgres::PgAcquire;
 
 #[derive(Debug, Clone, serde::Serialize, sqlx::FromRow, ToSchema)]
@@ -34,6 +38,9 @@ pub struct TableDetail {
     pub name: String,
     pub schema: String,
     pub share: String,
+    #[serde(skip)]
+    #[sqlx(default)]
+    pub created_at: Option<DateTime<Utc>>,
 }
 
 pub struct Service;
@@ -141,7 +148,8 @@ impl Service {
     pub async fn query_by_share_name(
         share_name: &ShareName,
         limit: Option<&i64>,
-        after: Option<&TableName>,
+        ordering: &Ordering,
+        after: Option<&Cursor>,
         executor: impl PgAcquire<'_>,
     ) -> Result<Vec<TableDetail>> {
         let mut conn = executor
@@ -155,7 +163,8 @@ impl Service {
                        share.id AS share_id,
                        "table".name AS name,
                        "schema".name AS schema,
-                       share.name AS share
+                       share.name AS share,
+                       "table".created_at AS created_at
                    FROM "table"
                    LEFT JOIN "schema" ON "schema".id = "table".schema_id
                    LEFT JOIN share ON share.id = "schema".share_id
@@ -168,22 +177,22 @@ impl Service {
                SELECT
                    name,
                    schema,
-                   share
+                   share,
+                   created_at
                FROM these_tables",
         );
-        if let Some(name) = after {
-            builder.push(" WHERE name >= ");
-            builder.push_bind(name);
+        if let Some(cursor) = after {
+            ordering.push_after(&mut builder, "WHERE", cursor);
         }
-        builder.push(" ORDER BY name ");
+        ordering.push_order(&mut builder);
         if let Some(limit) = limit {
             builder.push(" LIMIT ");
             builder.push_bind(limit);
         }
         let mut query = sqlx::query_as::<_, TableDetail>(builder.build().sql());
         query = query.bind(share_name);
-        if let Some(name) = after {
-            query = query.bind(name);
+        if let Some(cursor) = after {
+            query = ordering.bind_after(query, cursor);
         }
         if let Some(limit) = limit {
             query = query.bind(limit);
@@ -199,7 +208,8 @@ impl Service {
         share_name: &ShareName,
         schema_name: &SchemaName,
         limit: Option<&i64>,
-        after: Option<&TableName>,
+        ordering: &Ordering,
+        after: Option<&Cursor>,
         executor: impl PgAcquire<'_>,
     ) -> Result<Vec<TableDetail>> {
         let mut conn = executor
@@ -213,7 +223,8 @@ impl Service {
                        share.id AS share_id,
                        "table".name AS name,
                        "schema".name AS schema,
-                       share.name AS share
+                       share.name AS share,
+                       "table".created_at AS created_at
                    FROM "table"
                    LEFT JOIN "schema" ON "schema".id = "table".schema_id
                    LEFT JOIN share ON share.id = "schema".share_id
@@ -230,14 +241,14 @@ impl Service {
                    share_id::text,
                    name,
                    schema,
-                   share
+                   share,
+                   created_at
                FROM these_tables",
         );
-        if let Some(name) = after {
-            builder.push(" WHERE name >= ");
-            builder.push_bind(name);
+        if let Some(cursor) = after {
+            ordering.push_after(&mut builder, "WHERE", cursor);
         }
-        builder.push(" ORDER BY name ");
+        ordering.push_order(&mut builder);
         if let Some(limit) = limit {
             builder.push(" LIMIT ");
             builder.push_bind(limit);
@@ -245,8 +256,8 @@ impl Service {
         let mut query = sqlx::query_as::<_, TableDetail>(builder.build().sql());
         query = query.bind(share_name);
         query = query.bind(schema_name);
-        if let Some(name) = after {
-            query = query.bind(name);
+        if let Some(cursor) = after {
+            query = ordering.bind_after(query, cursor);
         }
         if let Some(limit) = limit {
             query = query.bind(limit);
@@ -257,4 +268,21 @@ impl Service {
             .context("failed to list tables from [table]")?;
         Ok(rows)
     }
+
+    /// Lists the locations of the tables stored on the local filesystem.
+    pub async fn query_local_locations(executor: impl PgAcquire<'_>) -> Result<Vec<String>> {
+        let mut conn = executor
+            .acquire()
+            .await
+            .context("failed to acquire postgres connection")?;
+        let rows: Vec<String> = sqlx::query_scalar(
+            r#"SELECT location
+               FROM "table"
+               WHERE location LIKE 'file://%'"#,
+        )
+        .fetch_all(&mut *conn)
+        .await
+        .context("failed to list local table locations from [table]")?;
+        Ok(rows)
+    }
 }
@@ -0,0 +1,144 @@
+use anyhow::Context;
+use anyhow::Result;
+use chrono::DateTime;
+use chrono::Utc;
+use sqlx::query_builder::QueryBuilder;
+use sqlx::Execute;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::server::entities::account::Name as AccountName;
+use crate::server::entities::token::Value as TokenValue;
+use crate::server::utilities::postgres::PgAcquire;
+
+#[derive(Debug, Clone, serde::Serialize, sqlx::FromRow, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Token {
+    pub id: String,
+    pub account: String,
+    pub email: String,
+    pub role: String,
+    #[schema(value_type = Option<String>)]
+    pub expires_at: Option<DateTime<Utc>>,
+    /// Whether the token is neither expired nor revoked.
+    pub active: bool,
+    #[schema(value_type = String)]
+    pub created_at: DateTime<Utc>,
+}
+
+pub struct Service;
+
+impl Service {
+    pub async fn query_by_account_name(
+        account_name: &AccountName,
+        limit: Option<&i64>,
+        after: Option<&(DateTime<Utc>, Uuid)>,
+        executor: impl PgAcquire<'_>,
+    ) -> Result<Vec<Token>> {
+        let mut conn = executor
+            .acquire()
+            .await
+            .context("failed to acquire postgres connection")?;
+        let mut builder = QueryBuilder::new(
+            "SELECT
+                 token.id::text AS id,
+                 account.name AS account,
+                 token.email,
+                 token.\"role\" AS role,
+                 token.expires_at,
+                 (token.expires_at IS NULL OR token.expires_at > CURRENT_TIMESTAMP)
+                     AND NOT EXISTS (
+                         SELECT 1
+                         FROM revoked_token
+                         WHERE revoked_token.token_hash = token.value_hash
+                     ) AS active,
+                 token.created_at
+             FROM token
+             INNER JOIN account ON token.created_by = account.id
+             WHERE account.name = ",
+        );
+        builder.push_bind(account_name);
+        builder.push(" AND account.deleted_at IS NULL");
+        if let Some((created_at, id)) = after {
+            builder.push(" AND (token.created_at, token.id) >= (");
+            builder.push_bind(created_at);
+            builder.push(", ");
+            builder.push_bind(id);
+            builder.push(")");
+        }
+        builder.push(" ORDER BY token.created_at, token.id ");
+        if let Some(limit) = limit {
+            builder.push(" LIMIT ");
+            builder.push_bind(limit);
+        }
+        let mut query = sqlx::query_as::<_, Token>(builder.build().sql());
+        query = query.bind(account_name);
+        if let Some((created_at, id)) = after {
+            query = query.bind(created_at).bind(id);
+        }
+        if let Some(limit) = limit {
+            query = query.bind(limit);
+        }
+        let rows: Vec<Token> = query.fetch_all(&mut *conn).await.context(format!(
+            r#"failed to list tokens issued for "{}" from [token]"#,
+            account_name.as_str()
+        ))?;
+        Ok(rows)
+    }
+
+    /// Deletes the tokens which expired at or before `now` and returns how many were
+    /// deleted. Tokens stored without an expiration are left untouched.
+    pub async fn delete_expired(now: DateTime<Utc>, executor: impl PgAcquire<'_>) -> Result<u64> {
+        let mut conn = executor
+            .acquire()
+            .await
+            .context("failed to acquire postgres connection")?;
+        let deleted = sqlx::query(
+            "DELETE FROM token
+             WHERE expires_at <= $1",
+        )
+        .bind(now)
+        .execute(&mut *conn)
+        .await
+        .context("failed to delete expired tokens from [token]")?;
+        Ok(deleted.rows_affected())
+    }
+
+    /// Replaces the bearer values still stored in plaintext with their hashes and
+    /// returns how many were replaced.
+    pub async fn hash_plaintext_values(executor: impl PgAcquire<'_>) -> Result<u64> {
+        let mut conn = executor
+            .acquire()
+            .await
+            .context("failed to acquire postgres connection")?;
+        let rows: Vec<(Uuid, String)> = sqlx::query_as(
+            "SELECT
+                 id,
+                 \"value\"
+             FROM token
+             WHERE \"value\" IS NOT NULL",
+        )
+        .fetch_all(&mut *conn)
+        .await
+        .context("failed to list plaintext tokens from [token]")?;
+        let mut hashed = 0;
+        for (id, value) in rows {
+            let value = TokenValue::new(value).context("failed to validate token value")?;
+            hashed += sqlx::query(
+                "UPDATE token
+                 SET \"value\" = NULL,
+                     value_hash = $2,
+                     expires_at = $3
+                 WHERE id = $1",
+            )
+            .bind(id)
+            .bind(value.hash())
+            .bind(value.expires_at())
+            .execute(&mut *conn)
+            .await
+            .context(format!(r#"failed to hash "{}" in [token]"#, id))?
+            .rows_affected();
+        }
+        Ok(hashed)
+    }
+}
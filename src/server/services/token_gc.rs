@@ -0,0 +1,45 @@
+use std::time::Duration;
+
+use anyhow::Result;
+use chrono::Utc;
+use sqlx::PgPool;
+
+use crate::server::services::revocation::Service as RevocationService;
+use crate::server::services::token::Service as TokenService;
+
+pub struct Collector;
+
+impl Collector {
+    /// Deletes expired tokens and their revocations once and returns how many token
+    /// rows were deleted.
+    pub async fn tick(pg_pool: &PgPool) -> Result<u64> {
+        let now = Utc::now();
+        let tokens = TokenService::delete_expired(now, pg_pool).await?;
+        let revocations = RevocationService::delete_expired(now, pg_pool).await?;
+        tracing::info!(
+            tokens,
+            revocations,
+            "expired tokens were successfully cleaned"
+        );
+        Ok(tokens)
+    }
+
+    /// Runs [`Collector::tick`] every `interval` until the runtime shuts down. A failed
+    /// tick is logged and retried on the next one; a zero interval disables the job.
+    pub fn spawn(pg_pool: PgPool, interval: Duration) {
+        if interval.is_zero() {
+            tracing::info!("token cleanup is disabled");
+            return;
+        }
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                ticker.tick().await;
+                if let Err(e) = Self::tick(&pg_pool).await {
+                    tracing::error!("failed to clean expired tokens: {:#}", e);
+                }
+            }
+        });
+    }
+}
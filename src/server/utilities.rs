@@ -1,6 +1,10 @@
 pub mod bootstrap;
 pub mod deltalake;
 pub mod json;
+pub mod object_reader;
+pub mod pagination;
 pub mod postgres;
+pub mod retry;
 pub mod signed_url;
 pub mod sql;
+pub mod validation;
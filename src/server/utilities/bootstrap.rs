@@ -5,13 +5,14 @@ use sqlx::PgPool;
 use crate::config;
 use crate::server::entities::account::Entity as Account;
 pub use crate::server::middlewares::jwt::Keys as JwtKeys;
+use crate::server::middlewares::jwt::Role;
 use crate::server::utilities::postgres::Utility as PostgresUtility;
 
 pub struct Utility;
 
 impl Utility {
     pub async fn init_postgres(pool: &PgPool) -> Result<Account> {
-        let admin = if let Ok(admin) = Account::new(
+        let mut admin = if let Ok(admin) = Account::new(
             None,
             config::fetch::<String>("admin_name"),
             config::fetch::<String>("admin_email"),
@@ -24,6 +25,7 @@ impl Utility {
             tracing::error!("admin account data is malformed");
             return Err(anyhow!("failed to validate admin account"));
         };
+        admin.set_role(Role::Admin);
         match PostgresUtility::error(admin.save(pool).await)? {
             Ok(_) => {
                 tracing::info!("admin account was successfully registered");
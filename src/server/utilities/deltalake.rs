@@ -2,6 +2,7 @@ use std::cmp::max;
 use std::cmp::min;
 use std::collections::hash_map::HashMap;
 use std::fmt;
+use std::str::FromStr;
 
 use anyhow::anyhow;
 use anyhow::Context;
@@ -12,9 +13,11 @@ use chrono::Utc;
 use deltalake::delta::open_table_with_storage_options;
 use deltalake::delta::DeltaTable;
 use deltalake::schema::SchemaDataType;
+use deltalake::DeltaTableBuilder;
 use utoipa::ToSchema;
 
 use crate::config;
+use crate::server::utilities::signed_url::Platform;
 
 pub type File = deltalake::action::Add;
 
@@ -177,6 +180,25 @@ pub struct Utility;
 
 impl Utility {
     pub async fn open_table(location: &str) -> Result<DeltaTable> {
+        let (location, storage_options) = Self::storage_options(location);
+        open_table_with_storage_options(&location, storage_options)
+            .await
+            .context("failed to open delta table")
+    }
+
+    /// Opens the table without loading any snapshot, for requests which only read its
+    /// log such as resolving the latest version.
+    pub fn open_table_log(location: &str) -> Result<DeltaTable> {
+        let (location, storage_options) = Self::storage_options(location);
+        DeltaTableBuilder::from_uri(location)
+            .with_storage_options(storage_options)
+            .build()
+            .context("failed to open delta table log")
+    }
+
+    /// Returns the location to open along with the storage options carrying the
+    /// configured credentials; R2 locations are rewritten to their S3 equivalent.
+    pub fn storage_options(location: &str) -> (String, HashMap<String, String>) {
         let google_service_account_path = format!(
             "{}",
             shellexpand::tilde(
@@ -188,19 +210,76 @@ impl Utility {
         );
         let aws_profile = std::env::var("AWS_PROFILE").unwrap_or(config::AWS_PROFILE.into());
         let aws_region = std::env::var("AWS_REGION").unwrap_or(config::AWS_REGION.into());
-        open_table_with_storage_options(
-            location,
-            HashMap::from([
+        let mut storage_options = HashMap::from([
+            (
+                String::from("google_service_account_path"),
+                google_service_account_path,
+            ),
+            (String::from("profile"), aws_profile),
+            (String::from("region"), aws_region),
+        ]);
+        let s3_endpoint = config::fetch::<String>("s3_endpoint");
+        if !s3_endpoint.is_empty() {
+            storage_options.insert(String::from("endpoint"), s3_endpoint);
+        }
+        // NOTE: R2 is read through the S3 object store pointed at the account endpoint.
+        let location = if let Ok(Platform::R2 {
+            account_id,
+            bucket,
+            path,
+            ..
+        }) = Platform::from_str(location)
+        {
+            storage_options.remove("profile");
+            storage_options.extend([
+                (
+                    String::from("endpoint"),
+                    format!("https://{}.r2.cloudflarestorage.com", account_id),
+                ),
+                (String::from("region"), String::from("auto")),
+                (
+                    String::from("aws_access_key_id"),
+                    config::fetch::<String>("r2_access_key_id"),
+                ),
                 (
-                    String::from("google_service_account_path"),
-                    google_service_account_path,
+                    String::from("aws_secret_access_key"),
+                    config::fetch::<String>("r2_secret_access_key"),
                 ),
-                (String::from("profile"), aws_profile),
-                (String::from("region"), aws_region),
-            ]),
-        )
-        .await
-        .context("failed to open delta table")
+            ]);
+            format!("s3://{}/{}", bucket, path)
+        } else {
+            location.to_string()
+        };
+        (location, storage_options)
+    }
+
+    /// Pins the table to the snapshot at `version` or at `timestamp` and
+    /// reports whether the table was time-traveled.
+    pub async fn load_snapshot(
+        table: &mut DeltaTable,
+        version: Option<i64>,
+        timestamp: Option<DateTime<Utc>>,
+    ) -> Result<bool> {
+        match (version, timestamp) {
+            (Some(_), Some(_)) => Err(anyhow!(
+                "version and timestamp must not be specified together"
+            )),
+            (Some(version), None) => {
+                table
+                    .load_version(version)
+                    .await
+                    .context("failed to load delta table version")?;
+                Ok(true)
+            }
+            (None, Some(timestamp)) => {
+                table
+                    .load_with_datetime(timestamp)
+                    .await
+                    .context("failed to load delta table at timestamp")?;
+                Ok(true)
+            }
+            (None, None) => Ok(false),
+        }
     }
 
     pub fn get_stats(file: &File) -> Result<Stats> {
@@ -224,6 +303,55 @@ impl Utility {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::path::Path;
+    use std::path::PathBuf;
+    use std::time::SystemTime;
+
+    const FIXTURE: &str = "tests/fixtures/delta/multi_commit";
+
+    fn paths_of(table: &DeltaTable) -> Vec<String> {
+        let mut paths: Vec<String> = table
+            .get_state()
+            .files()
+            .iter()
+            .map(|f| f.path.clone())
+            .collect();
+        paths.sort();
+        paths
+    }
+
+    fn fixture_with_commit_times(commit_times: &[i64]) -> PathBuf {
+        let source = Path::new(env!("CARGO_MANIFEST_DIR")).join(FIXTURE);
+        let target = std::env::temp_dir().join(testutils::rand::uuid());
+        let log = target.join("_delta_log");
+        std::fs::create_dir_all(&log).expect("temporary delta log should be created");
+        for (version, seconds) in commit_times.iter().enumerate() {
+            let name = format!("{:020}.json", version);
+            std::fs::copy(source.join("_delta_log").join(&name), log.join(&name))
+                .expect("delta log commit should be copied");
+            let modified = SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(*seconds as u64);
+            std::fs::File::options()
+                .write(true)
+                .open(log.join(&name))
+                .and_then(|file| file.set_modified(modified))
+                .expect("delta log commit time should be set");
+        }
+        target
+    }
+
+    #[tokio::test]
+    async fn test_open_table_log() {
+        let mut table = Utility::open_table_log(FIXTURE).expect("delta table log should be opened");
+        assert_eq!(table.version(), -1);
+        assert_eq!(
+            table
+                .get_latest_version()
+                .await
+                .expect("latest version should be resolved"),
+            2
+        );
+        assert!(table.get_state().files().is_empty());
+    }
 
     #[test]
     fn test_i64_interval() {
@@ -266,4 +394,79 @@ mod tests {
         let interval = Interval::new(&min, &max);
         assert!(interval.is_empty());
     }
+
+    #[tokio::test]
+    async fn test_load_snapshot_latest() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join(FIXTURE);
+        let mut table = deltalake::delta::open_table(path.to_str().expect("path should be UTF-8"))
+            .await
+            .expect("delta table should be opened");
+        let is_time_traveled = Utility::load_snapshot(&mut table, None, None)
+            .await
+            .expect("delta table should be loaded");
+        assert!(!is_time_traveled);
+        assert_eq!(table.version(), 2);
+        assert_eq!(
+            paths_of(&table),
+            vec!["part-00001.parquet", "part-00002.parquet"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_load_snapshot_with_version() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join(FIXTURE);
+        let mut table = deltalake::delta::open_table(path.to_str().expect("path should be UTF-8"))
+            .await
+            .expect("delta table should be opened");
+        let is_time_traveled = Utility::load_snapshot(&mut table, Some(0), None)
+            .await
+            .expect("delta table should be loaded");
+        assert!(is_time_traveled);
+        assert_eq!(table.version(), 0);
+        assert_eq!(paths_of(&table), vec!["part-00000.parquet"]);
+        Utility::load_snapshot(&mut table, Some(1), None)
+            .await
+            .expect("delta table should be loaded");
+        assert_eq!(table.version(), 1);
+        assert_eq!(
+            paths_of(&table),
+            vec!["part-00000.parquet", "part-00001.parquet"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_load_snapshot_with_timestamp() {
+        let path = fixture_with_commit_times(&[1700000000, 1700000100, 1700000200]);
+        let mut table = deltalake::delta::open_table(path.to_str().expect("path should be UTF-8"))
+            .await
+            .expect("delta table should be opened");
+        let timestamp = Utc
+            .timestamp_opt(1700000150, 0)
+            .single()
+            .expect("timestamp should be valid");
+        let is_time_traveled = Utility::load_snapshot(&mut table, None, Some(timestamp))
+            .await
+            .expect("delta table should be loaded");
+        assert!(is_time_traveled);
+        assert_eq!(table.version(), 1);
+        assert_eq!(
+            paths_of(&table),
+            vec!["part-00000.parquet", "part-00001.parquet"]
+        );
+        std::fs::remove_dir_all(path).expect("temporary delta table should be removed");
+    }
+
+    #[tokio::test]
+    async fn test_load_snapshot_with_version_and_timestamp() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join(FIXTURE);
+        let mut table = deltalake::delta::open_table(path.to_str().expect("path should be UTF-8"))
+            .await
+            .expect("delta table should be opened");
+        assert!(
+            Utility::load_snapshot(&mut table, Some(0), Some(Utc::now()))
+                .await
+                .is_err()
+        );
+        assert_eq!(table.version(), 2);
+    }
 }
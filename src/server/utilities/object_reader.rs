@@ -0,0 +1,302 @@
+use std::str::FromStr;
+use std::sync::Arc;
+
+use anyhow::anyhow;
+use anyhow::Context;
+use anyhow::Result;
+use async_trait::async_trait;
+use bytes::Bytes;
+use deltalake::DeltaTableBuilder;
+use deltalake::ObjectStore;
+use deltalake::Path as ObjectPath;
+use url::Position;
+use url::Url;
+
+use crate::server::utilities::deltalake::Utility as DeltalakeUtility;
+use crate::server::utilities::signed_url::Platform;
+
+#[async_trait]
+pub trait Backend: Send + Sync {
+    /// Reads the object at `path` under `root`, e.g. `s3://bucket`.
+    async fn read(&self, root: &str, path: &str) -> Result<Bytes>;
+
+    /// Lists the URLs of the objects directly under `prefix`.
+    async fn list(&self, root: &str, prefix: &str) -> Result<Vec<String>>;
+}
+
+/// Reads through the object stores of `deltalake`, configured with the same
+/// credentials as the tables opened by the server.
+pub struct ObjectStoreBackend;
+
+#[async_trait]
+impl Backend for ObjectStoreBackend {
+    async fn read(&self, root: &str, path: &str) -> Result<Bytes> {
+        let (location, storage_options) = DeltalakeUtility::storage_options(root);
+        let store = DeltaTableBuilder::from_uri(location)
+            .with_storage_options(storage_options)
+            .build_storage()
+            .context(format!(r#"failed to build object store for "{}""#, root))?;
+        store
+            .get(&ObjectPath::from(path))
+            .await
+            .context(format!(r#"failed to get "{}" from "{}""#, path, root))?
+            .bytes()
+            .await
+            .context(format!(r#"failed to read "{}" from "{}""#, path, root))
+    }
+
+    async fn list(&self, root: &str, prefix: &str) -> Result<Vec<String>> {
+        let (location, storage_options) = DeltalakeUtility::storage_options(root);
+        let store = DeltaTableBuilder::from_uri(location)
+            .with_storage_options(storage_options)
+            .build_storage()
+            .context(format!(r#"failed to build object store for "{}""#, root))?;
+        let listed = store
+            .list_with_delimiter(Some(&ObjectPath::from(prefix)))
+            .await
+            .context(format!(r#"failed to list "{}" in "{}""#, prefix, root))?;
+        Ok(listed
+            .objects
+            .into_iter()
+            .map(|object| format!("{}/{}", root, object.location))
+            .collect())
+    }
+}
+
+pub struct LocalBackend;
+
+#[async_trait]
+impl Backend for LocalBackend {
+    async fn read(&self, _root: &str, path: &str) -> Result<Bytes> {
+        let bytes = tokio::fs::read(path)
+            .await
+            .context(format!(r#"failed to read "{}""#, path))?;
+        Ok(Bytes::from(bytes))
+    }
+
+    async fn list(&self, root: &str, prefix: &str) -> Result<Vec<String>> {
+        let mut entries = tokio::fs::read_dir(prefix)
+            .await
+            .context(format!(r#"failed to list "{}""#, prefix))?;
+        let mut urls = Vec::new();
+        while let Some(entry) = entries
+            .next_entry()
+            .await
+            .context(format!(r#"failed to list "{}""#, prefix))?
+        {
+            if entry.path().is_file() {
+                urls.push(format!("{}{}", root, entry.path().display()));
+            }
+        }
+        urls.sort();
+        Ok(urls)
+    }
+}
+
+/// Reads objects such as `_delta_log` commits by their URL, dispatching on the
+/// platform the URL scheme denotes.
+#[derive(Clone)]
+pub struct Reader {
+    aws: Arc<dyn Backend>,
+    gcp: Arc<dyn Backend>,
+    azure: Arc<dyn Backend>,
+    r2: Arc<dyn Backend>,
+    file: Arc<dyn Backend>,
+}
+
+impl Default for Reader {
+    fn default() -> Self {
+        let store: Arc<dyn Backend> = Arc::new(ObjectStoreBackend);
+        Self {
+            aws: store.clone(),
+            gcp: store.clone(),
+            azure: store.clone(),
+            r2: store,
+            file: Arc::new(LocalBackend),
+        }
+    }
+}
+
+impl Reader {
+    pub fn with_backends(
+        aws: impl Backend + 'static,
+        gcp: impl Backend + 'static,
+        azure: impl Backend + 'static,
+        r2: impl Backend + 'static,
+        file: impl Backend + 'static,
+    ) -> Self {
+        Self {
+            aws: Arc::new(aws),
+            gcp: Arc::new(gcp),
+            azure: Arc::new(azure),
+            r2: Arc::new(r2),
+            file: Arc::new(file),
+        }
+    }
+
+    fn resolve(&self, url: &str) -> Result<(&dyn Backend, String, String)> {
+        let root = Url::parse(url)
+            .map(|parsed| parsed[..Position::BeforePath].to_string())
+            .context("failed to parse URL")?;
+        let (backend, path) = match Platform::from_str(url)? {
+            Platform::Aws { path, .. } => (&self.aws, path),
+            Platform::Gcp { path, .. } => (&self.gcp, path),
+            Platform::Azure { path, .. } => (&self.azure, path),
+            Platform::R2 { path, .. } => (&self.r2, path),
+            Platform::File { path, .. } => (&self.file, path),
+            Platform::None { url } => {
+                return Err(anyhow!(r#"unsupported object store of "{}""#, url));
+            }
+        };
+        Ok((backend.as_ref(), root, path))
+    }
+
+    pub async fn read(&self, url: &str) -> Result<Bytes> {
+        let (backend, root, path) = self.resolve(url)?;
+        backend.read(&root, &path).await
+    }
+
+    pub async fn list(&self, prefix: &str) -> Result<Vec<String>> {
+        let (backend, root, path) = self.resolve(prefix)?;
+        backend.list(&root, &path).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    type Calls = Arc<Mutex<Vec<(&'static str, String, String)>>>;
+
+    struct MockBackend {
+        name: &'static str,
+        calls: Calls,
+    }
+
+    #[async_trait]
+    impl Backend for MockBackend {
+        async fn read(&self, root: &str, path: &str) -> Result<Bytes> {
+            self.calls.lock().expect("calls should be recorded").push((
+                self.name,
+                root.to_string(),
+                path.to_string(),
+            ));
+            Ok(Bytes::from(self.name))
+        }
+
+        async fn list(&self, root: &str, prefix: &str) -> Result<Vec<String>> {
+            self.calls.lock().expect("calls should be recorded").push((
+                self.name,
+                root.to_string(),
+                prefix.to_string(),
+            ));
+            Ok(vec![format!("{}/{}", root, prefix)])
+        }
+    }
+
+    fn reader() -> (Reader, Calls) {
+        let calls = Calls::default();
+        let mock = |name| MockBackend {
+            name,
+            calls: calls.clone(),
+        };
+        (
+            Reader::with_backends(
+                mock("aws"),
+                mock("gcp"),
+                mock("azure"),
+                mock("r2"),
+                mock("file"),
+            ),
+            calls,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_read_dispatch() {
+        // NOTE: Hosts are normalized to lowercase when URLs are parsed.
+        let bucket = testutils::rand::string(10).to_lowercase();
+        let path = format!(
+            "{}/_delta_log/00000000000000000000.json",
+            testutils::rand::string(10)
+        );
+        let cases = [
+            (format!("s3://{}", bucket), "aws", path.clone()),
+            (format!("s3a://{}", bucket), "aws", path.clone()),
+            (format!("gs://{}", bucket), "gcp", path.clone()),
+            (
+                format!("abfss://{}@account.dfs.core.windows.net", bucket),
+                "azure",
+                path.clone(),
+            ),
+            (format!("r2://{}@account", bucket), "r2", path.clone()),
+            (String::from("file://"), "file", format!("/{}", path)),
+        ];
+        for (root, name, path) in cases {
+            let (reader, calls) = reader();
+            let url = if name == "file" {
+                format!("{}{}", root, path)
+            } else {
+                format!("{}/{}", root, path)
+            };
+            let bytes = reader.read(&url).await.expect("object should be read");
+            assert_eq!(bytes, Bytes::from(name));
+            assert_eq!(
+                *calls.lock().expect("calls should be recorded"),
+                vec![(name, root, path)]
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_list_dispatch() {
+        let (reader, calls) = reader();
+        let bucket = testutils::rand::string(10).to_lowercase();
+        let prefix = format!("{}/_delta_log/", testutils::rand::string(10));
+        let listed = reader
+            .list(&format!("gs://{}/{}", bucket, prefix))
+            .await
+            .expect("objects should be listed");
+        assert_eq!(listed, vec![format!("gs://{}/{}", bucket, prefix)]);
+        assert_eq!(
+            *calls.lock().expect("calls should be recorded"),
+            vec![("gcp", format!("gs://{}", bucket), prefix)]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_read_unsupported_scheme() {
+        let (reader, calls) = reader();
+        let url = format!(
+            "ftp://{}/{}",
+            testutils::rand::string(10),
+            testutils::rand::string(10)
+        );
+        assert!(reader.read(&url).await.is_err());
+        assert!(calls.lock().expect("calls should be recorded").is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_local_read_and_list() {
+        let dir = std::fs::canonicalize("tests/fixtures/delta/multi_commit/_delta_log")
+            .expect("fixture should exist");
+        let dir = dir.to_str().expect("path should be UTF-8");
+        let reader = Reader::default();
+        let listed = reader
+            .list(&format!("file://{}", dir))
+            .await
+            .expect("delta log should be listed");
+        assert_eq!(
+            listed,
+            (0..3)
+                .map(|version| format!("file://{}/{:020}.json", dir, version))
+                .collect::<Vec<_>>()
+        );
+        let commit = reader
+            .read(&listed[0])
+            .await
+            .expect("commit should be read");
+        assert!(!commit.is_empty());
+    }
+}
@@ -0,0 +1,491 @@
+use std::str::FromStr;
+
+use anyhow::anyhow;
+use anyhow::Context;
+use anyhow::Result;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chrono::DateTime;
+use chrono::SecondsFormat;
+use chrono::Utc;
+use hmac::Hmac;
+use hmac::Mac;
+use sha2::Sha256;
+use sqlx::postgres::PgArguments;
+use sqlx::query::QueryAs;
+use sqlx::query_builder::QueryBuilder;
+use sqlx::Postgres;
+
+use crate::config;
+use crate::server::utilities::validation::ValidationError;
+
+#[derive(
+    Debug, Default, Clone, Copy, PartialEq, Eq, strum_macros::Display, strum_macros::EnumString,
+)]
+#[strum(serialize_all = "snake_case")]
+pub enum OrderBy {
+    #[default]
+    Name,
+    CreatedAt,
+}
+
+#[derive(
+    Debug, Default, Clone, Copy, PartialEq, Eq, strum_macros::Display, strum_macros::EnumString,
+)]
+#[strum(serialize_all = "snake_case")]
+pub enum Direction {
+    #[default]
+    Asc,
+    Desc,
+}
+
+/// Ordering of a listing over its `name` and `created_at` columns. Names break ties
+/// between equal timestamps so that keyset pages never skip or repeat rows.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Ordering {
+    pub order_by: OrderBy,
+    pub direction: Direction,
+}
+
+impl Ordering {
+    fn operator(&self) -> &'static str {
+        match self.direction {
+            Direction::Asc => ">=",
+            Direction::Desc => "<=",
+        }
+    }
+
+    /// Appends the condition resuming the listing at `cursor`, joined to the
+    /// preceding clause by `conjunction`.
+    pub fn push_after(
+        &self,
+        builder: &mut QueryBuilder<'_, Postgres>,
+        conjunction: &str,
+        cursor: &Cursor,
+    ) {
+        match (self.order_by, cursor.created_at) {
+            (OrderBy::CreatedAt, Some(created_at)) => {
+                builder.push(format!(
+                    " {} (created_at, name) {} (",
+                    conjunction,
+                    self.operator()
+                ));
+                builder.push_bind(created_at);
+                builder.push(", ");
+                builder.push_bind(cursor.name.clone());
+                builder.push(")");
+            }
+            _ => {
+                builder.push(format!(" {} name {} ", conjunction, self.operator()));
+                builder.push_bind(cursor.name.clone());
+            }
+        }
+    }
+
+    /// Binds the arguments pushed by [`Ordering::push_after`].
+    pub fn bind_after<'q, O>(
+        &self,
+        query: QueryAs<'q, Postgres, O, PgArguments>,
+        cursor: &'q Cursor,
+    ) -> QueryAs<'q, Postgres, O, PgArguments> {
+        match (self.order_by, cursor.created_at) {
+            (OrderBy::CreatedAt, Some(created_at)) => query.bind(created_at).bind(&cursor.name),
+            _ => query.bind(&cursor.name),
+        }
+    }
+
+    pub fn push_order(&self, builder: &mut QueryBuilder<'_, Postgres>) {
+        let direction = match self.direction {
+            Direction::Asc => "ASC",
+            Direction::Desc => "DESC",
+        };
+        match self.order_by {
+            OrderBy::Name => builder.push(format!(" ORDER BY name {} ", direction)),
+            OrderBy::CreatedAt => {
+                builder.push(format!(" ORDER BY created_at {0}, name {0} ", direction))
+            }
+        };
+    }
+}
+
+/// Ordering requested with a listing. Unset fields follow the page token, or the
+/// default ordering on the first page.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct RequestedOrdering {
+    pub order_by: Option<OrderBy>,
+    pub direction: Option<Direction>,
+}
+
+/// Sort key of the first row of the next page.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cursor {
+    pub name: String,
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+impl Cursor {
+    pub fn new(name: impl Into<String>, created_at: Option<DateTime<Utc>>) -> Self {
+        Self {
+            name: name.into(),
+            created_at,
+        }
+    }
+}
+
+pub struct Utility;
+
+impl Utility {
+    /// Returns the page size to list with, falling back to `default` when no size is
+    /// requested and clamping sizes above `max` instead of rejecting them. Sizes below
+    /// one are malformed.
+    pub fn clamp_page_size(requested: Option<i64>, default: usize, max: usize) -> Option<usize> {
+        let Some(requested) = requested else {
+            return Some(default.min(max));
+        };
+        if requested < 1 {
+            return None;
+        }
+        Some(usize::try_from(requested).map_or(max, |requested| requested.min(max)))
+    }
+
+    pub fn page_token_secret() -> Result<String> {
+        let secret = config::fetch::<String>("page_token_secret");
+        if secret.is_empty() {
+            return Err(anyhow!("page_token_secret must be set"));
+        }
+        Ok(secret)
+    }
+
+    /// Keys page tokens by `page_token_secret` rather than a JWT secret, so that
+    /// page tokens cannot be forged from, nor rotate with, the signing keys.
+    fn mac(scope: &str, cursor: &str) -> Hmac<Sha256> {
+        let secret = config::fetch::<String>("page_token_secret");
+        let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes())
+            .expect("HMAC should accept keys of any size");
+        mac.update(scope.as_bytes());
+        mac.update(b"\n");
+        mac.update(cursor.as_bytes());
+        mac
+    }
+
+    pub fn encode(scope: &str, cursor: &str) -> String {
+        let signature = Self::mac(scope, cursor).finalize().into_bytes();
+        format!(
+            "{}.{}",
+            URL_SAFE_NO_PAD.encode(cursor.as_bytes()),
+            URL_SAFE_NO_PAD.encode(signature)
+        )
+    }
+
+    pub fn decode(scope: &str, token: &str) -> Result<String> {
+        let Some((cursor, signature)) = token.split_once('.') else {
+            return Err(anyhow!("page token is not signed"));
+        };
+        let cursor = URL_SAFE_NO_PAD
+            .decode(cursor)
+            .context("failed to decode page token")?;
+        let cursor = String::from_utf8(cursor).context("failed to parse page token as UTF-8")?;
+        let signature = URL_SAFE_NO_PAD
+            .decode(signature)
+            .context("failed to decode page token signature")?;
+        Self::mac(scope, &cursor)
+            .verify_slice(&signature)
+            .map_err(|_| anyhow!("page token signature does not match"))?;
+        Ok(cursor)
+    }
+
+    /// Validates the requested ordering against the supported keys and directions.
+    pub fn requested_ordering(
+        order_by: Option<&str>,
+        direction: Option<&str>,
+    ) -> Result<RequestedOrdering> {
+        let mut errors = ValidationError::default();
+        let order_by = order_by.and_then(|order_by| {
+            errors.check(
+                "orderBy",
+                OrderBy::from_str(order_by)
+                    .map_err(|_| anyhow!(r#"ordering by "{}" is not supported"#, order_by)),
+            )
+        });
+        let direction = direction.and_then(|direction| {
+            errors.check(
+                "direction",
+                Direction::from_str(direction)
+                    .map_err(|_| anyhow!(r#"direction "{}" is not supported"#, direction)),
+            )
+        });
+        if !errors.fields.is_empty() {
+            return Err(errors.into());
+        }
+        Ok(RequestedOrdering {
+            order_by,
+            direction,
+        })
+    }
+
+    fn encode_cursor(ordering: &Ordering, cursor: &Cursor) -> String {
+        match (ordering.order_by, cursor.created_at) {
+            (OrderBy::CreatedAt, Some(created_at)) => format!(
+                "{}/{}/{}/{}",
+                ordering.order_by,
+                ordering.direction,
+                created_at.to_rfc3339_opts(SecondsFormat::Micros, true),
+                cursor.name
+            ),
+            _ => format!(
+                "{}/{}/{}",
+                ordering.order_by, ordering.direction, cursor.name
+            ),
+        }
+    }
+
+    fn decode_cursor(cursor: &str) -> Option<(Ordering, Cursor)> {
+        let mut parts = cursor.splitn(3, '/');
+        let order_by = OrderBy::from_str(parts.next()?).ok()?;
+        let direction = Direction::from_str(parts.next()?).ok()?;
+        let key = parts.next()?;
+        let ordering = Ordering {
+            order_by,
+            direction,
+        };
+        match order_by {
+            OrderBy::Name => Some((ordering, Cursor::new(key, None))),
+            OrderBy::CreatedAt => {
+                let (created_at, name) = key.split_once('/')?;
+                let created_at = DateTime::parse_from_rfc3339(created_at).ok()?;
+                Some((
+                    ordering,
+                    Cursor::new(name, Some(created_at.with_timezone(&Utc))),
+                ))
+            }
+        }
+    }
+
+    /// Returns the signed token of the page starting at `cursor` under `ordering`.
+    pub fn encode_page(scope: &str, ordering: &Ordering, cursor: &Cursor) -> String {
+        Self::encode(scope, &Self::encode_cursor(ordering, cursor))
+    }
+
+    /// Resolves the ordering and cursor of the requested page. A page token carries
+    /// the ordering of the first page, which the request may repeat but not change.
+    pub fn resume(
+        scope: &str,
+        token: Option<&str>,
+        requested: RequestedOrdering,
+    ) -> Result<(Ordering, Option<Cursor>)> {
+        let Some(token) = token else {
+            let ordering = Ordering {
+                order_by: requested.order_by.unwrap_or_default(),
+                direction: requested.direction.unwrap_or_default(),
+            };
+            return Ok((ordering, None));
+        };
+        let cursor = Self::decode(scope, token)?;
+        let Some((ordering, cursor)) = Self::decode_cursor(&cursor) else {
+            return Err(anyhow!("page token cursor is malformed"));
+        };
+        if requested
+            .order_by
+            .map_or(false, |order_by| order_by != ordering.order_by)
+            || requested
+                .direction
+                .map_or(false, |direction| direction != ordering.direction)
+        {
+            return Err(anyhow!("requested ordering does not match page token"));
+        }
+        Ok((ordering, Some(cursor)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clamp_page_size() {
+        let max = testutils::rand::u64(2, 1000) as usize;
+        let default = testutils::rand::u64(1, max as u64) as usize;
+        let requested = testutils::rand::i64(1, max as i64 + 1);
+        assert_eq!(Utility::clamp_page_size(None, default, max), Some(default));
+        assert_eq!(
+            Utility::clamp_page_size(Some(requested), default, max),
+            Some(requested as usize)
+        );
+        assert_eq!(
+            Utility::clamp_page_size(Some(max as i64 + 1), default, max),
+            Some(max)
+        );
+        assert_eq!(
+            Utility::clamp_page_size(Some(i64::MAX), default, max),
+            Some(max)
+        );
+    }
+
+    #[test]
+    fn test_clamp_page_size_with_default_above_max() {
+        let max = testutils::rand::u64(1, 1000) as usize;
+        assert_eq!(Utility::clamp_page_size(None, max + 1, max), Some(max));
+    }
+
+    #[test]
+    fn test_clamp_page_size_not_positive() {
+        assert_eq!(Utility::clamp_page_size(Some(0), 10, 500), None);
+        assert_eq!(
+            Utility::clamp_page_size(Some(-testutils::rand::i64(1, 100000)), 10, 500),
+            None
+        );
+    }
+
+    #[test]
+    fn test_encode_and_decode() {
+        let scope = testutils::rand::string(10);
+        let cursor = testutils::rand::string(10);
+        let token = Utility::encode(&scope, &cursor);
+        assert_ne!(token, cursor);
+        assert_eq!(
+            Utility::decode(&scope, &token).expect("page token should be decoded"),
+            cursor
+        );
+    }
+
+    #[test]
+    fn test_decode_malformed() {
+        assert!(Utility::decode("shares", "!!!").is_err());
+        assert!(Utility::decode("shares", "!!!.!!!").is_err());
+    }
+
+    #[test]
+    fn test_decode_unsigned() {
+        let cursor = testutils::rand::string(10);
+        let token = URL_SAFE_NO_PAD.encode(cursor.as_bytes());
+        assert!(Utility::decode("shares", &token).is_err());
+    }
+
+    #[test]
+    fn test_decode_tampered_cursor() {
+        let scope = testutils::rand::string(10);
+        let token = Utility::encode(&scope, "a");
+        let (_, signature) = token.split_once('.').expect("page token should be signed");
+        let tampered = format!("{}.{}", URL_SAFE_NO_PAD.encode("z".as_bytes()), signature);
+        assert!(Utility::decode(&scope, &tampered).is_err());
+    }
+
+    #[test]
+    fn test_decode_tampered_signature() {
+        let scope = testutils::rand::string(10);
+        let token = Utility::encode(&scope, "a");
+        let (cursor, _) = token.split_once('.').expect("page token should be signed");
+        let tampered = format!("{}.{}", cursor, URL_SAFE_NO_PAD.encode([0u8; 32]));
+        assert!(Utility::decode(&scope, &tampered).is_err());
+    }
+
+    #[test]
+    fn test_requested_ordering() {
+        assert_eq!(
+            Utility::requested_ordering(None, None).expect("ordering should be parsed"),
+            RequestedOrdering::default()
+        );
+        assert_eq!(
+            Utility::requested_ordering(Some("created_at"), Some("desc"))
+                .expect("ordering should be parsed"),
+            RequestedOrdering {
+                order_by: Some(OrderBy::CreatedAt),
+                direction: Some(Direction::Desc),
+            }
+        );
+        let error = Utility::requested_ordering(Some("location"), Some("sideways"))
+            .expect_err("unsupported ordering should be rejected")
+            .downcast::<ValidationError>()
+            .expect("error should be a validation error");
+        assert_eq!(error.names(), vec!["orderBy", "direction"]);
+    }
+
+    #[test]
+    fn test_resume_first_page() {
+        let (ordering, cursor) = Utility::resume(
+            "shares",
+            None,
+            RequestedOrdering {
+                order_by: None,
+                direction: Some(Direction::Desc),
+            },
+        )
+        .expect("first page should be resolved");
+        assert_eq!(
+            ordering,
+            Ordering {
+                order_by: OrderBy::Name,
+                direction: Direction::Desc,
+            }
+        );
+        assert_eq!(cursor, None);
+    }
+
+    #[test]
+    fn test_resume_by_created_at() {
+        let scope = testutils::rand::string(10);
+        let ordering = Ordering {
+            order_by: OrderBy::CreatedAt,
+            direction: Direction::Desc,
+        };
+        let created_at = DateTime::parse_from_rfc3339("2023-12-20T10:20:30.123456Z")
+            .expect("timestamp should be parsed")
+            .with_timezone(&Utc);
+        let cursor = Cursor::new(
+            format!(
+                "{}/{}",
+                testutils::rand::string(5),
+                testutils::rand::string(5)
+            ),
+            Some(created_at),
+        );
+        let token = Utility::encode_page(&scope, &ordering, &cursor);
+        assert_eq!(
+            Utility::resume(&scope, Some(&token), RequestedOrdering::default())
+                .expect("page token should be resumed"),
+            (ordering, Some(cursor))
+        );
+    }
+
+    #[test]
+    fn test_resume_with_other_ordering() {
+        let scope = testutils::rand::string(10);
+        let ordering = Ordering {
+            order_by: OrderBy::CreatedAt,
+            direction: Direction::Desc,
+        };
+        let cursor = Cursor::new(testutils::rand::string(10), Some(Utc::now()));
+        let token = Utility::encode_page(&scope, &ordering, &cursor);
+        assert!(Utility::resume(
+            &scope,
+            Some(&token),
+            RequestedOrdering {
+                order_by: Some(OrderBy::Name),
+                direction: None,
+            },
+        )
+        .is_err());
+        assert!(Utility::resume(
+            &scope,
+            Some(&token),
+            RequestedOrdering {
+                order_by: Some(OrderBy::CreatedAt),
+                direction: Some(Direction::Asc),
+            },
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_page_token_secret() {
+        let secret = Utility::page_token_secret().expect("page token secret should be set");
+        assert_ne!(secret, config::fetch::<String>("jwt_secret"));
+    }
+
+    #[test]
+    fn test_decode_other_scope() {
+        let cursor = testutils::rand::string(10);
+        let token = Utility::encode("shares/a", &cursor);
+        assert!(Utility::decode("shares/b", &token).is_err());
+    }
+}
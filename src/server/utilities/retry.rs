@@ -0,0 +1,151 @@
+use std::future::Future;
+use std::time::Duration;
+
+use anyhow::Result;
+
+use crate::config;
+
+const MAX_ATTEMPTS: u32 = 5;
+
+const MAX_DELAY: Duration = Duration::from_secs(2);
+
+/// Marks an error as worth retrying; errors without it are returned at once.
+#[derive(Debug)]
+pub struct Transient;
+
+impl std::fmt::Display for Transient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "transient failure")
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Policy {
+    pub attempts: u32,
+    pub base_delay: Duration,
+}
+
+impl Policy {
+    pub fn from_config() -> Self {
+        Self {
+            attempts: config::fetch::<u32>("signing_retry_attempts").clamp(1, MAX_ATTEMPTS),
+            base_delay: Duration::from_millis(config::fetch::<u64>("signing_retry_base_delay_ms")),
+        }
+    }
+
+    /// Returns the delay before the `retry`-th retry, doubling from the base delay
+    /// up to `MAX_DELAY`.
+    pub fn delay(&self, retry: u32) -> Duration {
+        self.base_delay
+            .saturating_mul(1u32.checked_shl(retry).unwrap_or(u32::MAX))
+            .min(MAX_DELAY)
+    }
+}
+
+pub struct Utility;
+
+impl Utility {
+    pub fn transient(e: anyhow::Error) -> anyhow::Error {
+        e.context(Transient)
+    }
+
+    pub fn is_transient(e: &anyhow::Error) -> bool {
+        e.downcast_ref::<Transient>().is_some()
+    }
+
+    pub async fn retry<T, F, Fut>(policy: &Policy, mut op: F) -> Result<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let mut retry = 0;
+        loop {
+            match op().await {
+                Err(e) if Self::is_transient(&e) && retry + 1 < policy.attempts => {
+                    tracing::warn!("retrying after transient failure: {:#}", e);
+                    tokio::time::sleep(policy.delay(retry)).await;
+                    retry += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::anyhow;
+    use std::cell::Cell;
+
+    fn policy(attempts: u32) -> Policy {
+        Policy {
+            attempts,
+            base_delay: Duration::from_millis(1),
+        }
+    }
+
+    #[test]
+    fn test_delay() {
+        let base_delay = Duration::from_millis(testutils::rand::u64(1, 100));
+        let policy = Policy {
+            attempts: 3,
+            base_delay,
+        };
+        assert_eq!(policy.delay(0), base_delay);
+        assert_eq!(policy.delay(1), base_delay * 2);
+        assert_eq!(policy.delay(2), base_delay * 4);
+        assert_eq!(policy.delay(64), MAX_DELAY);
+    }
+
+    #[test]
+    fn test_policy_from_config() {
+        let policy = Policy::from_config();
+        assert!(policy.attempts >= 1);
+        assert!(policy.attempts <= MAX_ATTEMPTS);
+    }
+
+    #[tokio::test]
+    async fn test_retry_flaky_signer() {
+        let calls = Cell::new(0);
+        let signed = Utility::retry(&policy(3), || {
+            calls.set(calls.get() + 1);
+            let call = calls.get();
+            async move {
+                if call < 2 {
+                    return Err(Utility::transient(anyhow!("failed to sign url")));
+                }
+                Ok("signed")
+            }
+        })
+        .await
+        .expect("url should be signed on retry");
+        assert_eq!(signed, "signed");
+        assert_eq!(calls.get(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_retry_gives_up_after_attempts() {
+        let attempts = testutils::rand::u64(1, 5) as u32;
+        let calls = Cell::new(0);
+        let result: Result<()> = Utility::retry(&policy(attempts), || {
+            calls.set(calls.get() + 1);
+            async { Err::<(), _>(Utility::transient(anyhow!("failed to sign url"))) }
+        })
+        .await;
+        assert!(result.is_err());
+        assert_eq!(calls.get(), attempts);
+    }
+
+    #[tokio::test]
+    async fn test_retry_permanent_failure() {
+        let calls = Cell::new(0);
+        let result: Result<()> = Utility::retry(&policy(3), || {
+            calls.set(calls.get() + 1);
+            async { Err::<(), _>(anyhow!("key was rejected")) }
+        })
+        .await;
+        assert!(result.is_err());
+        assert_eq!(calls.get(), 1);
+    }
+}
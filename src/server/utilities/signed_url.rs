@@ -1,13 +1,26 @@
+use std::collections::HashMap;
+use std::collections::HashSet;
 use std::str::FromStr;
 use std::time::Duration;
 
+use anyhow::anyhow;
 use anyhow::Context;
 use anyhow::Result;
+use base64::engine::general_purpose::STANDARD;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chrono::SecondsFormat;
+use chrono::Utc;
+use hmac::Hmac;
+use hmac::Mac;
+use once_cell::sync::Lazy;
+use rusoto_core::signature::SignedRequest;
 use rusoto_core::Region;
 use rusoto_credential::AwsCredentials as AWS;
 use rusoto_s3::util::PreSignedRequest;
 use rusoto_s3::util::PreSignedRequestOption;
 use rusoto_s3::GetObjectRequest;
+use sha2::Sha256;
 use tame_gcs::signed_url::SignedUrlOptional;
 use tame_gcs::signed_url::UrlSigner;
 use tame_gcs::signing::ServiceAccount as GCP;
@@ -15,7 +28,10 @@ use tame_gcs::BucketName;
 use tame_gcs::ObjectName;
 use url::Url;
 
-#[derive(Debug, PartialEq, Eq)]
+use crate::config;
+use crate::server::utilities::retry::Utility as RetryUtility;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Platform {
     Aws {
         url: String,
@@ -27,6 +43,22 @@ pub enum Platform {
         bucket: String,
         path: String,
     },
+    Azure {
+        url: String,
+        account: String,
+        container: String,
+        path: String,
+    },
+    R2 {
+        url: String,
+        account_id: String,
+        bucket: String,
+        path: String,
+    },
+    File {
+        url: String,
+        path: String,
+    },
     None {
         url: String,
     },
@@ -53,6 +85,26 @@ impl FromStr for Platform {
                 bucket: String::from(url.domain().unwrap_or("")),
                 path: String::from(url.path().strip_prefix('/').unwrap_or("")),
             }),
+            "wasbs" | "abfss" => Ok(Self::Azure {
+                url: String::from(url.as_str()),
+                account: String::from(
+                    url.domain()
+                        .and_then(|domain| domain.split('.').next())
+                        .unwrap_or(""),
+                ),
+                container: String::from(url.username()),
+                path: String::from(url.path().strip_prefix('/').unwrap_or("")),
+            }),
+            "r2" => Ok(Self::R2 {
+                url: String::from(url.as_str()),
+                account_id: String::from(url.host_str().unwrap_or("")),
+                bucket: String::from(url.username()),
+                path: String::from(url.path().strip_prefix('/').unwrap_or("")),
+            }),
+            "file" => Ok(Self::File {
+                url: String::from(url.as_str()),
+                path: String::from(url.path()),
+            }),
             _ => Ok(Self::None {
                 url: String::from(url.as_str()),
             }),
@@ -60,37 +112,465 @@ impl FromStr for Platform {
     }
 }
 
+#[derive(Debug, Copy, Clone, PartialEq, Eq, strum_macros::EnumString)]
+pub enum SchemeGuard {
+    #[strum(ascii_case_insensitive)]
+    Off,
+    #[strum(ascii_case_insensitive)]
+    Upgrade,
+    #[strum(ascii_case_insensitive)]
+    Reject,
+}
+
+impl SchemeGuard {
+    pub fn from_config() -> Result<Self> {
+        let guard = config::fetch::<String>("signed_url_scheme_guard");
+        Self::from_str(&guard)
+            .map_err(|_| anyhow!("signed url scheme guard `{}` is not supported", guard))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct AzureCredentials {
+    pub account: String,
+    pub key: Vec<u8>,
+}
+
+/// Addressing of presigned S3-compatible URLs: `virtual_host` puts the bucket in
+/// the host name, `path` in the first path segment.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, strum_macros::EnumString)]
+#[strum(serialize_all = "snake_case", ascii_case_insensitive)]
+pub enum SigningStyle {
+    #[default]
+    VirtualHost,
+    Path,
+}
+
+/// Credentials of the buckets owned by accounts other than the default one, along
+/// with the buckets readable without any credentials.
+#[derive(Default)]
+pub struct BucketCredentials {
+    pub aws: HashMap<String, AWS>,
+    pub gcp: HashMap<String, GCP>,
+    pub public: HashSet<String>,
+    pub signing_styles: HashMap<String, SigningStyle>,
+}
+
+impl BucketCredentials {
+    fn resolve<'a, C>(
+        mapped: &'a HashMap<String, C>,
+        bucket: &str,
+        default: Option<&'a C>,
+    ) -> Option<&'a C> {
+        if let Some(credentials) = mapped.get(bucket) {
+            return Some(credentials);
+        }
+        if !mapped.is_empty() {
+            tracing::warn!(
+                r#"bucket "{}" is not mapped to any credentials, falling back to the default ones"#,
+                bucket
+            );
+        }
+        default
+    }
+
+    pub fn aws_for<'a>(&'a self, bucket: &str, default: Option<&'a AWS>) -> Option<&'a AWS> {
+        Self::resolve(&self.aws, bucket, default)
+    }
+
+    pub fn gcp_for<'a>(&'a self, bucket: &str, default: Option<&'a GCP>) -> Option<&'a GCP> {
+        Self::resolve(&self.gcp, bucket, default)
+    }
+
+    pub fn is_public(&self, bucket: &str) -> bool {
+        self.public.contains(bucket)
+    }
+
+    pub fn signing_style_for(&self, bucket: &str) -> SigningStyle {
+        self.signing_styles.get(bucket).copied().unwrap_or_default()
+    }
+}
+
+/// Response headers a presigned URL asks the store to override when the file is
+/// downloaded, so that clients save it under a recognizable name.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ResponseOverrides {
+    pub content_disposition: Option<String>,
+    pub content_type: Option<String>,
+}
+
+impl ResponseOverrides {
+    /// Names the download after the table and the base name of the file. Characters
+    /// other than ASCII alphanumerics, `.`, `-` and `_` are replaced so that the
+    /// filename never needs quoting or encoding.
+    pub fn for_file(table: &str, path: &str) -> Self {
+        let file = path.rsplit('/').next().unwrap_or(path);
+        let filename: String = format!("{}_{}", table, file)
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_') {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        let content_type = if file.ends_with(".parquet") {
+            "application/vnd.apache.parquet"
+        } else {
+            "application/octet-stream"
+        };
+        Self {
+            content_disposition: Some(format!(r#"attachment; filename="{}""#, filename)),
+            content_type: Some(content_type.to_string()),
+        }
+    }
+
+    fn params(&self) -> Vec<(&'static str, &str)> {
+        let mut params = Vec::new();
+        if let Some(content_disposition) = &self.content_disposition {
+            params.push(("response-content-disposition", content_disposition.as_str()));
+        }
+        if let Some(content_type) = &self.content_type {
+            params.push(("response-content-type", content_type.as_str()));
+        }
+        params
+    }
+}
+
+const AZURE_SAS_VERSION: &str = "2020-12-06";
+
+const R2_REGION: &str = "auto";
+
+const GCS_PUBLIC_ENDPOINT: &str = "https://storage.googleapis.com";
+
+pub const LOCAL_PATH: &str = "/sharing/_local";
+
+const VERIFICATION_TIMEOUT: Duration = Duration::from_secs(10);
+
+static VERIFICATION_CLIENT: Lazy<reqwest::Client> = Lazy::new(|| {
+    reqwest::Client::builder()
+        .timeout(VERIFICATION_TIMEOUT)
+        .build()
+        .expect("verification client should be built")
+});
+
 pub struct Utility;
 
 impl Utility {
-    pub fn sign_aws(aws: &AWS, bucket: &str, path: &str, duration: &u64) -> Result<Url> {
-        let region = Region::default();
+    pub fn aws_region(endpoint: &str) -> Region {
+        if endpoint.is_empty() {
+            return Region::default();
+        }
+        Region::Custom {
+            name: std::env::var("AWS_REGION").unwrap_or(config::AWS_REGION.into()),
+            endpoint: endpoint.to_string(),
+        }
+    }
+
+    /// Returns true when `bucket` can be addressed as a subdomain of the region's
+    /// endpoint; buckets with dots would break TLS and IP or `localhost` endpoints
+    /// have no subdomains, so those fall back to path-style URLs.
+    fn is_virtual_hostable(region: &Region, bucket: &str) -> bool {
+        let dns_compatible = (3..=63).contains(&bucket.len())
+            && bucket
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-');
+        if !dns_compatible {
+            return false;
+        }
+        let Region::Custom { endpoint, .. } = region else {
+            return true;
+        };
+        let Ok(endpoint) = Url::parse(endpoint) else {
+            return false;
+        };
+        matches!(endpoint.host(), Some(url::Host::Domain(domain)) if domain != "localhost")
+    }
+
+    pub fn sign_aws(
+        aws: &AWS,
+        region: &Region,
+        bucket: &str,
+        path: &str,
+        duration: &u64,
+        style: &SigningStyle,
+        overrides: Option<&ResponseOverrides>,
+    ) -> Result<Url> {
         let options = PreSignedRequestOption {
             expires_in: Duration::from_secs(*duration),
         };
+        if *style == SigningStyle::VirtualHost && Self::is_virtual_hostable(region, bucket) {
+            let mut request = SignedRequest::new("GET", "s3", region, &format!("/{}", path));
+            request.set_hostname(Some(format!("{}.{}", bucket, request.hostname())));
+            for (key, value) in overrides.map(ResponseOverrides::params).unwrap_or_default() {
+                request.add_param(key, value);
+            }
+            let url = request.generate_presigned_url(aws, &options.expires_in, false);
+            let url = Url::parse(&url).context("failed to parse AWS signed URL")?;
+            return Ok(url);
+        }
         let request = GetObjectRequest {
             bucket: bucket.to_string(),
             key: path.to_string(),
+            response_content_disposition: overrides
+                .and_then(|overrides| overrides.content_disposition.clone()),
+            response_content_type: overrides.and_then(|overrides| overrides.content_type.clone()),
             ..Default::default()
         };
-        let url = request.get_presigned_url(&region, aws, &options);
+        let url = request.get_presigned_url(region, aws, &options);
         let url = Url::parse(&url).context("failed to parse AWS signed URL")?;
         Ok(url)
     }
 
-    pub fn sign_gcp(gcp: &GCP, bucket: &str, path: &str, duration: &u64) -> Result<Url> {
+    pub fn public_aws(region: &Region, bucket: &str, path: &str) -> Result<Url> {
+        let (mut url, bucket) = match region {
+            Region::Custom { endpoint, .. } => (
+                Url::parse(endpoint).context("failed to parse AWS endpoint")?,
+                Some(bucket),
+            ),
+            _ => (
+                Url::parse(&format!(
+                    "https://{}.s3.{}.amazonaws.com",
+                    bucket,
+                    region.name()
+                ))
+                .context("failed to parse AWS public URL")?,
+                None,
+            ),
+        };
+        url.path_segments_mut()
+            .map_err(|_| anyhow!("AWS endpoint cannot be a base URL"))?
+            .pop_if_empty()
+            .extend(bucket)
+            .extend(path.split('/'));
+        Ok(url)
+    }
+
+    pub fn r2_region(account_id: &str) -> Region {
+        Region::Custom {
+            name: R2_REGION.into(),
+            endpoint: format!("https://{}.r2.cloudflarestorage.com", account_id),
+        }
+    }
+
+    pub fn sign_r2(
+        r2: &AWS,
+        account_id: &str,
+        bucket: &str,
+        path: &str,
+        duration: &u64,
+        style: &SigningStyle,
+        overrides: Option<&ResponseOverrides>,
+    ) -> Result<Url> {
+        Self::sign_aws(
+            r2,
+            &Self::r2_region(account_id),
+            bucket,
+            path,
+            duration,
+            style,
+            overrides,
+        )
+        .context("failed to sign Cloudflare R2 URL")
+    }
+
+    pub fn sign_gcp(
+        gcp: &GCP,
+        bucket: &str,
+        path: &str,
+        duration: &u64,
+        overrides: Option<&ResponseOverrides>,
+    ) -> Result<Url> {
         let bucket = BucketName::try_from(bucket).context("failed to parse bucket name")?;
         let object = ObjectName::try_from(path).context("failed to parse object name")?;
-        let options = SignedUrlOptional {
+        let mut options = SignedUrlOptional {
             duration: Duration::from_secs(*duration),
             ..Default::default()
         };
+        for (key, value) in overrides.map(ResponseOverrides::params).unwrap_or_default() {
+            options
+                .query_params
+                .insert(key.to_string(), value.to_string());
+        }
         let signer = UrlSigner::with_ring();
         let url = signer
             .generate(gcp, &(&bucket, &object), options)
-            .context("failed to generate signed url")?;
+            .map_err(|e| {
+                // NOTE: A rejected key is a misconfiguration and fails the same way on every attempt.
+                let rejected = matches!(e, tame_gcs::Error::KeyRejected(_));
+                let e = anyhow::Error::new(e).context("failed to generate signed url");
+                if rejected {
+                    e
+                } else {
+                    RetryUtility::transient(e)
+                }
+            })?;
         Ok(url)
     }
+
+    pub fn public_gcp(bucket: &str, path: &str) -> Result<Url> {
+        let mut url =
+            Url::parse(GCS_PUBLIC_ENDPOINT).context("failed to parse GCP public endpoint")?;
+        url.path_segments_mut()
+            .map_err(|_| anyhow!("GCP public endpoint cannot be a base URL"))?
+            .pop_if_empty()
+            .push(bucket)
+            .extend(path.split('/'));
+        Ok(url)
+    }
+
+    pub fn sign_azure(
+        azure: &AzureCredentials,
+        container: &str,
+        path: &str,
+        duration: &u64,
+    ) -> Result<Url> {
+        let seconds = i64::try_from(*duration).context("failed to convert duration")?;
+        let expiry = (Utc::now() + chrono::Duration::seconds(seconds))
+            .to_rfc3339_opts(SecondsFormat::Secs, true);
+        let resource = format!("/blob/{}/{}/{}", azure.account, container, path);
+        // NOTE: The fields follow the service SAS string-to-sign layout for the pinned version.
+        let string_to_sign = [
+            "r",
+            "",
+            &expiry,
+            &resource,
+            "",
+            "",
+            "https",
+            AZURE_SAS_VERSION,
+            "b",
+            "",
+            "",
+            "",
+            "",
+            "",
+            "",
+            "",
+        ]
+        .join("\n");
+        let mut mac = Hmac::<Sha256>::new_from_slice(&azure.key)
+            .context("failed to create Azure SAS signer")?;
+        mac.update(string_to_sign.as_bytes());
+        let signature = STANDARD.encode(mac.finalize().into_bytes());
+        let mut url = Url::parse(&format!(
+            "https://{}.blob.core.windows.net/{}/{}",
+            azure.account, container, path
+        ))
+        .context("failed to parse Azure blob URL")?;
+        url.query_pairs_mut()
+            .append_pair("sp", "r")
+            .append_pair("se", &expiry)
+            .append_pair("spr", "https")
+            .append_pair("sv", AZURE_SAS_VERSION)
+            .append_pair("sr", "b")
+            .append_pair("sig", &signature);
+        Ok(url)
+    }
+
+    /// Returns the secret local file URLs are signed with, or `None` when tables on
+    /// the local filesystem are not served. The secret is required once they are.
+    pub fn local_url_secret() -> Result<Option<String>> {
+        if !config::fetch::<bool>("local_tables") {
+            return Ok(None);
+        }
+        let secret = config::fetch::<String>("local_url_secret");
+        if secret.is_empty() {
+            return Err(anyhow!(
+                "local_url_secret must be set when local_tables is enabled"
+            ));
+        }
+        Ok(Some(secret))
+    }
+
+    fn file_mac(secret: &str, path: &str, expires: i64) -> Hmac<Sha256> {
+        let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes())
+            .expect("HMAC should accept keys of any size");
+        mac.update(path.as_bytes());
+        mac.update(b"\n");
+        mac.update(expires.to_string().as_bytes());
+        mac
+    }
+
+    /// Signs a URL to the local download handler served at `endpoint`, which streams
+    /// the file at `path` until the URL expires.
+    pub fn sign_file(endpoint: &str, path: &str, duration: &u64) -> Result<Url> {
+        let Some(secret) = Self::local_url_secret()? else {
+            return Err(anyhow!("tables on the local filesystem are not served"));
+        };
+        let seconds = i64::try_from(*duration).context("failed to convert duration")?;
+        let expires = Utc::now().timestamp() + seconds;
+        let signature = URL_SAFE_NO_PAD.encode(
+            Self::file_mac(&secret, path, expires)
+                .finalize()
+                .into_bytes(),
+        );
+        let mut url = Url::parse(&format!("{}{}", endpoint, LOCAL_PATH))
+            .context("failed to parse local download URL")?;
+        url.query_pairs_mut()
+            .append_pair("path", path)
+            .append_pair("expires", &expires.to_string())
+            .append_pair("sig", &signature);
+        Ok(url)
+    }
+
+    pub fn verify_file(path: &str, expires: i64, signature: &str) -> Result<()> {
+        let Some(secret) = Self::local_url_secret()? else {
+            return Err(anyhow!("tables on the local filesystem are not served"));
+        };
+        let signature = URL_SAFE_NO_PAD
+            .decode(signature)
+            .context("failed to decode local download signature")?;
+        Self::file_mac(&secret, path, expires)
+            .verify_slice(&signature)
+            .map_err(|_| anyhow!("local download signature does not match"))?;
+        if expires < Utc::now().timestamp() {
+            return Err(anyhow!("local download URL expired"));
+        }
+        Ok(())
+    }
+
+    pub fn guard(url: Url, guard: &SchemeGuard) -> Result<Url> {
+        if url.scheme() != "http" {
+            return Ok(url);
+        }
+        match guard {
+            SchemeGuard::Off => Ok(url),
+            SchemeGuard::Upgrade => {
+                tracing::warn!("signed url was downgraded to http, upgrading to https");
+                let mut url = url;
+                url.set_scheme("https")
+                    .map_err(|_| anyhow!("failed to upgrade signed url to https"))?;
+                Ok(url)
+            }
+            SchemeGuard::Reject => {
+                tracing::warn!("signed url was downgraded to http, rejecting");
+                Err(anyhow!("signed url was downgraded to http"))
+            }
+        }
+    }
+
+    /// Returns true if the URL can be downloaded. URLs that are not `http(s)`, such
+    /// as the unsigned table location, cannot be checked and are reported missing.
+    pub async fn exists(url: String) -> bool {
+        let Ok(parsed) = Url::parse(&url) else {
+            return false;
+        };
+        if !matches!(parsed.scheme(), "http" | "https") {
+            return false;
+        }
+        // NOTE: Signed URLs are bound to the GET method, so a ranged GET is issued instead of HEAD.
+        match VERIFICATION_CLIENT
+            .get(parsed)
+            .header(reqwest::header::RANGE, "bytes=0-0")
+            .send()
+            .await
+        {
+            Ok(response) => response.status().is_success(),
+            Err(_) => false,
+        }
+    }
 }
 
 #[cfg(test)]
@@ -140,6 +620,500 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_azure_url() {
+        let container = testutils::rand::string(10).to_lowercase();
+        let account = testutils::rand::string(10).to_lowercase();
+        let path = testutils::rand::string(10);
+        for (scheme, endpoint) in [("wasbs", "blob"), ("abfss", "dfs")] {
+            let url = format!(
+                "{}://{}@{}.{}.core.windows.net/{}",
+                scheme, container, account, endpoint, path
+            );
+            let provider = Platform::from_str(&url).expect("should parse azure url properly");
+            if let Platform::Azure {
+                url: parsed_url,
+                account: parsed_account,
+                container: parsed_container,
+                path: parsed_path,
+            } = provider
+            {
+                assert_eq!(parsed_url, url);
+                assert_eq!(parsed_account, account);
+                assert_eq!(parsed_container, container);
+                assert_eq!(parsed_path, path);
+            } else {
+                panic!("should be parsed as Azure url");
+            }
+        }
+    }
+
+    #[test]
+    fn test_r2_url() {
+        let account_id = testutils::rand::string(10).to_lowercase();
+        let bucket = testutils::rand::string(10).to_lowercase();
+        let path = testutils::rand::string(10);
+        let url = format!("r2://{}@{}/{}", bucket, account_id, path);
+        let provider = Platform::from_str(&url).expect("should parse r2 url properly");
+        if let Platform::R2 {
+            url: parsed_url,
+            account_id: parsed_account_id,
+            bucket: parsed_bucket,
+            path: parsed_path,
+        } = provider
+        {
+            assert_eq!(parsed_url, url);
+            assert_eq!(parsed_account_id, account_id);
+            assert_eq!(parsed_bucket, bucket);
+            assert_eq!(parsed_path, path);
+        } else {
+            panic!("should be parsed as R2 url");
+        }
+    }
+
+    #[test]
+    fn test_r2_sign() {
+        let creds = AWS::new(
+            testutils::rand::string(20),
+            testutils::rand::string(40),
+            None,
+            None,
+        );
+        let account_id = testutils::rand::string(10).to_lowercase();
+        let bucket = testutils::rand::string(10).to_lowercase();
+        let path = testutils::rand::string(10);
+        let url = Utility::sign_r2(
+            &creds,
+            &account_id,
+            &bucket,
+            &path,
+            &300,
+            &SigningStyle::Path,
+            None,
+        )
+        .expect("R2 url should be signed properly");
+        assert_eq!(url.scheme(), "https");
+        assert_eq!(
+            url.host_str(),
+            Some(format!("{}.r2.cloudflarestorage.com", account_id).as_str())
+        );
+        assert_eq!(url.path(), format!("/{}/{}", bucket, path));
+        let url = Utility::sign_r2(
+            &creds,
+            &account_id,
+            &bucket,
+            &path,
+            &300,
+            &SigningStyle::VirtualHost,
+            None,
+        )
+        .expect("R2 url should be signed properly");
+        assert_eq!(
+            url.host_str(),
+            Some(format!("{}.{}.r2.cloudflarestorage.com", bucket, account_id).as_str())
+        );
+        assert_eq!(url.path(), format!("/{}", path));
+    }
+
+    #[test]
+    fn test_aws_sign_styles() {
+        let creds = AWS::new(
+            testutils::rand::string(20),
+            testutils::rand::string(40),
+            None,
+            None,
+        );
+        let bucket = testutils::rand::string(10).to_lowercase();
+        let path = testutils::rand::string(10);
+        let region = Region::UsWest2;
+        let virtual_host = Utility::sign_aws(
+            &creds,
+            &region,
+            &bucket,
+            &path,
+            &300,
+            &SigningStyle::VirtualHost,
+            None,
+        )
+        .expect("AWS url should be signed properly");
+        let path_style = Utility::sign_aws(
+            &creds,
+            &region,
+            &bucket,
+            &path,
+            &300,
+            &SigningStyle::Path,
+            None,
+        )
+        .expect("AWS url should be signed properly");
+        assert_eq!(
+            virtual_host.host_str(),
+            Some(format!("{}.s3.us-west-2.amazonaws.com", bucket).as_str())
+        );
+        assert_eq!(virtual_host.path(), format!("/{}", path));
+        assert_eq!(path_style.host_str(), Some("s3.us-west-2.amazonaws.com"));
+        assert_eq!(path_style.path(), format!("/{}/{}", bucket, path));
+        for url in [&virtual_host, &path_style] {
+            assert!(url.query_pairs().any(|(k, _)| k == "X-Amz-Signature"));
+        }
+        let dotted = format!("{}.{}", bucket, testutils::rand::string(5).to_lowercase());
+        let url = Utility::sign_aws(
+            &creds,
+            &region,
+            &dotted,
+            &path,
+            &300,
+            &SigningStyle::VirtualHost,
+            None,
+        )
+        .expect("AWS url should be signed properly");
+        assert_eq!(url.host_str(), Some("s3.us-west-2.amazonaws.com"));
+    }
+
+    #[test]
+    fn test_signing_style() {
+        assert_eq!(
+            SigningStyle::from_str("virtual_host").expect("style should be parsed"),
+            SigningStyle::VirtualHost
+        );
+        assert_eq!(
+            SigningStyle::from_str("PATH").expect("style should be parsed"),
+            SigningStyle::Path
+        );
+        assert!(SigningStyle::from_str("v2").is_err());
+        let bucket = testutils::rand::string(10);
+        let mut credentials = BucketCredentials::default();
+        assert_eq!(
+            credentials.signing_style_for(&bucket),
+            SigningStyle::VirtualHost
+        );
+        credentials
+            .signing_styles
+            .insert(bucket.clone(), SigningStyle::Path);
+        assert_eq!(credentials.signing_style_for(&bucket), SigningStyle::Path);
+    }
+
+    #[test]
+    fn test_azure_sign() {
+        let azure = AzureCredentials {
+            account: testutils::rand::string(10).to_lowercase(),
+            key: testutils::rand::string(32).into_bytes(),
+        };
+        let container = testutils::rand::string(10).to_lowercase();
+        let path = testutils::rand::string(10);
+        let url = Utility::sign_azure(&azure, &container, &path, &300)
+            .expect("Azure url should be signed properly");
+        assert_eq!(url.scheme(), "https");
+        assert_eq!(
+            url.host_str(),
+            Some(format!("{}.blob.core.windows.net", azure.account).as_str())
+        );
+        assert_eq!(url.path(), format!("/{}/{}", container, path));
+        let pairs: std::collections::HashMap<_, _> = url.query_pairs().into_owned().collect();
+        assert_eq!(pairs.get("sr").map(String::as_str), Some("b"));
+        assert_eq!(pairs.get("sp").map(String::as_str), Some("r"));
+        assert!(pairs.contains_key("se"));
+        assert!(pairs.contains_key("sig"));
+    }
+
+    #[test]
+    fn test_aws_sign_custom_endpoint() {
+        let creds = AWS::new(
+            testutils::rand::string(20),
+            testutils::rand::string(40),
+            None,
+            None,
+        );
+        let region = Utility::aws_region("http://127.0.0.1:9000");
+        let bucket = testutils::rand::string(10).to_lowercase();
+        let path = testutils::rand::string(10);
+        let url = Utility::sign_aws(
+            &creds,
+            &region,
+            &bucket,
+            &path,
+            &300,
+            &SigningStyle::VirtualHost,
+            None,
+        )
+        .expect("AWS url should be signed properly");
+        assert_eq!(url.host_str(), Some("127.0.0.1"));
+        assert_eq!(url.port(), Some(9000));
+        assert!(!matches!(Utility::aws_region(""), Region::Custom { .. }));
+    }
+
+    #[test]
+    fn test_response_overrides_for_file() {
+        let overrides = ResponseOverrides::for_file(
+            "events",
+            "date=2023-12-01/part-00000-c0ffee.snappy.parquet",
+        );
+        assert_eq!(
+            overrides.content_disposition.as_deref(),
+            Some(r#"attachment; filename="events_part-00000-c0ffee.snappy.parquet""#)
+        );
+        assert_eq!(
+            overrides.content_type.as_deref(),
+            Some("application/vnd.apache.parquet")
+        );
+        let overrides = ResponseOverrides::for_file(r#"a "quoted" table"#, "file.json");
+        assert_eq!(
+            overrides.content_disposition.as_deref(),
+            Some(r#"attachment; filename="a__quoted__table_file.json""#)
+        );
+        assert_eq!(
+            overrides.content_type.as_deref(),
+            Some("application/octet-stream")
+        );
+    }
+
+    #[test]
+    fn test_aws_sign_response_overrides() {
+        let creds = AWS::new(
+            testutils::rand::string(20),
+            testutils::rand::string(40),
+            None,
+            None,
+        );
+        let region = Region::default();
+        let bucket = testutils::rand::string(10).to_lowercase();
+        let table = testutils::rand::string(10);
+        let path = format!("{}/part-00000.parquet", testutils::rand::string(10));
+        let overrides = ResponseOverrides::for_file(&table, &path);
+        for style in [SigningStyle::VirtualHost, SigningStyle::Path] {
+            let url = Utility::sign_aws(
+                &creds,
+                &region,
+                &bucket,
+                &path,
+                &300,
+                &style,
+                Some(&overrides),
+            )
+            .expect("AWS url should be signed properly");
+            let params: HashMap<String, String> = url.query_pairs().into_owned().collect();
+            assert_eq!(
+                params.get("response-content-disposition"),
+                Some(&format!(
+                    r#"attachment; filename="{}_part-00000.parquet""#,
+                    table
+                ))
+            );
+            assert_eq!(
+                params.get("response-content-type").map(String::as_str),
+                Some("application/vnd.apache.parquet")
+            );
+        }
+        let url = Utility::sign_aws(
+            &creds,
+            &region,
+            &bucket,
+            &path,
+            &300,
+            &SigningStyle::default(),
+            None,
+        )
+        .expect("AWS url should be signed properly");
+        assert!(!url
+            .query_pairs()
+            .any(|(key, _)| key.starts_with("response-content-")));
+    }
+
+    #[test]
+    fn test_file_url() {
+        let path = format!(
+            "/{}/{}",
+            testutils::rand::string(10),
+            testutils::rand::string(10)
+        );
+        let url = format!("file://{}", path);
+        let provider = Platform::from_str(&url).expect("should parse file url properly");
+        if let Platform::File {
+            url: parsed_url,
+            path: parsed_path,
+        } = provider
+        {
+            assert_eq!(parsed_url, url);
+            assert_eq!(parsed_path, path);
+        } else {
+            panic!("should be parsed as file url");
+        }
+    }
+
+    #[test]
+    fn test_file_sign() {
+        let endpoint = format!("http://{}:8080", testutils::rand::string(10).to_lowercase());
+        let path = format!(
+            "/{}/{}",
+            testutils::rand::string(10),
+            testutils::rand::string(10)
+        );
+        let duration = testutils::rand::u64(60, 3600);
+        let url = Utility::sign_file(&endpoint, &path, &duration).expect("should sign file url");
+        assert!(url
+            .as_str()
+            .starts_with(&format!("{}{}", endpoint, LOCAL_PATH)));
+        let query: std::collections::HashMap<_, _> = url.query_pairs().into_owned().collect();
+        assert_eq!(query["path"], path);
+        let expires: i64 = query["expires"]
+            .parse()
+            .expect("expires should be a number");
+        assert!(Utility::verify_file(&path, expires, &query["sig"]).is_ok());
+        assert!(Utility::verify_file(&format!("{}x", path), expires, &query["sig"]).is_err());
+        assert!(Utility::verify_file(&path, expires + 1, &query["sig"]).is_err());
+        assert!(Utility::verify_file(&path, expires, &testutils::rand::string(10)).is_err());
+    }
+
+    #[test]
+    fn test_file_sign_expired() {
+        let path = format!("/{}", testutils::rand::string(10));
+        let expires = Utc::now().timestamp() - 1;
+        let secret = Utility::local_url_secret()
+            .expect("local url secret should be configured")
+            .expect("local tables should be enabled");
+        let signature = URL_SAFE_NO_PAD.encode(
+            Utility::file_mac(&secret, &path, expires)
+                .finalize()
+                .into_bytes(),
+        );
+        assert!(Utility::verify_file(&path, expires, &signature).is_err());
+    }
+
+    #[test]
+    fn test_file_sign_with_other_secret() {
+        let path = format!("/{}", testutils::rand::string(10));
+        let expires = Utc::now().timestamp() + 60;
+        let signature = URL_SAFE_NO_PAD.encode(
+            Utility::file_mac(&testutils::rand::string(20), &path, expires)
+                .finalize()
+                .into_bytes(),
+        );
+        assert!(Utility::verify_file(&path, expires, &signature).is_err());
+    }
+
+    #[test]
+    fn test_bucket_credentials() {
+        let new_credentials = || {
+            AWS::new(
+                testutils::rand::string(20).to_uppercase(),
+                testutils::rand::string(40),
+                None,
+                None,
+            )
+        };
+        let (first, second) = (testutils::rand::string(10), testutils::rand::string(10));
+        let default = new_credentials();
+        let mut credentials = BucketCredentials::default();
+        credentials.aws.insert(first.clone(), new_credentials());
+        credentials.aws.insert(second.clone(), new_credentials());
+        let region = Region::UsEast1;
+        for bucket in [&first, &second] {
+            let keys = credentials
+                .aws_for(bucket, Some(&default))
+                .expect("mapped bucket should be resolved");
+            assert_eq!(
+                keys.aws_access_key_id(),
+                credentials.aws[bucket].aws_access_key_id()
+            );
+            let url = Utility::sign_aws(
+                keys,
+                &region,
+                bucket,
+                &testutils::rand::string(10),
+                &60,
+                &SigningStyle::default(),
+                None,
+            )
+            .expect("should sign AWS url");
+            let credential = url
+                .query_pairs()
+                .find(|(k, _)| k == "X-Amz-Credential")
+                .map(|(_, v)| v.to_string())
+                .expect("signed url should carry credential");
+            assert!(credential.starts_with(credentials.aws[bucket].aws_access_key_id()));
+        }
+        let unmapped = testutils::rand::string(11);
+        assert_eq!(
+            credentials
+                .aws_for(&unmapped, Some(&default))
+                .map(|keys| keys.aws_access_key_id()),
+            Some(default.aws_access_key_id())
+        );
+        assert!(BucketCredentials::default()
+            .aws_for(&unmapped, None)
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_exists_of_unsigned_url() {
+        assert!(
+            !Utility::exists(format!(
+                "s3://{}/{}",
+                testutils::rand::string(10),
+                testutils::rand::string(10)
+            ))
+            .await
+        );
+        assert!(!Utility::exists(testutils::rand::string(10)).await);
+    }
+
+    #[test]
+    fn test_public_url() {
+        let bucket = testutils::rand::string(10).to_lowercase();
+        let path = format!(
+            "{}/{}.parquet",
+            testutils::rand::string(10),
+            testutils::rand::string(10)
+        );
+        let url = Utility::public_gcp(&bucket, &path).expect("GCP public url should be built");
+        assert_eq!(
+            url.as_str(),
+            format!("https://storage.googleapis.com/{}/{}", bucket, path)
+        );
+        assert!(url.query().is_none());
+        let url = Utility::public_aws(&Region::UsWest2, &bucket, &path)
+            .expect("AWS public url should be built");
+        assert_eq!(
+            url.as_str(),
+            format!("https://{}.s3.us-west-2.amazonaws.com/{}", bucket, path)
+        );
+        assert!(url.query().is_none());
+        let url = Utility::public_aws(
+            &Utility::aws_region("http://127.0.0.1:9000"),
+            &bucket,
+            &path,
+        )
+        .expect("AWS public url should be built");
+        assert_eq!(
+            url.as_str(),
+            format!("http://127.0.0.1:9000/{}/{}", bucket, path)
+        );
+        let mut credentials = BucketCredentials::default();
+        credentials.public.insert(bucket.clone());
+        assert!(credentials.is_public(&bucket));
+        assert!(!credentials.is_public(&testutils::rand::string(11)));
+    }
+
+    #[test]
+    fn test_guard() {
+        let signer = |path: &str| {
+            Url::parse(&format!("http://{}/{}", testutils::rand::string(10), path))
+                .expect("fake signer should return valid url")
+        };
+        let path = testutils::rand::string(10);
+        let url = Utility::guard(signer(&path), &SchemeGuard::Off)
+            .expect("http url should be passed through");
+        assert_eq!(url.scheme(), "http");
+        let url = Utility::guard(signer(&path), &SchemeGuard::Upgrade)
+            .expect("http url should be upgraded");
+        assert_eq!(url.scheme(), "https");
+        assert_eq!(url.path(), format!("/{}", path));
+        assert!(Utility::guard(signer(&path), &SchemeGuard::Reject).is_err());
+        let url = Url::parse(&format!("https://{}/{}", testutils::rand::string(10), path))
+            .expect("https url should be valid");
+        assert!(Utility::guard(url, &SchemeGuard::Reject).is_ok());
+    }
+
     //#[tokio::test]
     async fn test_aws_sign_local() {
         let aws_profile = std::env::var("AWS_PROFILE").expect("AWS profile should be specified");
@@ -152,7 +1126,15 @@ mod tests {
         if let Ok(Platform::Aws { bucket, path, .. }) =
             Platform::from_str("s3://delta-sharing-test/covid")
         {
-            if let Ok(url) = Utility::sign_aws(&creds, &bucket, &path, &300) {
+            if let Ok(url) = Utility::sign_aws(
+                &creds,
+                &Region::default(),
+                &bucket,
+                &path,
+                &300,
+                &SigningStyle::default(),
+                None,
+            ) {
                 println!("{:?}", url);
             }
         } else {
@@ -160,6 +1142,33 @@ mod tests {
         };
     }
 
+    //#[tokio::test]
+    async fn test_r2_sign_local() {
+        let creds =
+            bootstrap::new_r2_credentials().expect("R2 credentials should be created properly");
+        if let Ok(Platform::R2 {
+            account_id,
+            bucket,
+            path,
+            ..
+        }) = Platform::from_str("r2://delta-sharing-test@account-id/covid")
+        {
+            if let Ok(url) = Utility::sign_r2(
+                &creds,
+                &account_id,
+                &bucket,
+                &path,
+                &300,
+                &SigningStyle::default(),
+                None,
+            ) {
+                println!("{:?}", url);
+            }
+        } else {
+            panic!("failed to parse R2 url");
+        };
+    }
+
     //#[tokio::test]
     async fn test_gcp_sign_local() {
         let path = format!(
@@ -176,7 +1185,7 @@ mod tests {
         if let Ok(Platform::Gcp { bucket, path, .. }) =
             Platform::from_str("gs://delta-sharing-test/covid")
         {
-            if let Ok(url) = Utility::sign_gcp(&sa, &bucket, &path, &300) {
+            if let Ok(url) = Utility::sign_gcp(&sa, &bucket, &path, &300, None) {
                 println!("{:?}", url);
             }
         } else {
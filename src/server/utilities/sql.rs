@@ -292,6 +292,41 @@ impl Utility {
         }
     }
 
+    pub fn filter_partition(
+        filter: &PartitionFilter,
+        value: Option<&String>,
+        schema: &Schema,
+    ) -> bool {
+        let Some(value) = value else {
+            return filter.predicate == Predicate::IsNull;
+        };
+        // NOTE: The server may try its best to filter files in a BEST EFFORT mode.
+        let Ok(field) = schema.get_field_with_name(&filter.column) else {
+            return true;
+        };
+        // NOTE: The server may try its best to filter files in a BEST EFFORT mode.
+        let Ok(column_type) = ValueType::try_from(field.get_type()) else {
+            return true;
+        };
+        match column_type {
+            ValueType::Boolean => {
+                // NOTE: The server may try its best to filter files in a BEST EFFORT mode.
+                let Ok(ref value) = value.parse::<bool>() else {
+                    return true;
+                };
+                Self::check(&filter.predicate, value, value, &0)
+            }
+            ValueType::String | ValueType::Date => Self::check(&filter.predicate, value, value, &0),
+            ValueType::Int | ValueType::Long => {
+                // NOTE: The server may try its best to filter files in a BEST EFFORT mode.
+                let Ok(ref value) = value.parse::<i64>() else {
+                    return true;
+                };
+                Self::check(&filter.predicate, value, value, &0)
+            }
+        }
+    }
+
     pub fn filter(filter: &PartitionFilter, stats: &Stats, schema: &Schema) -> bool {
         // NOTE: The server may try its best to filter files in a BEST EFFORT mode.
         let Some(null_count) = stats.null_count.get(&filter.column) else {
@@ -0,0 +1,59 @@
+use anyhow::Result;
+use utoipa::ToSchema;
+use validator::ValidationErrors;
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ValidationError {
+    pub fields: Vec<FieldError>,
+}
+
+impl ValidationError {
+    pub fn check<T>(&mut self, field: &str, result: Result<T>) -> Option<T> {
+        match result {
+            Ok(value) => Some(value),
+            Err(e) => {
+                self.fields.push(FieldError {
+                    field: field.to_string(),
+                    message: Utility::describe(&e),
+                });
+                None
+            }
+        }
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        self.fields.iter().map(|f| f.field.as_str()).collect()
+    }
+}
+
+impl std::fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid field(s): {}", self.names().join(", "))
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+pub struct Utility;
+
+impl Utility {
+    fn describe(error: &anyhow::Error) -> String {
+        let Some(errors) = error.downcast_ref::<ValidationErrors>() else {
+            return error.to_string();
+        };
+        errors
+            .field_errors()
+            .values()
+            .flat_map(|errors| errors.iter())
+            .map(|e| format!("{} constraint is not satisfied", e.code))
+            .collect::<Vec<String>>()
+            .join(", ")
+    }
+}
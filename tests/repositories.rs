@@ -6,9 +6,16 @@ use anyhow::Result;
 use sqlx::PgPool;
 
 use delta_sharing::server::AccountRepository;
+use delta_sharing::server::Role;
 use delta_sharing::server::SchemaRepository;
+use delta_sharing::server::ShareDefaultTtl;
+use delta_sharing::server::ShareEntity;
 use delta_sharing::server::ShareRepository;
 use delta_sharing::server::TableRepository;
+use delta_sharing::server::TokenEntity;
+use delta_sharing::server::TokenIdempotencyKey;
+use delta_sharing::server::TokenRepository;
+use delta_sharing::server::TokenValue;
 
 use common::{create_account, create_schema, create_share, create_table, create_token};
 
@@ -33,6 +40,7 @@ async fn test_account_create_and_select_by_name(pool: PgPool) -> Result<()> {
     assert_eq!(&fetched.password, account.password().as_str());
     assert_eq!(&fetched.namespace, account.namespace().as_str());
     assert_eq!(&fetched.ttl, account.ttl().as_i64());
+    assert_eq!(&fetched.role, account.role());
 
     tx.rollback()
         .await
@@ -40,6 +48,95 @@ async fn test_account_create_and_select_by_name(pool: PgPool) -> Result<()> {
     Ok(())
 }
 
+#[sqlx::test]
+async fn test_account_partial_update(pool: PgPool) -> Result<()> {
+    let mut tx = pool
+        .begin()
+        .await
+        .expect("transaction should be started properly");
+    let mut account = create_account(&mut tx)
+        .await
+        .expect("new account should be created");
+    let email = testutils::rand::email();
+    account
+        .update(None, Some(email.clone()), None, None, None)
+        .expect("account should be updated properly");
+    AccountRepository::upsert(&account, &mut tx)
+        .await
+        .expect("updated account should be saved");
+    let fetched = AccountRepository::select_by_name(account.name(), &mut tx)
+        .await
+        .expect("updated account should be found")
+        .expect("updated account should exist");
+    assert_eq!(&fetched.id, account.id().as_uuid());
+    assert_eq!(&fetched.email, &email);
+    assert_eq!(&fetched.namespace, account.namespace().as_str());
+    assert_eq!(&fetched.ttl, account.ttl().as_i64());
+    tx.rollback()
+        .await
+        .expect("rollback should be done properly");
+    Ok(())
+}
+
+#[sqlx::test]
+async fn test_account_promotion(pool: PgPool) -> Result<()> {
+    let mut tx = pool
+        .begin()
+        .await
+        .expect("transaction should be started properly");
+    let mut account = create_account(&mut tx)
+        .await
+        .expect("new account should be created");
+    let fetched = AccountRepository::select_by_name(account.name(), &mut tx)
+        .await
+        .expect("created account should be found")
+        .expect("created account should exist");
+    assert_eq!(fetched.role, Role::Guest);
+    account
+        .update(None, None, None, None, Some(String::from("admin")))
+        .expect("account should be promoted properly");
+    AccountRepository::upsert(&account, &mut tx)
+        .await
+        .expect("promoted account should be saved");
+    let fetched = AccountRepository::select_by_name(account.name(), &mut tx)
+        .await
+        .expect("promoted account should be found")
+        .expect("promoted account should exist");
+    assert_eq!(fetched.role, Role::Admin);
+    tx.rollback()
+        .await
+        .expect("rollback should be done properly");
+    Ok(())
+}
+
+#[sqlx::test]
+async fn test_account_rename_collision(pool: PgPool) -> Result<()> {
+    let mut tx = pool
+        .begin()
+        .await
+        .expect("transaction should be started properly");
+    let existing = create_account(&mut tx)
+        .await
+        .expect("new account should be created");
+    let mut account = create_account(&mut tx)
+        .await
+        .expect("new account should be created");
+    account
+        .update(Some(existing.name().to_string()), None, None, None, None)
+        .expect("account should be updated properly");
+    let error = AccountRepository::upsert(&account, &mut tx)
+        .await
+        .expect_err("renaming to an existing name should be rejected");
+    let Ok(sqlx::Error::Database(error)) = error.downcast::<sqlx::Error>() else {
+        panic!("database error should be returned");
+    };
+    assert_eq!(error.code().as_deref(), Some("23505"));
+    tx.rollback()
+        .await
+        .expect("rollback should be done properly");
+    Ok(())
+}
+
 #[sqlx::test]
 async fn test_token_create(pool: PgPool) -> Result<()> {
     let mut tx = pool
@@ -58,6 +155,144 @@ async fn test_token_create(pool: PgPool) -> Result<()> {
     Ok(())
 }
 
+#[sqlx::test]
+async fn test_token_create_and_select_by_value_hash(pool: PgPool) -> Result<()> {
+    let mut tx = pool
+        .begin()
+        .await
+        .expect("transaction should be started properly");
+    let account = create_account(&mut tx)
+        .await
+        .expect("new account should be created");
+    let token = create_token(account.id(), &mut tx)
+        .await
+        .expect("new token should be created");
+    let fetched = TokenRepository::select_by_value_hash(token.value_hash(), &mut tx)
+        .await
+        .expect("token should be selected properly")
+        .expect("created token should be found");
+    assert_eq!(&fetched.id, token.id().as_uuid());
+    let revoked = TokenValue::new(testutils::rand::string(10))
+        .expect("token value should be created properly");
+    let fetched = TokenRepository::select_by_value_hash(&revoked.hash(), &mut tx)
+        .await
+        .expect("token should be selected properly");
+    assert!(fetched.is_none());
+    tx.rollback()
+        .await
+        .expect("rollback should be done properly");
+    Ok(())
+}
+
+#[sqlx::test]
+async fn test_token_value_is_stored_hashed(pool: PgPool) -> Result<()> {
+    let mut tx = pool
+        .begin()
+        .await
+        .expect("transaction should be started properly");
+    let account = create_account(&mut tx)
+        .await
+        .expect("new account should be created");
+    let issued = testutils::rand::string(20);
+    let token = TokenEntity::new(
+        None,
+        testutils::rand::email(),
+        Role::Guest,
+        issued.clone(),
+        account.id().to_uuid().to_string(),
+    )
+    .expect("token should be created properly");
+    TokenRepository::upsert(&token, &mut tx)
+        .await
+        .expect("token should be stored properly");
+    let (value, value_hash): (Option<String>, String) =
+        sqlx::query_as(r#"SELECT "value", value_hash FROM token WHERE id = $1"#)
+            .bind(token.id().as_uuid())
+            .fetch_one(&mut *tx)
+            .await
+            .expect("token should be selected properly");
+    assert!(value.is_none());
+    assert_ne!(value_hash, issued);
+    let value = TokenValue::new(issued).expect("token value should be created properly");
+    assert_eq!(value_hash, value.hash().to_string());
+    tx.rollback()
+        .await
+        .expect("rollback should be done properly");
+    Ok(())
+}
+
+#[sqlx::test]
+async fn test_token_save_all_rolls_back_on_failure(pool: PgPool) -> Result<()> {
+    let mut conn = pool
+        .acquire()
+        .await
+        .expect("connection should be acquired properly");
+    let account = create_account(&mut conn)
+        .await
+        .expect("new account should be created");
+    let new_token = |created_by: String| {
+        TokenEntity::new(
+            None,
+            testutils::rand::email(),
+            Role::Guest,
+            testutils::rand::string(10),
+            created_by,
+        )
+        .expect("token should be created properly")
+    };
+    // NOTE: The second token refers to a missing account and violates the foreign key.
+    let tokens = vec![
+        new_token(account.id().to_uuid().to_string()),
+        new_token(testutils::rand::uuid()),
+    ];
+    assert!(TokenEntity::save_all(&tokens, &pool).await.is_err());
+    let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM token WHERE created_by = $1")
+        .bind(account.id().as_uuid())
+        .fetch_one(&mut *conn)
+        .await
+        .expect("tokens should be counted properly");
+    assert_eq!(count, 0);
+    Ok(())
+}
+
+#[sqlx::test]
+async fn test_token_create_and_select_by_idempotency_key(pool: PgPool) -> Result<()> {
+    let mut tx = pool
+        .begin()
+        .await
+        .expect("transaction should be started properly");
+    let account = create_account(&mut tx)
+        .await
+        .expect("new account should be created");
+    let mut token = create_token(account.id(), &mut tx)
+        .await
+        .expect("new token should be created");
+    let key = TokenIdempotencyKey::new(testutils::rand::string(10))
+        .expect("idempotency key should be created");
+    token.set_idempotency_key(Some(key.clone()));
+    TokenRepository::upsert(&token, &mut tx)
+        .await
+        .expect("token should be updated");
+    let fetched = TokenRepository::select_by_idempotency_key(&key, 60, &mut tx)
+        .await
+        .expect("token should be selected")
+        .expect("token issued with the key should be found");
+    assert_eq!(&fetched.id, token.id().as_uuid());
+    assert_eq!(&fetched.value_hash, token.value_hash().as_str());
+    let other = TokenIdempotencyKey::new(testutils::rand::string(10))
+        .expect("idempotency key should be created");
+    assert!(
+        TokenRepository::select_by_idempotency_key(&other, 60, &mut tx)
+            .await
+            .expect("token should be selected")
+            .is_none()
+    );
+    tx.rollback()
+        .await
+        .expect("rollback should be done properly");
+    Ok(())
+}
+
 #[sqlx::test]
 async fn test_share_create_and_select_by_name(pool: PgPool) -> Result<()> {
     let mut tx = pool
@@ -86,6 +321,73 @@ async fn test_share_create_and_select_by_name(pool: PgPool) -> Result<()> {
     Ok(())
 }
 
+#[sqlx::test]
+async fn test_share_create_with_default_ttl(pool: PgPool) -> Result<()> {
+    let mut tx = pool
+        .begin()
+        .await
+        .expect("transaction should be started properly");
+    let account = create_account(&mut tx)
+        .await
+        .expect("new account should be created");
+    let mut share = ShareEntity::new(
+        testutils::rand::uuid(),
+        testutils::rand::string(10),
+        account.id().to_uuid().to_string(),
+    )
+    .expect("share should be created properly");
+    let default_ttl = testutils::rand::i64(1, 100000);
+    share.set_default_ttl(Some(
+        ShareDefaultTtl::new(default_ttl).expect("default ttl should be created properly"),
+    ));
+    ShareRepository::upsert(&share, &mut tx)
+        .await
+        .expect("share should be stored properly");
+    let fetched = ShareRepository::select_by_name(share.name(), &mut tx)
+        .await
+        .expect("created share should be found")
+        .expect("created share should exist");
+    assert_eq!(fetched.default_ttl, Some(default_ttl));
+    tx.rollback()
+        .await
+        .expect("rollback should be done properly");
+    Ok(())
+}
+
+#[sqlx::test]
+async fn test_share_create_name_collision(pool: PgPool) -> Result<()> {
+    let mut tx = pool
+        .begin()
+        .await
+        .expect("transaction should be started properly");
+    let account = create_account(&mut tx)
+        .await
+        .expect("new account should be created");
+    let other = create_account(&mut tx)
+        .await
+        .expect("new account should be created");
+    let existing = create_share(account.id(), &mut tx)
+        .await
+        .expect("new share should be created");
+    let share = ShareEntity::new(
+        testutils::rand::uuid(),
+        existing.name().to_string(),
+        other.id().to_uuid().to_string(),
+    )
+    .expect("share should be created properly");
+    let error = ShareRepository::upsert(&share, &mut tx)
+        .await
+        .expect_err("registering an existing name should be rejected");
+    let Ok(sqlx::Error::Database(error)) = error.downcast::<sqlx::Error>() else {
+        panic!("database error should be returned");
+    };
+    assert_eq!(error.code().as_deref(), Some("23505"));
+    tx.rollback()
+        .await
+        .expect("rollback should be done properly");
+    Ok(())
+}
+
 #[sqlx::test]
 async fn test_schema_create_and_select_by_name(pool: PgPool) -> Result<()> {
     let mut tx = pool
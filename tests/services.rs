@@ -7,12 +7,29 @@ use std::cmp::min;
 use anyhow::Result;
 use sqlx::PgPool;
 
+use delta_sharing::server::AccountEntity;
+use delta_sharing::server::AccountRepository;
 use delta_sharing::server::AccountService;
+use delta_sharing::server::AuditService;
+use delta_sharing::server::AuditTrace;
+use delta_sharing::server::PageCursor;
+use delta_sharing::server::PageDirection;
+use delta_sharing::server::PageOrderBy;
+use delta_sharing::server::PageOrdering;
+use delta_sharing::server::RefreshTokenExchange;
+use delta_sharing::server::RefreshTokenService;
+use delta_sharing::server::RevocationService;
+use delta_sharing::server::Role;
 use delta_sharing::server::SchemaService;
 use delta_sharing::server::ShareService;
 use delta_sharing::server::TableService;
+use delta_sharing::server::TokenCollector;
+use delta_sharing::server::TokenEntity;
+use delta_sharing::server::TokenRepository;
+use delta_sharing::server::TokenService;
+use delta_sharing::server::TokenValue;
 
-use common::{create_account, create_schema, create_share, create_table};
+use common::{create_account, create_schema, create_share, create_table, create_token};
 
 #[sqlx::test]
 async fn test_account_create_and_query_with_default_limit(pool: PgPool) -> Result<()> {
@@ -26,7 +43,7 @@ async fn test_account_create_and_query_with_default_limit(pool: PgPool) -> Resul
             .await
             .expect("new account should be created");
     }
-    let fetched = AccountService::query(None, None, &mut tx)
+    let fetched = AccountService::query(None, &PageOrdering::default(), None, &mut tx)
         .await
         .expect("created account should be listed");
     assert_eq!(records as usize, fetched.len());
@@ -49,7 +66,7 @@ async fn test_account_create_and_query_with_specified_limit(pool: PgPool) -> Res
             .expect("new account should be created");
     }
     let limit = testutils::rand::i64(0, 20);
-    let fetched = AccountService::query(Some(&limit), None, &mut tx)
+    let fetched = AccountService::query(Some(&limit), &PageOrdering::default(), None, &mut tx)
         .await
         .expect("created account should be listed");
     assert_eq!(min(records, limit) as usize, fetched.len());
@@ -85,6 +102,53 @@ async fn test_account_create_and_query_by_name(pool: PgPool) -> Result<()> {
     Ok(())
 }
 
+#[sqlx::test]
+async fn test_account_search_by_name_prefix(pool: PgPool) -> Result<()> {
+    let mut tx = pool
+        .begin()
+        .await
+        .expect("transaction should be started properly");
+    // NOTE: The underscore must be matched literally rather than as a LIKE wildcard.
+    let stem = testutils::rand::string(5);
+    let prefix = format!("{}_", stem);
+    let names = vec![
+        format!("{}{}", prefix, testutils::rand::string(5)),
+        format!("{}{}", prefix, testutils::rand::string(5)),
+        format!("{}{}", testutils::rand::string(5), prefix),
+        format!("{}{}", stem, testutils::rand::string(6)),
+    ];
+    for name in &names {
+        let account = AccountEntity::new(
+            testutils::rand::uuid(),
+            name.clone(),
+            testutils::rand::email(),
+            testutils::rand::string(10),
+            testutils::rand::string(10),
+            testutils::rand::i64(1, 100000),
+        )
+        .expect("account should be validated");
+        AccountRepository::upsert(&account, &mut tx)
+            .await
+            .expect("new account should be created");
+    }
+    let fetched = AccountService::search_by_name_prefix(&prefix, None, &mut tx)
+        .await
+        .expect("prefixed accounts should be searched");
+    let mut expected = vec![names[0].clone(), names[1].clone()];
+    expected.sort();
+    let mut fetched: Vec<String> = fetched.into_iter().map(|account| account.name).collect();
+    fetched.sort();
+    assert_eq!(fetched, expected);
+    let fetched = AccountService::search_by_name_prefix(&prefix, Some(&1), &mut tx)
+        .await
+        .expect("prefixed accounts should be searched");
+    assert_eq!(fetched.len(), 1);
+    tx.rollback()
+        .await
+        .expect("rollback should be done properly");
+    Ok(())
+}
+
 #[sqlx::test]
 async fn test_share_create_and_query_with_default_limit(pool: PgPool) -> Result<()> {
     let mut tx = pool
@@ -100,9 +164,21 @@ async fn test_share_create_and_query_with_default_limit(pool: PgPool) -> Result<
             .await
             .expect("new share should be created");
     }
-    let fetched = ShareService::query(None, None, &mut tx)
+    let other = create_account(&mut tx)
         .await
-        .expect("created share should be listed");
+        .expect("new account should be created");
+    create_share(other.id(), &mut tx)
+        .await
+        .expect("new share should be created");
+    let fetched = ShareService::query(
+        account.namespace().as_str(),
+        None,
+        &PageOrdering::default(),
+        None,
+        &mut tx,
+    )
+    .await
+    .expect("created share should be listed");
     assert_eq!(records as usize, fetched.len());
     tx.rollback()
         .await
@@ -126,9 +202,15 @@ async fn test_share_create_and_query_with_specified_limit(pool: PgPool) -> Resul
             .expect("new share should be created");
     }
     let limit = testutils::rand::i64(0, 20);
-    let fetched = ShareService::query(Some(&limit), None, &mut tx)
-        .await
-        .expect("created share should be listed");
+    let fetched = ShareService::query(
+        account.namespace().as_str(),
+        Some(&limit),
+        &PageOrdering::default(),
+        None,
+        &mut tx,
+    )
+    .await
+    .expect("created share should be listed");
     assert_eq!(min(records, limit) as usize, fetched.len());
     tx.rollback()
         .await
@@ -136,6 +218,63 @@ async fn test_share_create_and_query_with_specified_limit(pool: PgPool) -> Resul
     Ok(())
 }
 
+#[sqlx::test]
+async fn test_share_create_and_query_by_created_at_descending(pool: PgPool) -> Result<()> {
+    let mut tx = pool
+        .begin()
+        .await
+        .expect("transaction should be started properly");
+    let account = create_account(&mut tx)
+        .await
+        .expect("new account should be created");
+    let records = testutils::rand::i64(3, 10);
+    let now = chrono::Utc::now();
+    let mut expected = Vec::new();
+    for offset in 0..records {
+        let share = create_share(account.id(), &mut tx)
+            .await
+            .expect("new share should be created");
+        // NOTE: Rows created in one transaction share CURRENT_TIMESTAMP, so the
+        //       creation times are spread explicitly.
+        let created_at = now - chrono::Duration::seconds(records - offset);
+        sqlx::query("UPDATE share SET created_at = $1 WHERE id = $2")
+            .bind(created_at)
+            .bind(share.id().to_uuid())
+            .execute(&mut *tx)
+            .await
+            .expect("creation time should be updated");
+        expected.push(share.name().as_str().to_string());
+    }
+    expected.reverse();
+    let ordering = PageOrdering {
+        order_by: PageOrderBy::CreatedAt,
+        direction: PageDirection::Desc,
+    };
+    let fetched = ShareService::query(account.namespace().as_str(), None, &ordering, None, &mut tx)
+        .await
+        .expect("created shares should be listed");
+    let names: Vec<String> = fetched.iter().map(|share| share.name.clone()).collect();
+    assert_eq!(names, expected);
+    let limit = testutils::rand::i64(1, records);
+    let next = &fetched[limit as usize];
+    let cursor = PageCursor::new(next.name.clone(), next.created_at);
+    let fetched = ShareService::query(
+        account.namespace().as_str(),
+        None,
+        &ordering,
+        Some(&cursor),
+        &mut tx,
+    )
+    .await
+    .expect("remaining shares should be listed");
+    let names: Vec<String> = fetched.iter().map(|share| share.name.clone()).collect();
+    assert_eq!(names, expected[limit as usize..]);
+    tx.rollback()
+        .await
+        .expect("rollback should be done properly");
+    Ok(())
+}
+
 #[sqlx::test]
 async fn test_share_create_and_query_by_name(pool: PgPool) -> Result<()> {
     let mut tx = pool
@@ -181,9 +320,15 @@ async fn test_schema_create_and_query_with_default_limit(pool: PgPool) -> Result
             .await
             .expect("new schema should be created");
     }
-    let fetched = SchemaService::query_by_share_name(share.name(), None, None, &mut tx)
-        .await
-        .expect("created schema should be listed");
+    let fetched = SchemaService::query_by_share_name(
+        share.name(),
+        None,
+        &PageOrdering::default(),
+        None,
+        &mut tx,
+    )
+    .await
+    .expect("created schema should be listed");
     assert_eq!(records as usize, fetched.len());
     tx.rollback()
         .await
@@ -210,9 +355,15 @@ async fn test_schema_create_and_query_with_specified_limit(pool: PgPool) -> Resu
             .expect("new schema should be created");
     }
     let limit = testutils::rand::i64(0, 20);
-    let fetched = SchemaService::query_by_share_name(share.name(), Some(&limit), None, &mut tx)
-        .await
-        .expect("created schema should be listed");
+    let fetched = SchemaService::query_by_share_name(
+        share.name(),
+        Some(&limit),
+        &PageOrdering::default(),
+        None,
+        &mut tx,
+    )
+    .await
+    .expect("created schema should be listed");
     assert_eq!(min(records, limit) as usize, fetched.len());
     tx.rollback()
         .await
@@ -373,9 +524,15 @@ async fn test_table_create_and_query_by_share_name_with_default_limit(pool: PgPo
                 .expect("new schema should be created");
         }
     }
-    let fetched = TableService::query_by_share_name(share.name(), None, None, &mut tx)
-        .await
-        .expect("created table should be listed");
+    let fetched = TableService::query_by_share_name(
+        share.name(),
+        None,
+        &PageOrdering::default(),
+        None,
+        &mut tx,
+    )
+    .await
+    .expect("created table should be listed");
     assert_eq!((num_schemas * num_tables) as usize, fetched.len());
     tx.rollback()
         .await
@@ -410,9 +567,15 @@ async fn test_table_create_and_query_by_share_name_with_specified_limit(
         }
     }
     let limit = testutils::rand::i64(0, 20);
-    let fetched = TableService::query_by_share_name(share.name(), Some(&limit), None, &mut tx)
-        .await
-        .expect("created schema should be listed");
+    let fetched = TableService::query_by_share_name(
+        share.name(),
+        Some(&limit),
+        &PageOrdering::default(),
+        None,
+        &mut tx,
+    )
+    .await
+    .expect("created schema should be listed");
     assert_eq!(min(num_schemas * num_tables, limit) as usize, fetched.len());
     tx.rollback()
         .await
@@ -447,6 +610,7 @@ async fn test_table_create_and_query_by_share_and_schema_name_with_default_limit
         share.name(),
         schema.name(),
         None,
+        &PageOrdering::default(),
         None,
         &mut tx,
     )
@@ -487,6 +651,7 @@ async fn test_table_create_and_query_by_share_and_schema_name_with_specified_lim
         share.name(),
         schema.name(),
         Some(&limit),
+        &PageOrdering::default(),
         None,
         &mut tx,
     )
@@ -498,3 +663,374 @@ async fn test_table_create_and_query_by_share_and_schema_name_with_specified_lim
         .expect("rollback should be done properly");
     Ok(())
 }
+
+#[sqlx::test]
+async fn test_token_create_and_query_by_account_name(pool: PgPool) -> Result<()> {
+    let mut tx = pool
+        .begin()
+        .await
+        .expect("transaction should be started properly");
+    let account = create_account(&mut tx)
+        .await
+        .expect("new account should be created");
+    let other = create_account(&mut tx)
+        .await
+        .expect("new account should be created");
+    let records = testutils::rand::i64(0, 20);
+    for _ in 0..records {
+        create_token(account.id(), &mut tx)
+            .await
+            .expect("new token should be created");
+    }
+    for _ in 0..testutils::rand::i64(0, 20) {
+        create_token(other.id(), &mut tx)
+            .await
+            .expect("new token should be created");
+    }
+    let limit = testutils::rand::i64(1, 20);
+    let fetched = TokenService::query_by_account_name(account.name(), Some(&limit), None, &mut tx)
+        .await
+        .expect("created tokens should be listed");
+    assert_eq!(min(records, limit) as usize, fetched.len());
+    for token in &fetched {
+        assert_eq!(&token.account, account.name().as_str());
+        let serialized = serde_json::to_value(token).expect("token should be serialized");
+        assert!(serialized.get("value").is_none());
+    }
+    tx.rollback()
+        .await
+        .expect("rollback should be done properly");
+    Ok(())
+}
+
+#[sqlx::test]
+async fn test_token_query_by_account_name_reports_revoked_as_inactive(pool: PgPool) -> Result<()> {
+    let mut tx = pool
+        .begin()
+        .await
+        .expect("transaction should be started properly");
+    let account = create_account(&mut tx)
+        .await
+        .expect("new account should be created");
+    let active = create_token(account.id(), &mut tx)
+        .await
+        .expect("new token should be created");
+    let value = testutils::rand::string(10);
+    let revoked = TokenEntity::new(
+        testutils::rand::uuid(),
+        testutils::rand::email(),
+        Role::Guest,
+        value.clone(),
+        account.id().to_uuid().to_string(),
+    )
+    .expect("token should be created properly");
+    TokenRepository::upsert(&revoked, &mut tx)
+        .await
+        .expect("token should be stored properly");
+    let expires_at = chrono::Utc::now() + chrono::Duration::hours(testutils::rand::i64(1, 24));
+    RevocationService::revoke(&value, expires_at, &mut tx)
+        .await
+        .expect("token should be revoked");
+    let fetched = TokenService::query_by_account_name(account.name(), None, None, &mut tx)
+        .await
+        .expect("created tokens should be listed");
+    assert_eq!(fetched.len(), 2);
+    let find = |id: String| {
+        fetched
+            .iter()
+            .find(|token| token.id == id)
+            .expect("token should be listed")
+    };
+    assert!(find(active.id().to_uuid().to_string()).active);
+    assert!(!find(revoked.id().to_uuid().to_string()).active);
+    tx.rollback()
+        .await
+        .expect("rollback should be done properly");
+    Ok(())
+}
+
+#[sqlx::test]
+async fn test_audit_record_and_query_by_recipient(pool: PgPool) -> Result<()> {
+    let mut tx = pool
+        .begin()
+        .await
+        .expect("transaction should be started properly");
+    let recipient = testutils::rand::string(10);
+    let provider = testutils::rand::string(10);
+    let ttl = testutils::rand::i64(1, 100000);
+    let issuance = AuditTrace {
+        request_id: Some(testutils::rand::uuid()),
+        correlation_id: Some(testutils::rand::uuid()),
+    };
+    AuditService::record_profile_issuance(&provider, &recipient, ttl, &issuance, &mut tx)
+        .await
+        .expect("profile issuance should be recorded");
+    let share = testutils::rand::string(10);
+    let schema = testutils::rand::string(10);
+    let table = testutils::rand::string(10);
+    let file_count = testutils::rand::i64(0, 100);
+    let query = AuditTrace {
+        request_id: Some(testutils::rand::uuid()),
+        correlation_id: None,
+    };
+    AuditService::record_table_query(
+        &recipient, &share, &schema, &table, file_count, &query, &mut tx,
+    )
+    .await
+    .expect("table query should be recorded");
+    AuditService::record_table_query(
+        &testutils::rand::string(10),
+        &share,
+        &schema,
+        &table,
+        file_count,
+        &AuditTrace::default(),
+        &mut tx,
+    )
+    .await
+    .expect("table query should be recorded");
+    let change_count = testutils::rand::i64(0, 100);
+    let changes = AuditTrace {
+        request_id: Some(testutils::rand::uuid()),
+        correlation_id: None,
+    };
+    AuditService::record_changes_read(
+        &recipient,
+        &share,
+        &schema,
+        &table,
+        change_count,
+        &changes,
+        &mut tx,
+    )
+    .await
+    .expect("changes read should be recorded");
+    let fetched = AuditService::query_by_recipient(&recipient, None, &mut tx)
+        .await
+        .expect("recorded audit logs should be listed");
+    assert_eq!(fetched.len(), 3);
+    let issued = fetched
+        .iter()
+        .find(|log| log.event == "profile_issued")
+        .expect("profile issuance should be listed");
+    assert_eq!(issued.provider.as_deref(), Some(provider.as_str()));
+    assert_eq!(issued.ttl, Some(ttl));
+    assert_eq!(issued.request_id, issuance.request_id);
+    assert_eq!(issued.correlation_id, issuance.correlation_id);
+    let queried = fetched
+        .iter()
+        .find(|log| log.event == "table_queried")
+        .expect("table query should be listed");
+    assert_eq!(queried.share.as_deref(), Some(share.as_str()));
+    assert_eq!(queried.schema.as_deref(), Some(schema.as_str()));
+    assert_eq!(queried.table.as_deref(), Some(table.as_str()));
+    assert_eq!(queried.file_count, Some(file_count));
+    assert_eq!(queried.request_id, query.request_id);
+    assert!(queried.correlation_id.is_none());
+    let read = fetched
+        .iter()
+        .find(|log| log.event == "changes_read")
+        .expect("changes read should be listed");
+    assert_eq!(read.table.as_deref(), Some(table.as_str()));
+    assert_eq!(read.file_count, Some(change_count));
+    assert_eq!(read.request_id, changes.request_id);
+    tx.rollback()
+        .await
+        .expect("rollback should be done properly");
+    Ok(())
+}
+
+#[sqlx::test]
+async fn test_revoke_and_check_token(pool: PgPool) -> Result<()> {
+    let mut tx = pool
+        .begin()
+        .await
+        .expect("transaction should be started properly");
+    let revoked = testutils::rand::string(255);
+    let active = testutils::rand::string(255);
+    let expires_at = chrono::Utc::now() + chrono::Duration::hours(testutils::rand::i64(1, 24));
+    RevocationService::revoke(&revoked, expires_at, &mut tx)
+        .await
+        .expect("token should be revoked");
+    RevocationService::revoke(&revoked, expires_at, &mut tx)
+        .await
+        .expect("token should be revoked again without conflict");
+    assert!(RevocationService::is_revoked(&revoked, &mut tx)
+        .await
+        .expect("revoked token should be checked"));
+    assert!(!RevocationService::is_revoked(&active, &mut tx)
+        .await
+        .expect("active token should be checked"));
+    let stored: String = sqlx::query_scalar("SELECT token_hash FROM revoked_token")
+        .fetch_one(&mut *tx)
+        .await
+        .expect("revoked token should be selected");
+    let value = TokenValue::new(revoked).expect("token value should be created");
+    assert_eq!(stored, value.hash().to_string());
+    tx.rollback()
+        .await
+        .expect("rollback should be done properly");
+    Ok(())
+}
+
+#[sqlx::test]
+async fn test_token_collector_tick(pool: PgPool) -> Result<()> {
+    let mut tx = pool
+        .begin()
+        .await
+        .expect("transaction should be started properly");
+    let account = create_account(&mut tx)
+        .await
+        .expect("new account should be created");
+    tx.commit().await.expect("commit should be done properly");
+    let now = chrono::Utc::now().timestamp();
+    let key = jsonwebtoken::EncodingKey::from_secret(testutils::rand::string(20).as_bytes());
+    let seed = |exp: i64| {
+        let value = jsonwebtoken::encode(
+            &jsonwebtoken::Header::default(),
+            &serde_json::json!({ "exp": exp }),
+            &key,
+        )
+        .expect("token should be signed properly");
+        let token = TokenEntity::new(
+            testutils::rand::uuid(),
+            testutils::rand::email(),
+            Role::Guest,
+            value.clone(),
+            account.id().to_uuid().to_string(),
+        )
+        .expect("token should be created properly");
+        let value = TokenValue::new(value).expect("token value should be created properly");
+        (token, value)
+    };
+    let (expired, expired_value) = seed(now - testutils::rand::i64(1, 100000));
+    let (active, active_value) = seed(now + testutils::rand::i64(3600, 100000));
+    for token in [&expired, &active] {
+        TokenRepository::upsert(token, &pool)
+            .await
+            .expect("token should be stored properly");
+    }
+    let expires_at = chrono::Utc::now() - chrono::Duration::hours(testutils::rand::i64(1, 24));
+    RevocationService::revoke(expired_value.as_str(), expires_at, &pool)
+        .await
+        .expect("token should be revoked");
+    let cleaned = TokenCollector::tick(&pool)
+        .await
+        .expect("expired tokens should be cleaned");
+    assert_eq!(cleaned, 1);
+    assert!(TokenEntity::load_by_value(&expired_value, &pool)
+        .await
+        .expect("expired token should be selected")
+        .is_none());
+    assert!(TokenEntity::load_by_value(&active_value, &pool)
+        .await
+        .expect("active token should be selected")
+        .is_some());
+    assert!(
+        !RevocationService::is_revoked(expired_value.as_str(), &pool)
+            .await
+            .expect("revocation should be checked")
+    );
+    Ok(())
+}
+
+#[sqlx::test]
+async fn test_refresh_token_exchange(pool: PgPool) -> Result<()> {
+    let mut tx = pool
+        .begin()
+        .await
+        .expect("transaction should be started properly");
+    let account = create_account(&mut tx)
+        .await
+        .expect("new account should be created");
+    let now = chrono::Utc::now();
+    let key = jsonwebtoken::EncodingKey::from_secret(testutils::rand::string(20).as_bytes());
+    let access = |exp: i64| {
+        let value = jsonwebtoken::encode(
+            &jsonwebtoken::Header::default(),
+            &serde_json::json!({ "exp": exp, "sub": testutils::rand::string(10) }),
+            &key,
+        )
+        .expect("access token should be signed properly");
+        TokenValue::new(value).expect("access token value should be created")
+    };
+    let family_expires_at = now + chrono::Duration::hours(testutils::rand::i64(1, 24));
+    let first = access(now.timestamp() + 3600);
+    let active = RefreshTokenService::issue(
+        account.id().as_uuid(),
+        None,
+        family_expires_at,
+        &first,
+        &mut tx,
+    )
+    .await
+    .expect("refresh token should be issued properly");
+    let expired = RefreshTokenService::issue(
+        account.id().as_uuid(),
+        None,
+        now - chrono::Duration::hours(testutils::rand::i64(1, 24)),
+        &access(now.timestamp() + 3600),
+        &mut tx,
+    )
+    .await
+    .expect("refresh token should be issued properly");
+    let Ok(RefreshTokenExchange::Rotated {
+        account: name,
+        family,
+        expires_at,
+    }) = RefreshTokenService::exchange(&active, now, &mut tx).await
+    else {
+        panic!("active refresh token should be rotated");
+    };
+    assert_eq!(name, account.name().as_str());
+    assert_eq!(expires_at.timestamp(), family_expires_at.timestamp());
+    let second = access(now.timestamp() + 3600);
+    let successor = RefreshTokenService::issue(
+        account.id().as_uuid(),
+        Some(family),
+        expires_at,
+        &second,
+        &mut tx,
+    )
+    .await
+    .expect("refresh token should be issued properly");
+    assert_eq!(
+        RefreshTokenService::exchange(&active, now, &mut tx)
+            .await
+            .expect("reused refresh token should be exchanged"),
+        RefreshTokenExchange::Reused {
+            account: account.name().to_string(),
+            family,
+        }
+    );
+    for token in [&first, &second] {
+        assert!(RevocationService::is_revoked(token.as_str(), &mut tx)
+            .await
+            .expect("revocation should be checked"));
+    }
+    assert_eq!(
+        RefreshTokenService::exchange(&successor, now, &mut tx)
+            .await
+            .expect("revoked refresh token should be exchanged"),
+        RefreshTokenExchange::Reused {
+            account: account.name().to_string(),
+            family,
+        }
+    );
+    assert_eq!(
+        RefreshTokenService::exchange(&expired, now, &mut tx)
+            .await
+            .expect("expired refresh token should be exchanged"),
+        RefreshTokenExchange::Invalid
+    );
+    assert_eq!(
+        RefreshTokenService::exchange(&testutils::rand::string(64), now, &mut tx)
+            .await
+            .expect("unknown refresh token should be exchanged"),
+        RefreshTokenExchange::Invalid
+    );
+    tx.rollback()
+        .await
+        .expect("rollback should be done properly");
+    Ok(())
+}